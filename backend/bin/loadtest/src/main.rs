@@ -0,0 +1,133 @@
+//! Load test for the API gateway: plays full games in many rooms at once and
+//! reports request latency, message throughput and error rates.
+//!
+//! Players sign in as synthetic guests through the server's test sign-in, so
+//! no Twitch account is needed and the per-address guest limit doesn't stop
+//! them. Start the server with `LOADTEST_TOKEN_SECRET` set to the same value
+//! as here; it refuses test sign-in otherwise. The server still needs its
+//! database, with words seeded by `seed-words`.
+//!
+//! Configured through environment variables:
+//! - `LOADTEST_TOKEN_SECRET`: the server's test sign-in secret (required)
+//! - `API_URL`: server under test (default `http://localhost:3000`)
+//! - `WS_URL`: its WebSocket endpoint (default derived from `API_URL`)
+//! - `LOADTEST_ROOMS`: rooms played at once (default 10)
+//! - `LOADTEST_PLAYERS`: players per room, at least 4 (default 4)
+//! - `LOADTEST_WORDS_PER_ROUND`, `LOADTEST_WIN_SCORE`: game length (default 5, 10)
+//! - `LOADTEST_MAX_ROUNDS`: rounds before a game is cut short (default 20)
+//! - `LOADTEST_THINK_MS`: explainer's time per word (default 200)
+//! - `LOADTEST_RAMP_MS`: pause between opening rooms (default 50)
+//! - `LOADTEST_ROOM_TIMEOUT_SECS`: give up on a room after this (default 300)
+
+mod room;
+mod stats;
+
+use std::error::Error;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use stats::Stats;
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub api_url: String,
+    pub ws_url: String,
+    pub token_secret: String,
+    pub rooms: usize,
+    pub players: usize,
+    pub words_per_round: u32,
+    pub win_score: i32,
+    pub max_rounds: u32,
+    pub think_time: Duration,
+    pub ramp: Duration,
+    pub room_timeout: Duration,
+}
+
+impl Config {
+    fn from_env() -> Result<Self, String> {
+        let api_url = std::env::var("API_URL")
+            .unwrap_or_else(|_| "http://localhost:3000".to_string())
+            .trim_end_matches('/')
+            .to_string();
+        let ws_url = std::env::var("WS_URL")
+            .unwrap_or_else(|_| format!("{}/ws", api_url.replacen("http", "ws", 1)));
+        let token_secret = std::env::var("LOADTEST_TOKEN_SECRET")
+            .map_err(|_| "LOADTEST_TOKEN_SECRET must be set, as on the server".to_string())?;
+
+        let config = Self {
+            api_url,
+            ws_url,
+            token_secret,
+            rooms: env_or("LOADTEST_ROOMS", 10)?,
+            players: env_or("LOADTEST_PLAYERS", 4)?,
+            words_per_round: env_or("LOADTEST_WORDS_PER_ROUND", 5)?,
+            win_score: env_or("LOADTEST_WIN_SCORE", 10)?,
+            max_rounds: env_or("LOADTEST_MAX_ROUNDS", 20)?,
+            think_time: Duration::from_millis(env_or("LOADTEST_THINK_MS", 200)?),
+            ramp: Duration::from_millis(env_or("LOADTEST_RAMP_MS", 50)?),
+            room_timeout: Duration::from_secs(env_or("LOADTEST_ROOM_TIMEOUT_SECS", 300)?),
+        };
+
+        // A game needs two teams of at least two
+        if config.players < 4 || config.players > u8::MAX as usize {
+            return Err("LOADTEST_PLAYERS must be between 4 and 255".to_string());
+        }
+        Ok(config)
+    }
+}
+
+fn env_or<T: FromStr>(name: &str, default: T) -> Result<T, String> {
+    match std::env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| format!("{} is not a valid number: {}", name, value)),
+        Err(_) => Ok(default),
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error>> {
+    // Initialize tracing
+    tracing_subscriber::fmt::init();
+
+    // Load environment variables
+    dotenv::dotenv().ok();
+
+    let config = Config::from_env()?;
+    info!(
+        "Playing {} rooms of {} players against {}",
+        config.rooms, config.players, config.api_url
+    );
+
+    let stats = Arc::new(Stats::default());
+    let started = Instant::now();
+
+    let mut rooms = JoinSet::new();
+    for index in 0..config.rooms {
+        rooms.spawn(room::play(index, config.clone(), stats.clone()));
+        tokio::time::sleep(config.ramp).await;
+    }
+
+    let (mut completed, mut failed) = (0, 0);
+    while let Some(result) = rooms.join_next().await {
+        match result {
+            Ok(Ok(())) => completed += 1,
+            Ok(Err(e)) => {
+                warn!("Room failed: {}", e);
+                failed += 1;
+            }
+            Err(e) => {
+                warn!("Room task panicked: {}", e);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("{}", stats.report(started.elapsed(), completed, failed));
+
+    Ok(())
+}
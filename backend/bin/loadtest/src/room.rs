@@ -0,0 +1,307 @@
+//! One room of simulated players, from sign-in to the end of the game.
+//!
+//! The host creates the room over REST and the others join it; then every
+//! player connects over WebSocket, takes a seat on alternating teams and
+//! plays. The host starts the game and each round, the teams mark ready, and
+//! explainers go through their words with a short think time each.
+
+use client::{Client, ClientConfig, ClientEvent};
+use rand::Rng;
+use serde_json::json;
+use shared::models::{
+    CreateRoomResponse, GameSettings, GuestLoginRequest, LoginResponse, WebSocketMessage,
+    WordResult,
+};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::{watch, Barrier};
+use tokio::task::JoinSet;
+use tokio::time::Instant;
+
+use crate::stats::Stats;
+use crate::Config;
+
+// The host's pause between one round ending and starting the next
+const ROUND_PAUSE: Duration = Duration::from_millis(500);
+// Share of words the explainer's team gets; the rest are skipped
+const GUESS_RATE: f64 = 0.8;
+
+/// Play one room's game; fails if any player does
+pub async fn play(index: usize, config: Config, stats: Arc<Stats>) -> Result<(), String> {
+    tokio::time::timeout(config.room_timeout, run(index, &config, &stats))
+        .await
+        .map_err(|_| format!("Room {} timed out", index))?
+}
+
+async fn run(index: usize, config: &Config, stats: &Arc<Stats>) -> Result<(), String> {
+    let http = reqwest::Client::new();
+
+    let mut tokens = Vec::with_capacity(config.players);
+    for seat in 0..config.players {
+        let name = format!("Load {}-{}", index, seat);
+        tokens.push(sign_in(&http, config, stats, &name).await?);
+    }
+
+    let room_code = create_room(&http, config, stats, &tokens[0], index).await?;
+    for token in &tokens[1..] {
+        join_room(&http, config, stats, token, &room_code).await?;
+    }
+
+    let seated = Arc::new(Barrier::new(config.players));
+    let (done, _) = watch::channel(false);
+    let mut players = JoinSet::new();
+    for (seat, token) in tokens.into_iter().enumerate() {
+        let player = Player {
+            seat,
+            room_code: room_code.clone(),
+            config: config.clone(),
+            stats: stats.clone(),
+            seated: seated.clone(),
+            done: done.subscribe(),
+        };
+        players.spawn(player.play(token));
+    }
+
+    // Once anyone is through, because the game ended, was cut short or a
+    // player failed, the rest stop too
+    let mut result = Ok(());
+    while let Some(outcome) = players.join_next().await {
+        let outcome = outcome.map_err(|e| e.to_string()).and_then(|r| r);
+        if let Err(e) = outcome {
+            if result.is_ok() {
+                result = Err(format!("Room {}: {}", index, e));
+            }
+        }
+        let _ = done.send(true);
+    }
+    result
+}
+
+// Test sign-in, which the server doesn't count against our address
+async fn sign_in(
+    http: &reqwest::Client,
+    config: &Config,
+    stats: &Stats,
+    display_name: &str,
+) -> Result<String, String> {
+    let request = GuestLoginRequest {
+        display_name: display_name.to_string(),
+    };
+    let response: LoginResponse = stats
+        .time("sign_in", async {
+            http.post(format!("{}/api/v1/auth/test-login", config.api_url))
+                .header("X-Test-Token-Secret", &config.token_secret)
+                .json(&request)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(response.access_token)
+}
+
+async fn create_room(
+    http: &reqwest::Client,
+    config: &Config,
+    stats: &Stats,
+    token: &str,
+    index: usize,
+) -> Result<String, String> {
+    let request = json!({
+        "name": format!("Load test {}", index),
+        "max_players": config.players,
+    });
+    let response: CreateRoomResponse = stats
+        .time("create_room", async {
+            http.post(format!("{}/api/v1/rooms", config.api_url))
+                .bearer_auth(token)
+                .json(&request)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(response.room_code)
+}
+
+async fn join_room(
+    http: &reqwest::Client,
+    config: &Config,
+    stats: &Stats,
+    token: &str,
+    room_code: &str,
+) -> Result<(), String> {
+    stats
+        .time("join_room", async {
+            http.post(format!(
+                "{}/api/v1/rooms/{}/join",
+                config.api_url, room_code
+            ))
+            .bearer_auth(token)
+            .send()
+            .await?
+            .error_for_status()
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+struct Player {
+    seat: usize, // The host sits first
+    room_code: String,
+    config: Config,
+    stats: Arc<Stats>,
+    seated: Arc<Barrier>,
+    done: watch::Receiver<bool>,
+}
+
+impl Player {
+    async fn play(mut self, token: String) -> Result<(), String> {
+        let config = ClientConfig::new(&self.config.ws_url, token);
+        let mut client = self
+            .stats
+            .time("connect", Client::connect(config))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let result = self.play_game(&mut client).await;
+        client.close();
+        result
+    }
+
+    async fn play_game(&mut self, client: &mut Client) -> Result<(), String> {
+        let stats = self.stats.clone();
+        stats
+            .time("ws_join_room", client.join_room(&self.room_code))
+            .await
+            .map_err(|e| e.to_string())?;
+        let team_id = if self.seat.is_multiple_of(2) {
+            "team_a"
+        } else {
+            "team_b"
+        };
+        stats
+            .time("join_team", client.join_team(team_id))
+            .await
+            .map_err(|e| e.to_string())?;
+
+        // The game starts once everyone has a team
+        select! {
+            _ = self.seated.wait() => {}
+            _ = self.done.changed() => return Ok(()),
+        }
+
+        let host = self.seat == 0;
+        if host {
+            let settings = GameSettings {
+                words_per_round: self.config.words_per_round,
+                win_score: self.config.win_score,
+                ..GameSettings::default()
+            };
+            stats
+                .time("start_game", client.start_game(Some(settings)))
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+
+        let mut ready_teams = HashSet::new();
+        let mut rounds_played = 0;
+        let mut next_round_at: Option<Instant> = None;
+
+        loop {
+            let event = select! {
+                event = client.next_event() => event,
+                _ = tokio::time::sleep_until(next_round_at.unwrap_or_else(Instant::now)),
+                    if next_round_at.is_some() =>
+                {
+                    next_round_at = None;
+                    self.start_round(client).await?;
+                    continue;
+                }
+                _ = self.done.changed() => return Ok(()),
+            };
+
+            let message = match event {
+                Some(ClientEvent::Message(message)) => message,
+                Some(ClientEvent::Reconnecting { .. }) => {
+                    stats.error("reconnect");
+                    continue;
+                }
+                Some(ClientEvent::Reconnected) => continue,
+                Some(ClientEvent::Disconnected) | None => {
+                    return Err("Disconnected from the server".to_string())
+                }
+            };
+            stats.message();
+
+            match *message {
+                WebSocketMessage::GameStarted => {
+                    stats
+                        .time("mark_ready", client.request(WebSocketMessage::MarkReady))
+                        .await
+                        .map_err(|e| e.to_string())?;
+                }
+                WebSocketMessage::TeamReady { team_id } if host => {
+                    // The first round starts when both teams are ready
+                    let newly_ready = ready_teams.insert(team_id);
+                    if newly_ready && ready_teams.len() == 2 {
+                        self.start_round(client).await?;
+                    }
+                }
+                WebSocketMessage::RoundStarted { round }
+                    if round.explainer_id == client.user().id =>
+                {
+                    self.explain(client, round.words.len()).await?;
+                }
+                WebSocketMessage::RoundEnded { .. } if host => {
+                    rounds_played += 1;
+                    if rounds_played >= self.config.max_rounds {
+                        stats.game_over(false);
+                        return Ok(());
+                    }
+                    next_round_at = Some(Instant::now() + ROUND_PAUSE);
+                }
+                WebSocketMessage::GameEnded { .. } => {
+                    if host {
+                        stats.game_over(true);
+                    }
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+    }
+
+    async fn start_round(&self, client: &Client) -> Result<(), String> {
+        self.stats
+            .time("start_round", client.start_round())
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    // Go through the round's words; the round ends with the last one
+    async fn explain(&self, client: &Client, words: usize) -> Result<(), String> {
+        for _ in 0..words {
+            tokio::time::sleep(self.config.think_time).await;
+            let result = if rand::thread_rng().gen_bool(GUESS_RATE) {
+                WordResult::Correct
+            } else {
+                WordResult::Skipped
+            };
+            self.stats
+                .time("word_action", client.word_action(result))
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
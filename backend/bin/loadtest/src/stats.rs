@@ -0,0 +1,190 @@
+//! Latency, throughput and error counts collected across all rooms.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Write};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::debug;
+
+#[derive(Default)]
+pub struct Stats {
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    latencies: BTreeMap<&'static str, Vec<Duration>>, // Successful requests by operation
+    errors: BTreeMap<&'static str, u64>,
+    messages: u64, // Server messages other than replies
+    games_finished: u64,
+    games_cut_short: u64,
+}
+
+impl Stats {
+    /// Time an operation, counting it as an error if it fails
+    pub async fn time<T, E, F>(&self, operation: &'static str, request: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: Display,
+    {
+        let started = Instant::now();
+        let result = request.await;
+        let mut inner = self.inner.lock().unwrap();
+        match &result {
+            Ok(_) => inner
+                .latencies
+                .entry(operation)
+                .or_default()
+                .push(started.elapsed()),
+            Err(e) => {
+                debug!("{} failed: {}", operation, e);
+                *inner.errors.entry(operation).or_default() += 1;
+            }
+        }
+        result
+    }
+
+    /// Count a failure outside of a timed operation
+    pub fn error(&self, operation: &'static str) {
+        *self
+            .inner
+            .lock()
+            .unwrap()
+            .errors
+            .entry(operation)
+            .or_default() += 1;
+    }
+
+    pub fn message(&self) {
+        self.inner.lock().unwrap().messages += 1;
+    }
+
+    /// Count a game that ended with a winner, or was cut short
+    pub fn game_over(&self, finished: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        if finished {
+            inner.games_finished += 1;
+        } else {
+            inner.games_cut_short += 1;
+        }
+    }
+
+    pub fn report(&self, elapsed: Duration, rooms_completed: usize, rooms_failed: usize) -> String {
+        let inner = self.inner.lock().unwrap();
+        let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+        let mut report = String::new();
+
+        let _ = writeln!(report, "Duration: {:.1}s", seconds);
+        let _ = writeln!(
+            report,
+            "Rooms: {} completed, {} failed",
+            rooms_completed, rooms_failed
+        );
+        let _ = writeln!(
+            report,
+            "Games: {} finished, {} cut short",
+            inner.games_finished, inner.games_cut_short
+        );
+        let _ = writeln!(
+            report,
+            "Messages received: {} ({:.1}/s)",
+            inner.messages,
+            inner.messages as f64 / seconds
+        );
+
+        let _ = writeln!(
+            report,
+            "\n{:<12} {:>8} {:>7} {:>9} {:>9} {:>9} {:>9}",
+            "operation", "requests", "errors", "p50 ms", "p90 ms", "p99 ms", "max ms"
+        );
+        let operations: Vec<&str> = inner
+            .latencies
+            .keys()
+            .chain(inner.errors.keys())
+            .copied()
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let (mut total, mut total_errors) = (0, 0);
+        for operation in operations {
+            let mut latencies = inner.latencies.get(operation).cloned().unwrap_or_default();
+            latencies.sort();
+            let errors = inner.errors.get(operation).copied().unwrap_or_default();
+            let requests = latencies.len() as u64 + errors;
+            total += requests;
+            total_errors += errors;
+
+            let _ = writeln!(
+                report,
+                "{:<12} {:>8} {:>7} {:>9} {:>9} {:>9} {:>9}",
+                operation,
+                requests,
+                errors,
+                millis(percentile(&latencies, 50.0)),
+                millis(percentile(&latencies, 90.0)),
+                millis(percentile(&latencies, 99.0)),
+                millis(latencies.last().copied()),
+            );
+        }
+
+        let error_rate = if total == 0 {
+            0.0
+        } else {
+            total_errors as f64 * 100.0 / total as f64
+        };
+        let _ = write!(
+            report,
+            "\nRequests: {} ({:.1}/s), error rate {:.2}%",
+            total,
+            total as f64 / seconds,
+            error_rate
+        );
+        report
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+pub fn percentile(sorted: &[Duration], percent: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.clamp(1, sorted.len()) - 1).copied()
+}
+
+fn millis(duration: Option<Duration>) -> String {
+    duration
+        .map(|d| format!("{:.1}", d.as_secs_f64() * 1000.0))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_use_nearest_rank() {
+        let samples: Vec<Duration> = (1..=10).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&samples, 50.0), Some(Duration::from_millis(5)));
+        assert_eq!(percentile(&samples, 90.0), Some(Duration::from_millis(9)));
+        assert_eq!(percentile(&samples, 99.0), Some(Duration::from_millis(10)));
+        assert_eq!(percentile(&samples, 0.0), Some(Duration::from_millis(1)));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[tokio::test]
+    async fn test_failed_requests_count_as_errors() {
+        let stats = Stats::default();
+        let _ = stats.time("join", async { Ok::<_, String>(()) }).await;
+        let _ = stats
+            .time("join", async { Err::<(), _>("full".to_string()) })
+            .await;
+        stats.message();
+
+        let report = stats.report(Duration::from_secs(1), 1, 0);
+        assert!(report.contains("Messages received: 1 (1.0/s)"));
+        assert!(report.contains("error rate 50.00%"));
+    }
+}
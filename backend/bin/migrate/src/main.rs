@@ -55,15 +55,48 @@ async fn main() -> Result<(), Box<dyn Error>> {
     // Create indexes
     info!("Creating indexes...");
 
-    // Users indexes
+    // Users: move Twitch IDs into linked identities
     let users_collection = db.collection::<mongodb::bson::Document>("users");
+    let converted = users_collection
+        .update_many(
+            doc! {"twitch_id": {"$exists": true, "$ne": ""}, "identities": {"$exists": false}},
+            vec![
+                doc! {"$set": {"identities": [{
+                    "provider": "twitch",
+                    "subject": "$twitch_id",
+                    "username": "$username",
+                    "linked_at": "$created_at",
+                }]}},
+                doc! {"$unset": "twitch_id"},
+            ],
+            None,
+        )
+        .await?;
+    if converted.modified_count > 0 {
+        info!(
+            "Linked Twitch identities for {} users",
+            converted.modified_count
+        );
+    }
+    users_collection
+        .update_many(
+            doc! {"twitch_id": {"$exists": true}},
+            doc! {"$unset": {"twitch_id": ""}},
+            None,
+        )
+        .await?;
+    // Replaced by the identities index; missing on fresh databases
+    let _ = users_collection.drop_index("twitch_id_1", None).await;
+
+    // Users indexes
     users_collection
         .create_index(
             mongodb::IndexModel::builder()
-                .keys(doc! {"twitch_id": 1})
+                .keys(doc! {"identities.provider": 1, "identities.subject": 1})
                 .options(
                     mongodb::options::IndexOptions::builder()
                         .unique(true)
+                        .partial_filter_expression(doc! {"identities.subject": {"$exists": true}})
                         .build(),
                 )
                 .build(),
@@ -27,7 +27,8 @@ pub async fn auth_middleware(
 
     let claims = state
         .auth_service
-        .verify_token(token)
+        .authenticate(token)
+        .await
         .map_err(AppError::from)?;
 
     // Get user from database
@@ -45,6 +46,7 @@ pub async fn auth_middleware(
         user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default()
     );
     req.extensions_mut().insert(user);
+    req.extensions_mut().insert(claims);
 
     Ok(next.run(req).await)
 }
@@ -54,7 +56,7 @@ pub async fn extract_user_from_token(
     token: &str,
     auth_service: &Arc<AuthService>,
 ) -> Result<User, AuthError> {
-    let claims = auth_service.verify_token(token)?;
+    let claims = auth_service.authenticate(token).await?;
 
     // Get user from database
     let user = auth_service
@@ -0,0 +1,228 @@
+//! Streamer-level ban lists, stored in MongoDB and copied into every room
+//! the streamer creates afterwards.
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::{Duration, Utc};
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::doc,
+    options::{IndexOptions, ReplaceOptions},
+    Collection, Database, IndexModel,
+};
+use serde::{Deserialize, Serialize};
+use shared::models::{Ban, BanRequest, User};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+use crate::error::AppError;
+use crate::AppState;
+
+const MAX_REASON_LEN: usize = 200;
+
+/// Build a ban from a request, checking the reason and duration
+pub fn new_ban(req: BanRequest, banned_by: &str) -> Result<Ban, String> {
+    let reason = req
+        .reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty());
+    if reason
+        .as_ref()
+        .is_some_and(|reason| reason.len() > MAX_REASON_LEN)
+    {
+        return Err(format!(
+            "Reason must be at most {} characters",
+            MAX_REASON_LEN
+        ));
+    }
+    if req.duration_minutes == Some(0) {
+        return Err("Ban duration must be at least a minute".to_string());
+    }
+
+    let banned_at = Utc::now();
+    Ok(Ban {
+        user_id: req.user_id,
+        reason,
+        banned_by: banned_by.to_string(),
+        banned_at,
+        expires_at: req
+            .duration_minutes
+            .map(|minutes| banned_at + Duration::minutes(minutes.into())),
+    })
+}
+
+/// Stored document: a ban owned by a streamer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StreamerBan {
+    streamer_id: String,
+    #[serde(flatten)]
+    ban: Ban,
+}
+
+enum Backend {
+    Mongo(Collection<StreamerBan>),
+    // Streamer ID -> banned user ID -> ban, for tests
+    Memory(RwLock<HashMap<String, HashMap<String, Ban>>>),
+}
+
+pub struct BanStore {
+    backend: Backend,
+}
+
+impl BanStore {
+    pub fn mongo(db: &Database) -> Self {
+        Self {
+            backend: Backend::Mongo(db.collection("streamer_bans")),
+        }
+    }
+
+    pub fn memory() -> Self {
+        Self {
+            backend: Backend::Memory(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// One ban per streamer and user
+    pub async fn ensure_indexes(&self) -> Result<(), String> {
+        if let Backend::Mongo(collection) = &self.backend {
+            let index = IndexModel::builder()
+                .keys(doc! { "streamer_id": 1, "user_id": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build();
+            collection
+                .create_index(index, None)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+
+    /// Add a ban to the streamer's list, replacing an earlier one
+    pub async fn ban(&self, streamer_id: &str, ban: &Ban) -> Result<(), String> {
+        match &self.backend {
+            Backend::Mongo(collection) => {
+                collection
+                    .replace_one(
+                        doc! { "streamer_id": streamer_id, "user_id": &ban.user_id },
+                        StreamerBan {
+                            streamer_id: streamer_id.to_string(),
+                            ban: ban.clone(),
+                        },
+                        ReplaceOptions::builder().upsert(true).build(),
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+            Backend::Memory(bans) => {
+                bans.write()
+                    .await
+                    .entry(streamer_id.to_string())
+                    .or_default()
+                    .insert(ban.user_id.clone(), ban.clone());
+            }
+        }
+        Ok(())
+    }
+
+    /// Lift a ban; false if the user wasn't banned
+    pub async fn unban(&self, streamer_id: &str, user_id: &str) -> Result<bool, String> {
+        match &self.backend {
+            Backend::Mongo(collection) => {
+                let result = collection
+                    .delete_one(
+                        doc! { "streamer_id": streamer_id, "user_id": user_id },
+                        None,
+                    )
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(result.deleted_count > 0)
+            }
+            Backend::Memory(bans) => Ok(bans
+                .write()
+                .await
+                .get_mut(streamer_id)
+                .and_then(|bans| bans.remove(user_id))
+                .is_some()),
+        }
+    }
+
+    /// The streamer's bans that haven't expired
+    pub async fn list(&self, streamer_id: &str) -> Result<Vec<Ban>, String> {
+        let bans: Vec<Ban> = match &self.backend {
+            Backend::Mongo(collection) => collection
+                .find(doc! { "streamer_id": streamer_id }, None)
+                .await
+                .map_err(|e| e.to_string())?
+                .map_ok(|stored| stored.ban)
+                .try_collect()
+                .await
+                .map_err(|e| e.to_string())?,
+            Backend::Memory(bans) => bans
+                .read()
+                .await
+                .get(streamer_id)
+                .map(|bans| bans.values().cloned().collect())
+                .unwrap_or_default(),
+        };
+
+        Ok(bans.into_iter().filter(Ban::is_active).collect())
+    }
+}
+
+fn storage_error(e: String) -> AppError {
+    tracing::error!("Ban storage failed: {}", e);
+    AppError::internal()
+}
+
+/// List the caller's streamer bans
+pub async fn list_bans(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> Result<Json<Vec<Ban>>, AppError> {
+    let streamer_id = user.id.unwrap().to_hex();
+    let bans = state.bans.list(&streamer_id).await.map_err(storage_error)?;
+    Ok(Json(bans))
+}
+
+/// Ban a user from every room the caller creates from now on
+pub async fn create_ban(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Json(req): Json<BanRequest>,
+) -> Result<Json<Ban>, AppError> {
+    let streamer_id = user.id.unwrap().to_hex();
+    if req.user_id == streamer_id {
+        return Err(AppError::bad_request("You cannot ban yourself".into()));
+    }
+
+    let ban = new_ban(req, &streamer_id).map_err(AppError::bad_request)?;
+    state
+        .bans
+        .ban(&streamer_id, &ban)
+        .await
+        .map_err(storage_error)?;
+
+    tracing::info!("Streamer {} banned user {}", streamer_id, ban.user_id);
+    Ok(Json(ban))
+}
+
+/// Lift one of the caller's streamer bans
+pub async fn delete_ban(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(user_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let streamer_id = user.id.unwrap().to_hex();
+    if !state
+        .bans
+        .unban(&streamer_id, &user_id)
+        .await
+        .map_err(storage_error)?
+    {
+        return Err(AppError::not_found("Ban not found".into()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
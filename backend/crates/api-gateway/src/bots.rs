@@ -0,0 +1,437 @@
+//! Bot players, for filling small teams and for practice.
+//!
+//! Bots are room participants with bot settings and no socket. Each round a
+//! bot plays in gets a task that acts for it: a bot explainer gives canned
+//! clues, skips words nobody gets and ends the round when time is up; bot
+//! guessers get words with their configured accuracy and latency. Bots take
+//! the same paths through the game engine as players' actions do.
+
+use axum::{
+    extract::{Extension, Path, State},
+    response::Json,
+};
+use chrono::Utc;
+use game_engine::bot::{self, BOT_ID_PREFIX};
+use game_engine::error::GameError;
+use game_engine::game::GameEngine;
+use game_engine::team::TeamManager;
+use shared::errors::{ErrorCode, ProtocolError};
+use shared::models::{
+    AddBotRequest, BotSettings, GameRoom, HatPhase, RoomInfo, RoomParticipant, Round, User,
+    UserRole, WebSocketMessage, WordResult,
+};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::error::AppError;
+use crate::game::GAME_ENGINES;
+use crate::websocket::game as ws_game;
+use crate::AppState;
+
+// Pause before a bot explainer starts its turn, so players see the last round end
+const TURN_DELAY: Duration = Duration::from_secs(3);
+// Time between a bot explainer's clues
+const CLUE_INTERVAL: Duration = Duration::from_secs(5);
+// A bot explainer skips words nobody has guessed by then
+const SKIP_AFTER: Duration = Duration::from_secs(20);
+// How often a round's bots check on the game
+const TICK: Duration = Duration::from_millis(100);
+
+/// Add a bot to a team, for the REST and WebSocket handlers (admin only)
+pub(crate) async fn add_to_room(
+    state: &AppState,
+    admin_id: &str,
+    room_code: &str,
+    team_id: &str,
+    settings: BotSettings,
+) -> Result<RoomParticipant, ProtocolError> {
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
+
+    if room.admin_id != admin_id {
+        return Err(ProtocolError::new(
+            ErrorCode::NotAdmin,
+            "Only admin can add bots",
+        ));
+    }
+
+    let participant = new_bot(room, team_id, settings)?;
+    announce(state, room, &participant).await;
+    drop(rooms);
+
+    join_game(state, room_code, &participant).await?;
+
+    info!(
+        "Admin {} added bot {} to team {} in room {}",
+        admin_id, participant.user_id, team_id, room_code
+    );
+    Ok(participant)
+}
+
+/// Seat a new bot on a team (checks are up to the caller)
+fn new_bot(
+    room: &mut GameRoom,
+    team_id: &str,
+    settings: BotSettings,
+) -> Result<RoomParticipant, ProtocolError> {
+    settings.validate()?;
+    if !TeamManager::new()
+        .get_teams()
+        .iter()
+        .any(|t| t.id == team_id)
+    {
+        return Err(ProtocolError::new(
+            ErrorCode::InvalidRequest,
+            format!("Team {} not found", team_id),
+        ));
+    }
+    if room.player_count() >= room.max_players as usize {
+        return Err(ProtocolError::new(ErrorCode::RoomFull, "Room is full"));
+    }
+
+    let number = (1..)
+        .find(|n| {
+            !room
+                .participants
+                .contains_key(&format!("{}{}", BOT_ID_PREFIX, n))
+        })
+        .unwrap_or_default();
+    let user_id = format!("{}{}", BOT_ID_PREFIX, number);
+
+    let participant = RoomParticipant {
+        user_id: user_id.clone(),
+        username: user_id.clone(),
+        display_name: format!("Бот {}", number),
+        profile_image_url: None,
+        role: UserRole::Player,
+        team_id: Some(team_id.to_string()),
+        is_connected: true, // Bots never leave on their own
+        is_afk: false,
+        joined_at: Utc::now(),
+        bot: Some(settings),
+    };
+
+    room.participants.insert(user_id, participant.clone());
+    room.updated_at = Utc::now();
+    Ok(participant)
+}
+
+/// Tell the room and the lobby about a new bot
+async fn announce(state: &AppState, room: &GameRoom, participant: &RoomParticipant) {
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            &room.room_code,
+            WebSocketMessage::UserJoined {
+                participant: participant.clone(),
+            },
+        )
+        .await;
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            &room.room_code,
+            WebSocketMessage::RoomUpdated { room: room.clone() },
+        )
+        .await;
+    state
+        .websocket_manager
+        .broadcast_to_lobby(WebSocketMessage::RoomInfoUpdated {
+            room_info: RoomInfo::from(room),
+        });
+}
+
+/// Put a new bot in its team of a game that is already on. The bot leaves
+/// the room again if the team won't take it.
+async fn join_game(
+    state: &AppState,
+    room_code: &str,
+    participant: &RoomParticipant,
+) -> Result<(), GameError> {
+    let engines = GAME_ENGINES.read().await;
+    let Some(engine) = engines.get(room_code) else {
+        return Ok(());
+    };
+    let mut engine = engine.write().await;
+
+    let team_id = participant.team_id.as_deref().unwrap_or_default();
+    if let Err(e) = engine
+        .team_manager
+        .add_player_to_team(participant.user_id.clone(), team_id)
+    {
+        drop(engine);
+        drop(engines);
+        let mut rooms = state.rooms.write().await;
+        if let Some(room) = rooms.get_mut(room_code) {
+            room.participants.remove(&participant.user_id);
+            state
+                .websocket_manager
+                .broadcast_to_room(
+                    room_code,
+                    WebSocketMessage::UserLeft {
+                        user_id: participant.user_id.clone(),
+                    },
+                )
+                .await;
+        }
+        return Err(e);
+    }
+
+    let teams = engine.team_manager.get_teams().to_vec();
+    state
+        .websocket_manager
+        .broadcast_to_room(room_code, WebSocketMessage::TeamsUpdated { teams })
+        .await;
+    Ok(())
+}
+
+/// Add a bot player to a team (admin only). Bots are removed like players,
+/// by kicking them.
+pub async fn add_bot(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(room_code): Path<String>,
+    Json(req): Json<AddBotRequest>,
+) -> Result<Json<RoomParticipant>, AppError> {
+    let user_id = user.id.unwrap().to_hex();
+    let participant = add_to_room(&state, &user_id, &room_code, &req.team_id, req.settings).await?;
+    Ok(Json(participant))
+}
+
+/// Let a bot explainer start its turn after a short pause. Called wherever
+/// the next turn may have become a bot's: when the game or a Hat phase
+/// begins, and when a round ends.
+pub fn schedule_turn(state: &AppState, room_code: &str) {
+    let state = state.clone();
+    let room_code = room_code.to_string();
+    tokio::spawn(async move {
+        tokio::time::sleep(TURN_DELAY).await;
+        if let Err(e) = start_turn(&state, &room_code).await {
+            warn!("Bot could not start a round in room {}: {}", room_code, e);
+        }
+    });
+}
+
+async fn start_turn(state: &AppState, room_code: &str) -> Result<(), ProtocolError> {
+    if !state.rooms.read().await.contains_key(room_code) {
+        return Ok(());
+    }
+
+    let engines = GAME_ENGINES.read().await;
+    let Some(engine) = engines.get(room_code) else {
+        return Ok(());
+    };
+    let mut engine = engine.write().await;
+
+    let game = &engine.game_state;
+    if game.started_at.is_none()
+        || game.current_round.is_some()
+        || game.winner_team_id.is_some()
+        || engine.hat_phase() == Some(HatPhase::Submission)
+        || !bot::is_bot(&engine.next_explainer()?)
+    {
+        return Ok(());
+    }
+
+    let round = engine.start_round().await?;
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            room_code,
+            WebSocketMessage::RoundStarted {
+                round: round.clone(),
+            },
+        )
+        .await;
+    info!(
+        "Bot {} started round {} in room {}",
+        round.explainer_id, round.round_number, room_code
+    );
+
+    round_started(state, room_code, &round).await;
+    Ok(())
+}
+
+/// Start the bots' part in a round that has just started, if any of them
+/// plays in it
+pub async fn round_started(state: &AppState, room_code: &str, round: &Round) {
+    let rooms = state.rooms.read().await;
+    let Some(room) = rooms.get(room_code) else {
+        return;
+    };
+
+    let explainer_is_bot = room
+        .participants
+        .get(&round.explainer_id)
+        .is_some_and(|p| p.is_bot());
+    let guessers: Vec<BotSettings> = room
+        .participants
+        .values()
+        .filter(|p| p.user_id != round.explainer_id && p.team_id.as_ref() == Some(&round.team_id))
+        .filter_map(|p| p.bot)
+        .collect();
+    if !explainer_is_bot && guessers.is_empty() {
+        return;
+    }
+
+    // Rounds handed to a bot midway keep the time they have left
+    let elapsed = round
+        .started_at
+        .and_then(|started_at| (Utc::now() - started_at).to_std().ok())
+        .unwrap_or_default();
+    let duration = Duration::from_secs(round.timer_seconds.into()).saturating_sub(elapsed);
+
+    let bots = RoundBots {
+        round_number: round.round_number,
+        explainer_id: round.explainer_id.clone(),
+        ends_at: Instant::now() + duration,
+        explainer_is_bot,
+        guessers,
+    };
+    tokio::spawn(play_round(state.clone(), room_code.to_string(), bots));
+}
+
+// Bots playing in a round
+struct RoundBots {
+    round_number: u32,
+    explainer_id: String,
+    ends_at: Instant,
+    explainer_is_bot: bool,
+    guessers: Vec<BotSettings>,
+}
+
+// What the bots will do with the current word
+struct WordPlan {
+    index: usize,
+    clues: VecDeque<String>, // Left to give, if a bot explains
+    next_clue_at: Instant,
+    guessed_at: Option<Instant>,
+    skip_at: Option<Instant>,
+}
+
+impl WordPlan {
+    fn new(bots: &RoundBots, index: usize, engine: &GameEngine) -> Option<Self> {
+        let word = engine.get_current_word()?;
+        let now = Instant::now();
+        let guessed_at =
+            bot::guess_time(&bots.guessers, &mut rand::thread_rng()).map(|time| now + time);
+
+        Some(Self {
+            index,
+            clues: if bots.explainer_is_bot {
+                bot::clues(word).into()
+            } else {
+                VecDeque::new()
+            },
+            next_clue_at: now,
+            guessed_at,
+            skip_at: (bots.explainer_is_bot && guessed_at.is_none()).then(|| now + SKIP_AFTER),
+        })
+    }
+}
+
+async fn play_round(state: AppState, room_code: String, mut bots: RoundBots) {
+    let mut plan: Option<WordPlan> = None;
+
+    loop {
+        if !state.rooms.read().await.contains_key(&room_code) {
+            return;
+        }
+        let engines = GAME_ENGINES.read().await;
+        let Some(engine) = engines.get(&room_code) else {
+            return;
+        };
+        let mut engine = engine.write().await;
+        // Over, or handed to another explainer, whose bots take it from here
+        if engine
+            .game_state
+            .current_round
+            .as_ref()
+            .is_none_or(|round| {
+                round.round_number != bots.round_number || round.explainer_id != bots.explainer_id
+            })
+        {
+            return;
+        }
+
+        // Bots wait out a pause, and so does the round's clock
+        if engine.is_paused() {
+            bots.ends_at += TICK;
+            drop(engine);
+            drop(engines);
+            tokio::time::sleep(TICK).await;
+            continue;
+        }
+
+        let now = Instant::now();
+        if bots.explainer_is_bot && now >= bots.ends_at {
+            if let Err(e) = ws_game::end_round(&mut engine, &room_code, &state).await {
+                warn!("Bot could not end the round in room {}: {}", room_code, e);
+            }
+            return;
+        }
+
+        let index = engine.game_state.current_word_index;
+        if plan.as_ref().is_none_or(|plan| plan.index != index) {
+            plan = WordPlan::new(&bots, index, &engine);
+        }
+        let Some(word) = plan.as_mut() else {
+            return;
+        };
+
+        if now >= word.next_clue_at {
+            if let Some(text) = word.clues.pop_front() {
+                match engine.add_bot_clue(&text) {
+                    Ok(clue) => {
+                        state
+                            .websocket_manager
+                            .broadcast_to_room(&room_code, WebSocketMessage::ClueAdded { clue })
+                            .await;
+                    }
+                    Err(e) => warn!("Bot clue failed in room {}: {}", room_code, e),
+                }
+                word.next_clue_at = now + CLUE_INTERVAL;
+            }
+        }
+
+        let result = if word.guessed_at.is_some_and(|at| now >= at) {
+            Some(WordResult::Correct)
+        } else if word.skip_at.is_some_and(|at| now >= at) {
+            Some(WordResult::Skipped)
+        } else {
+            None
+        };
+        if let Some(result) = result {
+            word.guessed_at = None;
+            word.skip_at = None;
+            if let Err(e) = play_word(&mut engine, result, &room_code, &state).await {
+                warn!("Bot word action failed in room {}: {}", room_code, e);
+            }
+        }
+
+        drop(engine);
+        drop(engines);
+        tokio::time::sleep(TICK).await;
+    }
+}
+
+async fn play_word(
+    engine: &mut GameEngine,
+    result: WordResult,
+    room_code: &str,
+    state: &AppState,
+) -> Result<(), ProtocolError> {
+    let score_change = engine.process_word_result(result)?;
+    // A human explainer gets the next word on their own connection
+    if let Some(next_word) =
+        ws_game::finish_word(engine, result, score_change, room_code, state).await?
+    {
+        ws_game::send_to_explainer(engine, next_word, room_code, state).await;
+    }
+    Ok(())
+}
@@ -0,0 +1,93 @@
+//! Per-connection delta encoding: full room and game state updates are
+//! replaced with patches against what the connection was sent last.
+
+use serde_json::Value;
+use shared::delta::{self, PatchOp};
+use shared::models::WebSocketMessage;
+
+// A document as last sent on this connection
+#[derive(Default)]
+struct SentDocument {
+    version: u64,
+    value: Option<Value>,
+}
+
+impl SentDocument {
+    /// Record a new value; returns the base version, new version and ops
+    fn update(&mut self, value: Value) -> (u64, u64, Vec<PatchOp>) {
+        let patch = self
+            .value
+            .as_ref()
+            .map(|old| delta::diff(old, &value))
+            // Only worth it when smaller than the document itself
+            .filter(|ops| serialized_len(ops) < serialized_len(&value));
+
+        let base_version = if patch.is_some() { self.version } else { 0 };
+        let ops = patch.unwrap_or_else(|| vec![PatchOp::replace_root(value.clone())]);
+        self.version += 1;
+        self.value = Some(value);
+        (base_version, self.version, ops)
+    }
+}
+
+fn serialized_len<T: serde::Serialize + ?Sized>(value: &T) -> usize {
+    serde_json::to_vec(value).map_or(usize::MAX, |bytes| bytes.len())
+}
+
+#[derive(Default)]
+pub struct DeltaEncoder {
+    room: SentDocument,
+    game_state: SentDocument,
+}
+
+impl DeltaEncoder {
+    /// Swap a full state update for a delta; other messages pass through
+    pub fn encode(&mut self, message: WebSocketMessage) -> WebSocketMessage {
+        match message {
+            WebSocketMessage::RoomUpdated { room } => match serde_json::to_value(&room) {
+                Ok(value) => {
+                    let (base_version, version, ops) = self.room.update(value);
+                    WebSocketMessage::RoomDelta {
+                        base_version,
+                        version,
+                        ops,
+                    }
+                }
+                Err(_) => WebSocketMessage::RoomUpdated { room },
+            },
+            WebSocketMessage::GameStateUpdated { game_state } => {
+                match serde_json::to_value(&game_state) {
+                    Ok(value) => {
+                        let (base_version, version, ops) = self.game_state.update(value);
+                        WebSocketMessage::GameStateDelta {
+                            base_version,
+                            version,
+                            ops,
+                        }
+                    }
+                    Err(_) => WebSocketMessage::GameStateUpdated { game_state },
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// Start over after the client got full room state some other way
+    pub fn sent_directly(&mut self, message: &WebSocketMessage) {
+        if matches!(
+            message,
+            WebSocketMessage::RoomJoined { .. }
+                | WebSocketMessage::Resumed { .. }
+                | WebSocketMessage::RoomSnapshot { .. }
+        ) {
+            self.reset();
+        }
+    }
+
+    /// Forget what was sent, so the next updates go out in full.
+    /// Versions keep counting up.
+    pub fn reset(&mut self) {
+        self.room.value = None;
+        self.game_state.value = None;
+    }
+}
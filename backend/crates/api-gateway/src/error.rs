@@ -3,8 +3,9 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use game_engine::error::GameError;
 use serde::Serialize;
-use shared::errors::{ApiError, AuthError};
+use shared::errors::{ApiError, AuthError, ErrorCode, ProtocolError};
 
 #[derive(Debug)]
 pub struct AppError(ApiError);
@@ -22,9 +23,41 @@ impl AppError {
         AppError(ApiError::Forbidden(msg))
     }
 
+    pub fn conflict(msg: String) -> Self {
+        AppError(ApiError::Conflict(msg))
+    }
+
     pub fn unauthorized() -> Self {
         AppError(ApiError::Auth(AuthError::Unauthorized))
     }
+
+    pub fn internal() -> Self {
+        AppError(ApiError::InternalServerError)
+    }
+
+    // Statuses follow the WebSocket error codes, so both APIs agree
+    fn from_code(code: ErrorCode, message: String) -> Self {
+        match code {
+            ErrorCode::RoomNotFound | ErrorCode::GameNotFound | ErrorCode::TeamNotFound => {
+                AppError::not_found(message)
+            }
+            ErrorCode::NotAuthenticated | ErrorCode::AuthenticationFailed => {
+                AppError::unauthorized()
+            }
+            ErrorCode::NotParticipant
+            | ErrorCode::NotAdmin
+            | ErrorCode::PermissionDenied
+            | ErrorCode::SpectatorNotAllowed
+            | ErrorCode::NotExplainer => AppError::forbidden(message),
+            ErrorCode::AlreadyStarted
+            | ErrorCode::GameEnded
+            | ErrorCode::NoActiveRound
+            | ErrorCode::WordAlreadyProcessed
+            | ErrorCode::TeamFull => AppError::conflict(message),
+            ErrorCode::Internal => AppError::internal(),
+            _ => AppError::bad_request(message),
+        }
+    }
 }
 
 impl From<ApiError> for AppError {
@@ -33,6 +66,18 @@ impl From<ApiError> for AppError {
     }
 }
 
+impl From<GameError> for AppError {
+    fn from(err: GameError) -> Self {
+        AppError::from_code(err.code(), err.to_string())
+    }
+}
+
+impl From<ProtocolError> for AppError {
+    fn from(err: ProtocolError) -> Self {
+        AppError::from_code(err.code, err.message)
+    }
+}
+
 impl From<AuthError> for AppError {
     fn from(err: AuthError) -> Self {
         AppError(ApiError::Auth(err))
@@ -45,6 +90,7 @@ impl IntoResponse for AppError {
             ApiError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             ApiError::NotFound => (StatusCode::NOT_FOUND, "Not found".to_string()),
             ApiError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            ApiError::Conflict(msg) => (StatusCode::CONFLICT, msg),
             ApiError::InternalServerError => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
@@ -55,7 +101,17 @@ impl IntoResponse for AppError {
                 }
                 AuthError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token".to_string()),
                 AuthError::TokenExpired => (StatusCode::UNAUTHORIZED, "Token expired".to_string()),
+                AuthError::TokenRevoked => (StatusCode::UNAUTHORIZED, "Token revoked".to_string()),
                 AuthError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()),
+                AuthError::InvalidRequest(msg) => (StatusCode::BAD_REQUEST, msg),
+                AuthError::RateLimited => (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    "Too many requests".to_string(),
+                ),
+                AuthError::AlreadyRegistered => (
+                    StatusCode::CONFLICT,
+                    "Account is already linked to another user".to_string(),
+                ),
                 _ => (
                     StatusCode::INTERNAL_SERVER_ERROR,
                     "Authentication error".to_string(),
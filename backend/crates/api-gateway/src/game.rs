@@ -3,16 +3,19 @@ use axum::{
     response::IntoResponse,
     Extension, Json,
 };
-use game_engine::{game::GameEngine, team::TeamManager};
+use game_engine::{bot, error::GameError, game::GameEngine, team::TeamManager};
 use serde::Serialize;
 use shared::models::{
-    GameState, JoinTeamRequest, StartGameRequest, Team, User, WordActionRequest, WordResult,
+    AdjustScoreRequest, GameState, JoinTeamRequest, ModeratorPermission, StartGameRequest,
+    SubmitWordsRequest, Team, User, UserRole, WebSocketMessage, WebhookEvent, WordActionRequest,
+    WordResult,
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::info;
 
-use crate::{error::AppError, AppState};
+use crate::websocket::game as ws_game;
+use crate::{bots, error::AppError, presence, twitch_chat, webhooks, AppState};
 
 #[derive(Serialize)]
 struct GameResponse {
@@ -56,6 +59,29 @@ lazy_static::lazy_static! {
     pub static ref GAME_ENGINES: GameEngineStorage = Arc::new(RwLock::new(std::collections::HashMap::new()));
 }
 
+fn user_id(user: &User) -> String {
+    user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default()
+}
+
+/// Reject game actions from spectators
+async fn ensure_not_spectator(
+    state: &AppState,
+    room_code: &str,
+    user: &User,
+) -> Result<(), AppError> {
+    let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
+    let rooms = state.rooms.read().await;
+    if rooms
+        .get(room_code)
+        .is_some_and(|room| room.is_spectator(&user_id))
+    {
+        return Err(AppError::forbidden(
+            "Spectators cannot take game actions".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 /// Initialize game for a room
 pub async fn initialize_game(
     State(state): State<AppState>,
@@ -81,38 +107,36 @@ pub async fn initialize_game(
     // Initialize teams from room participants
     let mut team_manager = TeamManager::new();
     for participant in room.participants.values() {
-        if participant.team_id.is_some() {
-            let team_id = participant.team_id.as_ref().unwrap();
-            team_manager
-                .add_player_to_team(participant.user_id.clone(), team_id)
-                .map_err(AppError::bad_request)?;
+        if let Some(team_id) = &participant.team_id {
+            team_manager.add_player_to_team(participant.user_id.clone(), team_id)?;
         }
     }
 
     // Validate teams are ready
-    team_manager
-        .validate_for_game_start()
-        .map_err(AppError::bad_request)?;
+    team_manager.validate_for_game_start()?;
 
     // Set teams in game engine
     game_engine.team_manager = team_manager;
-    game_engine
-        .start_game()
-        .await
-        .map_err(AppError::bad_request)?;
+    game_engine.start_game().await?;
+
+    twitch_chat::connect_game(&room_code, &game_engine.game_state.settings, &user, &state).await;
 
     // Store game engine
     let mut engines = GAME_ENGINES.write().await;
+    let started = webhooks::game_started_data(&game_engine.game_state);
     engines.insert(room_code.clone(), Arc::new(RwLock::new(game_engine)));
 
     drop(rooms);
 
+    webhooks::notify_room(&state, &room_code, WebhookEvent::GameStarted, started).await;
+
     // Get updated game state
     let engines = GAME_ENGINES.read().await;
     let engine = engines.get(&room_code).unwrap();
     let engine = engine.read().await;
 
     info!("Game initialized for room {}", room_code);
+    bots::schedule_turn(&state, &room_code);
 
     Ok(Json(GameResponse {
         message: "Game started successfully".to_string(),
@@ -120,21 +144,37 @@ pub async fn initialize_game(
     }))
 }
 
-/// Get game state
+/// Get game state. Only players of the room see the secret words.
 pub async fn get_game_state(
+    State(state): State<AppState>,
     Path(room_code): Path<String>,
-    Extension(_user): Extension<User>,
+    Extension(user): Extension<User>,
 ) -> Result<impl IntoResponse, AppError> {
+    let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
+    let is_player = state
+        .rooms
+        .read()
+        .await
+        .get(&room_code)
+        .is_some_and(|room| {
+            room.participants.contains_key(&user_id) && !room.is_spectator(&user_id)
+        });
+
     let engines = GAME_ENGINES.read().await;
     let engine = engines
         .get(&room_code)
         .ok_or_else(|| AppError::not_found("Game not found for this room".to_string()))?;
 
     let engine = engine.read().await;
+    let game_state = if is_player {
+        engine.game_state.clone()
+    } else {
+        engine.game_state.redacted()
+    };
 
     Ok(Json(GameResponse {
         message: "Game state retrieved".to_string(),
-        game_state: Some(engine.game_state.clone()),
+        game_state: Some(game_state),
     }))
 }
 
@@ -156,6 +196,12 @@ pub async fn join_team(
         .get_mut(&user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default())
         .ok_or_else(|| AppError::bad_request("User not in room".to_string()))?;
 
+    if participant.role == UserRole::Spectator {
+        return Err(AppError::forbidden(
+            "Spectators cannot join teams".to_string(),
+        ));
+    }
+
     participant.team_id = Some(request.team_id.clone());
 
     drop(rooms);
@@ -164,13 +210,10 @@ pub async fn join_team(
     let engines = GAME_ENGINES.read().await;
     if let Some(engine) = engines.get(&room_code) {
         let mut engine = engine.write().await;
-        engine
-            .team_manager
-            .add_player_to_team(
-                user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default(),
-                &request.team_id,
-            )
-            .map_err(AppError::bad_request)?;
+        engine.team_manager.add_player_to_team(
+            user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default(),
+            &request.team_id,
+        )?;
 
         let teams = engine.team_manager.get_teams().to_vec();
 
@@ -250,9 +293,12 @@ pub async fn get_teams(
 
 /// Start a new round
 pub async fn start_round(
+    State(state): State<AppState>,
     Path(room_code): Path<String>,
-    Extension(_user): Extension<User>,
+    Extension(user): Extension<User>,
 ) -> Result<impl IntoResponse, AppError> {
+    ensure_not_spectator(&state, &room_code, &user).await?;
+
     let engines = GAME_ENGINES.read().await;
     let engine = engines
         .get(&room_code)
@@ -260,19 +306,31 @@ pub async fn start_round(
 
     let mut engine = engine.write().await;
 
-    // Check if user is the current explainer
-    let _current_team = engine
-        .game_state
-        .teams
-        .get(engine.game_state.current_team_index)
-        .ok_or_else(|| AppError::bad_request("Invalid team index".to_string()))?;
+    // The next explainer starts their round; admin and moderators can too
+    if engine.next_explainer()? != user_id(&user) {
+        let rooms = state.rooms.read().await;
+        let room = rooms
+            .get(&room_code)
+            .ok_or_else(|| AppError::not_found("Room not found".to_string()))?;
+        if !room.has_permission(&user_id(&user), ModeratorPermission::StartRound) {
+            return Err(AppError::forbidden(
+                "Only the next explainer, admin or moderators can start the round".to_string(),
+            ));
+        }
+    }
 
-    let round = engine.start_round().await.map_err(AppError::bad_request)?;
+    let round = engine.start_round().await?;
 
     info!(
         "Round {} started for room {} by team {}",
         round.round_number, room_code, round.team_id
     );
+    bots::round_started(&state, &room_code, &round).await;
+    drop(engine);
+    drop(engines);
+
+    // A teammate stands in if the explainer is already gone
+    presence::check_explainer(&state, &room_code).await;
 
     Ok(Json(RoundResponse {
         message: "Round started".to_string(),
@@ -300,7 +358,7 @@ pub async fn get_current_word(
         .game_state
         .current_round
         .as_ref()
-        .ok_or_else(|| AppError::bad_request("No active round".to_string()))?;
+        .ok_or(GameError::NoActiveRound)?;
 
     if round.explainer_id != user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default() {
         return Err(AppError::forbidden(
@@ -308,9 +366,7 @@ pub async fn get_current_word(
         ));
     }
 
-    let word = engine
-        .get_current_word()
-        .ok_or_else(|| AppError::bad_request("No more words in this round".to_string()))?;
+    let word = engine.get_current_word().ok_or(GameError::NoCurrentWord)?;
 
     Ok(Json(WordResponse {
         word: word.word.clone(),
@@ -325,6 +381,12 @@ pub async fn submit_word_result(
     Extension(user): Extension<User>,
     Json(request): Json<WordActionRequest>,
 ) -> Result<impl IntoResponse, AppError> {
+    if request.word_result == WordResult::Stolen {
+        return Err(AppError::bad_request(
+            "Only the audience can steal words".to_string(),
+        ));
+    }
+
     let engines = GAME_ENGINES.read().await;
     let engine = engines
         .get(&room_code)
@@ -339,9 +401,17 @@ pub async fn submit_word_result(
             .game_state
             .current_round
             .as_ref()
-            .ok_or_else(|| AppError::bad_request("No active round".to_string()))?;
-
-        if round.explainer_id != user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default() {
+            .ok_or(GameError::NoActiveRound)?;
+
+        // Teammates of a bot explainer say when they got the word
+        let guessed_for_bot = request.word_result == WordResult::Correct
+            && bot::is_bot(&round.explainer_id)
+            && engine
+                .team_manager
+                .get_teams()
+                .iter()
+                .any(|t| t.id == round.team_id && t.players.contains(&user_id(&user)));
+        if round.explainer_id != user_id(&user) && !guessed_for_bot {
             return Err(AppError::forbidden(
                 "Only the explainer can submit word results".to_string(),
             ));
@@ -349,16 +419,14 @@ pub async fn submit_word_result(
         round.team_id.clone()
     };
 
-    let score_change = engine
-        .process_word_result(request.word_result)
-        .map_err(AppError::bad_request)?;
+    let score_change = engine.process_word_result(request.word_result)?;
 
     let team = engine
         .game_state
         .teams
         .iter()
         .find(|t| t.id == team_id)
-        .ok_or_else(|| AppError::bad_request("Team not found".to_string()))?;
+        .ok_or_else(|| GameError::TeamNotFound(team_id.clone()))?;
 
     Ok(Json(ScoreResponse {
         score_change,
@@ -367,12 +435,45 @@ pub async fn submit_word_result(
             WordResult::Correct => "Word guessed correctly!".to_string(),
             WordResult::Skipped => "Word skipped".to_string(),
             WordResult::Penalty => "Penalty applied".to_string(),
+            WordResult::Stolen => "Word stolen by the audience".to_string(),
+        },
+    }))
+}
+
+/// Secretly submit words for the Hat mode
+pub async fn submit_hat_words(
+    State(state): State<AppState>,
+    Path(room_code): Path<String>,
+    Extension(user): Extension<User>,
+    Json(request): Json<SubmitWordsRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    ensure_not_spectator(&state, &room_code, &user).await?;
+
+    let engines = GAME_ENGINES.read().await;
+    let engine = engines
+        .get(&room_code)
+        .ok_or_else(|| AppError::not_found("Game not found for this room".to_string()))?;
+
+    let mut engine = engine.write().await;
+
+    // Announced like words submitted over WebSocket
+    let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
+    let began =
+        ws_game::submit_words(&mut engine, &user_id, request.words, &room_code, &state).await?;
+
+    Ok(Json(GameResponse {
+        message: if began {
+            "Words submitted. All players are ready, the hat is full".to_string()
+        } else {
+            "Words submitted".to_string()
         },
+        game_state: Some(engine.game_state.clone()),
     }))
 }
 
 /// End current round
 pub async fn end_round(
+    State(state): State<AppState>,
     Path(room_code): Path<String>,
     Extension(user): Extension<User>,
 ) -> Result<impl IntoResponse, AppError> {
@@ -388,14 +489,25 @@ pub async fn end_round(
         .game_state
         .current_round
         .as_ref()
-        .ok_or_else(|| AppError::bad_request("No active round".to_string()))?;
-
-    if round.explainer_id != user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default() {
-        // Check if admin
-        // TODO: Add admin check from room state
+        .ok_or(GameError::NoActiveRound)?;
+
+    let user_id = user_id(&user);
+    if round.explainer_id != user_id {
+        let is_admin = state
+            .rooms
+            .read()
+            .await
+            .get(&room_code)
+            .is_some_and(|room| room.admin_id == user_id);
+        if !is_admin {
+            return Err(AppError::forbidden(
+                "Only explainer or admin can end round".to_string(),
+            ));
+        }
     }
 
-    let round = engine.end_round().map_err(AppError::bad_request)?;
+    // Announced and reported to webhooks like rounds ended over WebSocket
+    let round = ws_game::end_round(&mut engine, &room_code, &state).await?;
 
     info!(
         "Round {} ended for room {}. Team {} scored {} points",
@@ -408,21 +520,75 @@ pub async fn end_round(
     }))
 }
 
+/// Correct a team's score by hand (admin or moderators)
+pub async fn adjust_score(
+    State(state): State<AppState>,
+    Path(room_code): Path<String>,
+    Extension(user): Extension<User>,
+    Json(request): Json<AdjustScoreRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let rooms = state.rooms.read().await;
+    let room = rooms
+        .get(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".to_string()))?;
+
+    if !room.has_permission(&user_id(&user), ModeratorPermission::AdjustScore) {
+        return Err(AppError::forbidden(
+            "Only room admin or moderators can adjust scores".to_string(),
+        ));
+    }
+    drop(rooms);
+
+    let engines = GAME_ENGINES.read().await;
+    let engine = engines
+        .get(&room_code)
+        .ok_or_else(|| AppError::not_found("Game not found for this room".to_string()))?;
+    let mut engine = engine.write().await;
+
+    engine.adjust_score(&request.team_id, request.delta)?;
+    let teams = engine.game_state.teams.clone();
+    drop(engine);
+    drop(engines);
+
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            &room_code,
+            WebSocketMessage::TeamsUpdated {
+                teams: teams.clone(),
+            },
+        )
+        .await;
+
+    info!(
+        "User {} adjusted score of team {} by {} in room {}",
+        user_id(&user),
+        request.team_id,
+        request.delta,
+        room_code
+    );
+
+    Ok(Json(TeamResponse {
+        message: "Score adjusted".to_string(),
+        teams,
+    }))
+}
+
 /// Pause the game
 pub async fn pause_game(
     State(state): State<AppState>,
     Path(room_code): Path<String>,
     Extension(user): Extension<User>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Check if user is admin
+    // Check if user is admin or a moderator allowed to pause
     let rooms = state.rooms.read().await;
     let room = rooms
         .get(&room_code)
         .ok_or_else(|| AppError::not_found("Room not found".to_string()))?;
 
-    if room.admin_id != user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default() {
+    if !room.has_permission(&user_id(&user), ModeratorPermission::Pause) {
         return Err(AppError::forbidden(
-            "Only room admin can pause the game".to_string(),
+            "Only room admin or moderators can pause the game".to_string(),
         ));
     }
 
@@ -434,7 +600,7 @@ pub async fn pause_game(
         .ok_or_else(|| AppError::not_found("Game not found for this room".to_string()))?;
 
     let mut engine = engine.write().await;
-    engine.pause_game().map_err(AppError::bad_request)?;
+    engine.pause_game()?;
 
     Ok(Json(GameResponse {
         message: "Game paused".to_string(),
@@ -448,15 +614,15 @@ pub async fn resume_game(
     Path(room_code): Path<String>,
     Extension(user): Extension<User>,
 ) -> Result<impl IntoResponse, AppError> {
-    // Check if user is admin
+    // Check if user is admin or a moderator allowed to pause
     let rooms = state.rooms.read().await;
     let room = rooms
         .get(&room_code)
         .ok_or_else(|| AppError::not_found("Room not found".to_string()))?;
 
-    if room.admin_id != user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default() {
+    if !room.has_permission(&user_id(&user), ModeratorPermission::Pause) {
         return Err(AppError::forbidden(
-            "Only room admin can resume the game".to_string(),
+            "Only room admin or moderators can resume the game".to_string(),
         ));
     }
 
@@ -468,7 +634,7 @@ pub async fn resume_game(
         .ok_or_else(|| AppError::not_found("Game not found for this room".to_string()))?;
 
     let mut engine = engine.write().await;
-    engine.resume_game().map_err(AppError::bad_request)?;
+    engine.resume_game()?;
 
     Ok(Json(GameResponse {
         message: "Game resumed".to_string(),
@@ -1,26 +1,40 @@
 use auth_service::AuthService;
 use axum::{
-    extract::State,
-    http::HeaderMap,
+    extract::{ConnectInfo, Extension, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
     middleware::from_fn_with_state,
     response::IntoResponse,
-    routing::{get, post},
+    routing::{delete, get, post, put},
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
 use shared::errors::AuthError;
-use shared::models::{GameRoom, LoginRequest, LoginResponse};
+use shared::models::{
+    AuthTokens, GameRoom, GuestLoginRequest, JwtClaims, LoginRequest, LoginResponse,
+    RefreshRequest, User, UserInfo,
+};
 use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tower_http::cors::{Any, CorsLayer};
 
 pub mod auth_middleware;
+pub mod bans;
+pub mod bots;
+pub mod delta;
 pub mod error;
 pub mod game;
+pub mod metrics;
+pub mod overlay;
+pub mod presence;
+pub mod protocol;
+pub mod room_access;
 pub mod rooms;
 #[cfg(debug_assertions)]
 mod test_utils;
+pub mod twitch_chat;
+pub mod webhooks;
 pub mod websocket;
 
 #[derive(Clone)]
@@ -32,6 +46,8 @@ pub struct AppState {
     pub auth_service: Arc<AuthService>,
     pub rooms: Arc<RwLock<HashMap<String, GameRoom>>>,
     pub websocket_manager: Arc<websocket::WebSocketManager>,
+    pub webhook_manager: Arc<webhooks::WebhookManager>,
+    pub bans: Arc<bans::BanStore>,
 }
 
 #[derive(Serialize)]
@@ -45,14 +61,41 @@ pub fn create_router(app_state: AppState) -> Router {
     // Build the router
     let app = Router::new()
         .route("/health", get(health_check))
+        .route("/metrics", get(metrics::metrics))
+        .route("/.well-known/jwks.json", get(jwks))
+        .route("/api/v1/auth/providers", get(list_providers))
+        // Twitch sign-in, kept for existing clients
         .route("/api/v1/auth/login", get(login))
         .route("/api/v1/auth/callback", post(auth_callback))
+        .route("/api/v1/auth/:provider/login", get(provider_login))
+        .route("/api/v1/auth/:provider/callback", post(provider_callback))
+        .route("/api/v1/auth/guest", post(guest_login))
+        .route("/api/v1/auth/test-login", post(test_login))
+        .route(
+            "/api/v1/auth/:provider/link",
+            post(link_identity)
+                .delete(unlink_identity)
+                .route_layer(from_fn_with_state(
+                    app_state.clone(),
+                    auth_middleware::auth_middleware,
+                )),
+        )
+        .route("/api/v1/auth/refresh", post(refresh))
         .route("/api/v1/auth/logout", post(logout))
-        .route("/api/v1/auth/me", get(get_current_user))
+        .route(
+            "/api/v1/auth/me",
+            get(get_current_user).delete(delete_account),
+        )
         .route("/ws", get(websocket::websocket_handler))
         // Public room routes (no auth required)
         .route("/api/v1/rooms", get(rooms::list_rooms))
-        .route("/api/v1/rooms/:room_code", get(rooms::get_room));
+        .route("/api/v1/rooms/:room_code", get(rooms::get_room))
+        // Overlay routes (overlay token in the query string)
+        .route("/api/v1/overlay/:room_code", get(overlay::get_snapshot))
+        .route(
+            "/api/v1/overlay/:room_code/events",
+            get(overlay::stream_events),
+        );
 
     // Add test route in debug mode
     #[cfg(debug_assertions)]
@@ -68,8 +111,23 @@ pub fn create_router(app_state: AppState) -> Router {
             Router::new()
                 .route("/", post(rooms::create_room))
                 .route("/:room_code/join", post(rooms::join_room))
+                .route("/:room_code/spectate", post(rooms::spectate_room))
                 .route("/:room_code/leave", post(rooms::leave_room))
                 .route("/:room_code/kick/:player_id", post(rooms::kick_player))
+                .route("/:room_code/bots", post(bots::add_bot))
+                .route("/:room_code/invites", post(rooms::create_invite))
+                .route(
+                    "/:room_code/bans",
+                    post(rooms::ban_player).get(rooms::list_room_bans),
+                )
+                .route("/:room_code/bans/:user_id", delete(rooms::unban_player))
+                .route("/:room_code/roles/:user_id", put(rooms::update_role))
+                .route("/:room_code/transfer-admin", post(rooms::transfer_admin))
+                .route(
+                    "/:room_code/moderator-permissions",
+                    put(rooms::update_moderator_permissions),
+                )
+                .route("/:room_code/overlay-token", post(overlay::rotate_token))
                 .route_layer(from_fn_with_state(
                     app_state.clone(),
                     auth_middleware::auth_middleware,
@@ -88,6 +146,8 @@ pub fn create_router(app_state: AppState) -> Router {
                 .route("/:room_code/round/end", post(game::end_round))
                 .route("/:room_code/word/current", get(game::get_current_word))
                 .route("/:room_code/word/result", post(game::submit_word_result))
+                .route("/:room_code/hat/words", post(game::submit_hat_words))
+                .route("/:room_code/score", post(game::adjust_score))
                 .route("/:room_code/pause", post(game::pause_game))
                 .route("/:room_code/resume", post(game::resume_game))
                 .route("/:room_code/reset", post(game::reset_game))
@@ -96,6 +156,33 @@ pub fn create_router(app_state: AppState) -> Router {
                     auth_middleware::auth_middleware,
                 )),
         )
+        // Streamer ban lists (auth required)
+        .nest(
+            "/api/v1/bans",
+            Router::new()
+                .route("/", post(bans::create_ban).get(bans::list_bans))
+                .route("/:user_id", delete(bans::delete_ban))
+                .route_layer(from_fn_with_state(
+                    app_state.clone(),
+                    auth_middleware::auth_middleware,
+                )),
+        )
+        // Webhook subscriptions (auth required)
+        .nest(
+            "/api/v1/webhooks",
+            Router::new()
+                .route(
+                    "/",
+                    post(webhooks::create_webhook).get(webhooks::list_webhooks),
+                )
+                .route("/:webhook_id", delete(webhooks::delete_webhook))
+                .route("/:webhook_id/deliveries", get(webhooks::list_deliveries))
+                .route("/:webhook_id/test", post(webhooks::test_webhook))
+                .route_layer(from_fn_with_state(
+                    app_state.clone(),
+                    auth_middleware::auth_middleware,
+                )),
+        )
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
@@ -112,52 +199,187 @@ async fn health_check() -> impl IntoResponse {
     })
 }
 
+/// Public keys other services use to verify access tokens
+pub async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        // Short enough for verifiers to pick up the next key before rotation
+        [(header::CACHE_CONTROL, "public, max-age=300")],
+        Json(state.auth_service.jwks()),
+    )
+}
+
 use error::AppError;
 
 #[derive(Deserialize)]
-#[allow(dead_code)]
-struct AuthCallbackQuery {
-    code: String,
-    state: Option<String>,
-}
-
-async fn login() -> impl IntoResponse {
-    // This endpoint will be used to initiate the OAuth flow
-    // For now, return the Twitch OAuth URL
-    let client_id = std::env::var("TWITCH_CLIENT_ID").unwrap_or_default();
-    let redirect_uri = std::env::var("TWITCH_REDIRECT_URI")
-        .unwrap_or_else(|_| "http://localhost:4200/auth/callback".to_string());
-
-    let auth_url = format!(
-        "https://id.twitch.tv/oauth2/authorize?client_id={}&redirect_uri={}&response_type=code&scope=user:read:email&force_verify=true",
-        client_id,
-        urlencoding::encode(&redirect_uri)
-    );
+pub struct LoginQuery {
+    redirect_uri: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct LoginUrlResponse {
+    pub auth_url: String,
+}
 
+/// Identity providers users can sign in with
+pub async fn list_providers(State(state): State<AppState>) -> impl IntoResponse {
     #[derive(Serialize)]
-    struct LoginUrlResponse {
-        auth_url: String,
+    struct ProvidersResponse {
+        providers: Vec<String>,
     }
 
-    Json(LoginUrlResponse { auth_url })
+    Json(ProvidersResponse {
+        providers: state.auth_service.provider_names(),
+    })
+}
+
+async fn login(
+    State(state): State<AppState>,
+    Query(query): Query<LoginQuery>,
+) -> Result<Json<LoginUrlResponse>, AppError> {
+    provider_login(State(state), Path("twitch".to_string()), Query(query)).await
 }
 
 async fn auth_callback(
     State(state): State<AppState>,
     Json(request): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    provider_callback(State(state), Path("twitch".to_string()), Json(request)).await
+}
+
+/// OAuth URL to start signing in with a provider. The redirect URI defaults
+/// to `<PROVIDER>_REDIRECT_URI`.
+pub async fn provider_login(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Query(query): Query<LoginQuery>,
+) -> Result<Json<LoginUrlResponse>, AppError> {
+    let redirect_uri = query.redirect_uri.unwrap_or_else(|| {
+        std::env::var(format!("{}_REDIRECT_URI", provider.to_uppercase()))
+            .unwrap_or_else(|_| "http://localhost:4200/auth/callback".to_string())
+    });
+
+    let auth_url = state
+        .auth_service
+        .authorize_url(&provider, &redirect_uri)
+        .map_err(AppError::from)?;
+
+    Ok(Json(LoginUrlResponse { auth_url }))
+}
+
+/// Finish signing in with a provider's authorization code
+pub async fn provider_callback(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Json(request): Json<LoginRequest>,
 ) -> Result<Json<LoginResponse>, AppError> {
     state
         .auth_service
-        .login(request)
+        .login(&provider, request)
         .await
         .map(Json)
         .map_err(AppError::from)
 }
 
-async fn get_current_user(
+/// Sign in as a temporary guest with a chosen display name
+pub async fn guest_login(
     State(state): State<AppState>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
     headers: HeaderMap,
-) -> Result<impl IntoResponse, AppError> {
+    Json(request): Json<GuestLoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let client_ip = client_ip(&headers, connect_info.map(|ConnectInfo(addr)| addr));
+    state
+        .auth_service
+        .guest_login(request, client_ip)
+        .await
+        .map(Json)
+        .map_err(AppError::from)
+}
+
+/// Sign in a synthetic guest for a load test, with the server's test token
+/// secret in the `X-Test-Token-Secret` header. Refused unless the server runs
+/// with `LOADTEST_TOKEN_SECRET`.
+pub async fn test_login(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<GuestLoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let secret = headers
+        .get("x-test-token-secret")
+        .and_then(|value| value.to_str().ok())
+        .ok_or(AppError::from(AuthError::Unauthorized))?;
+    state
+        .auth_service
+        .test_login(secret, request)
+        .await
+        .map(Json)
+        .map_err(AppError::from)
+}
+
+/// The address a request came from. Behind a reverse proxy that sets
+/// X-Forwarded-For (TRUST_PROXY=true) that is the last address it added.
+fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+    if std::env::var("TRUST_PROXY").is_ok_and(|v| v == "true") {
+        return headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+    }
+    peer.map(|addr| addr.ip())
+}
+
+/// Link a provider account to the signed-in user. Guests become registered
+/// users this way.
+pub async fn link_identity(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Extension(user): Extension<User>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, AppError> {
+    let response = state
+        .auth_service
+        .link_identity(&user, &claims, &provider, request)
+        .await
+        .map_err(AppError::from)?;
+
+    tracing::info!("User {} linked {} account", response.user.id, provider);
+
+    Ok(Json(response))
+}
+
+/// Unlink a provider account from the signed-in user
+pub async fn unlink_identity(
+    State(state): State<AppState>,
+    Path(provider): Path<String>,
+    Extension(user): Extension<User>,
+) -> Result<Json<UserInfo>, AppError> {
+    let user = state
+        .auth_service
+        .unlink_identity(&user, &provider)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(Json(user.into()))
+}
+
+/// Exchange a refresh token for a new token pair; the old refresh token
+/// stops working
+pub async fn refresh(
+    State(state): State<AppState>,
+    Json(request): Json<RefreshRequest>,
+) -> Result<Json<AuthTokens>, AppError> {
+    state
+        .auth_service
+        .refresh(&request.refresh_token)
+        .await
+        .map(Json)
+        .map_err(AppError::from)
+}
+
+/// Bearer token of a request, checked against the revocation list
+async fn authenticate_header(state: &AppState, headers: &HeaderMap) -> Result<JwtClaims, AppError> {
     let auth_header = headers
         .get("Authorization")
         .and_then(|h| h.to_str().ok())
@@ -167,34 +389,70 @@ async fn get_current_user(
         .strip_prefix("Bearer ")
         .ok_or(AppError::from(AuthError::InvalidToken))?;
 
-    let claims = state
+    state
         .auth_service
-        .verify_token(token)
-        .map_err(AppError::from)?;
+        .authenticate(token)
+        .await
+        .map_err(AppError::from)
+}
+
+async fn get_current_user(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = authenticate_header(&state, &headers).await?;
 
     #[derive(Serialize)]
     struct UserResponse {
         id: String,
         username: String,
-        twitch_id: String,
     }
 
     Ok(Json(UserResponse {
         id: claims.sub,
         username: claims.username,
-        twitch_id: claims.twitch_id,
     }))
 }
 
-async fn logout() -> impl IntoResponse {
+/// Revoke the caller's session: its access and refresh tokens stop working
+pub async fn logout(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
     #[derive(Serialize)]
     struct LogoutResponse {
         success: bool,
         message: String,
     }
 
-    Json(LogoutResponse {
+    let claims = authenticate_header(&state, &headers).await?;
+    state
+        .auth_service
+        .logout(&claims)
+        .await
+        .map_err(AppError::from)?;
+
+    tracing::info!("User {} logged out", claims.username);
+
+    Ok(Json(LogoutResponse {
         success: true,
         message: "Logged out successfully".to_string(),
-    })
+    }))
+}
+
+/// Delete the caller's account; their Twitch access is revoked too
+pub async fn delete_account(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<StatusCode, AppError> {
+    let claims = authenticate_header(&state, &headers).await?;
+    state
+        .auth_service
+        .delete_account(&claims)
+        .await
+        .map_err(AppError::from)?;
+
+    tracing::info!("User {} deleted their account", claims.username);
+
+    Ok(StatusCode::NO_CONTENT)
 }
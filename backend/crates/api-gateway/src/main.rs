@@ -1,12 +1,27 @@
-use auth_service::AuthService;
+use auth_service::{
+    discord::DiscordProvider,
+    keys::{KeyRing, SigningKey},
+    oidc::OidcProvider,
+    provider::IdentityProviders,
+    session::SessionStore,
+    token_store::{TokenCipher, TokenStore},
+    twitch::TwitchClient,
+    twitch_tokens::{self, TwitchTokens},
+    AuthService,
+};
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use api_gateway::{create_router, websocket::WebSocketManager, AppState};
+use api_gateway::{
+    bans::BanStore, create_router, webhooks::WebhookManager, websocket::WebSocketManager, AppState,
+};
+
+// Test sign-in skips the guest limit, so its secret must not be guessable
+const MIN_TEST_TOKEN_SECRET_LEN: usize = 16;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -41,21 +56,69 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let db = mongo_client.database("alias_game");
 
     // Initialize Auth Service
-    let jwt_secret =
-        std::env::var("JWT_SECRET").expect("JWT_SECRET environment variable must be set");
+    let (signing_keys, rotation_interval) = signing_keys();
     let twitch_client_id = std::env::var("TWITCH_CLIENT_ID")
         .expect("TWITCH_CLIENT_ID environment variable must be set");
     let twitch_client_secret = std::env::var("TWITCH_CLIENT_SECRET")
         .expect("TWITCH_CLIENT_SECRET environment variable must be set");
+    let providers = identity_providers(&twitch_client_id, &twitch_client_secret).await;
 
     info!("Starting API Gateway with environment variables configured");
 
-    let auth_service = Arc::new(AuthService::new(
+    let mut auth_service = AuthService::new(
         &db,
-        &jwt_secret,
-        twitch_client_id,
-        twitch_client_secret,
-    ));
+        signing_keys.clone(),
+        providers,
+        SessionStore::redis((*redis_client).clone()),
+    );
+    // Keep Twitch tokens for API calls on users' behalf, encrypted at rest
+    match std::env::var("TOKEN_ENCRYPTION_KEY") {
+        Ok(key) => {
+            let cipher = TokenCipher::from_base64(&key)
+                .expect("TOKEN_ENCRYPTION_KEY must be a base64-encoded 32-byte key");
+            auth_service = auth_service.with_twitch_tokens(TwitchTokens::new(
+                TwitchClient::new(twitch_client_id, twitch_client_secret),
+                TokenStore::mongo(&db, cipher),
+            ));
+        }
+        Err(_) => warn!("TOKEN_ENCRYPTION_KEY not set, Twitch tokens won't be stored"),
+    }
+    // Players behind one NAT share an address, so the guest limit may need raising
+    if let Ok(limit) = std::env::var("GUESTS_PER_ADDRESS") {
+        let limit = limit
+            .parse()
+            .expect("GUESTS_PER_ADDRESS must be a number of guests per hour");
+        auth_service = auth_service.with_guest_limit(limit);
+    }
+    // Load tests sign in their players with a shared secret instead of
+    // running into the per-address guest limit
+    if let Ok(secret) = std::env::var("LOADTEST_TOKEN_SECRET") {
+        assert!(
+            secret.len() >= MIN_TEST_TOKEN_SECRET_LEN,
+            "LOADTEST_TOKEN_SECRET must be at least {} characters",
+            MIN_TEST_TOKEN_SECRET_LEN
+        );
+        warn!(
+            "LOADTEST_TOKEN_SECRET is set; test sign-in is enabled, don't use this in production"
+        );
+        auth_service = auth_service.with_test_tokens(secret);
+    }
+    let auth_service = Arc::new(auth_service);
+    if let Err(e) = auth_service.ensure_indexes().await {
+        warn!("Failed to create user indexes: {}", e);
+    }
+
+    let bans = Arc::new(BanStore::mongo(&db));
+    if let Err(e) = bans.ensure_indexes().await {
+        warn!("Failed to create ban indexes: {}", e);
+    }
+
+    // Webhooks may only reach public addresses, unless allowed for local development
+    let mut webhook_manager = WebhookManager::new();
+    if std::env::var("WEBHOOK_ALLOW_PRIVATE_ADDRESSES").is_ok_and(|v| v == "true") {
+        warn!("Webhooks may target private addresses");
+        webhook_manager = webhook_manager.allowing_private_addresses();
+    }
 
     // Create app state
     let app_state = AppState {
@@ -64,10 +127,47 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         auth_service,
         rooms: Arc::new(RwLock::new(HashMap::new())),
         websocket_manager: Arc::new(WebSocketManager::new()),
+        webhook_manager: Arc::new(webhook_manager),
+        bans,
     };
 
     let app = create_router(app_state.clone());
 
+    // Rotate the shared JWT signing keys when due, and pick up rotations
+    // done by other instances
+    if signing_keys.is_shared() {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(60));
+            loop {
+                interval.tick().await;
+                // Key files and the rotation lock are blocking file I/O
+                let signing_keys = signing_keys.clone();
+                let checked = tokio::task::spawn_blocking(move || {
+                    if let Some(max_age) = rotation_interval {
+                        match signing_keys.rotate_if_older(max_age) {
+                            Ok(Some(kid)) => {
+                                info!("Rotated JWT signing key, now signing with {}", kid)
+                            }
+                            Ok(None) => {}
+                            Err(e) => warn!("Failed to rotate JWT signing key: {}", e),
+                        }
+                    }
+                    match signing_keys.refresh() {
+                        Ok(true) => {
+                            info!("Now signing with JWT key {}", signing_keys.current().kid())
+                        }
+                        Ok(false) => {}
+                        Err(e) => warn!("Failed to reload JWT signing keys: {}", e),
+                    }
+                })
+                .await;
+                if let Err(e) = checked {
+                    warn!("JWT key rotation check failed: {}", e);
+                }
+            }
+        });
+    }
+
     // Spawn background task to clean up abandoned rooms periodically
     let cleanup_state = app_state.clone();
     tokio::spawn(async move {
@@ -80,12 +180,122 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
+    // Validate stored Twitch tokens hourly, as Twitch requires
+    let validation_service = app_state.auth_service.clone();
+    tokio::spawn(async move {
+        let period = twitch_tokens::VALIDATION_INTERVAL
+            .to_std()
+            .expect("positive interval");
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            if let Err(e) = validation_service.validate_twitch_tokens().await {
+                warn!("Twitch token validation failed: {}", e);
+            }
+        }
+    });
+
     // Start the server
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
     info!("API Gateway listening on {}", addr);
 
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Peer addresses limit how many guests one client creates
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
+
+/// Sign-in providers from the environment. Twitch is required; Discord and a
+/// generic OpenID Connect provider are enabled when configured.
+async fn identity_providers(
+    twitch_client_id: &str,
+    twitch_client_secret: &str,
+) -> IdentityProviders {
+    let mut providers = IdentityProviders::new().with(TwitchClient::new(
+        twitch_client_id.to_string(),
+        twitch_client_secret.to_string(),
+    ));
+
+    if let (Ok(client_id), Ok(client_secret)) = (
+        std::env::var("DISCORD_CLIENT_ID"),
+        std::env::var("DISCORD_CLIENT_SECRET"),
+    ) {
+        providers = providers.with(DiscordProvider::new(client_id, client_secret));
+    }
+
+    if let (Ok(issuer), Ok(client_id), Ok(client_secret)) = (
+        std::env::var("OIDC_ISSUER"),
+        std::env::var("OIDC_CLIENT_ID"),
+        std::env::var("OIDC_CLIENT_SECRET"),
+    ) {
+        let name = std::env::var("OIDC_PROVIDER_NAME").unwrap_or_else(|_| "oidc".to_string());
+        match OidcProvider::discover(&name, &issuer, client_id, client_secret).await {
+            Ok(provider) => providers = providers.with(provider),
+            Err(e) => warn!("Failed to configure OIDC provider {}: {}", name, e),
+        }
+    }
+
+    info!("Identity providers: {}", providers.names().join(", "));
+    providers
+}
+
+/// JWT signing keys and how old the signing key may get before rotating.
+/// `JWT_KEY_DIR` is a directory every instance shares; keys are created there
+/// on first start and rotated every `JWT_KEY_ROTATION_HOURS` (default 24,
+/// 0 turns rotation off). `JWT_PRIVATE_KEY_FILE` (Ed25519 or RSA PEM) pins a
+/// single key instead. With neither, a debug build generates a key for this
+/// process only, so tokens won't survive a restart or verify on other
+/// instances; a release build refuses to start.
+fn signing_keys() -> (Arc<KeyRing>, Option<chrono::Duration>) {
+    let rotation_hours = std::env::var("JWT_KEY_ROTATION_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse().ok());
+    let overlap_minutes = std::env::var("JWT_KEY_OVERLAP_MINUTES")
+        .ok()
+        .and_then(|minutes| minutes.parse().ok())
+        .unwrap_or(60);
+
+    let keys = match (
+        std::env::var("JWT_KEY_DIR"),
+        std::env::var("JWT_PRIVATE_KEY_FILE"),
+    ) {
+        (Ok(dir), _) => KeyRing::load(&dir)
+            .unwrap_or_else(|e| panic!("Failed to load JWT keys from JWT_KEY_DIR {}: {}", dir, e)),
+        (Err(_), Ok(path)) => {
+            let pem = std::fs::read_to_string(&path)
+                .unwrap_or_else(|e| panic!("Failed to read JWT_PRIVATE_KEY_FILE {}: {}", path, e));
+            KeyRing::new(
+                SigningKey::from_pem(&pem)
+                    .expect("JWT_PRIVATE_KEY_FILE is not a valid signing key"),
+            )
+        }
+        (Err(_), Err(_)) => {
+            if !cfg!(debug_assertions) {
+                panic!(
+                    "Set JWT_KEY_DIR (or JWT_PRIVATE_KEY_FILE) so access tokens survive restarts"
+                );
+            }
+            warn!("JWT_KEY_DIR is not set; access tokens are signed with a key only this process knows");
+            KeyRing::new(SigningKey::generate().expect("Failed to generate JWT signing key"))
+        }
+    };
+    info!("Signing access tokens with key {}", keys.current().kid());
+
+    let rotation_hours = if keys.is_shared() {
+        rotation_hours.unwrap_or(24)
+    } else {
+        if rotation_hours.is_some_and(|hours| hours > 0) {
+            warn!("Ignoring JWT_KEY_ROTATION_HOURS: rotating keys needs JWT_KEY_DIR");
+        }
+        0
+    };
+    let keys = keys.with_overlap(chrono::Duration::minutes(overlap_minutes));
+    let rotation_interval = (rotation_hours > 0).then(|| chrono::Duration::hours(rotation_hours));
+
+    (Arc::new(keys), rotation_interval)
+}
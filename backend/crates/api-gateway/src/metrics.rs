@@ -0,0 +1,65 @@
+//! Counters for the WebSocket layer, served as JSON from `/metrics`.
+
+use axum::{extract::State, response::Json};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::AppState;
+
+#[derive(Default)]
+pub struct WebSocketMetrics {
+    open_connections: AtomicU64,
+    lag_events: AtomicU64,
+    lagged_messages: AtomicU64,
+    resyncs: AtomicU64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebSocketMetricsSnapshot {
+    pub open_connections: u64,
+    /// Times a connection fell behind a room or lobby channel
+    pub lag_events: u64,
+    /// Broadcasts those connections skipped
+    pub lagged_messages: u64,
+    /// Snapshots sent to bring a lagging connection back in sync
+    pub resyncs: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MetricsResponse {
+    pub websocket: WebSocketMetricsSnapshot,
+}
+
+impl WebSocketMetrics {
+    pub fn connection_opened(&self) {
+        self.open_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn connection_closed(&self) {
+        self.open_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn record_lag(&self, skipped: u64) {
+        self.lag_events.fetch_add(1, Ordering::Relaxed);
+        self.lagged_messages.fetch_add(skipped, Ordering::Relaxed);
+    }
+
+    pub fn record_resync(&self) {
+        self.resyncs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> WebSocketMetricsSnapshot {
+        WebSocketMetricsSnapshot {
+            open_connections: self.open_connections.load(Ordering::Relaxed),
+            lag_events: self.lag_events.load(Ordering::Relaxed),
+            lagged_messages: self.lagged_messages.load(Ordering::Relaxed),
+            resyncs: self.resyncs.load(Ordering::Relaxed),
+        }
+    }
+}
+
+pub async fn metrics(State(state): State<AppState>) -> Json<MetricsResponse> {
+    Json(MetricsResponse {
+        websocket: state.websocket_manager.metrics.snapshot(),
+    })
+}
@@ -0,0 +1,203 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        Json,
+    },
+};
+use futures_util::stream::{self, Stream};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::Deserialize;
+use std::convert::Infallible;
+use tokio::select;
+use tokio::sync::broadcast::error::RecvError;
+
+use shared::models::{OverlayRound, OverlaySnapshot, OverlayTokenResponse, User};
+
+use crate::error::AppError;
+use crate::game::GAME_ENGINES;
+use crate::AppState;
+
+const OVERLAY_TOKEN_LEN: usize = 32;
+
+#[derive(Deserialize)]
+pub struct OverlayQuery {
+    token: Option<String>,
+}
+
+/// Generate a random overlay token
+pub fn generate_overlay_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(OVERLAY_TOKEN_LEN)
+        .map(char::from)
+        .collect()
+}
+
+/// Compare tokens without exiting early on the first mismatch
+fn tokens_match(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected
+            .bytes()
+            .zip(given.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Check the overlay token of a room (browser sources can't send headers,
+/// so it comes in the query string)
+async fn authorize(
+    state: &AppState,
+    room_code: &str,
+    query: &OverlayQuery,
+) -> Result<(), AppError> {
+    let rooms = state.rooms.read().await;
+    let room = rooms
+        .get(room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    match &query.token {
+        Some(token)
+            if !room.overlay_token.is_empty() && tokens_match(&room.overlay_token, token) =>
+        {
+            Ok(())
+        }
+        _ => Err(AppError::unauthorized()),
+    }
+}
+
+/// Current scores, timer and explainer for the overlay's initial render
+pub async fn get_snapshot(
+    State(state): State<AppState>,
+    Path(room_code): Path<String>,
+    Query(query): Query<OverlayQuery>,
+) -> Result<Json<OverlaySnapshot>, AppError> {
+    authorize(&state, &room_code, &query).await?;
+
+    let rooms = state.rooms.read().await;
+    let room = rooms
+        .get(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    let mut snapshot = OverlaySnapshot {
+        room_code: room.room_code.clone(),
+        room_name: room.name.clone(),
+        state: room.state,
+        teams: Vec::new(),
+        current_round: None,
+        audience_score: None,
+    };
+
+    let engines = GAME_ENGINES.read().await;
+    if let Some(engine) = engines.get(&room_code) {
+        let engine = engine.read().await;
+        let game_state = &engine.game_state;
+
+        snapshot.teams = game_state.teams.clone();
+        snapshot.audience_score = game_state.audience.as_ref().map(|a| a.score);
+        snapshot.current_round = game_state.current_round.as_ref().map(|round| OverlayRound {
+            round_number: round.round_number,
+            team_id: round.team_id.clone(),
+            explainer_id: round.explainer_id.clone(),
+            explainer_name: room
+                .participants
+                .get(&round.explainer_id)
+                .map(|p| p.display_name.clone())
+                .unwrap_or_default(),
+            time_remaining: round.time_remaining,
+            score_gained: round.score_gained,
+        });
+    }
+
+    Ok(Json(snapshot))
+}
+
+/// Live room events for the overlay, with secret words redacted. The
+/// stream ends when the overlay token is rotated.
+pub async fn stream_events(
+    State(state): State<AppState>,
+    Path(room_code): Path<String>,
+    Query(query): Query<OverlayQuery>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    authorize(&state, &room_code, &query).await?;
+    // Check again once watching, in case the token was rotated in between
+    let rotated = state
+        .websocket_manager
+        .watch_overlay_token(&room_code)
+        .await;
+    authorize(&state, &room_code, &query).await?;
+
+    let receiver = state
+        .websocket_manager
+        .get_or_create_room_sender(&room_code)
+        .await
+        .subscribe();
+
+    tracing::info!("Overlay connected to room {}", room_code);
+
+    let events = stream::unfold(
+        (receiver, rotated),
+        |(mut receiver, mut rotated)| async move {
+            loop {
+                let received = select! {
+                    biased;
+                    // The token this stream was opened with is gone
+                    _ = rotated.changed() => return None,
+                    received = receiver.recv() => received,
+                };
+                match received {
+                    Ok(message) => {
+                        let Some(message) = message.message.redacted_for_audience() else {
+                            continue;
+                        };
+                        let Ok(event) = Event::default()
+                            .event(message.type_name())
+                            .json_data(&message)
+                        else {
+                            continue;
+                        };
+                        return Some((Ok(event), (receiver, rotated)));
+                    }
+                    // A slow overlay only misses some updates
+                    Err(RecvError::Lagged(_)) => continue,
+                    // Room was removed
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        },
+    );
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// Issue a new overlay token, invalidating the old one (admin only)
+pub async fn rotate_token(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(room_code): Path<String>,
+) -> Result<Json<OverlayTokenResponse>, AppError> {
+    let user_id = user.id.unwrap().to_hex();
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    if room.admin_id != user_id {
+        return Err(AppError::forbidden(
+            "Only admin can manage the overlay".into(),
+        ));
+    }
+
+    room.overlay_token = generate_overlay_token();
+    let overlay_token = room.overlay_token.clone();
+    drop(rooms);
+
+    // Streams opened with the old token end
+    state
+        .websocket_manager
+        .overlay_token_rotated(&room_code)
+        .await;
+
+    Ok(Json(OverlayTokenResponse { overlay_token }))
+}
@@ -0,0 +1,278 @@
+//! Player presence: heartbeats, AFK detection and stand-ins for absent
+//! explainers.
+//!
+//! The server pings every connection and drops the ones that stop
+//! answering, so half-open sockets count as disconnected. Players who send
+//! nothing but keepalives for a while are shown to the room as AFK. When the
+//! explainer of a running round is gone (AFK, or disconnected for longer than
+//! a short grace period) the next present teammate takes over the turn; if
+//! nobody can, the game pauses until the explainer is back.
+
+use chrono::Utc;
+use game_engine::game::GameEngine;
+use shared::models::{GameRoom, Round, WebSocketMessage};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::bots;
+use crate::game::GAME_ENGINES;
+use crate::AppState;
+
+// How often the server pings each connection and checks on its player
+pub const PING_INTERVAL: Duration = Duration::from_secs(15);
+// A connection that sends nothing for this long, pongs included, is dropped
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(45);
+// Players who send nothing but keepalives for this long are AFK
+pub const AFK_TIMEOUT: Duration = Duration::from_secs(90);
+// How long a disconnected explainer has to come back before the turn moves on
+const EXPLAINER_GRACE: Duration = Duration::from_secs(10);
+
+/// Liveness and activity of one connection
+pub struct Heartbeat {
+    timeout: Duration,
+    afk_after: Duration,
+    last_seen: Instant,   // Any frame, pongs included
+    last_active: Instant, // Requests other than keepalive pings
+    afk: bool,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new(HEARTBEAT_TIMEOUT, AFK_TIMEOUT)
+    }
+}
+
+impl Heartbeat {
+    pub fn new(timeout: Duration, afk_after: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            timeout,
+            afk_after,
+            last_seen: now,
+            last_active: now,
+            afk: false,
+        }
+    }
+
+    /// Something arrived from the client
+    pub fn seen(&mut self) {
+        self.last_seen = Instant::now();
+    }
+
+    /// The player did something. Returns true if they were AFK until now.
+    pub fn active(&mut self) -> bool {
+        self.seen();
+        self.last_active = self.last_seen;
+        std::mem::replace(&mut self.afk, false)
+    }
+
+    /// Whether the player is idle on this connection
+    pub fn is_afk(&self) -> bool {
+        self.afk
+    }
+
+    /// The client stopped answering pings
+    pub fn timed_out(&self) -> bool {
+        self.last_seen.elapsed() > self.timeout
+    }
+
+    /// True once, when the player has been idle for long enough to be AFK
+    pub fn went_afk(&mut self) -> bool {
+        if self.afk || self.last_active.elapsed() < self.afk_after {
+            return false;
+        }
+        self.afk = true;
+        true
+    }
+}
+
+/// Show a player to the room as AFK, or as back
+pub async fn set_afk(state: &AppState, room_code: &str, user_id: &str, afk: bool) {
+    {
+        let mut rooms = state.rooms.write().await;
+        let Some(room) = rooms.get_mut(room_code) else {
+            return;
+        };
+        let Some(participant) = room.participants.get_mut(user_id) else {
+            return;
+        };
+        if participant.is_afk == afk {
+            return;
+        }
+        participant.is_afk = afk;
+        room.updated_at = Utc::now();
+
+        state
+            .websocket_manager
+            .broadcast_to_room(
+                room_code,
+                WebSocketMessage::RoomUpdated { room: room.clone() },
+            )
+            .await;
+        if afk {
+            info!("User {} is AFK in room {}", user_id, room_code);
+        } else {
+            info!("User {} is back in room {}", user_id, room_code);
+        }
+    }
+
+    check_explainer(state, room_code).await;
+}
+
+/// Give a disconnected explainer a moment to reconnect, then pass their
+/// turn on
+pub async fn explainer_disconnected(state: &AppState, room_code: &str, user_id: &str) {
+    let explaining = match GAME_ENGINES.read().await.get(room_code) {
+        Some(engine) => engine
+            .read()
+            .await
+            .game_state
+            .current_round
+            .as_ref()
+            .is_some_and(|round| round.explainer_id == user_id),
+        None => false,
+    };
+    if !explaining {
+        return;
+    }
+
+    let state = state.clone();
+    let room_code = room_code.to_string();
+    tokio::spawn(async move {
+        tokio::time::sleep(EXPLAINER_GRACE).await;
+        check_explainer(&state, &room_code).await;
+    });
+}
+
+/// Make sure the current round's explainer is there. When they are gone a
+/// present teammate takes over, or the game pauses if there is none; the
+/// game resumes once the explainer is back.
+pub async fn check_explainer(state: &AppState, room_code: &str) {
+    let engines = GAME_ENGINES.read().await;
+    let Some(engine) = engines.get(room_code) else {
+        return;
+    };
+    let mut engine = engine.write().await;
+    let Some(round) = engine.game_state.current_round.clone() else {
+        return;
+    };
+
+    let rooms = state.rooms.read().await;
+    let Some(room) = rooms.get(room_code) else {
+        return;
+    };
+    if is_present(room, &round.explainer_id) {
+        drop(rooms);
+        if engine.paused_for() == Some(round.explainer_id.as_str()) {
+            resume(&mut engine, room_code, state).await;
+        }
+        return;
+    }
+    let substitute = substitute(&engine, room, &round);
+    drop(rooms);
+
+    match substitute {
+        Some(explainer_id) => {
+            hand_off(
+                &mut engine,
+                &round.explainer_id,
+                &explainer_id,
+                room_code,
+                state,
+            )
+            .await
+        }
+        None if !engine.is_paused() => {
+            if let Err(e) = engine.pause_for_absent_explainer(&round.explainer_id) {
+                warn!("Could not pause the game in room {}: {}", room_code, e);
+                return;
+            }
+            state
+                .websocket_manager
+                .broadcast_to_room(room_code, WebSocketMessage::GamePaused)
+                .await;
+            info!(
+                "Explainer {} is gone and nobody can take over, game in room {} paused",
+                round.explainer_id, room_code
+            );
+        }
+        None => {}
+    }
+}
+
+fn is_present(room: &GameRoom, user_id: &str) -> bool {
+    room.participants
+        .get(user_id)
+        .is_some_and(|p| p.is_present())
+}
+
+// The next present player of the round's team, in explaining order
+fn substitute(engine: &GameEngine, room: &GameRoom, round: &Round) -> Option<String> {
+    let team_size = engine.team_manager.get_team(&round.team_id)?.players.len();
+    let mut candidate = round.explainer_id.clone();
+    for _ in 1..team_size {
+        candidate = engine
+            .team_manager
+            .get_next_explainer(&round.team_id, Some(&candidate))?;
+        if candidate != round.explainer_id && is_present(room, &candidate) {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+async fn hand_off(
+    engine: &mut GameEngine,
+    previous_explainer_id: &str,
+    explainer_id: &str,
+    room_code: &str,
+    state: &AppState,
+) {
+    let round = match engine.hand_off_round(explainer_id) {
+        Ok(round) => round,
+        Err(e) => {
+            warn!("Could not hand off the round in room {}: {}", room_code, e);
+            return;
+        }
+    };
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            room_code,
+            WebSocketMessage::ExplainerChanged {
+                round_number: round.round_number,
+                previous_explainer_id: previous_explainer_id.to_string(),
+                explainer_id: explainer_id.to_string(),
+            },
+        )
+        .await;
+
+    if engine.is_paused() {
+        resume(engine, room_code, state).await;
+    }
+
+    // The new explainer finds the round's words in the game state
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            room_code,
+            WebSocketMessage::GameStateUpdated {
+                game_state: Box::new(engine.game_state.clone()),
+            },
+        )
+        .await;
+    bots::round_started(state, room_code, &round).await;
+}
+
+async fn resume(engine: &mut GameEngine, room_code: &str, state: &AppState) {
+    if let Err(e) = engine.resume_game() {
+        warn!("Could not resume the game in room {}: {}", room_code, e);
+        return;
+    }
+    state
+        .websocket_manager
+        .broadcast_to_room(room_code, WebSocketMessage::GameResumed)
+        .await;
+    info!("Game in room {} resumed", room_code);
+}
@@ -0,0 +1,139 @@
+//! Protocol version and frame encoding, negotiated per connection.
+//!
+//! Clients open with `hello`, naming the newest protocol version they speak
+//! and the encodings they accept. Clients that never say hello are served
+//! protocol version 1 over JSON, as before versioning existed.
+
+use axum::extract::ws::Message;
+use serde::Serialize;
+use serde_json::{json, Value};
+use shared::errors::{ErrorCode, ProtocolError};
+use shared::models::{Encoding, RequestEnvelope, SequencedMessage, WebSocketMessage};
+
+pub const PROTOCOL_VERSION: u32 = 2;
+pub const MIN_PROTOCOL_VERSION: u32 = 1;
+
+// Version 1 had no request ids, acks, broadcast sequence numbers or error
+// codes. Requests look the same in both versions.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Codec {
+    pub version: u32,
+    pub encoding: Encoding,
+}
+
+impl Default for Codec {
+    fn default() -> Self {
+        Self {
+            version: MIN_PROTOCOL_VERSION,
+            encoding: Encoding::Json,
+        }
+    }
+}
+
+/// A frame that isn't a valid request
+#[derive(Debug)]
+pub struct InvalidFrame {
+    pub request_id: Option<String>, // Recovered when possible
+    pub reason: String,
+}
+
+/// Decode a request: text frames are JSON, binary frames MessagePack.
+/// None for frames that carry no request (ping, pong, close).
+pub fn decode(frame: &Message) -> Option<Result<RequestEnvelope, InvalidFrame>> {
+    let result = match frame {
+        Message::Text(text) => serde_json::from_str(text).map_err(|e| InvalidFrame {
+            request_id: serde_json::from_str(text).ok().and_then(request_id_of),
+            reason: e.to_string(),
+        }),
+        Message::Binary(bytes) => rmp_serde::from_slice(bytes).map_err(|e| InvalidFrame {
+            request_id: rmp_serde::from_slice(bytes).ok().and_then(request_id_of),
+            reason: e.to_string(),
+        }),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn request_id_of(value: Value) -> Option<String> {
+    value.get("request_id")?.as_str().map(str::to_string)
+}
+
+impl Codec {
+    /// Settle on the highest version both sides speak and the client's
+    /// preferred encoding
+    pub fn negotiate(protocol_version: u32, encodings: &[Encoding]) -> Result<Self, ProtocolError> {
+        if protocol_version < MIN_PROTOCOL_VERSION {
+            return Err(ProtocolError::new(
+                ErrorCode::UnsupportedVersion,
+                format!(
+                    "Protocol version {} is not supported (oldest is {})",
+                    protocol_version, MIN_PROTOCOL_VERSION
+                ),
+            ));
+        }
+
+        Ok(Self {
+            version: protocol_version.min(PROTOCOL_VERSION),
+            encoding: encodings.first().copied().unwrap_or_default(),
+        })
+    }
+
+    pub fn welcome(&self) -> WebSocketMessage {
+        WebSocketMessage::Welcome {
+            protocol_version: self.version,
+            encoding: self.encoding,
+        }
+    }
+
+    /// Encode a message that answers no particular request
+    pub fn message(&self, message: &WebSocketMessage) -> Option<Message> {
+        match message {
+            WebSocketMessage::Error { message, .. } if self.version < 2 => {
+                self.serialize(&json!({ "type": "error", "message": message }))
+            }
+            _ => self.serialize(message),
+        }
+    }
+
+    /// Encode direct replies, echoing the id of the request they answer.
+    /// Requests with an id always get a reply, if only an ack.
+    pub fn replies(
+        &self,
+        request_id: Option<String>,
+        mut messages: Vec<WebSocketMessage>,
+    ) -> Vec<Message> {
+        if messages.is_empty() && request_id.is_some() && self.version >= 2 {
+            messages.push(WebSocketMessage::Ack);
+        }
+
+        messages
+            .into_iter()
+            .filter_map(|message| {
+                if self.version < 2 {
+                    return self.message(&message);
+                }
+                self.serialize(&RequestEnvelope {
+                    request_id: request_id.clone(),
+                    message,
+                })
+            })
+            .collect()
+    }
+
+    /// Encode a numbered room broadcast
+    pub fn broadcast(&self, message: &SequencedMessage) -> Option<Message> {
+        if self.version < 2 {
+            return self.message(&message.message);
+        }
+        self.serialize(message)
+    }
+
+    fn serialize<T: Serialize>(&self, frame: &T) -> Option<Message> {
+        match self.encoding {
+            Encoding::Json => serde_json::to_string(frame).ok().map(Message::Text),
+            // Named fields, so frames decode to the same maps as JSON
+            Encoding::Msgpack => rmp_serde::to_vec_named(frame).ok().map(Message::Binary),
+        }
+    }
+}
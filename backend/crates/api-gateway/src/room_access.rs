@@ -0,0 +1,83 @@
+//! Entry rules for rooms that aren't public: room passwords and signed,
+//! expiring invite links.
+
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::Sha256;
+
+pub const DEFAULT_INVITE_TTL: Duration = Duration::hours(24);
+pub const MAX_INVITE_TTL: Duration = Duration::days(7);
+pub const MAX_PASSWORD_LEN: usize = 64;
+
+const SALT_LEN: usize = 16;
+const INVITE_SECRET_LEN: usize = 32;
+
+fn random_string(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+fn mac(key: &str) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts any key length")
+}
+
+/// Generate the per-room secret invites are signed with
+pub fn generate_invite_secret() -> String {
+    random_string(INVITE_SECRET_LEN)
+}
+
+/// Hash a room password with Argon2id, as a PHC string. Slow on purpose,
+/// so keep it off the async workers.
+pub fn hash_password(password: &str) -> String {
+    let salt =
+        SaltString::encode_b64(&rand::random::<[u8; SALT_LEN]>()).expect("salt length is valid");
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("Argon2 accepts any password length")
+        .to_string()
+}
+
+pub fn verify_password(hash: &str, password: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| {
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok()
+    })
+}
+
+fn sign_invite(secret: &str, room_code: &str, expires: i64) -> Hmac<Sha256> {
+    let mut mac = mac(secret);
+    mac.update(room_code.as_bytes());
+    mac.update(b".");
+    mac.update(expires.to_string().as_bytes());
+    mac
+}
+
+/// Invite token: "<expiry as unix seconds>.<hex HMAC-SHA256 of room code and expiry>"
+pub fn create_invite(secret: &str, room_code: &str, expires_at: DateTime<Utc>) -> String {
+    let expires = expires_at.timestamp();
+    let signature = sign_invite(secret, room_code, expires).finalize();
+    format!("{}.{}", expires, hex::encode(signature.into_bytes()))
+}
+
+/// Check an invite was signed for this room and hasn't expired
+pub fn verify_invite(secret: &str, room_code: &str, invite: &str) -> bool {
+    let Some((expires, signature)) = invite.split_once('.') else {
+        return false;
+    };
+    let (Ok(expires), Ok(signature)) = (expires.parse::<i64>(), hex::decode(signature)) else {
+        return false;
+    };
+
+    !secret.is_empty()
+        && expires > Utc::now().timestamp()
+        && sign_invite(secret, room_code, expires)
+            .verify_slice(&signature)
+            .is_ok()
+}
@@ -3,21 +3,33 @@ use axum::{
     http::StatusCode,
     response::Json,
 };
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use mongodb::bson::oid::ObjectId;
 use rand::Rng;
 use std::collections::HashMap;
 
 use shared::models::{
-    CreateRoomRequest, CreateRoomResponse, GameRoom, RoomInfo, RoomParticipant, RoomState, User,
-    UserRole, WebSocketMessage,
+    Ban, BanRequest, CreateInviteRequest, CreateRoomRequest, CreateRoomResponse, GameRoom,
+    KickRequest, ModeratorPermission, ModeratorPermissionsRequest, RoomAccessRequest, RoomInfo,
+    RoomInvite, RoomParticipant, RoomState, RoomVisibility, TransferAdminRequest,
+    UpdateRoleRequest, User, UserRole, WebSocketMessage, WebhookEvent,
 };
 
+use crate::bans;
 use crate::error::AppError;
+use crate::game::GAME_ENGINES;
+use crate::overlay;
+use crate::room_access;
+use crate::twitch_chat;
+use crate::websocket::UserControl;
 use crate::AppState;
 
 // Removed unused type alias
 
+// Spectators are not counted against max_players, but still capped
+const MAX_SPECTATORS: usize = 100;
+const MAX_SPECTATOR_DELAY_SECONDS: u32 = 600;
+
 /// Generate a unique room code
 fn generate_room_code() -> String {
     let mut rng = rand::thread_rng();
@@ -33,6 +45,79 @@ fn generate_room_code() -> String {
         .collect()
 }
 
+/// Check a room's entry rules: guests in registered-only rooms, and the
+/// password or an invite for password rooms. Participants coming back don't
+/// need the password again. `password_ok` is the outcome of `check_password`.
+fn ensure_can_enter(
+    room: &GameRoom,
+    user: &User,
+    access: &RoomAccessRequest,
+    password_ok: bool,
+) -> Result<(), AppError> {
+    let user_id = user.id.unwrap().to_hex();
+    if let Some(ban) = room.active_ban(&user_id) {
+        return Err(AppError::forbidden(match &ban.reason {
+            Some(reason) => format!("You are banned from this room: {}", reason),
+            None => "You are banned from this room".to_string(),
+        }));
+    }
+
+    if room.registered_only && user.is_guest {
+        return Err(AppError::forbidden(
+            "Room is for registered users only".into(),
+        ));
+    }
+
+    if room.visibility != RoomVisibility::Password || room.participants.contains_key(&user_id) {
+        return Ok(());
+    }
+
+    if let Some(invite) = &access.invite {
+        if room_access::verify_invite(&room.invite_secret, &room.room_code, invite) {
+            return Ok(());
+        }
+        return Err(AppError::forbidden("Invite is invalid or expired".into()));
+    }
+
+    match (&access.password, password_ok) {
+        (Some(_), true) => Ok(()),
+        (Some(_), false) => Err(AppError::forbidden("Incorrect room password".into())),
+        (None, _) => Err(AppError::forbidden(
+            "Room requires a password or an invite".into(),
+        )),
+    }
+}
+
+/// Check the password offered for a password room, if it's needed to get
+/// in. Runs before the rooms lock is taken, as hashing takes a while.
+async fn check_password(
+    state: &AppState,
+    room_code: &str,
+    user_id: &str,
+    access: &RoomAccessRequest,
+) -> bool {
+    let Some(password) = access.password.clone() else {
+        return false;
+    };
+    let hash = match state.rooms.read().await.get(room_code) {
+        Some(room)
+            if room.visibility == RoomVisibility::Password
+                && !room.participants.contains_key(user_id)
+                && access.invite.is_none() =>
+        {
+            room.password_hash.clone()
+        }
+        _ => None,
+    };
+    let Some(hash) = hash else {
+        return false;
+    };
+
+    tokio::task::spawn_blocking(move || room_access::verify_password(&hash, &password))
+        .await
+        .unwrap_or(false)
+}
+
 /// Create a new game room
 pub async fn create_room(
     State(state): State<AppState>,
@@ -45,11 +130,52 @@ pub async fn create_room(
             "Max players must be between 4 and 10".into(),
         ));
     }
+    if req.spectator_delay_seconds > MAX_SPECTATOR_DELAY_SECONDS {
+        return Err(AppError::bad_request(format!(
+            "Spectator delay must be at most {} seconds",
+            MAX_SPECTATOR_DELAY_SECONDS
+        )));
+    }
+    let password_hash = match (req.visibility, req.password.as_deref()) {
+        (RoomVisibility::Password, Some(password))
+            if !password.is_empty() && password.len() <= room_access::MAX_PASSWORD_LEN =>
+        {
+            let password = password.to_string();
+            let hash = tokio::task::spawn_blocking(move || room_access::hash_password(&password))
+                .await
+                .map_err(|_| AppError::internal())?;
+            Some(hash)
+        }
+        (RoomVisibility::Password, _) => {
+            return Err(AppError::bad_request(format!(
+                "Password rooms need a password of 1 to {} characters",
+                room_access::MAX_PASSWORD_LEN
+            )));
+        }
+        (_, Some(_)) => {
+            return Err(AppError::bad_request(
+                "Only password rooms take a password".into(),
+            ));
+        }
+        (_, None) => None,
+    };
 
     let room_code = generate_room_code();
     let room_id = ObjectId::new();
     let user_id = user.id.unwrap().to_hex();
 
+    // The streamer's ban list applies to every room they create
+    let bans = match state.bans.list(&user_id).await {
+        Ok(bans) => bans
+            .into_iter()
+            .map(|ban| (ban.user_id.clone(), ban))
+            .collect(),
+        Err(e) => {
+            tracing::warn!("Failed to load bans of streamer {}: {}", user_id, e);
+            HashMap::new()
+        }
+    };
+
     // Create room participant for the creator (admin)
     let admin_participant = RoomParticipant {
         user_id: user_id.clone(),
@@ -59,7 +185,9 @@ pub async fn create_room(
         role: UserRole::Admin,
         team_id: None,
         is_connected: true,
+        is_afk: false,
         joined_at: Utc::now(),
+        bot: None,
     };
 
     // Create the game room
@@ -78,24 +206,39 @@ pub async fn create_room(
         created_at: Utc::now(),
         updated_at: Utc::now(),
         game_data: None,
+        spectator_delay_seconds: req.spectator_delay_seconds,
+        overlay_token: overlay::generate_overlay_token(),
+        registered_only: req.registered_only,
+        visibility: req.visibility,
+        password_hash,
+        invite_secret: room_access::generate_invite_secret(),
+        bans,
+        moderator_permissions: ModeratorPermission::ALL.to_vec(),
     };
 
     // Store room in memory (later we'll use Redis)
     let mut rooms = state.rooms.write().await;
     rooms.insert(room_code.clone(), room.clone());
 
+    state
+        .websocket_manager
+        .create_audience_channel(&room_code, req.spectator_delay_seconds)
+        .await;
+
     // Create RoomInfo for the broadcast
-    let room_info = RoomInfo {
-        id: room_id.to_hex(),
-        room_code: room_code.clone(),
-        name: req.name.clone(),
-        current_players: 1,
-        max_players: req.max_players,
-        state: RoomState::Waiting,
-        admin_username: user.username.clone(),
-    };
+    let room_info = RoomInfo::from(&room);
 
-    // Broadcast room creation to all connected clients in the lobby
+    state
+        .webhook_manager
+        .dispatch(
+            WebhookEvent::RoomCreated,
+            &room_code,
+            &user_id,
+            serde_json::to_value(&room_info).unwrap_or_default(),
+        )
+        .await;
+
+    // Broadcast room creation to the lobby (only public rooms are listed)
     state
         .websocket_manager
         .broadcast_to_lobby(WebSocketMessage::RoomCreated { room_info });
@@ -111,6 +254,7 @@ pub async fn create_room(
         room_code,
         name: req.name,
         admin_id: user_id,
+        overlay_token: room.overlay_token,
     }))
 }
 
@@ -119,19 +263,50 @@ pub async fn join_room(
     State(state): State<AppState>,
     Extension(user): Extension<User>,
     Path(room_code): Path<String>,
+    access: Option<Json<RoomAccessRequest>>,
 ) -> Result<Json<GameRoom>, AppError> {
     let user_id = user.id.unwrap().to_hex();
+    let access = access.map(|Json(a)| a).unwrap_or_default();
+    let password_ok = check_password(&state, &room_code, &user_id, &access).await;
     let mut rooms = state.rooms.write().await;
 
     let room = rooms
         .get_mut(&room_code)
         .ok_or_else(|| AppError::not_found("Room not found".into()))?;
 
-    // Check if room is full
-    if room.participants.len() >= room.max_players as usize {
+    ensure_can_enter(room, &user, &access, password_ok)?;
+
+    // Check if room is full (spectators don't take a seat)
+    if room.player_count() >= room.max_players as usize {
         return Err(AppError::bad_request("Room is full".into()));
     }
 
+    // A spectator joining takes a seat as a player
+    if room.is_spectator(&user_id) {
+        if let Some(participant) = room.participants.get_mut(&user_id) {
+            participant.role = UserRole::Player;
+            participant.is_connected = true;
+        }
+        room.updated_at = Utc::now();
+
+        state
+            .websocket_manager
+            .broadcast_to_room(
+                &room_code,
+                WebSocketMessage::RoleUpdated {
+                    user_id: user_id.clone(),
+                    role: UserRole::Player,
+                },
+            )
+            .await;
+        state
+            .websocket_manager
+            .broadcast_to_lobby(WebSocketMessage::RoomInfoUpdated {
+                room_info: RoomInfo::from(&*room),
+            });
+        return Ok(Json(room.clone()));
+    }
+
     // Check if user is already in the room
     if room.participants.contains_key(&user_id) {
         // Mark as connected if they're rejoining
@@ -151,7 +326,9 @@ pub async fn join_room(
         role: UserRole::Player,
         team_id: None,
         is_connected: true,
+        is_afk: false,
         joined_at: Utc::now(),
+        bot: None,
     };
 
     room.participants
@@ -185,19 +362,7 @@ pub async fn join_room(
         .await;
 
     // Broadcast updated room info to lobby
-    let room_info = RoomInfo {
-        id: room.id.unwrap().to_hex(),
-        room_code: room_code.clone(),
-        name: room.name.clone(),
-        current_players: room.participants.len(),
-        max_players: room.max_players,
-        state: room.state,
-        admin_username: room
-            .participants
-            .get(&room.admin_id)
-            .map(|p| p.username.clone())
-            .unwrap_or_default(),
-    };
+    let room_info = RoomInfo::from(&*room);
     state
         .websocket_manager
         .broadcast_to_lobby(WebSocketMessage::RoomInfoUpdated { room_info });
@@ -212,6 +377,73 @@ pub async fn join_room(
     Ok(Json(room.clone()))
 }
 
+/// Join a room as a spectator
+pub async fn spectate_room(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(room_code): Path<String>,
+    access: Option<Json<RoomAccessRequest>>,
+) -> Result<Json<GameRoom>, AppError> {
+    let user_id = user.id.unwrap().to_hex();
+    let access = access.map(|Json(a)| a).unwrap_or_default();
+    let password_ok = check_password(&state, &room_code, &user_id, &access).await;
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    ensure_can_enter(room, &user, &access, password_ok)?;
+
+    // Players keep their seat; spectators just reconnect
+    if let Some(participant) = room.participants.get_mut(&user_id) {
+        if participant.role != UserRole::Spectator {
+            return Err(AppError::bad_request(
+                "Already in the room as a player".into(),
+            ));
+        }
+        participant.is_connected = true;
+        room.updated_at = Utc::now();
+        return Ok(Json(room.clone()));
+    }
+
+    if room.spectator_count() >= MAX_SPECTATORS {
+        return Err(AppError::bad_request("Too many spectators".into()));
+    }
+
+    let participant = RoomParticipant {
+        user_id: user_id.clone(),
+        username: user.username.clone(),
+        display_name: user.display_name.clone(),
+        profile_image_url: user.profile_image_url.clone(),
+        role: UserRole::Spectator,
+        team_id: None,
+        is_connected: true,
+        is_afk: false,
+        joined_at: Utc::now(),
+        bot: None,
+    };
+
+    room.participants
+        .insert(user_id.clone(), participant.clone());
+    room.updated_at = Utc::now();
+
+    state
+        .websocket_manager
+        .broadcast_to_room(&room_code, WebSocketMessage::UserJoined { participant })
+        .await;
+
+    state
+        .websocket_manager
+        .broadcast_to_lobby(WebSocketMessage::RoomInfoUpdated {
+            room_info: RoomInfo::from(&*room),
+        });
+
+    tracing::info!("User {} is spectating room {}", user_id, room_code);
+
+    Ok(Json(room.clone()))
+}
+
 /// Get room info
 pub async fn get_room(
     State(state): State<AppState>,
@@ -232,34 +464,66 @@ pub async fn get_room(
     Ok(Json(room.clone()))
 }
 
-/// List available rooms
+/// List public rooms
 pub async fn list_rooms(State(state): State<AppState>) -> Result<Json<Vec<RoomInfo>>, AppError> {
     let rooms = state.rooms.read().await;
 
     let room_list: Vec<RoomInfo> = rooms
         .values()
-        .map(|room| {
-            let admin = room
-                .participants
-                .get(&room.admin_id)
-                .map(|p| p.username.clone())
-                .unwrap_or_default();
-
-            RoomInfo {
-                id: room.id.map(|id| id.to_hex()).unwrap_or_default(),
-                room_code: room.room_code.clone(),
-                name: room.name.clone(),
-                current_players: room.participants.len(),
-                max_players: room.max_players,
-                state: room.state,
-                admin_username: admin,
-            }
-        })
+        .filter(|room| room.visibility == RoomVisibility::Public)
+        .map(RoomInfo::from)
         .collect();
 
     Ok(Json(room_list))
 }
 
+/// Create a signed invite that lets anyone holding it in, skipping the
+/// password (admin only)
+pub async fn create_invite(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(room_code): Path<String>,
+    req: Option<Json<CreateInviteRequest>>,
+) -> Result<Json<RoomInvite>, AppError> {
+    let user_id = user.id.unwrap().to_hex();
+    let rooms = state.rooms.read().await;
+
+    let room = rooms
+        .get(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    if room.admin_id != user_id {
+        return Err(AppError::forbidden("Only admin can create invites".into()));
+    }
+
+    let ttl = match req.and_then(|Json(req)| req.expires_in_minutes) {
+        Some(minutes) => Duration::minutes(minutes.into()),
+        None => room_access::DEFAULT_INVITE_TTL,
+    };
+    if ttl <= Duration::zero() || ttl > room_access::MAX_INVITE_TTL {
+        return Err(AppError::bad_request(format!(
+            "Invites must expire within {} minutes",
+            room_access::MAX_INVITE_TTL.num_minutes()
+        )));
+    }
+
+    let expires_at = Utc::now() + ttl;
+    let invite = room_access::create_invite(&room.invite_secret, &room_code, expires_at);
+
+    tracing::info!(
+        "Admin {} created an invite to room {} until {}",
+        user_id,
+        room_code,
+        expires_at
+    );
+
+    Ok(Json(RoomInvite {
+        room_code,
+        invite,
+        expires_at,
+    }))
+}
+
 /// Leave a room
 pub async fn leave_room(
     State(state): State<AppState>,
@@ -288,11 +552,12 @@ pub async fn leave_room(
         )
         .await;
 
-    // Check if room is now empty (regardless of who left)
-    if room.participants.is_empty() {
+    // Check if room is now empty (regardless of who left); bots don't keep it open
+    if !room.has_humans() {
         // Remove empty room
         rooms.remove(&room_code);
         state.websocket_manager.remove_room(&room_code).await;
+        twitch_chat::stop_connector(&room_code).await;
 
         // Broadcast room deletion to lobby
         state
@@ -309,25 +574,9 @@ pub async fn leave_room(
         return Ok(StatusCode::OK);
     }
 
-    // If admin leaves but room is not empty, transfer admin role
+    // If admin leaves but room is not empty, pass the admin role on
     if room.admin_id == user_id {
-        if let Some((new_admin_id, participant)) = room.participants.iter_mut().next() {
-            // Transfer admin role to another participant
-            participant.role = UserRole::Admin;
-            room.admin_id = new_admin_id.clone();
-
-            // Broadcast role update
-            state
-                .websocket_manager
-                .broadcast_to_room(
-                    &room_code,
-                    WebSocketMessage::RoleUpdated {
-                        user_id: new_admin_id.clone(),
-                        role: UserRole::Admin,
-                    },
-                )
-                .await;
-
+        if let Some(new_admin_id) = hand_off_admin(&state, room).await {
             tracing::info!(
                 "Admin role transferred from {} to {} in room {}",
                 user_id,
@@ -349,12 +598,78 @@ pub async fn leave_room(
     Ok(StatusCode::OK)
 }
 
-/// Kick a player from the room (admin only)
-pub async fn kick_player(
+/// Pass the admin role to the next in line (see `GameRoom::admin_successor`)
+/// and tell the room. Returns the new admin, if there is anyone to take over.
+pub(crate) async fn hand_off_admin(state: &AppState, room: &mut GameRoom) -> Option<String> {
+    let successor = room.admin_successor()?;
+    transfer_admin_to(state, room, &successor).await.ok()?;
+    Some(successor)
+}
+
+/// Make another participant admin and broadcast the role changes
+pub(crate) async fn transfer_admin_to(
+    state: &AppState,
+    room: &mut GameRoom,
+    new_admin_id: &str,
+) -> Result<(), String> {
+    let old_admin_id = room.admin_id.clone();
+    room.transfer_admin(new_admin_id)?;
+
+    let room_code = room.room_code.clone();
+    for (user_id, role) in [
+        (new_admin_id.to_string(), UserRole::Admin),
+        (old_admin_id, UserRole::Moderator),
+    ] {
+        if room.participants.contains_key(&user_id) {
+            state
+                .websocket_manager
+                .broadcast_to_room(&room_code, WebSocketMessage::RoleUpdated { user_id, role })
+                .await;
+        }
+    }
+    Ok(())
+}
+
+/// Make a player a moderator or back (checks are up to the caller)
+pub(crate) fn set_moderator(
+    room: &mut GameRoom,
+    user_id: &str,
+    role: UserRole,
+) -> Result<(), String> {
+    if !matches!(role, UserRole::Moderator | UserRole::Player) {
+        return Err("Role must be moderator or player".to_string());
+    }
+    let participant = room
+        .participants
+        .get_mut(user_id)
+        .ok_or("Player not found in room")?;
+    if !matches!(participant.role, UserRole::Moderator | UserRole::Player) || participant.is_bot() {
+        return Err("Only players can become moderators".to_string());
+    }
+
+    participant.role = role;
+    room.updated_at = Utc::now();
+    Ok(())
+}
+
+/// Replace what moderators may do, keeping each permission once
+pub(crate) fn set_moderator_permissions(
+    room: &mut GameRoom,
+    mut permissions: Vec<ModeratorPermission>,
+) {
+    permissions.sort_by_key(|p| ModeratorPermission::ALL.iter().position(|all| all == p));
+    permissions.dedup();
+    room.moderator_permissions = permissions;
+    room.updated_at = Utc::now();
+}
+
+/// Make a player a moderator, or a moderator a player again (admin only)
+pub async fn update_role(
     State(state): State<AppState>,
     Extension(user): Extension<User>,
     Path((room_code, player_id)): Path<(String, String)>,
-) -> Result<StatusCode, AppError> {
+    Json(req): Json<UpdateRoleRequest>,
+) -> Result<Json<GameRoom>, AppError> {
     let admin_id = user.id.unwrap().to_hex();
     let mut rooms = state.rooms.write().await;
 
@@ -362,38 +677,146 @@ pub async fn kick_player(
         .get_mut(&room_code)
         .ok_or_else(|| AppError::not_found("Room not found".into()))?;
 
-    // Check if the user is the admin
     if room.admin_id != admin_id {
-        return Err(AppError::forbidden("Only admin can kick players".into()));
+        return Err(AppError::forbidden("Only admin can change roles".into()));
     }
+    set_moderator(room, &player_id, req.role).map_err(AppError::bad_request)?;
 
-    // Check if the player exists in the room
-    if !room.participants.contains_key(&player_id) {
-        return Err(AppError::not_found("Player not found in room".into()));
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            &room_code,
+            WebSocketMessage::RoleUpdated {
+                user_id: player_id.clone(),
+                role: req.role,
+            },
+        )
+        .await;
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            &room_code,
+            WebSocketMessage::RoomUpdated { room: room.clone() },
+        )
+        .await;
+
+    tracing::info!(
+        "Admin {} made {} a {:?} in room {}",
+        admin_id,
+        player_id,
+        req.role,
+        room_code
+    );
+
+    Ok(Json(room.clone()))
+}
+
+/// Hand the admin role to another player (admin only)
+pub async fn transfer_admin(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(room_code): Path<String>,
+    Json(req): Json<TransferAdminRequest>,
+) -> Result<Json<GameRoom>, AppError> {
+    let admin_id = user.id.unwrap().to_hex();
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    if room.admin_id != admin_id {
+        return Err(AppError::forbidden("Only admin can transfer admin".into()));
     }
+    transfer_admin_to(&state, room, &req.user_id)
+        .await
+        .map_err(AppError::bad_request)?;
+
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            &room_code,
+            WebSocketMessage::RoomUpdated { room: room.clone() },
+        )
+        .await;
+    state
+        .websocket_manager
+        .broadcast_to_lobby(WebSocketMessage::RoomInfoUpdated {
+            room_info: RoomInfo::from(&*room),
+        });
 
-    // Cannot kick the admin themselves
-    if player_id == admin_id {
-        return Err(AppError::bad_request("Admin cannot kick themselves".into()));
+    tracing::info!(
+        "Admin {} handed room {} to {}",
+        admin_id,
+        room_code,
+        req.user_id
+    );
+
+    Ok(Json(room.clone()))
+}
+
+/// Choose what moderators may do (admin only)
+pub async fn update_moderator_permissions(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(room_code): Path<String>,
+    Json(req): Json<ModeratorPermissionsRequest>,
+) -> Result<Json<GameRoom>, AppError> {
+    let admin_id = user.id.unwrap().to_hex();
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    if room.admin_id != admin_id {
+        return Err(AppError::forbidden(
+            "Only admin can change moderator permissions".into(),
+        ));
     }
 
-    // Remove the player
-    room.participants.remove(&player_id);
+    set_moderator_permissions(room, req.permissions);
+
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            &room_code,
+            WebSocketMessage::RoomUpdated { room: room.clone() },
+        )
+        .await;
+
+    Ok(Json(room.clone()))
+}
+
+/// Remove a kicked or banned participant and tell the room why. The kicked
+/// user's connections get the message, then stop following the room.
+pub(crate) async fn remove_kicked(
+    state: &AppState,
+    room: &mut GameRoom,
+    user_id: &str,
+    kicked_by: &str,
+    reason: Option<String>,
+    banned: bool,
+) {
+    if room.participants.remove(user_id).is_none() {
+        return;
+    }
     room.updated_at = Utc::now();
 
-    // Broadcast kick notification
+    let room_code = room.room_code.clone();
     state
         .websocket_manager
         .broadcast_to_room(
             &room_code,
             WebSocketMessage::UserKicked {
-                user_id: player_id.to_string(),
-                kicked_by: admin_id.clone(),
+                user_id: user_id.to_string(),
+                kicked_by: kicked_by.to_string(),
+                reason,
+                banned,
             },
         )
         .await;
 
-    // Broadcast updated room state
     state
         .websocket_manager
         .broadcast_to_room(
@@ -402,12 +825,184 @@ pub async fn kick_player(
         )
         .await;
 
+    state
+        .websocket_manager
+        .send_to_user(
+            user_id,
+            UserControl::LeaveRoom {
+                room_code: room_code.clone(),
+            },
+        )
+        .await;
+
+    // The caller holds the rooms lock, which is taken after the engine's
+    let state = state.clone();
+    let user_id = user_id.to_string();
+    tokio::spawn(async move { leave_teams(&state, &room_code, &user_id).await });
+}
+
+/// Take a player who left the room out of the game's teams
+async fn leave_teams(state: &AppState, room_code: &str, user_id: &str) {
+    let engines = GAME_ENGINES.read().await;
+    let Some(engine) = engines.get(room_code) else {
+        return;
+    };
+    let mut engine = engine.write().await;
+    if engine.team_manager.remove_player(user_id).is_none() {
+        return;
+    }
+
+    let teams = engine.team_manager.get_teams().to_vec();
+    state
+        .websocket_manager
+        .broadcast_to_room(room_code, WebSocketMessage::TeamsUpdated { teams })
+        .await;
+}
+
+/// Kick a player from the room (admin, or moderators allowed to kick)
+pub async fn kick_player(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path((room_code, player_id)): Path<(String, String)>,
+    req: Option<Json<KickRequest>>,
+) -> Result<StatusCode, AppError> {
+    let kicker_id = user.id.unwrap().to_hex();
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    if !room.has_permission(&kicker_id, ModeratorPermission::Kick) {
+        return Err(AppError::forbidden(
+            "Only admin or moderators can kick players".into(),
+        ));
+    }
+
+    // Check if the player exists in the room
+    let Some(player) = room.participants.get(&player_id) else {
+        return Err(AppError::not_found("Player not found in room".into()));
+    };
+
+    if player_id == kicker_id {
+        return Err(AppError::bad_request("You cannot kick yourself".into()));
+    }
+
+    // Moderators can't kick the admin or each other
+    if kicker_id != room.admin_id && matches!(player.role, UserRole::Admin | UserRole::Moderator) {
+        return Err(AppError::forbidden(
+            "Moderators can only kick players and spectators".into(),
+        ));
+    }
+
+    let reason = req.and_then(|Json(req)| req.reason);
+    remove_kicked(&state, room, &player_id, &kicker_id, reason, false).await;
+
     tracing::info!(
-        "Admin {} kicked player {} from room {}",
-        admin_id,
+        "User {} kicked player {} from room {}",
+        kicker_id,
         player_id,
         room_code
     );
 
     Ok(StatusCode::OK)
 }
+
+/// Ban a user from the room, kicking them if they are in it (admin only)
+pub async fn ban_player(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(room_code): Path<String>,
+    Json(req): Json<BanRequest>,
+) -> Result<Json<Ban>, AppError> {
+    let admin_id = user.id.unwrap().to_hex();
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    if room.admin_id != admin_id {
+        return Err(AppError::forbidden("Only admin can ban players".into()));
+    }
+    if req.user_id == admin_id {
+        return Err(AppError::bad_request("Admin cannot ban themselves".into()));
+    }
+
+    let ban = bans::new_ban(req, &admin_id).map_err(AppError::bad_request)?;
+    room.bans.insert(ban.user_id.clone(), ban.clone());
+    remove_kicked(
+        &state,
+        room,
+        &ban.user_id,
+        &admin_id,
+        ban.reason.clone(),
+        true,
+    )
+    .await;
+
+    tracing::info!(
+        "Admin {} banned user {} from room {}",
+        admin_id,
+        ban.user_id,
+        room_code
+    );
+
+    Ok(Json(ban))
+}
+
+/// List the room's bans that haven't expired (admin only)
+pub async fn list_room_bans(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(room_code): Path<String>,
+) -> Result<Json<Vec<Ban>>, AppError> {
+    let admin_id = user.id.unwrap().to_hex();
+    let rooms = state.rooms.read().await;
+
+    let room = rooms
+        .get(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    if room.admin_id != admin_id {
+        return Err(AppError::forbidden("Only admin can see bans".into()));
+    }
+
+    Ok(Json(
+        room.bans
+            .values()
+            .filter(|ban| ban.is_active())
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// Lift a ban from the room (admin only)
+pub async fn unban_player(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path((room_code, user_id)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    let admin_id = user.id.unwrap().to_hex();
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(&room_code)
+        .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+
+    if room.admin_id != admin_id {
+        return Err(AppError::forbidden("Only admin can lift bans".into()));
+    }
+    if room.bans.remove(&user_id).is_none() {
+        return Err(AppError::not_found("Ban not found".into()));
+    }
+
+    tracing::info!(
+        "Admin {} lifted the ban of {} in room {}",
+        admin_id,
+        user_id,
+        room_code
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -18,13 +18,15 @@ pub async fn test_join_room(
     // Create a test user
     let test_user = User {
         id: Some(ObjectId::new()),
-        twitch_id: "test_user_2".to_string(),
+        identities: Vec::new(),
         username: "test_player".to_string(),
         display_name: "Test Player".to_string(),
         profile_image_url: Some("https://via.placeholder.com/150".to_string()),
         email: Some("test@example.com".to_string()),
         created_at: Utc::now(),
         updated_at: Utc::now(),
+        is_guest: false,
+        guest_expires_at: None,
     };
 
     let user_id = test_user.id.unwrap().to_hex();
@@ -35,7 +37,7 @@ pub async fn test_join_room(
         .ok_or_else(|| AppError::not_found("Room not found".into()))?;
 
     // Check if room is full
-    if room.participants.len() >= room.max_players as usize {
+    if room.player_count() >= room.max_players as usize {
         return Err(AppError::bad_request("Room is full".into()));
     }
 
@@ -48,7 +50,9 @@ pub async fn test_join_room(
         role: UserRole::Player,
         team_id: None,
         is_connected: true,
+        is_afk: false,
         joined_at: Utc::now(),
+        bot: None,
     };
 
     room.participants.insert(user_id, participant);
@@ -0,0 +1,224 @@
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, RwLock};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use shared::models::{GameSettings, User};
+
+use crate::websocket::game::handle_audience_message;
+use crate::AppState;
+
+// Twitch chat (TMI) over plain IRC; override with TWITCH_IRC_ADDR
+pub const DEFAULT_IRC_ADDR: &str = "irc.chat.twitch.tv:6667";
+
+// Reconnect backoff bounds
+const INITIAL_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+/// Message typed by a viewer in chat
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChatMessage {
+    pub user: String,
+    pub text: String,
+}
+
+/// IRC lines the connector cares about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IrcLine {
+    Ping(String),
+    Privmsg {
+        channel: String,
+        user: String,
+        text: String,
+    },
+    Other,
+}
+
+/// Parse a single IRC line, with optional IRCv3 tags
+pub fn parse_line(line: &str) -> IrcLine {
+    let mut rest = line.trim_end_matches(['\r', '\n']);
+
+    // @display-name=Foo;color=... :prefix COMMAND params
+    let mut display_name = None;
+    if let Some(tagged) = rest.strip_prefix('@') {
+        let Some((tags, tail)) = tagged.split_once(' ') else {
+            return IrcLine::Other;
+        };
+        display_name = tags
+            .split(';')
+            .find_map(|tag| tag.strip_prefix("display-name="))
+            .filter(|name| !name.is_empty())
+            .map(str::to_string);
+        rest = tail;
+    }
+
+    let mut nick = None;
+    if let Some(prefixed) = rest.strip_prefix(':') {
+        let Some((prefix, tail)) = prefixed.split_once(' ') else {
+            return IrcLine::Other;
+        };
+        nick = prefix.split('!').next().map(str::to_string);
+        rest = tail;
+    }
+
+    let (command, params) = rest.split_once(' ').unwrap_or((rest, ""));
+    match command {
+        "PING" => IrcLine::Ping(params.trim_start_matches(':').to_string()),
+        "PRIVMSG" => {
+            let Some((channel, text)) = params.split_once(" :") else {
+                return IrcLine::Other;
+            };
+            match display_name.or(nick) {
+                Some(user) => IrcLine::Privmsg {
+                    channel: channel.trim_start_matches('#').to_lowercase(),
+                    user,
+                    text: text.to_string(),
+                },
+                None => IrcLine::Other,
+            }
+        }
+        _ => IrcLine::Other,
+    }
+}
+
+/// Join a channel anonymously and forward its messages until the
+/// connection closes
+pub async fn run_chat_client(
+    addr: &str,
+    channel: &str,
+    messages: mpsc::Sender<ChatMessage>,
+) -> Result<(), String> {
+    let channel = channel.trim_start_matches('#').to_lowercase();
+    let stream = TcpStream::connect(addr)
+        .await
+        .map_err(|e| format!("Failed to connect to chat: {}", e))?;
+    let (reader, mut writer) = stream.into_split();
+
+    // justinfan<digits> is Twitch's read-only anonymous login
+    let nick = format!("justinfan{}", rand::thread_rng().gen_range(10000..100000));
+    let handshake = format!(
+        "CAP REQ :twitch.tv/tags\r\nNICK {}\r\nJOIN #{}\r\n",
+        nick, channel
+    );
+    writer
+        .write_all(handshake.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to join chat: {}", e))?;
+
+    info!("Joined Twitch chat #{} as {}", channel, nick);
+
+    let mut lines = BufReader::new(reader).lines();
+    while let Some(line) = lines
+        .next_line()
+        .await
+        .map_err(|e| format!("Chat connection error: {}", e))?
+    {
+        match parse_line(&line) {
+            IrcLine::Ping(server) => {
+                writer
+                    .write_all(format!("PONG :{}\r\n", server).as_bytes())
+                    .await
+                    .map_err(|e| format!("Chat connection error: {}", e))?;
+            }
+            IrcLine::Privmsg {
+                channel: from,
+                user,
+                text,
+            } if from == channel => {
+                messages
+                    .send(ChatMessage { user, text })
+                    .await
+                    .map_err(|_| "Chat receiver closed".to_string())?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+// Running connectors, stopped when dropped
+struct ChatConnector {
+    client: JoinHandle<()>,
+    relay: JoinHandle<()>,
+}
+
+impl Drop for ChatConnector {
+    fn drop(&mut self) {
+        self.client.abort();
+        self.relay.abort();
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref CHAT_CONNECTORS: Arc<RwLock<HashMap<String, ChatConnector>>> = Arc::new(RwLock::new(HashMap::new()));
+}
+
+/// Connect a room's game to the streamer's chat, replacing any earlier connector
+pub async fn start_connector(room_code: &str, channel: &str, state: AppState) {
+    let addr = std::env::var("TWITCH_IRC_ADDR").unwrap_or_else(|_| DEFAULT_IRC_ADDR.to_string());
+    let (sender, mut receiver) = mpsc::channel::<ChatMessage>(256);
+
+    let chat_channel = channel.to_string();
+    let client = tokio::spawn(async move {
+        let mut delay = INITIAL_RECONNECT_DELAY;
+        loop {
+            let result = run_chat_client(&addr, &chat_channel, sender.clone()).await;
+            if sender.is_closed() {
+                break;
+            }
+            match result {
+                Ok(()) => warn!("Twitch chat #{} disconnected", chat_channel),
+                Err(e) => warn!("Twitch chat #{}: {}", chat_channel, e),
+            }
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    });
+
+    let relay_room = room_code.to_string();
+    let relay = tokio::spawn(async move {
+        while let Some(message) = receiver.recv().await {
+            handle_audience_message(&message, &relay_room, &state).await;
+        }
+    });
+
+    CHAT_CONNECTORS
+        .write()
+        .await
+        .insert(room_code.to_string(), ChatConnector { client, relay });
+
+    info!("Twitch chat #{} connected to room {}", channel, room_code);
+}
+
+/// Connect chat for a new game if its settings ask for it.
+/// Defaults to the admin's own channel (guests have none).
+pub async fn connect_game(
+    room_code: &str,
+    settings: &GameSettings,
+    admin: &User,
+    state: &AppState,
+) {
+    let Some(chat) = &settings.twitch_chat else {
+        return;
+    };
+    let own_channel = admin
+        .identity("twitch")
+        .map(|identity| identity.username.as_str());
+    match chat.channel.as_deref().or(own_channel) {
+        Some(channel) => start_connector(room_code, channel, state.clone()).await,
+        None => warn!("Room {} has no Twitch channel to connect", room_code),
+    }
+}
+
+/// Disconnect a room from chat
+pub async fn stop_connector(room_code: &str) {
+    if CHAT_CONNECTORS.write().await.remove(room_code).is_some() {
+        info!("Twitch chat disconnected from room {}", room_code);
+    }
+}
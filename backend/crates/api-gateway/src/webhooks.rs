@@ -0,0 +1,669 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::Sha256;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use shared::models::{
+    CreateWebhookRequest, CreateWebhookResponse, DeliveryStatus, GameState, User, Webhook,
+    WebhookDelivery, WebhookEvent, WebhookPayload,
+};
+
+use crate::error::AppError;
+use crate::AppState;
+
+pub const SIGNATURE_HEADER: &str = "X-Alias-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Alias-Timestamp";
+pub const EVENT_HEADER: &str = "X-Alias-Event";
+
+const MAX_WEBHOOKS_PER_USER: usize = 10;
+// Deliveries kept per webhook, oldest dropped first
+const DELIVERY_LOG_SIZE: usize = 50;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Default retry policy: 5 attempts, waiting 1s, 2s, 4s, 8s in between
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Sign a payload: hex HMAC-SHA256 of "<timestamp>.<body>"
+pub fn sign(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn random_id(prefix: &str, len: usize) -> String {
+    let suffix: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect();
+    format!("{}{}", prefix, suffix)
+}
+
+/// Whether an address is reachable on the public internet. Private,
+/// loopback, link-local (cloud metadata included) and other special ranges
+/// are not.
+pub fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_ipv4(mapped),
+            None => is_public_ipv6(ip),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0 // "This network"
+        || (a == 100 && (64..128).contains(&b)) // Carrier-grade NAT
+        || (a == 198 && (18..20).contains(&b)) // Benchmarking
+        || a >= 240) // Reserved
+}
+
+fn is_public_ipv6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || (first & 0xfe00) == 0xfc00 // Unique local
+        || (first & 0xffc0) == 0xfe80 // Link-local
+        || (first & 0xffc0) == 0xfec0 // Site-local
+        || first == 0x2001 && ip.segments()[1] == 0x0db8) // Documentation
+}
+
+/// Why a webhook can't be delivered to its URL
+#[derive(Debug)]
+pub enum TargetError {
+    Invalid(String),     // Not an http(s) URL with a host
+    Forbidden(String),   // Points at a private or otherwise internal address
+    Unreachable(String), // Couldn't reach the host, maybe only for now
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetError::Invalid(message)
+            | TargetError::Forbidden(message)
+            | TargetError::Unreachable(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Resolve a webhook URL, making sure every address of its host is public.
+/// Returns the host and the addresses deliveries should connect to, so a
+/// later DNS answer can't point them somewhere else.
+pub async fn resolve_public_target(url: &str) -> Result<(String, Vec<SocketAddr>), TargetError> {
+    let url = reqwest::Url::parse(url)
+        .map_err(|e| TargetError::Invalid(format!("Invalid webhook URL: {}", e)))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(TargetError::Invalid(
+            "Webhook URL must be http(s)".to_string(),
+        ));
+    }
+    let host = url
+        .host_str()
+        .ok_or_else(|| TargetError::Invalid("Webhook URL needs a host".to_string()))?;
+    let port = url.port_or_known_default().unwrap_or(80);
+    // IPv6 hosts come in brackets
+    let bare_host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs: Vec<SocketAddr> = match bare_host.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => tokio::net::lookup_host((bare_host, port))
+            .await
+            .map_err(|e| TargetError::Unreachable(format!("Could not resolve {}: {}", host, e)))?
+            .collect(),
+    };
+
+    if addrs.is_empty() {
+        return Err(TargetError::Unreachable(
+            "Webhook host has no addresses".to_string(),
+        ));
+    }
+    if addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        return Err(TargetError::Forbidden(
+            "Webhook URL must point to a public address".to_string(),
+        ));
+    }
+    Ok((bare_host.to_string(), addrs))
+}
+
+fn http_client() -> reqwest::ClientBuilder {
+    // Redirects could lead to internal addresses
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+}
+
+// Webhook together with its signing secret
+struct Subscription {
+    webhook: Webhook,
+    secret: String,
+}
+
+/// Outgoing webhook subscriptions and their delivery logs
+pub struct WebhookManager {
+    subscriptions: Arc<RwLock<HashMap<String, Subscription>>>,
+    deliveries: Arc<RwLock<HashMap<String, VecDeque<WebhookDelivery>>>>,
+    client: reqwest::Client,
+    max_attempts: u32,
+    base_delay: Duration,
+    allow_private: bool,
+}
+
+impl WebhookManager {
+    pub fn new() -> Self {
+        Self::with_retry_policy(DEFAULT_MAX_ATTEMPTS, DEFAULT_BASE_DELAY)
+    }
+
+    /// Manager with custom retries; the delay doubles after every failed attempt
+    pub fn with_retry_policy(max_attempts: u32, base_delay: Duration) -> Self {
+        Self {
+            subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            deliveries: Arc::new(RwLock::new(HashMap::new())),
+            client: http_client()
+                .build()
+                .expect("Failed to build webhook HTTP client"),
+            max_attempts: max_attempts.max(1),
+            base_delay,
+            allow_private: false,
+        }
+    }
+
+    /// Also deliver to private and local addresses, e.g. for local development
+    pub fn allowing_private_addresses(mut self) -> Self {
+        self.allow_private = true;
+        self
+    }
+
+    pub async fn register(
+        &self,
+        owner_id: &str,
+        request: CreateWebhookRequest,
+    ) -> Result<CreateWebhookResponse, String> {
+        if !self.allow_private {
+            resolve_public_target(&request.url)
+                .await
+                .map_err(|e| e.to_string())?;
+        } else if !request.url.starts_with("http://") && !request.url.starts_with("https://") {
+            return Err("Webhook URL must be http(s)".to_string());
+        }
+        if request.events.is_empty() {
+            return Err("Subscribe to at least one event".to_string());
+        }
+        if request.events.contains(&WebhookEvent::Ping) {
+            return Err("Ping is only sent by test deliveries".to_string());
+        }
+
+        let mut subscriptions = self.subscriptions.write().await;
+        let owned = subscriptions
+            .values()
+            .filter(|s| s.webhook.owner_id == owner_id)
+            .count();
+        if owned >= MAX_WEBHOOKS_PER_USER {
+            return Err(format!(
+                "At most {} webhooks per user",
+                MAX_WEBHOOKS_PER_USER
+            ));
+        }
+
+        let mut events: Vec<WebhookEvent> = Vec::new();
+        for event in request.events {
+            if !events.contains(&event) {
+                events.push(event);
+            }
+        }
+
+        let webhook = Webhook {
+            id: random_id("wh_", 16),
+            owner_id: owner_id.to_string(),
+            room_code: request.room_code,
+            url: request.url,
+            events,
+            created_at: Utc::now(),
+        };
+        let secret = random_id("whsec_", 32);
+
+        subscriptions.insert(
+            webhook.id.clone(),
+            Subscription {
+                webhook: webhook.clone(),
+                secret: secret.clone(),
+            },
+        );
+
+        info!("Webhook {} registered by user {}", webhook.id, owner_id);
+
+        Ok(CreateWebhookResponse { webhook, secret })
+    }
+
+    pub async fn list(&self, owner_id: &str) -> Vec<Webhook> {
+        let subscriptions = self.subscriptions.read().await;
+        let mut webhooks: Vec<Webhook> = subscriptions
+            .values()
+            .filter(|s| s.webhook.owner_id == owner_id)
+            .map(|s| s.webhook.clone())
+            .collect();
+        webhooks.sort_by_key(|w| w.created_at);
+        webhooks
+    }
+
+    /// Remove a webhook; returns false if the user has no such webhook
+    pub async fn remove(&self, owner_id: &str, webhook_id: &str) -> bool {
+        let mut subscriptions = self.subscriptions.write().await;
+        if subscriptions
+            .get(webhook_id)
+            .is_none_or(|s| s.webhook.owner_id != owner_id)
+        {
+            return false;
+        }
+        subscriptions.remove(webhook_id);
+        self.deliveries.write().await.remove(webhook_id);
+        true
+    }
+
+    /// Delivery log of a webhook, newest first
+    pub async fn deliveries(
+        &self,
+        owner_id: &str,
+        webhook_id: &str,
+    ) -> Option<Vec<WebhookDelivery>> {
+        if !self.is_owner(owner_id, webhook_id).await {
+            return None;
+        }
+        let deliveries = self.deliveries.read().await;
+        Some(
+            deliveries
+                .get(webhook_id)
+                .map(|log| log.iter().rev().cloned().collect())
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Send a ping to a webhook once and wait for the result
+    pub async fn test_delivery(&self, owner_id: &str, webhook_id: &str) -> Option<WebhookDelivery> {
+        if !self.is_owner(owner_id, webhook_id).await {
+            return None;
+        }
+        let target = self.target(webhook_id).await?;
+        let data = serde_json::json!({ "message": "Test delivery" });
+        let delivery = self
+            .start_delivery(&target.0, WebhookEvent::Ping, None, data)
+            .await;
+        Some(self.worker(1).deliver(target, delivery).await)
+    }
+
+    /// Queue an event for every matching webhook of the room admin
+    pub async fn dispatch(
+        &self,
+        event: WebhookEvent,
+        room_code: &str,
+        admin_id: &str,
+        data: serde_json::Value,
+    ) {
+        let targets: Vec<(Webhook, String)> = {
+            let subscriptions = self.subscriptions.read().await;
+            subscriptions
+                .values()
+                .filter(|s| {
+                    s.webhook.owner_id == admin_id
+                        && s.webhook.events.contains(&event)
+                        && s.webhook
+                            .room_code
+                            .as_deref()
+                            .is_none_or(|code| code == room_code)
+                })
+                .map(|s| (s.webhook.clone(), s.secret.clone()))
+                .collect()
+        };
+
+        for target in targets {
+            let delivery = self
+                .start_delivery(&target.0, event, Some(room_code), data.clone())
+                .await;
+            let worker = self.worker(self.max_attempts);
+            tokio::spawn(async move {
+                worker.deliver(target, delivery).await;
+            });
+        }
+    }
+
+    async fn is_owner(&self, owner_id: &str, webhook_id: &str) -> bool {
+        self.subscriptions
+            .read()
+            .await
+            .get(webhook_id)
+            .is_some_and(|s| s.webhook.owner_id == owner_id)
+    }
+
+    async fn target(&self, webhook_id: &str) -> Option<(Webhook, String)> {
+        self.subscriptions
+            .read()
+            .await
+            .get(webhook_id)
+            .map(|s| (s.webhook.clone(), s.secret.clone()))
+    }
+
+    /// Log a new pending delivery and build its payload
+    async fn start_delivery(
+        &self,
+        webhook: &Webhook,
+        event: WebhookEvent,
+        room_code: Option<&str>,
+        data: serde_json::Value,
+    ) -> WebhookPayload {
+        let payload = WebhookPayload {
+            delivery_id: random_id("dlv_", 16),
+            event,
+            room_code: room_code.map(str::to_string),
+            created_at: Utc::now(),
+            data,
+        };
+
+        let mut deliveries = self.deliveries.write().await;
+        let log = deliveries.entry(webhook.id.clone()).or_default();
+        log.push_back(WebhookDelivery {
+            id: payload.delivery_id.clone(),
+            webhook_id: webhook.id.clone(),
+            event,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            response_status: None,
+            error: None,
+            created_at: payload.created_at,
+            completed_at: None,
+        });
+        while log.len() > DELIVERY_LOG_SIZE {
+            log.pop_front();
+        }
+
+        payload
+    }
+
+    fn worker(&self, max_attempts: u32) -> DeliveryWorker {
+        DeliveryWorker {
+            deliveries: self.deliveries.clone(),
+            client: self.client.clone(),
+            max_attempts,
+            base_delay: self.base_delay,
+            allow_private: self.allow_private,
+        }
+    }
+}
+
+impl Default for WebhookManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Sends one payload with retries, updating the delivery log as it goes
+struct DeliveryWorker {
+    deliveries: Arc<RwLock<HashMap<String, VecDeque<WebhookDelivery>>>>,
+    client: reqwest::Client,
+    max_attempts: u32,
+    base_delay: Duration,
+    allow_private: bool,
+}
+
+impl DeliveryWorker {
+    /// Client for one attempt, pinned to the checked public addresses of
+    /// the webhook's host
+    async fn client_for(&self, url: &str) -> Result<reqwest::Client, TargetError> {
+        if self.allow_private {
+            return Ok(self.client.clone());
+        }
+        let (host, addrs) = resolve_public_target(url).await?;
+        http_client()
+            .resolve_to_addrs(&host, &addrs)
+            .build()
+            .map_err(|e| TargetError::Invalid(format!("Could not build client: {}", e)))
+    }
+
+    async fn deliver(
+        self,
+        (webhook, secret): (Webhook, String),
+        payload: WebhookPayload,
+    ) -> WebhookDelivery {
+        let body = serde_json::to_vec(&payload).unwrap_or_default();
+        let mut delay = self.base_delay;
+
+        for attempt in 1..=self.max_attempts {
+            let timestamp = Utc::now().timestamp();
+            // Checked again on every attempt, as DNS may have changed since
+            let result = match self.client_for(&webhook.url).await {
+                Ok(client) => client
+                    .post(&webhook.url)
+                    .header("Content-Type", "application/json")
+                    .header(EVENT_HEADER, payload.event.as_str())
+                    .header(TIMESTAMP_HEADER, timestamp.to_string())
+                    .header(SIGNATURE_HEADER, sign(&secret, timestamp, &body))
+                    .body(body.clone())
+                    .send()
+                    .await
+                    .map_err(|e| TargetError::Unreachable(format!("Request failed: {}", e))),
+                Err(e) => Err(e),
+            };
+
+            let (response_status, error, retryable) = match result {
+                Ok(response) if response.status().is_success() => {
+                    let status = response.status().as_u16();
+                    return self
+                        .record(&webhook.id, &payload, |d| {
+                            d.attempts = attempt;
+                            d.status = DeliveryStatus::Delivered;
+                            d.response_status = Some(status);
+                            d.error = None;
+                            d.completed_at = Some(Utc::now());
+                        })
+                        .await;
+                }
+                Ok(response) => {
+                    let status = response.status();
+                    // Client errors won't fix themselves, except timeouts and rate limits
+                    let retryable = status.is_server_error()
+                        || status == reqwest::StatusCode::REQUEST_TIMEOUT
+                        || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+                    (
+                        Some(status.as_u16()),
+                        format!("Endpoint responded with {}", status),
+                        retryable,
+                    )
+                }
+                Err(TargetError::Unreachable(error)) => (None, error, true),
+                Err(e) => (None, e.to_string(), false),
+            };
+
+            let last_attempt = attempt == self.max_attempts || !retryable;
+            let delivery = self
+                .record(&webhook.id, &payload, |d| {
+                    d.attempts = attempt;
+                    d.response_status = response_status;
+                    d.error = Some(error.clone());
+                    if last_attempt {
+                        d.status = DeliveryStatus::Failed;
+                        d.completed_at = Some(Utc::now());
+                    }
+                })
+                .await;
+
+            if last_attempt {
+                warn!(
+                    "Webhook {} delivery {} failed after {} attempts: {}",
+                    webhook.id, payload.delivery_id, attempt, error
+                );
+                return delivery;
+            }
+
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+
+        unreachable!("max_attempts is at least 1")
+    }
+
+    /// Update the logged delivery after an attempt
+    async fn record(
+        &self,
+        webhook_id: &str,
+        payload: &WebhookPayload,
+        update: impl FnOnce(&mut WebhookDelivery),
+    ) -> WebhookDelivery {
+        let mut deliveries = self.deliveries.write().await;
+        match deliveries
+            .get_mut(webhook_id)
+            .and_then(|log| log.iter_mut().find(|d| d.id == payload.delivery_id))
+        {
+            Some(delivery) => {
+                update(delivery);
+                delivery.clone()
+            }
+            // Webhook was removed or the entry rotated out of the log
+            None => {
+                let mut delivery = WebhookDelivery {
+                    id: payload.delivery_id.clone(),
+                    webhook_id: webhook_id.to_string(),
+                    event: payload.event,
+                    status: DeliveryStatus::Pending,
+                    attempts: 0,
+                    response_status: None,
+                    error: None,
+                    created_at: payload.created_at,
+                    completed_at: None,
+                };
+                update(&mut delivery);
+                delivery
+            }
+        }
+    }
+}
+
+/// Payload data of a game_started event
+pub fn game_started_data(game_state: &GameState) -> serde_json::Value {
+    serde_json::json!({
+        "teams": game_state.teams,
+        "settings": game_state.settings,
+        "started_at": game_state.started_at,
+    })
+}
+
+/// Fire a room event for the webhooks of the room's admin
+pub async fn notify_room(
+    state: &AppState,
+    room_code: &str,
+    event: WebhookEvent,
+    data: serde_json::Value,
+) {
+    let admin_id = match state.rooms.read().await.get(room_code) {
+        Some(room) => room.admin_id.clone(),
+        None => return,
+    };
+    state
+        .webhook_manager
+        .dispatch(event, room_code, &admin_id, data)
+        .await;
+}
+
+/// Register a webhook
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Json(req): Json<CreateWebhookRequest>,
+) -> Result<Json<CreateWebhookResponse>, AppError> {
+    let user_id = user.id.unwrap().to_hex();
+
+    // Room webhooks are for the room admin only
+    if let Some(room_code) = &req.room_code {
+        let rooms = state.rooms.read().await;
+        let room = rooms
+            .get(room_code)
+            .ok_or_else(|| AppError::not_found("Room not found".into()))?;
+        if room.admin_id != user_id {
+            return Err(AppError::forbidden(
+                "Only admin can add room webhooks".into(),
+            ));
+        }
+    }
+
+    let response = state
+        .webhook_manager
+        .register(&user_id, req)
+        .await
+        .map_err(AppError::bad_request)?;
+
+    Ok(Json(response))
+}
+
+/// List the user's webhooks
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+) -> Result<Json<Vec<Webhook>>, AppError> {
+    let user_id = user.id.unwrap().to_hex();
+    Ok(Json(state.webhook_manager.list(&user_id).await))
+}
+
+/// Delete a webhook
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(webhook_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    let user_id = user.id.unwrap().to_hex();
+    if !state.webhook_manager.remove(&user_id, &webhook_id).await {
+        return Err(AppError::not_found("Webhook not found".into()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delivery log of a webhook
+pub async fn list_deliveries(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(webhook_id): Path<String>,
+) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    let user_id = user.id.unwrap().to_hex();
+    state
+        .webhook_manager
+        .deliveries(&user_id, &webhook_id)
+        .await
+        .map(Json)
+        .ok_or_else(|| AppError::not_found("Webhook not found".into()))
+}
+
+/// Send a test ping and return its delivery result
+pub async fn test_webhook(
+    State(state): State<AppState>,
+    Extension(user): Extension<User>,
+    Path(webhook_id): Path<String>,
+) -> Result<Json<WebhookDelivery>, AppError> {
+    let user_id = user.id.unwrap().to_hex();
+    state
+        .webhook_manager
+        .test_delivery(&user_id, &webhook_id)
+        .await
+        .map(Json)
+        .ok_or_else(|| AppError::not_found("Webhook not found".into()))
+}
@@ -5,26 +5,181 @@ use axum::{
     },
     response::Response,
 };
-use futures_util::{SinkExt, StreamExt};
-use serde_json;
-use std::collections::HashMap;
+use futures_util::{Sink, SinkExt, Stream, StreamExt};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::select;
-use tokio::sync::{broadcast, RwLock};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, watch, RwLock};
+use tokio::time::Instant;
 use tracing::{error, info, warn};
 
 use crate::auth_middleware;
+use crate::bans;
+use crate::bots;
+use crate::delta::DeltaEncoder;
+use crate::game::GAME_ENGINES;
+use crate::metrics::WebSocketMetrics;
+use crate::presence::{self, Heartbeat};
+use crate::protocol::{self, Codec, InvalidFrame};
+use crate::rooms;
+use crate::twitch_chat;
 use crate::AppState;
-use shared::models::{User, UserInfo, UserRole, WebSocketMessage};
+use shared::errors::{ErrorCode, ProtocolError};
+use shared::models::{
+    BanRequest, BotSettings, GameRoom, ModeratorPermission, RequestEnvelope, RoomInfo,
+    RoomVisibility, SequencedMessage, User, UserInfo, UserRole, WebSocketMessage,
+};
+
+pub(crate) mod game;
 
-mod game;
+// How long a disconnected admin keeps the room before it is handed on
+const ADMIN_HANDOFF_GRACE: Duration = Duration::from_secs(30);
+
+// Broadcasts kept per room for clients resuming after a reconnect
+pub const REPLAY_BUFFER_SIZE: usize = 256;
 
 // WebSocket connection manager
 pub struct WebSocketManager {
-    // Room ID -> broadcast sender for that room
-    pub room_senders: Arc<RwLock<HashMap<String, broadcast::Sender<WebSocketMessage>>>>,
+    // Room ID -> sequenced broadcast stream for that room
+    room_streams: Arc<RwLock<HashMap<String, RoomStream>>>,
     // Global broadcast channel for lobby events (room creation, etc.)
     pub lobby_sender: broadcast::Sender<WebSocketMessage>,
+    // Room ID -> redacted (and optionally delayed) stream for spectators
+    audience_channels: Arc<RwLock<HashMap<String, AudienceChannel>>>,
+    // User ID -> that user's open connections
+    user_channels: Arc<RwLock<HashMap<String, Vec<UserConnection>>>>,
+    next_connection_id: AtomicU64,
+    // Room ID -> notifies overlay streams when the overlay token is rotated
+    overlay_rotations: Arc<RwLock<HashMap<String, watch::Sender<()>>>>,
+    pub metrics: WebSocketMetrics,
+}
+
+/// One open connection of a user: its control channel, the room it follows
+/// and whether its player has gone idle there
+struct UserConnection {
+    id: u64,
+    control: mpsc::UnboundedSender<UserControl>,
+    room_code: Option<String>,
+    afk: bool,
+}
+
+impl UserConnection {
+    fn in_room(&self, room_code: &str) -> bool {
+        !self.control.is_closed() && self.room_code.as_deref() == Some(room_code)
+    }
+}
+
+/// Instructions for a user's own connections, from outside their socket
+#[derive(Debug, Clone)]
+pub enum UserControl {
+    /// Stop following a room the user was kicked or banned from
+    LeaveRoom { room_code: String },
+    /// A message for this user alone, while they are in the room
+    Send {
+        room_code: String,
+        message: Box<WebSocketMessage>,
+    },
+}
+
+// Player stream of a room with its most recent broadcasts
+struct RoomStream {
+    sender: broadcast::Sender<SequencedMessage>,
+    last_seq: u64,
+    recent: VecDeque<SequencedMessage>,
+}
+
+impl RoomStream {
+    fn new() -> Self {
+        let (sender, _) = broadcast::channel(100);
+        Self {
+            sender,
+            last_seq: 0,
+            recent: VecDeque::with_capacity(REPLAY_BUFFER_SIZE),
+        }
+    }
+
+    fn push(&mut self, message: WebSocketMessage) -> SequencedMessage {
+        self.last_seq += 1;
+        let sequenced = SequencedMessage {
+            seq: self.last_seq,
+            message,
+        };
+        if self.recent.len() == REPLAY_BUFFER_SIZE {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(sequenced.clone());
+        sequenced
+    }
+
+    /// Broadcasts after `last_seq`, or None if some were already dropped
+    fn since(&self, last_seq: u64) -> Option<Vec<SequencedMessage>> {
+        if last_seq > self.last_seq {
+            return None;
+        }
+        let oldest = self.recent.front().map_or(self.last_seq + 1, |m| m.seq);
+        if last_seq + 1 < oldest {
+            return None;
+        }
+        Some(
+            self.recent
+                .iter()
+                .filter(|m| m.seq > last_seq)
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+/// How a reconnecting client catches up with a room
+pub enum Resumption {
+    /// Every missed broadcast, oldest first
+    Replay(Vec<SequencedMessage>),
+    /// Too much was missed; the client needs a snapshot as of `seq`
+    Snapshot { seq: u64 },
+}
+
+// Spectator stream of a room
+struct AudienceChannel {
+    sender: broadcast::Sender<SequencedMessage>,
+    // Queue feeding the sender after the delay, if the room has one
+    delay_queue: Option<(Duration, mpsc::UnboundedSender<(Instant, SequencedMessage)>)>,
+}
+
+impl AudienceChannel {
+    fn new(delay_seconds: u32) -> Self {
+        let (sender, _) = broadcast::channel(100);
+        let delay_queue = (delay_seconds > 0).then(|| {
+            let (queue, mut pending) = mpsc::unbounded_channel::<(Instant, SequencedMessage)>();
+            let delayed_sender = sender.clone();
+            tokio::spawn(async move {
+                // Messages share the same delay, so they stay in order
+                while let Some((due, message)) = pending.recv().await {
+                    tokio::time::sleep_until(due).await;
+                    let _ = delayed_sender.send(message);
+                }
+            });
+            (Duration::from_secs(delay_seconds as u64), queue)
+        });
+
+        Self {
+            sender,
+            delay_queue,
+        }
+    }
+
+    fn send(&self, message: SequencedMessage) {
+        match &self.delay_queue {
+            Some((delay, queue)) => {
+                let _ = queue.send((Instant::now() + *delay, message));
+            }
+            None => {
+                let _ = self.sender.send(message);
+            }
+        }
+    }
 }
 
 impl Default for WebSocketManager {
@@ -37,48 +192,246 @@ impl WebSocketManager {
     pub fn new() -> Self {
         let (lobby_sender, _) = broadcast::channel(100);
         Self {
-            room_senders: Arc::new(RwLock::new(HashMap::new())),
+            room_streams: Arc::new(RwLock::new(HashMap::new())),
             lobby_sender,
+            audience_channels: Arc::new(RwLock::new(HashMap::new())),
+            user_channels: Arc::new(RwLock::new(HashMap::new())),
+            next_connection_id: AtomicU64::new(0),
+            overlay_rotations: Arc::new(RwLock::new(HashMap::new())),
+            metrics: WebSocketMetrics::default(),
         }
     }
 
     pub async fn get_or_create_room_sender(
         &self,
         room_code: &str,
-    ) -> broadcast::Sender<WebSocketMessage> {
-        let mut senders = self.room_senders.write().await;
-        if let Some(sender) = senders.get(room_code) {
-            sender.clone()
-        } else {
-            let (sender, _) = broadcast::channel(100);
-            senders.insert(room_code.to_string(), sender.clone());
-            sender
-        }
+    ) -> broadcast::Sender<SequencedMessage> {
+        let mut streams = self.room_streams.write().await;
+        streams
+            .entry(room_code.to_string())
+            .or_insert_with(RoomStream::new)
+            .sender
+            .clone()
     }
 
     pub async fn broadcast_to_room(&self, room_code: &str, message: WebSocketMessage) {
-        if let Some(sender) = self.room_senders.read().await.get(room_code) {
-            let subscriber_count = sender.receiver_count();
-            info!(
-                "Broadcasting {} message to room {} with {} subscribers",
-                message.type_name(),
-                room_code,
-                subscriber_count
-            );
+        // Held until spectators get their copy so both streams stay in order
+        let mut streams = self.room_streams.write().await;
+        let Some(stream) = streams.get_mut(room_code) else {
+            warn!("No WebSocket channel found for room {}", room_code);
+            return;
+        };
 
-            if let Err(e) = sender.send(message) {
-                warn!("Failed to broadcast message to room {}: {}", room_code, e);
+        info!(
+            "Broadcasting {} message to room {} with {} subscribers",
+            message.type_name(),
+            room_code,
+            stream.sender.receiver_count()
+        );
+
+        let sequenced = stream.push(message);
+        if let Err(e) = stream.sender.send(sequenced.clone()) {
+            warn!("Failed to broadcast message to room {}: {}", room_code, e);
+        }
+
+        // Forward a redacted copy to spectators, under the same number
+        if let Some(channel) = self.audience_channels.read().await.get(room_code) {
+            if let Some(redacted) = sequenced.message.redacted_for_audience() {
+                channel.send(SequencedMessage {
+                    seq: sequenced.seq,
+                    message: redacted,
+                });
             }
-        } else {
-            warn!("No WebSocket channel found for room {}", room_code);
         }
     }
 
+    /// Subscribe to a room again after a reconnect, along with what the
+    /// client missed since `last_seq`. Spectators always get a snapshot.
+    pub async fn resume_room(
+        &self,
+        room_code: &str,
+        last_seq: u64,
+        spectator: bool,
+    ) -> (broadcast::Receiver<SequencedMessage>, Resumption) {
+        let mut streams = self.room_streams.write().await;
+        let stream = streams
+            .entry(room_code.to_string())
+            .or_insert_with(RoomStream::new);
+
+        if spectator {
+            let receiver = self
+                .audience_channels
+                .write()
+                .await
+                .entry(room_code.to_string())
+                .or_insert_with(|| AudienceChannel::new(0))
+                .sender
+                .subscribe();
+            return (
+                receiver,
+                Resumption::Snapshot {
+                    seq: stream.last_seq,
+                },
+            );
+        }
+
+        let resumption = match stream.since(last_seq) {
+            Some(missed) => Resumption::Replay(missed),
+            None => Resumption::Snapshot {
+                seq: stream.last_seq,
+            },
+        };
+        (stream.sender.subscribe(), resumption)
+    }
+
+    /// Sequence number of the room's latest broadcast
+    pub async fn last_seq(&self, room_code: &str) -> u64 {
+        self.room_streams
+            .read()
+            .await
+            .get(room_code)
+            .map_or(0, |stream| stream.last_seq)
+    }
+
+    /// How far the spectator stream of a room runs behind, if at all
+    pub async fn audience_delay(&self, room_code: &str) -> Option<Duration> {
+        let channels = self.audience_channels.read().await;
+        channels
+            .get(room_code)?
+            .delay_queue
+            .as_ref()
+            .map(|(delay, _)| *delay)
+    }
+
+    /// Set up the spectator stream of a room with the given delay
+    pub async fn create_audience_channel(&self, room_code: &str, delay_seconds: u32) {
+        self.audience_channels
+            .write()
+            .await
+            .insert(room_code.to_string(), AudienceChannel::new(delay_seconds));
+    }
+
+    pub async fn get_or_create_audience_sender(
+        &self,
+        room_code: &str,
+    ) -> broadcast::Sender<SequencedMessage> {
+        let mut channels = self.audience_channels.write().await;
+        channels
+            .entry(room_code.to_string())
+            .or_insert_with(|| AudienceChannel::new(0))
+            .sender
+            .clone()
+    }
+
     pub async fn remove_room(&self, room_code: &str) {
-        self.room_senders.write().await.remove(room_code);
+        self.room_streams.write().await.remove(room_code);
+        self.audience_channels.write().await.remove(room_code);
+        self.overlay_rotations.write().await.remove(room_code);
+    }
+
+    /// Changes when the room's overlay token is rotated; closes with the room
+    pub async fn watch_overlay_token(&self, room_code: &str) -> watch::Receiver<()> {
+        let mut rotations = self.overlay_rotations.write().await;
+        rotations
+            .entry(room_code.to_string())
+            .or_insert_with(|| watch::channel(()).0)
+            .subscribe()
+    }
+
+    /// Tell the room's overlay streams their token is no longer valid
+    pub async fn overlay_token_rotated(&self, room_code: &str) {
+        if let Some(rotation) = self.overlay_rotations.read().await.get(room_code) {
+            rotation.send_replace(());
+        }
+    }
+
+    /// Open a control channel for one of the user's connections. Returns the
+    /// connection's ID along with the channel.
+    pub async fn register_user(
+        &self,
+        user_id: &str,
+    ) -> (u64, mpsc::UnboundedReceiver<UserControl>) {
+        let (control, receiver) = mpsc::unbounded_channel();
+        let id = self.next_connection_id.fetch_add(1, Ordering::Relaxed);
+        let mut channels = self.user_channels.write().await;
+        let connections = channels.entry(user_id.to_string()).or_default();
+        connections.retain(|c| !c.control.is_closed());
+        connections.push(UserConnection {
+            id,
+            control,
+            room_code: None,
+            afk: false,
+        });
+        (id, receiver)
+    }
+
+    /// Forget the user's connections that have closed
+    pub async fn unregister_user(&self, user_id: &str) {
+        let mut channels = self.user_channels.write().await;
+        if let Some(connections) = channels.get_mut(user_id) {
+            connections.retain(|c| !c.control.is_closed());
+            if connections.is_empty() {
+                channels.remove(user_id);
+            }
+        }
+    }
+
+    /// Record the room one of the user's connections follows, and whether its
+    /// player has gone idle
+    pub async fn track_connection(
+        &self,
+        user_id: &str,
+        connection_id: u64,
+        room_code: Option<&str>,
+        afk: bool,
+    ) {
+        let mut channels = self.user_channels.write().await;
+        let connection = channels
+            .get_mut(user_id)
+            .and_then(|connections| connections.iter_mut().find(|c| c.id == connection_id));
+        if let Some(connection) = connection {
+            connection.room_code = room_code.map(str::to_string);
+            connection.afk = afk;
+        }
+    }
+
+    /// Whether the user has an open connection following the room
+    pub async fn connected_to_room(&self, user_id: &str, room_code: &str) -> bool {
+        self.user_channels
+            .read()
+            .await
+            .get(user_id)
+            .is_some_and(|connections| connections.iter().any(|c| c.in_room(room_code)))
+    }
+
+    /// Whether the user is active on any of their connections to the room
+    pub async fn active_in_room(&self, user_id: &str, room_code: &str) -> bool {
+        self.user_channels
+            .read()
+            .await
+            .get(user_id)
+            .is_some_and(|connections| connections.iter().any(|c| c.in_room(room_code) && !c.afk))
+    }
+
+    /// Pass a control message to every open connection of the user
+    pub async fn send_to_user(&self, user_id: &str, control: UserControl) {
+        if let Some(connections) = self.user_channels.read().await.get(user_id) {
+            for connection in connections {
+                let _ = connection.control.send(control.clone());
+            }
+        }
     }
 
     pub fn broadcast_to_lobby(&self, message: WebSocketMessage) {
+        // Unlisted and password rooms stay out of the lobby
+        if let WebSocketMessage::RoomCreated { room_info }
+        | WebSocketMessage::RoomInfoUpdated { room_info } = &message
+        {
+            if room_info.visibility != RoomVisibility::Public {
+                return;
+            }
+        }
+
         let subscriber_count = self.lobby_sender.receiver_count();
         info!(
             "Broadcasting {} message to lobby with {} subscribers",
@@ -109,109 +462,364 @@ pub async fn websocket_handler(ws: WebSocketUpgrade, State(state): State<AppStat
 }
 
 async fn handle_socket(socket: WebSocket, state: AppState) {
-    let (mut sender, mut receiver) = socket.split();
+    let (sender, receiver) = socket.split();
+    serve_connection(sender, receiver, state, None).await;
+}
+
+/// Serve one client connection until it closes. `user` is set when the
+/// client was authenticated before the connection was opened.
+pub async fn serve_connection<S, R>(
+    mut sender: S,
+    mut receiver: R,
+    state: AppState,
+    user: Option<User>,
+) where
+    S: Sink<Message, Error = axum::Error> + Unpin,
+    R: Stream<Item = Result<Message, axum::Error>> + Unpin,
+{
     let mut authenticated_user: Option<User> = None;
+    let mut control_receiver: Option<(String, u64, mpsc::UnboundedReceiver<UserControl>)> = None;
     let mut current_room: Option<String> = None;
-    let mut room_receiver: Option<broadcast::Receiver<WebSocketMessage>> = None;
+    let mut tracked_room: Option<String> = None;
+    let mut room_receiver: Option<broadcast::Receiver<SequencedMessage>> = None;
     let mut lobby_receiver: Option<broadcast::Receiver<WebSocketMessage>> = None;
+    let mut deltas: Option<DeltaEncoder> = None;
+    let mut resync: Option<Resync> = None;
+    let mut codec = Codec::default();
+    let mut heartbeat = Heartbeat::default();
+    let mut ping = tokio::time::interval(presence::PING_INTERVAL);
+    ping.tick().await; // The first tick completes immediately
 
     info!("WebSocket connection established");
+    state.websocket_manager.metrics.connection_opened();
+    if let Some(user) = user {
+        lobby_receiver = Some(state.websocket_manager.lobby_sender.subscribe());
+        authenticated_user = Some(user);
+    }
 
     // Main event loop using select! to handle multiple event sources
     loop {
+        // Follow control messages for whoever the socket is authenticated as
+        if let Some(user) = &authenticated_user {
+            let user_id = user.id.unwrap().to_hex();
+            if control_receiver.as_ref().map(|(id, _, _)| id) != Some(&user_id) {
+                let (connection_id, receiver) =
+                    state.websocket_manager.register_user(&user_id).await;
+                control_receiver = Some((user_id, connection_id, receiver));
+                tracked_room = None;
+            }
+        }
+
+        // Let the user's other connections know which room this one follows
+        if let Some((user_id, connection_id, _)) = &control_receiver {
+            if tracked_room != current_room {
+                state
+                    .websocket_manager
+                    .track_connection(
+                        user_id,
+                        *connection_id,
+                        current_room.as_deref(),
+                        heartbeat.is_afk(),
+                    )
+                    .await;
+                tracked_room = current_room.clone();
+            }
+        }
+
         select! {
             // Handle incoming WebSocket messages
             Some(msg) = receiver.next() => {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        match serde_json::from_str::<WebSocketMessage>(&text) {
-                            Ok(ws_msg) => {
-                                match handle_websocket_message(
-                                    ws_msg,
-                                    &mut authenticated_user,
-                                    &mut current_room,
-                                    &mut room_receiver,
-                                    &mut lobby_receiver,
-                                    &state,
-                                )
-                                .await
-                                {
-                                    Ok(Some(response)) => {
-                                        if let Ok(response_text) = serde_json::to_string(&response) {
-                                            if sender.send(Message::Text(response_text)).await.is_err() {
-                                                break;
-                                            }
-                                        }
-                                    }
-                                    Ok(None) => {} // No response needed
-                                    Err(error_msg) => {
-                                        let error_response = WebSocketMessage::Error {
-                                            message: error_msg,
-                                        };
-                                        if let Ok(error_text) = serde_json::to_string(&error_response) {
-                                            if sender.send(Message::Text(error_text)).await.is_err() {
-                                                break;
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                warn!("Failed to parse WebSocket message: {}", e);
-                                let error_response = WebSocketMessage::Error {
-                                    message: "Invalid message format".to_string(),
-                                };
-                                if let Ok(error_text) = serde_json::to_string(&error_response) {
-                                    if sender.send(Message::Text(error_text)).await.is_err() {
-                                        break;
-                                    }
-                                }
-                            }
-                        }
-                    }
+                let frame = match msg {
                     Ok(Message::Close(_)) => {
                         info!("WebSocket connection closed");
                         break;
                     }
+                    Ok(frame) => frame,
                     Err(e) => {
                         error!("WebSocket error: {}", e);
                         break;
                     }
-                    _ => {} // Ignore other message types
+                };
+                heartbeat.seen();
+
+                // Anything but a keepalive means the player is there
+                let decoded = protocol::decode(&frame);
+                if let Some(Ok(RequestEnvelope { message, .. })) = &decoded {
+                    if !matches!(message, WebSocketMessage::Ping) && heartbeat.active() {
+                        if let (Some((user_id, connection_id, _)), Some(room_code)) =
+                            (control_receiver.as_ref(), current_room.as_ref())
+                        {
+                            state
+                                .websocket_manager
+                                .track_connection(user_id, *connection_id, Some(room_code), false)
+                                .await;
+                            presence::set_afk(&state, room_code, user_id, false).await;
+                        }
+                    }
+                }
+
+                let outgoing = match decoded {
+                    None => continue, // Ping and pong frames
+                    Some(Ok(RequestEnvelope {
+                        request_id,
+                        message:
+                            WebSocketMessage::Hello {
+                                protocol_version,
+                                encodings,
+                            },
+                    })) => match Codec::negotiate(protocol_version, &encodings) {
+                        Ok(negotiated) => {
+                            codec = negotiated;
+                            info!(
+                                "Negotiated protocol version {} with {:?} encoding",
+                                codec.version, codec.encoding
+                            );
+                            codec.replies(request_id, vec![codec.welcome()])
+                        }
+                        Err(e) => codec.replies(request_id, vec![e.into()]),
+                    },
+                    // Delta settings and snapshots only concern this connection
+                    Some(Ok(RequestEnvelope {
+                        request_id,
+                        message:
+                            ws_msg @ (WebSocketMessage::SetDeltaUpdates { .. }
+                            | WebSocketMessage::RequestSnapshot),
+                    })) => {
+                        let messages = match handle_sync_request(
+                            ws_msg,
+                            &authenticated_user,
+                            &current_room,
+                            &mut deltas,
+                            &mut resync,
+                            &state,
+                        )
+                        .await
+                        {
+                            Ok(messages) => encode_all(&mut deltas, messages),
+                            Err(e) => vec![e.into()],
+                        };
+                        codec.replies(request_id, messages)
+                    }
+                    Some(Ok(RequestEnvelope { request_id, message })) => {
+                        match handle_websocket_message(
+                            message,
+                            &mut authenticated_user,
+                            &mut current_room,
+                            &mut room_receiver,
+                            &mut lobby_receiver,
+                            &mut resync,
+                            &state,
+                        )
+                        .await
+                        {
+                            Ok(Some(response)) => {
+                                if let Some(encoder) = deltas.as_mut() {
+                                    encoder.sent_directly(&response);
+                                }
+                                codec.replies(request_id, vec![response])
+                            }
+                            // Acknowledged when the client is waiting on a reply
+                            Ok(None) => codec.replies(request_id, Vec::new()),
+                            Err(e) => codec.replies(request_id, vec![e.into()]),
+                        }
+                    }
+                    Some(Err(InvalidFrame { request_id, reason })) => {
+                        warn!("Failed to parse WebSocket message: {}", reason);
+                        let error = ProtocolError::new(
+                            ErrorCode::InvalidMessage,
+                            "Invalid message format",
+                        );
+                        codec.replies(request_id, vec![error.into()])
+                    }
+                };
+                if send_all(&mut sender, outgoing).await.is_err() {
+                    break;
                 }
             }
 
             // Handle room broadcasts
-            Ok(broadcast_msg) = async {
+            result = async {
                 match &mut room_receiver {
                     Some(receiver) => receiver.recv().await,
                     None => futures_util::future::pending().await,
                 }
             } => {
-                if let Ok(broadcast_text) = serde_json::to_string(&broadcast_msg) {
-                    if sender.send(Message::Text(broadcast_text)).await.is_err() {
-                        break;
+                let outgoing = match result {
+                    Ok(mut broadcast_msg) => {
+                        let mut outgoing = Vec::new();
+                        if let Some(resync) = resync
+                            .as_mut()
+                            .filter(|resync| current_room.as_ref() == Some(&resync.room_code))
+                        {
+                            // The snapshot already covers it
+                            if broadcast_msg.seq <= resync.seq {
+                                continue;
+                            }
+                            if let Some((_, snapshot)) = resync.held.take() {
+                                outgoing = codec.replies(None, encode_all(&mut deltas, vec![snapshot]));
+                            }
+                        }
+                        if let Some(encoder) = deltas.as_mut() {
+                            broadcast_msg.message = encoder.encode(broadcast_msg.message);
+                        }
+                        outgoing.extend(codec.broadcast(&broadcast_msg));
+                        outgoing
+                    }
+                    // Too slow to keep up: skip ahead with a snapshot
+                    Err(RecvError::Lagged(skipped)) => {
+                        let (Some(user), Some(room_code)) =
+                            (authenticated_user.as_ref(), current_room.as_ref())
+                        else {
+                            continue;
+                        };
+                        warn!(
+                            "Connection of user {} lagged {} messages behind room {}",
+                            user.username, skipped, room_code
+                        );
+                        state.websocket_manager.metrics.record_lag(skipped);
+                        state.websocket_manager.metrics.record_resync();
+                        let user_id = user.id.unwrap().to_hex();
+                        let seq = state.websocket_manager.last_seq(room_code).await;
+                        let Some((new_resync, snapshot)) =
+                            resync_snapshot(&user_id, room_code, seq, &state).await
+                        else {
+                            continue;
+                        };
+                        resync = Some(new_resync);
+                        codec.replies(None, encode_all(&mut deltas, snapshot.into_iter().collect()))
+                    }
+                    // Room was removed
+                    Err(RecvError::Closed) => {
+                        room_receiver = None;
+                        Vec::new()
                     }
+                };
+                if send_all(&mut sender, outgoing).await.is_err() {
+                    break;
+                }
+            }
+
+            // A delayed spectator's snapshot, once their stream has caught up
+            _ = async {
+                match resync.as_ref().and_then(|resync| resync.held.as_ref()) {
+                    Some((due, _)) => tokio::time::sleep_until(*due).await,
+                    None => futures_util::future::pending().await,
+                }
+            } => {
+                // Dropped if the socket has left the room since
+                let snapshot = resync.as_mut().and_then(|resync| {
+                    resync
+                        .held
+                        .take()
+                        .filter(|_| current_room.as_ref() == Some(&resync.room_code))
+                        .map(|(_, snapshot)| snapshot)
+                });
+                let outgoing = codec.replies(None, encode_all(&mut deltas, snapshot.into_iter().collect()));
+                if send_all(&mut sender, outgoing).await.is_err() {
+                    break;
+                }
+            }
+
+            // Kicks, and messages for this user alone
+            Some(control) = async {
+                match &mut control_receiver {
+                    Some((_, _, receiver)) => receiver.recv().await,
+                    None => futures_util::future::pending().await,
+                }
+            } => {
+                let outgoing = match control {
+                    UserControl::LeaveRoom { room_code }
+                        if current_room.as_ref() == Some(&room_code) =>
+                    {
+                        // Deliver what the room saw up to the kick, then stop following it
+                        let mut outgoing = Vec::new();
+                        if let Some(mut receiver) = room_receiver.take() {
+                            while let Ok(mut broadcast_msg) = receiver.try_recv() {
+                                if let Some(encoder) = deltas.as_mut() {
+                                    broadcast_msg.message = encoder.encode(broadcast_msg.message);
+                                }
+                                outgoing.extend(codec.broadcast(&broadcast_msg));
+                            }
+                        }
+                        current_room = None;
+                        info!("Connection left room {} after a kick", room_code);
+                        outgoing
+                    }
+                    UserControl::Send { room_code, message }
+                        if current_room.as_ref() == Some(&room_code) =>
+                    {
+                        codec.message(&message).into_iter().collect()
+                    }
+                    _ => Vec::new(),
+                };
+                if send_all(&mut sender, outgoing).await.is_err() {
+                    break;
                 }
             }
 
             // Handle lobby broadcasts
-            Ok(broadcast_msg) = async {
+            result = async {
                 match &mut lobby_receiver {
                     Some(receiver) => receiver.recv().await,
                     None => futures_util::future::pending().await,
                 }
             } => {
-                info!("Sending lobby broadcast to client: {:?}", broadcast_msg.type_name());
-                if let Ok(broadcast_text) = serde_json::to_string(&broadcast_msg) {
-                    if sender.send(Message::Text(broadcast_text)).await.is_err() {
-                        break;
+                let outgoing = match result {
+                    Ok(broadcast_msg) => {
+                        info!("Sending lobby broadcast to client: {:?}", broadcast_msg.type_name());
+                        codec.message(&broadcast_msg).into_iter().collect()
                     }
+                    // Replace whatever was missed with a fresh room list
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("Connection lagged {} messages behind the lobby", skipped);
+                        state.websocket_manager.metrics.record_lag(skipped);
+                        state.websocket_manager.metrics.record_resync();
+                        let room_list = get_room_list(&state).await;
+                        codec.message(&room_list).into_iter().collect()
+                    }
+                    Err(RecvError::Closed) => {
+                        lobby_receiver = None;
+                        Vec::new()
+                    }
+                };
+                if send_all(&mut sender, outgoing).await.is_err() {
+                    break;
+                }
+            }
+
+            // Ping the client, and drop it once it stops answering
+            _ = ping.tick() => {
+                if heartbeat.timed_out() {
+                    warn!("WebSocket connection timed out");
+                    break;
+                }
+                if heartbeat.went_afk() {
+                    if let (Some((user_id, connection_id, _)), Some(room_code)) =
+                        (control_receiver.as_ref(), current_room.as_ref())
+                    {
+                        // An idle tab doesn't make a player AFK who is busy in another
+                        state
+                            .websocket_manager
+                            .track_connection(user_id, *connection_id, Some(room_code), true)
+                            .await;
+                        if !state.websocket_manager.active_in_room(user_id, room_code).await {
+                            presence::set_afk(&state, room_code, user_id, true).await;
+                        }
+                    }
+                }
+                if sender.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
                 }
             }
         }
     }
 
+    state.websocket_manager.metrics.connection_closed();
+    if let Some((user_id, _, receiver)) = control_receiver {
+        drop(receiver);
+        state.websocket_manager.unregister_user(&user_id).await;
+    }
+
     // Mark user as disconnected on WebSocket disconnect
     if let (Some(user), Some(room_code)) = (authenticated_user, current_room) {
         handle_user_disconnect(&user, &room_code, &state).await;
@@ -220,14 +828,161 @@ async fn handle_socket(socket: WebSocket, state: AppState) {
     info!("WebSocket connection terminated");
 }
 
+/// Send serialized messages to the client in order
+async fn send_all<S>(sender: &mut S, messages: Vec<Message>) -> Result<(), axum::Error>
+where
+    S: Sink<Message, Error = axum::Error> + Unpin,
+{
+    for message in messages {
+        sender.send(message).await?;
+    }
+    Ok(())
+}
+
+/// Turn full state updates into deltas if the client asked for them
+fn encode_all(
+    deltas: &mut Option<DeltaEncoder>,
+    messages: Vec<WebSocketMessage>,
+) -> Vec<WebSocketMessage> {
+    match deltas.as_mut() {
+        Some(encoder) => messages
+            .into_iter()
+            .map(|message| encoder.encode(message))
+            .collect(),
+        None => messages,
+    }
+}
+
+/// A snapshot that resynced a connection with its room. Broadcasts it
+/// already covers are skipped, and a delayed spectator's snapshot is held
+/// until their stream reaches it.
+struct Resync {
+    room_code: String,
+    seq: u64,
+    held: Option<(Instant, WebSocketMessage)>,
+}
+
+/// Snapshot the room for a connection that needs to resync. `seq` must be
+/// read before this is called, so the snapshot covers every broadcast up to
+/// it. Also returns the snapshot, unless it has to be held back.
+async fn resync_snapshot(
+    user_id: &str,
+    room_code: &str,
+    seq: u64,
+    state: &AppState,
+) -> Option<(Resync, Option<WebSocketMessage>)> {
+    let room = state.rooms.read().await.get(room_code).cloned()?;
+    let game_state = match GAME_ENGINES.read().await.get(room_code) {
+        Some(engine) => Some(Box::new(engine.read().await.game_state.clone())),
+        None => None,
+    };
+    let is_spectator = room.is_spectator(user_id);
+    let snapshot = WebSocketMessage::RoomSnapshot {
+        room,
+        game_state,
+        seq,
+    };
+
+    let mut resync = Resync {
+        room_code: room_code.to_string(),
+        seq,
+        held: None,
+    };
+    if !is_spectator {
+        return Some((resync, Some(snapshot)));
+    }
+    let snapshot = snapshot.redacted_for_audience().unwrap_or(snapshot);
+    match state.websocket_manager.audience_delay(room_code).await {
+        Some(delay) => {
+            resync.held = Some((Instant::now() + delay, snapshot));
+            Some((resync, None))
+        }
+        None => Some((resync, Some(snapshot))),
+    }
+}
+
+fn authenticated(user: &Option<User>) -> Result<&User, ProtocolError> {
+    user.as_ref()
+        .ok_or_else(|| ProtocolError::new(ErrorCode::NotAuthenticated, "Must authenticate first"))
+}
+
+/// The authenticated user and the room their socket is in
+fn in_room<'a>(
+    user: &'a Option<User>,
+    room_code: &'a Option<String>,
+) -> Result<(&'a User, &'a str), ProtocolError> {
+    let user = authenticated(user)?;
+    let room_code = room_code
+        .as_deref()
+        .ok_or_else(|| ProtocolError::new(ErrorCode::NotInRoom, "Not in a room"))?;
+    Ok((user, room_code))
+}
+
+async fn handle_sync_request(
+    message: WebSocketMessage,
+    user: &Option<User>,
+    room_code: &Option<String>,
+    deltas: &mut Option<DeltaEncoder>,
+    resync: &mut Option<Resync>,
+    state: &AppState,
+) -> Result<Vec<WebSocketMessage>, ProtocolError> {
+    match message {
+        WebSocketMessage::SetDeltaUpdates { enabled } => {
+            if !enabled {
+                *deltas = None;
+            } else if deltas.is_none() {
+                *deltas = Some(DeltaEncoder::default());
+            }
+            Ok(Vec::new())
+        }
+        WebSocketMessage::RequestSnapshot => {
+            let (user, room_code) = in_room(user, room_code)?;
+            // The next updates go out in full
+            if let Some(encoder) = deltas.as_mut() {
+                encoder.reset();
+            }
+            // Same as a lag resync, so delayed spectators wait for theirs
+            let user_id = user.id.unwrap().to_hex();
+            let seq = state.websocket_manager.last_seq(room_code).await;
+            let Some((new_resync, snapshot)) =
+                resync_snapshot(&user_id, room_code, seq, state).await
+            else {
+                return Ok(Vec::new());
+            };
+            *resync = Some(new_resync);
+            Ok(snapshot.into_iter().collect())
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
 async fn handle_websocket_message(
     message: WebSocketMessage,
     authenticated_user: &mut Option<User>,
     current_room: &mut Option<String>,
-    room_receiver: &mut Option<broadcast::Receiver<WebSocketMessage>>,
+    room_receiver: &mut Option<broadcast::Receiver<SequencedMessage>>,
     lobby_receiver: &mut Option<broadcast::Receiver<WebSocketMessage>>,
+    resync: &mut Option<Resync>,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
+    // Spectators can only watch
+    if message.is_game_action() {
+        if let (Some(user), Some(room_code)) = (authenticated_user.as_ref(), current_room.as_ref())
+        {
+            let user_id = user.id.unwrap().to_hex();
+            let rooms = state.rooms.read().await;
+            if rooms
+                .get(room_code)
+                .is_some_and(|room| room.is_spectator(&user_id))
+            {
+                return Err(ProtocolError::new(
+                    ErrorCode::SpectatorNotAllowed,
+                    "Spectators cannot take game actions",
+                ));
+            }
+        }
+    }
+
     match message {
         WebSocketMessage::Authenticate { token } => {
             match auth_middleware::extract_user_from_token(&token, &state.auth_service).await {
@@ -243,38 +998,47 @@ async fn handle_websocket_message(
                 }
                 Err(e) => {
                     warn!("WebSocket authentication failed: {}", e);
-                    Err("Authentication failed".to_string())
+                    Err(ProtocolError::new(
+                        ErrorCode::AuthenticationFailed,
+                        "Authentication failed",
+                    ))
                 }
             }
         }
 
         WebSocketMessage::JoinRoom { room_code } => {
-            if let Some(user) = authenticated_user.as_ref() {
-                handle_join_room(user, &room_code, current_room, room_receiver, state).await
-            } else {
-                Err("Must authenticate first".to_string())
-            }
+            let user = authenticated(authenticated_user)?;
+            handle_join_room(user, &room_code, current_room, room_receiver, state).await
+        }
+
+        WebSocketMessage::Resume {
+            room_code,
+            last_seq,
+        } => {
+            let user = authenticated(authenticated_user)?;
+            handle_resume(
+                user,
+                &room_code,
+                last_seq,
+                current_room,
+                room_receiver,
+                resync,
+                state,
+            )
+            .await
         }
 
         WebSocketMessage::LeaveRoom => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                handle_leave_room(user, room_code, state).await?;
-                *current_room = None;
-                *room_receiver = None;
-                Ok(None)
-            } else {
-                Err("Not in a room".to_string())
-            }
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            handle_leave_room(user, room_code, state).await?;
+            *current_room = None;
+            *room_receiver = None;
+            Ok(None)
         }
 
         WebSocketMessage::RequestRoomList => {
-            if authenticated_user.is_some() {
-                handle_request_room_list(state).await
-            } else {
-                Err("Must authenticate first".to_string())
-            }
+            authenticated(authenticated_user)?;
+            handle_request_room_list(state).await
         }
 
         WebSocketMessage::Ping => {
@@ -282,145 +1046,185 @@ async fn handle_websocket_message(
             Ok(Some(WebSocketMessage::Pong))
         }
 
-        WebSocketMessage::KickPlayer { user_id } => {
-            if let (Some(admin), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                handle_kick_player(admin, &user_id, room_code, state).await?;
-                Ok(None)
-            } else {
-                Err("Not authorized or not in a room".to_string())
-            }
+        // Only resets the AFK clock, which every request does
+        WebSocketMessage::Activity => Ok(None),
+
+        WebSocketMessage::KickPlayer { user_id, reason } => {
+            let (admin, room_code) = in_room(authenticated_user, current_room)?;
+            handle_kick_player(admin, &user_id, reason, room_code, state).await?;
+            Ok(None)
+        }
+
+        WebSocketMessage::UpdateRole { user_id, role } => {
+            let (admin, room_code) = in_room(authenticated_user, current_room)?;
+            handle_update_role(admin, &user_id, role, room_code, state).await?;
+            Ok(None)
+        }
+
+        WebSocketMessage::TransferAdmin { user_id } => {
+            let (admin, room_code) = in_room(authenticated_user, current_room)?;
+            handle_transfer_admin(admin, &user_id, room_code, state).await?;
+            Ok(None)
+        }
+
+        WebSocketMessage::UpdateModeratorPermissions { permissions } => {
+            let (admin, room_code) = in_room(authenticated_user, current_room)?;
+            handle_update_moderator_permissions(admin, permissions, room_code, state).await?;
+            Ok(None)
+        }
+
+        WebSocketMessage::BanPlayer {
+            user_id,
+            reason,
+            duration_minutes,
+        } => {
+            let (admin, room_code) = in_room(authenticated_user, current_room)?;
+            let request = BanRequest {
+                user_id,
+                reason,
+                duration_minutes,
+            };
+            handle_ban_player(admin, request, room_code, state).await?;
+            Ok(None)
+        }
+
+        WebSocketMessage::AddBot { team_id, settings } => {
+            let (admin, room_code) = in_room(authenticated_user, current_room)?;
+            handle_add_bot(admin, &team_id, settings, room_code, state).await?;
+            Ok(None)
         }
         // Game-specific messages
         WebSocketMessage::JoinTeam { team_id } => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                game::handle_join_team(user, &team_id, room_code, state).await
-            } else {
-                Err("Not authenticated or not in a room".to_string())
-            }
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_join_team(user, &team_id, room_code, state).await
         }
         WebSocketMessage::LeaveTeam => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                game::handle_leave_team(user, room_code, state).await
-            } else {
-                Err("Not authenticated or not in a room".to_string())
-            }
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_leave_team(user, room_code, state).await
         }
         WebSocketMessage::MarkReady => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                game::handle_mark_ready(user, room_code, state).await
-            } else {
-                Err("Not authenticated or not in a room".to_string())
-            }
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_mark_ready(user, room_code, state).await
         }
-        WebSocketMessage::StartGame => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                game::handle_start_game(user, room_code, state).await
-            } else {
-                Err("Not authenticated or not in a room".to_string())
-            }
+        WebSocketMessage::StartGame { settings } => {
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_start_game(user, settings, room_code, state).await
+        }
+        WebSocketMessage::AdjustScore { team_id, delta } => {
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_adjust_score(user, &team_id, delta, room_code, state).await
         }
         WebSocketMessage::StartRound => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                game::handle_start_round(user, room_code, state).await
-            } else {
-                Err("Not authenticated or not in a room".to_string())
-            }
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_start_round(user, room_code, state).await
         }
         WebSocketMessage::WordAction { result } => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                game::handle_word_action(user, result, room_code, state).await
-            } else {
-                Err("Not authenticated or not in a room".to_string())
-            }
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_word_action(user, result, room_code, state).await
         }
         WebSocketMessage::RequestNewWord => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                game::handle_request_new_word(user, room_code, state).await
-            } else {
-                Err("Not authenticated or not in a room".to_string())
-            }
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_request_new_word(user, room_code, state).await
         }
         WebSocketMessage::EndRound => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                game::handle_end_round(user, room_code, state).await
-            } else {
-                Err("Not authenticated or not in a room".to_string())
-            }
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_end_round(user, room_code, state).await
+        }
+        WebSocketMessage::SubmitWords { words } => {
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_submit_words(user, words, room_code, state).await
+        }
+        WebSocketMessage::SubmitClue { text } => {
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_submit_clue(user, &text, room_code, state).await
+        }
+        WebSocketMessage::DrawStroke { stroke } => {
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_canvas_action(user, game::CanvasAction::Draw(stroke), room_code, state)
+                .await
+        }
+        WebSocketMessage::UndoStroke => {
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_canvas_action(user, game::CanvasAction::Undo, room_code, state).await
+        }
+        WebSocketMessage::ClearCanvas => {
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_canvas_action(user, game::CanvasAction::Clear, room_code, state).await
+        }
+        WebSocketMessage::RequestCanvas => {
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_request_canvas(user, room_code, state).await
         }
         WebSocketMessage::PauseGame => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                game::handle_pause_game(user, room_code, state).await
-            } else {
-                Err("Not authenticated or not in a room".to_string())
-            }
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_pause_game(user, room_code, state).await
         }
         WebSocketMessage::ResumeGame => {
-            if let (Some(user), Some(room_code)) =
-                (authenticated_user.as_ref(), current_room.as_ref())
-            {
-                game::handle_resume_game(user, room_code, state).await
-            } else {
-                Err("Not authenticated or not in a room".to_string())
-            }
+            let (user, room_code) = in_room(authenticated_user, current_room)?;
+            game::handle_resume_game(user, room_code, state).await
         }
 
-        _ => Err("Message type not supported yet".to_string()),
+        other => Err(ProtocolError::new(
+            ErrorCode::UnsupportedMessage,
+            format!("Message type {} is not supported", other.type_name()),
+        )),
     }
 }
 
-async fn handle_join_room(
-    user: &User,
+/// Mark a participant's socket as connected to the room
+async fn connect_participant(
+    user_id: &str,
     room_code: &str,
-    current_room: &mut Option<String>,
-    room_receiver: &mut Option<broadcast::Receiver<WebSocketMessage>>,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
-    let user_id = user.id.unwrap().to_hex();
+) -> Result<GameRoom, ProtocolError> {
     let mut rooms = state.rooms.write().await;
 
     let room = rooms
         .get_mut(room_code)
-        .ok_or_else(|| "Room not found".to_string())?;
-
-    // Check if user is in the room
-    if !room.participants.contains_key(&user_id) {
-        return Err("User is not a participant in this room".to_string());
+        .ok_or_else(ProtocolError::room_not_found)?;
+
+    // Only participants can subscribe; entry rules (registered-only,
+    // passwords, invites) are checked when joining over REST
+    if !room.participants.contains_key(user_id) {
+        return Err(ProtocolError::new(
+            ErrorCode::NotParticipant,
+            "User is not a participant in this room",
+        ));
     }
 
     // Mark user as connected when they join via WebSocket
-    if let Some(participant) = room.participants.get_mut(&user_id) {
+    if let Some(participant) = room.participants.get_mut(user_id) {
         participant.is_connected = true;
+        participant.is_afk = false;
         room.updated_at = chrono::Utc::now();
     }
 
-    let room_clone = room.clone();
-    drop(rooms); // Release the write lock
+    Ok(room.clone())
+}
 
-    // Set up room subscription
-    let sender = state
-        .websocket_manager
-        .get_or_create_room_sender(room_code)
-        .await;
+async fn handle_join_room(
+    user: &User,
+    room_code: &str,
+    current_room: &mut Option<String>,
+    room_receiver: &mut Option<broadcast::Receiver<SequencedMessage>>,
+    state: &AppState,
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
+    let user_id = user.id.unwrap().to_hex();
+    let room_clone = connect_participant(&user_id, room_code, state).await?;
+    let is_spectator = room_clone.is_spectator(&user_id);
+
+    // Set up room subscription (spectators get the redacted stream)
+    let sender = if is_spectator {
+        state
+            .websocket_manager
+            .get_or_create_audience_sender(room_code)
+            .await
+    } else {
+        state
+            .websocket_manager
+            .get_or_create_room_sender(room_code)
+            .await
+    };
     *room_receiver = Some(sender.subscribe());
     *current_room = Some(room_code.to_string());
 
@@ -440,16 +1244,84 @@ async fn handle_join_room(
         user_id, room_code
     );
 
+    // A returning explainer resumes their round; a returning teammate may
+    // take over one
+    presence::check_explainer(state, room_code).await;
+
     Ok(Some(WebSocketMessage::RoomJoined { room: room_clone }))
 }
 
-async fn handle_leave_room(user: &User, room_code: &str, state: &AppState) -> Result<(), String> {
+/// Reconnect to a room, replaying the broadcasts missed since `last_seq`
+/// or sending a snapshot when they are no longer buffered. A delayed
+/// spectator's snapshot follows once their stream catches up with it.
+async fn handle_resume(
+    user: &User,
+    room_code: &str,
+    last_seq: u64,
+    current_room: &mut Option<String>,
+    room_receiver: &mut Option<broadcast::Receiver<SequencedMessage>>,
+    resync: &mut Option<Resync>,
+    state: &AppState,
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
+    let user_id = user.id.unwrap().to_hex();
+    let room = connect_participant(&user_id, room_code, state).await?;
+    let is_spectator = room.is_spectator(&user_id);
+
+    // Subscribes and reads the sequence number before the snapshot is taken
+    let (receiver, resumption) = state
+        .websocket_manager
+        .resume_room(room_code, last_seq, is_spectator)
+        .await;
+    *room_receiver = Some(receiver);
+    *current_room = Some(room_code.to_string());
+
+    let response = match resumption {
+        Resumption::Replay(events) => {
+            info!(
+                "User {} resumed room {} with {} missed messages",
+                user_id,
+                room_code,
+                events.len()
+            );
+            Some(WebSocketMessage::Resumed {
+                room_code: room_code.to_string(),
+                events,
+            })
+        }
+        Resumption::Snapshot { seq } => {
+            info!(
+                "User {} resumed room {} from a snapshot",
+                user_id, room_code
+            );
+            let (new_resync, snapshot) = resync_snapshot(&user_id, room_code, seq, state)
+                .await
+                .ok_or_else(ProtocolError::room_not_found)?;
+            *resync = Some(new_resync);
+            snapshot
+        }
+    };
+
+    // Let the room know the user is back
+    state
+        .websocket_manager
+        .broadcast_to_room(room_code, WebSocketMessage::RoomUpdated { room })
+        .await;
+    presence::check_explainer(state, room_code).await;
+
+    Ok(response)
+}
+
+async fn handle_leave_room(
+    user: &User,
+    room_code: &str,
+    state: &AppState,
+) -> Result<(), ProtocolError> {
     let user_id = user.id.unwrap().to_hex();
     let mut rooms = state.rooms.write().await;
 
     let room = rooms
         .get_mut(room_code)
-        .ok_or_else(|| "Room not found".to_string())?;
+        .ok_or_else(ProtocolError::room_not_found)?;
 
     // Remove the participant
     room.participants.remove(&user_id);
@@ -466,11 +1338,12 @@ async fn handle_leave_room(user: &User, room_code: &str, state: &AppState) -> Re
         )
         .await;
 
-    // Check if room is now empty (regardless of who left)
-    if room.participants.is_empty() {
+    // Check if room is now empty (regardless of who left); bots don't keep it open
+    if !room.has_humans() {
         // Remove empty room
         rooms.remove(room_code);
         ws_manager.remove_room(room_code).await;
+        twitch_chat::stop_connector(room_code).await;
 
         // Broadcast room deletion to lobby
         ws_manager.broadcast_to_lobby(WebSocketMessage::RoomDeleted {
@@ -484,24 +1357,9 @@ async fn handle_leave_room(user: &User, room_code: &str, state: &AppState) -> Re
         return Ok(());
     }
 
-    // If admin leaves but room is not empty, transfer admin role
+    // If admin leaves but room is not empty, pass the admin role on
     if room.admin_id == user_id {
-        if let Some((new_admin_id, participant)) = room.participants.iter_mut().next() {
-            // Transfer admin role to another participant
-            participant.role = UserRole::Admin;
-            room.admin_id = new_admin_id.clone();
-
-            // Broadcast role update
-            ws_manager
-                .broadcast_to_room(
-                    room_code,
-                    WebSocketMessage::RoleUpdated {
-                        user_id: new_admin_id.clone(),
-                        role: UserRole::Admin,
-                    },
-                )
-                .await;
-
+        if let Some(new_admin_id) = rooms::hand_off_admin(state, room).await {
             info!(
                 "Admin role transferred from {} to {} in room {}",
                 user_id, new_admin_id, room_code
@@ -518,105 +1376,239 @@ async fn handle_leave_room(user: &User, room_code: &str, state: &AppState) -> Re
         .await;
 
     // Broadcast updated room info to lobby
-    let room_info = shared::models::RoomInfo {
-        id: room.id.unwrap().to_hex(),
-        room_code: room_code.to_string(),
-        name: room.name.clone(),
-        current_players: room.participants.len(),
-        max_players: room.max_players,
-        state: room.state,
-        admin_username: room
-            .participants
-            .get(&room.admin_id)
-            .map(|p| p.username.clone())
-            .unwrap_or_default(),
-    };
+    let room_info = RoomInfo::from(&*room);
     ws_manager.broadcast_to_lobby(WebSocketMessage::RoomInfoUpdated { room_info });
 
     Ok(())
 }
 
 async fn handle_kick_player(
-    admin: &User,
+    kicker: &User,
     player_id: &str,
+    reason: Option<String>,
     room_code: &str,
     state: &AppState,
-) -> Result<(), String> {
-    let admin_id = admin.id.unwrap().to_hex();
+) -> Result<(), ProtocolError> {
+    let kicker_id = kicker.id.unwrap().to_hex();
     let mut rooms = state.rooms.write().await;
 
     let room = rooms
         .get_mut(room_code)
-        .ok_or_else(|| "Room not found".to_string())?;
+        .ok_or_else(ProtocolError::room_not_found)?;
 
-    // Check if the user is the admin
-    if room.admin_id != admin_id {
-        return Err("Only admin can kick players".to_string());
+    if !room.has_permission(&kicker_id, ModeratorPermission::Kick) {
+        return Err(ProtocolError::new(
+            ErrorCode::PermissionDenied,
+            "Only admin or moderators can kick players",
+        ));
     }
 
     // Check if the player exists in the room
-    if !room.participants.contains_key(player_id) {
-        return Err("Player not found in room".to_string());
+    let player = room
+        .participants
+        .get(player_id)
+        .ok_or_else(|| ProtocolError::new(ErrorCode::NotParticipant, "Player not found in room"))?;
+
+    if player_id == kicker_id {
+        return Err(ProtocolError::new(
+            ErrorCode::InvalidRequest,
+            "You cannot kick yourself",
+        ));
     }
 
-    // Cannot kick the admin themselves
-    if player_id == admin_id {
-        return Err("Admin cannot kick themselves".to_string());
+    // Moderators can't kick the admin or each other
+    if kicker_id != room.admin_id && matches!(player.role, UserRole::Admin | UserRole::Moderator) {
+        return Err(ProtocolError::new(
+            ErrorCode::PermissionDenied,
+            "Moderators can only kick players and spectators",
+        ));
     }
 
-    // Remove the player
-    room.participants.remove(player_id);
-    room.updated_at = chrono::Utc::now();
+    rooms::remove_kicked(state, room, player_id, &kicker_id, reason, false).await;
+
+    info!(
+        "User {} kicked player {} from room {}",
+        kicker_id, player_id, room_code
+    );
+
+    Ok(())
+}
+
+async fn handle_add_bot(
+    admin: &User,
+    team_id: &str,
+    settings: BotSettings,
+    room_code: &str,
+    state: &AppState,
+) -> Result<(), ProtocolError> {
+    let admin_id = admin.id.unwrap().to_hex();
+    bots::add_to_room(state, &admin_id, room_code, team_id, settings).await?;
+    Ok(())
+}
+
+async fn handle_ban_player(
+    admin: &User,
+    request: BanRequest,
+    room_code: &str,
+    state: &AppState,
+) -> Result<(), ProtocolError> {
+    let admin_id = admin.id.unwrap().to_hex();
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
+
+    if room.admin_id != admin_id {
+        return Err(ProtocolError::new(
+            ErrorCode::NotAdmin,
+            "Only admin can ban players",
+        ));
+    }
+    if request.user_id == admin_id {
+        return Err(ProtocolError::new(
+            ErrorCode::InvalidRequest,
+            "Admin cannot ban themselves",
+        ));
+    }
+
+    let ban = bans::new_ban(request, &admin_id).map_err(ProtocolError::invalid_request)?;
+    room.bans.insert(ban.user_id.clone(), ban.clone());
+    rooms::remove_kicked(state, room, &ban.user_id, &admin_id, ban.reason, true).await;
+
+    info!(
+        "Admin {} banned user {} from room {}",
+        admin_id, ban.user_id, room_code
+    );
+
+    Ok(())
+}
+
+async fn handle_update_role(
+    admin: &User,
+    player_id: &str,
+    role: UserRole,
+    room_code: &str,
+    state: &AppState,
+) -> Result<(), ProtocolError> {
+    let admin_id = admin.id.unwrap().to_hex();
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
+
+    if room.admin_id != admin_id {
+        return Err(ProtocolError::new(
+            ErrorCode::NotAdmin,
+            "Only admin can change roles",
+        ));
+    }
+    rooms::set_moderator(room, player_id, role).map_err(ProtocolError::invalid_request)?;
 
-    // Broadcast kick notification
     let ws_manager = &state.websocket_manager;
     ws_manager
         .broadcast_to_room(
             room_code,
-            WebSocketMessage::UserKicked {
+            WebSocketMessage::RoleUpdated {
                 user_id: player_id.to_string(),
-                kicked_by: admin_id.clone(),
+                role,
             },
         )
         .await;
+    ws_manager
+        .broadcast_to_room(
+            room_code,
+            WebSocketMessage::RoomUpdated { room: room.clone() },
+        )
+        .await;
 
-    // Broadcast updated room state
+    Ok(())
+}
+
+async fn handle_transfer_admin(
+    admin: &User,
+    new_admin_id: &str,
+    room_code: &str,
+    state: &AppState,
+) -> Result<(), ProtocolError> {
+    let admin_id = admin.id.unwrap().to_hex();
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
+
+    if room.admin_id != admin_id {
+        return Err(ProtocolError::new(
+            ErrorCode::NotAdmin,
+            "Only admin can transfer admin",
+        ));
+    }
+    rooms::transfer_admin_to(state, room, new_admin_id)
+        .await
+        .map_err(ProtocolError::invalid_request)?;
+
+    let ws_manager = &state.websocket_manager;
     ws_manager
         .broadcast_to_room(
             room_code,
             WebSocketMessage::RoomUpdated { room: room.clone() },
         )
         .await;
+    ws_manager.broadcast_to_lobby(WebSocketMessage::RoomInfoUpdated {
+        room_info: RoomInfo::from(&*room),
+    });
 
     info!(
-        "Admin {} kicked player {} from room {}",
-        admin_id, player_id, room_code
+        "Admin {} handed room {} to {}",
+        admin_id, room_code, new_admin_id
     );
 
     Ok(())
 }
 
+async fn handle_update_moderator_permissions(
+    admin: &User,
+    permissions: Vec<ModeratorPermission>,
+    room_code: &str,
+    state: &AppState,
+) -> Result<(), ProtocolError> {
+    let admin_id = admin.id.unwrap().to_hex();
+    let mut rooms = state.rooms.write().await;
+
+    let room = rooms
+        .get_mut(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
+
+    if room.admin_id != admin_id {
+        return Err(ProtocolError::new(
+            ErrorCode::NotAdmin,
+            "Only admin can change moderator permissions",
+        ));
+    }
+
+    rooms::set_moderator_permissions(room, permissions);
+
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            room_code,
+            WebSocketMessage::RoomUpdated { room: room.clone() },
+        )
+        .await;
+
+    Ok(())
+}
+
 async fn get_room_list(state: &AppState) -> WebSocketMessage {
     let rooms = state.rooms.read().await;
 
-    let room_list: Vec<shared::models::RoomInfo> = rooms
+    let room_list: Vec<RoomInfo> = rooms
         .values()
-        .filter_map(|room| {
-            // Only include rooms with valid IDs
-            room.id.map(|id| shared::models::RoomInfo {
-                id: id.to_hex(),
-                room_code: room.room_code.clone(),
-                name: room.name.clone(),
-                current_players: room.participants.len(),
-                max_players: room.max_players,
-                state: room.state,
-                admin_username: room
-                    .participants
-                    .get(&room.admin_id)
-                    .map(|p| p.username.clone())
-                    .unwrap_or_default(),
-            })
-        })
+        // Only include public rooms with valid IDs
+        .filter(|room| room.id.is_some() && room.visibility == RoomVisibility::Public)
+        .map(RoomInfo::from)
         .collect();
 
     info!("Preparing room list with {} rooms", room_list.len());
@@ -624,13 +1616,32 @@ async fn get_room_list(state: &AppState) -> WebSocketMessage {
     WebSocketMessage::RoomList { rooms: room_list }
 }
 
-async fn handle_request_room_list(state: &AppState) -> Result<Option<WebSocketMessage>, String> {
+async fn handle_request_room_list(
+    state: &AppState,
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
     let room_list = get_room_list(state).await;
     Ok(Some(room_list))
 }
 
 async fn handle_user_disconnect(user: &User, room_code: &str, state: &AppState) {
     let user_id = user.id.unwrap().to_hex();
+
+    // The player is still there on another connection, though maybe idle
+    if state
+        .websocket_manager
+        .connected_to_room(&user_id, room_code)
+        .await
+    {
+        if !state
+            .websocket_manager
+            .active_in_room(&user_id, room_code)
+            .await
+        {
+            presence::set_afk(state, room_code, &user_id, true).await;
+        }
+        return;
+    }
+
     let mut rooms = state.rooms.write().await;
 
     if let Some(room) = rooms.get_mut(room_code) {
@@ -653,8 +1664,58 @@ async fn handle_user_disconnect(user: &User, room_code: &str, state: &AppState)
                 "User {} marked as disconnected in room {} (can reconnect)",
                 user_id, room_code
             );
+
+            // Give the admin a moment to reconnect before handing the room on
+            if room.admin_id == user_id {
+                let state = state.clone();
+                let room_code = room_code.to_string();
+                let user_id = user_id.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(ADMIN_HANDOFF_GRACE).await;
+                    hand_off_absent_admin(&state, &room_code, &user_id).await;
+                });
+            }
         }
     }
+    drop(rooms);
+
+    // The explainer gets the same moment before their turn passes on
+    presence::explainer_disconnected(state, room_code, &user_id).await;
+}
+
+/// Pass the admin role on if the admin is still disconnected and a connected
+/// participant can take over
+pub async fn hand_off_absent_admin(state: &AppState, room_code: &str, admin_id: &str) {
+    let mut rooms = state.rooms.write().await;
+    let Some(room) = rooms.get_mut(room_code) else {
+        return;
+    };
+    let admin_away = room.admin_id == admin_id
+        && room
+            .participants
+            .get(admin_id)
+            .is_some_and(|p| !p.is_connected);
+    let successor_connected = room
+        .admin_successor()
+        .and_then(|id| room.participants.get(&id))
+        .is_some_and(|p| p.is_connected);
+    if !admin_away || !successor_connected {
+        return;
+    }
+
+    if let Some(new_admin_id) = rooms::hand_off_admin(state, room).await {
+        state
+            .websocket_manager
+            .broadcast_to_room(
+                room_code,
+                WebSocketMessage::RoomUpdated { room: room.clone() },
+            )
+            .await;
+        info!(
+            "Admin {} disconnected, room {} handed to {}",
+            admin_id, room_code, new_admin_id
+        );
+    }
 }
 
 // Clean up abandoned rooms where all users have been disconnected for too long
@@ -664,8 +1725,12 @@ pub async fn cleanup_abandoned_rooms(state: &AppState, disconnect_timeout_minute
     let mut rooms_to_remove = Vec::new();
 
     for (room_code, room) in rooms.iter() {
-        // Check if all participants are disconnected
-        let all_disconnected = room.participants.values().all(|p| !p.is_connected);
+        // Check if all participants are disconnected; bots never disconnect
+        let all_disconnected = room
+            .participants
+            .values()
+            .filter(|p| !p.is_bot())
+            .all(|p| !p.is_connected);
 
         // If all users are disconnected and the room hasn't been updated recently
         if all_disconnected && room.updated_at < cutoff_time {
@@ -681,6 +1746,7 @@ pub async fn cleanup_abandoned_rooms(state: &AppState, disconnect_timeout_minute
     for room_code in rooms_to_remove {
         rooms.remove(&room_code);
         state.websocket_manager.remove_room(&room_code).await;
+        twitch_chat::stop_connector(&room_code).await;
 
         // Broadcast room deletion to lobby
         state
@@ -1,10 +1,20 @@
-use game_engine::game::GameEngine;
-use shared::models::{User, WebSocketMessage, WordResult};
+use game_engine::bot;
+use game_engine::game::{ClueOutcome, GameEngine};
+use shared::errors::{ErrorCode, ProtocolError};
+use shared::models::{
+    GameSettings, ModeratorPermission, Round, Stroke, User, WebSocketMessage, WebhookEvent,
+    WordResult,
+};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::info;
+use tracing::{info, warn};
 
+use crate::bots;
 use crate::game::GAME_ENGINES;
+use crate::presence;
+use crate::twitch_chat::{self, ChatMessage};
+use crate::webhooks;
+use crate::websocket::UserControl;
 use crate::AppState;
 
 /// Handle team join message
@@ -13,17 +23,19 @@ pub async fn handle_join_team(
     team_id: &str,
     room_code: &str,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
     let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
 
     // Update room participant
     let mut rooms = state.rooms.write().await;
-    let room = rooms.get_mut(room_code).ok_or("Room not found")?;
+    let room = rooms
+        .get_mut(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
 
     let participant = room
         .participants
         .get_mut(&user_id)
-        .ok_or("User not in room")?;
+        .ok_or_else(|| ProtocolError::new(ErrorCode::NotParticipant, "User not in room"))?;
 
     participant.team_id = Some(team_id.to_string());
     drop(rooms);
@@ -34,8 +46,7 @@ pub async fn handle_join_team(
         let mut engine = engine.write().await;
         engine
             .team_manager
-            .add_player_to_team(user_id.clone(), team_id)
-            .map_err(|e| e.to_string())?;
+            .add_player_to_team(user_id.clone(), team_id)?;
 
         let teams = engine.team_manager.get_teams().to_vec();
 
@@ -64,17 +75,19 @@ pub async fn handle_leave_team(
     user: &User,
     room_code: &str,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
     let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
 
     // Update room participant
     let mut rooms = state.rooms.write().await;
-    let room = rooms.get_mut(room_code).ok_or("Room not found")?;
+    let room = rooms
+        .get_mut(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
 
     let participant = room
         .participants
         .get_mut(&user_id)
-        .ok_or("User not in room")?;
+        .ok_or_else(|| ProtocolError::new(ErrorCode::NotParticipant, "User not in room"))?;
 
     let team_id = participant.team_id.clone();
     participant.team_id = None;
@@ -112,11 +125,13 @@ pub async fn handle_mark_ready(
     user: &User,
     room_code: &str,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
     let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
 
     let engines = GAME_ENGINES.read().await;
-    let engine = engines.get(room_code).ok_or("Game not initialized")?;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
 
     let mut engine = engine.write().await;
 
@@ -126,7 +141,7 @@ pub async fn handle_mark_ready(
         .get_teams()
         .iter()
         .find(|t| t.players.contains(&user_id))
-        .ok_or("User not in any team")?
+        .ok_or_else(|| ProtocolError::new(ErrorCode::NotInTeam, "User not in any team"))?
         .clone();
 
     // Mark team as ready
@@ -151,24 +166,30 @@ pub async fn handle_mark_ready(
 /// Handle start game message (admin only)
 pub async fn handle_start_game(
     user: &User,
+    settings: Option<GameSettings>,
     room_code: &str,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
     let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
 
     // Check if user is admin
     let rooms = state.rooms.read().await;
-    let room = rooms.get(room_code).ok_or("Room not found")?;
+    let room = rooms
+        .get(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
 
     if room.admin_id != user_id {
-        return Err("Only admin can start the game".to_string());
+        return Err(ProtocolError::new(
+            ErrorCode::NotAdmin,
+            "Only admin can start the game",
+        ));
     }
     drop(rooms);
 
     // Create game engine if not exists
     let mut engines = GAME_ENGINES.write().await;
     if !engines.contains_key(room_code) {
-        let mut game_engine = GameEngine::new(&state.mongo_client, None).await;
+        let mut game_engine = GameEngine::new(&state.mongo_client, settings).await;
 
         // Initialize teams from room participants
         let rooms = state.rooms.read().await;
@@ -178,19 +199,17 @@ pub async fn handle_start_game(
             if let Some(team_id) = &participant.team_id {
                 game_engine
                     .team_manager
-                    .add_player_to_team(participant.user_id.clone(), team_id)
-                    .map_err(|e| e.to_string())?;
+                    .add_player_to_team(participant.user_id.clone(), team_id)?;
             }
         }
         drop(rooms);
 
         // Validate and start game
-        game_engine
-            .team_manager
-            .validate_for_game_start()
-            .map_err(|e| e.to_string())?;
+        game_engine.team_manager.validate_for_game_start()?;
+
+        game_engine.start_game().await?;
 
-        game_engine.start_game().await.map_err(|e| e.to_string())?;
+        twitch_chat::connect_game(room_code, &game_engine.game_state.settings, user, state).await;
 
         engines.insert(room_code.to_string(), Arc::new(RwLock::new(game_engine)));
     }
@@ -203,6 +222,13 @@ pub async fn handle_start_game(
         .websocket_manager
         .broadcast_to_room(room_code, WebSocketMessage::GameStarted)
         .await;
+    webhooks::notify_room(
+        state,
+        room_code,
+        WebhookEvent::GameStarted,
+        webhooks::game_started_data(&engine.game_state),
+    )
+    .await;
 
     // Send game state
     state
@@ -210,28 +236,47 @@ pub async fn handle_start_game(
         .broadcast_to_room(
             room_code,
             WebSocketMessage::GameStateUpdated {
-                game_state: engine.game_state.clone(),
+                game_state: Box::new(engine.game_state.clone()),
             },
         )
         .await;
 
     info!("Game started in room {}", room_code);
+    bots::schedule_turn(state, room_code);
 
     Ok(None)
 }
 
 /// Handle start round message
 pub async fn handle_start_round(
-    _user: &User,
+    user: &User,
     room_code: &str,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
+    let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
+
     let engines = GAME_ENGINES.read().await;
-    let engine = engines.get(room_code).ok_or("Game not found")?;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
 
     let mut engine = engine.write().await;
 
-    let round = engine.start_round().await.map_err(|e| e.to_string())?;
+    // The next explainer starts their round; admin and moderators can too
+    if engine.next_explainer()? != user_id {
+        let rooms = state.rooms.read().await;
+        let room = rooms
+            .get(room_code)
+            .ok_or_else(ProtocolError::room_not_found)?;
+        if !room.has_permission(&user_id, ModeratorPermission::StartRound) {
+            return Err(ProtocolError::new(
+                ErrorCode::PermissionDenied,
+                "Only the next explainer, admin or moderators can start the round",
+            ));
+        }
+    }
+
+    let round = engine.start_round().await?;
 
     // Broadcast round started
     state
@@ -245,6 +290,12 @@ pub async fn handle_start_round(
         .await;
 
     info!("Round {} started in room {}", round.round_number, room_code);
+    bots::round_started(state, room_code, &round).await;
+    drop(engine);
+    drop(engines);
+
+    // A teammate stands in if the explainer is already gone
+    presence::check_explainer(state, room_code).await;
 
     Ok(None)
 }
@@ -255,11 +306,20 @@ pub async fn handle_word_action(
     result: WordResult,
     room_code: &str,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
     let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
 
+    if result == WordResult::Stolen {
+        return Err(ProtocolError::new(
+            ErrorCode::InvalidAction,
+            "Only the audience can steal words",
+        ));
+    }
+
     let engines = GAME_ENGINES.read().await;
-    let engine = engines.get(room_code).ok_or("Game not found")?;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
 
     let mut engine = engine.write().await;
 
@@ -268,15 +328,266 @@ pub async fn handle_word_action(
         .game_state
         .current_round
         .as_ref()
-        .ok_or("No active round")?;
+        .ok_or_else(ProtocolError::no_active_round)?;
+
+    // Bots can't hear guesses, so their teammates say when they got the word
+    let explaining = round.explainer_id == user_id;
+    let guessed_for_bot = result == WordResult::Correct
+        && bot::is_bot(&round.explainer_id)
+        && engine
+            .team_manager
+            .get_teams()
+            .iter()
+            .any(|t| t.id == round.team_id && t.players.contains(&user_id));
+    if !explaining && !guessed_for_bot {
+        return Err(ProtocolError::new(
+            ErrorCode::NotExplainer,
+            "Only explainer can submit word results",
+        ));
+    }
+
+    let score_change = engine.process_word_result(result)?;
+
+    // Only the explainer gets to see the next word
+    let reply = finish_word(&mut engine, result, score_change, room_code, state).await?;
+    Ok(reply.filter(|_| explaining))
+}
+
+/// Handle a typed clue (text-clue mode, explainer only)
+pub async fn handle_submit_clue(
+    user: &User,
+    text: &str,
+    room_code: &str,
+    state: &AppState,
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
+    let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
+
+    let engines = GAME_ENGINES.read().await;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
+
+    let mut engine = engine.write().await;
+
+    // Check if user is current explainer
+    let round = engine
+        .game_state
+        .current_round
+        .as_ref()
+        .ok_or_else(ProtocolError::no_active_round)?;
 
     if round.explainer_id != user_id {
-        return Err("Only explainer can submit word results".to_string());
+        return Err(ProtocolError::new(
+            ErrorCode::NotExplainer,
+            "Only explainer can send clues",
+        ));
+    }
+
+    match engine.submit_clue(text)? {
+        ClueOutcome::Accepted(clue) => {
+            // Guessers see the clue live
+            state
+                .websocket_manager
+                .broadcast_to_room(room_code, WebSocketMessage::ClueAdded { clue })
+                .await;
+
+            Ok(None)
+        }
+        ClueOutcome::Rejected { score_change } => {
+            state
+                .websocket_manager
+                .broadcast_to_room(
+                    room_code,
+                    WebSocketMessage::ClueRejected {
+                        user_id,
+                        score_change,
+                    },
+                )
+                .await;
+
+            finish_word(
+                &mut engine,
+                WordResult::Penalty,
+                score_change,
+                room_code,
+                state,
+            )
+            .await
+        }
     }
+}
+
+/// Drawing actions the explainer can take on the canvas
+pub enum CanvasAction {
+    Draw(Stroke),
+    Undo,
+    Clear,
+}
 
-    let score_change = engine
-        .process_word_result(result)
-        .map_err(|e| e.to_string())?;
+/// Handle a canvas action (drawing mode, explainer only) and relay it to the room
+pub async fn handle_canvas_action(
+    user: &User,
+    action: CanvasAction,
+    room_code: &str,
+    state: &AppState,
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
+    let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
+
+    let engines = GAME_ENGINES.read().await;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
+
+    let mut engine = engine.write().await;
+
+    // Check if user is current explainer
+    let round = engine
+        .game_state
+        .current_round
+        .as_ref()
+        .ok_or_else(ProtocolError::no_active_round)?;
+
+    if round.explainer_id != user_id {
+        return Err(ProtocolError::new(
+            ErrorCode::NotExplainer,
+            "Only explainer can draw",
+        ));
+    }
+
+    let message = match action {
+        CanvasAction::Draw(stroke) => WebSocketMessage::StrokeDrawn {
+            stroke: engine.draw_stroke(stroke)?,
+        },
+        CanvasAction::Undo => {
+            engine.undo_stroke()?;
+            WebSocketMessage::StrokeUndone
+        }
+        CanvasAction::Clear => {
+            engine.clear_canvas()?;
+            WebSocketMessage::CanvasCleared
+        }
+    };
+
+    state
+        .websocket_manager
+        .broadcast_to_room(room_code, message)
+        .await;
+
+    Ok(None)
+}
+
+/// Handle canvas request from late joiners and reconnecting players.
+/// Spectators behind a delay follow the drawing on their delayed stream.
+pub async fn handle_request_canvas(
+    user: &User,
+    room_code: &str,
+    state: &AppState,
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
+    let user_id = user.id.unwrap().to_hex();
+    let is_spectator = state
+        .rooms
+        .read()
+        .await
+        .get(room_code)
+        .is_some_and(|room| room.is_spectator(&user_id));
+    if is_spectator
+        && state
+            .websocket_manager
+            .audience_delay(room_code)
+            .await
+            .is_some()
+    {
+        return Err(ProtocolError::new(
+            ErrorCode::SpectatorNotAllowed,
+            "Delayed spectators can't request the live canvas",
+        ));
+    }
+
+    let engines = GAME_ENGINES.read().await;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
+
+    let engine = engine.read().await;
+
+    Ok(Some(WebSocketMessage::CanvasSnapshot {
+        strokes: engine.canvas().to_vec(),
+    }))
+}
+
+/// Score a Twitch chat message for the audience team
+pub async fn handle_audience_message(message: &ChatMessage, room_code: &str, state: &AppState) {
+    let engines = GAME_ENGINES.read().await;
+    let Some(engine) = engines.get(room_code) else {
+        return;
+    };
+    let mut engine = engine.write().await;
+
+    let Some(guess) = engine.record_audience_guess(&message.user, &message.text) else {
+        return;
+    };
+
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            room_code,
+            WebSocketMessage::AudienceGuessed {
+                viewer: message.user.clone(),
+                // The playing team is still guessing unless the word was stolen
+                word: guess.stolen.then_some(guess.word),
+                stolen: guess.stolen,
+                audience_score: guess.audience_score,
+            },
+        )
+        .await;
+
+    // A stolen word moves the round on, and the explainer gets the next one
+    if guess.stolen {
+        match finish_word(&mut engine, WordResult::Stolen, 0, room_code, state).await {
+            Ok(Some(next_word)) => send_to_explainer(&engine, next_word, room_code, state).await,
+            Ok(None) => {}
+            Err(e) => warn!("Failed to finish stolen word in room {}: {}", room_code, e),
+        }
+    }
+}
+
+/// Send a message to the current explainer's own connections, for word
+/// results that didn't come in on their socket
+pub(crate) async fn send_to_explainer(
+    engine: &GameEngine,
+    message: WebSocketMessage,
+    room_code: &str,
+    state: &AppState,
+) {
+    let Some(round) = engine.game_state.current_round.as_ref() else {
+        return;
+    };
+    state
+        .websocket_manager
+        .send_to_user(
+            &round.explainer_id,
+            UserControl::Send {
+                room_code: room_code.to_string(),
+                message: Box::new(message),
+            },
+        )
+        .await;
+}
+
+/// Broadcast a recorded word result, then hand out the next word or end the round
+pub(crate) async fn finish_word(
+    engine: &mut GameEngine,
+    result: WordResult,
+    score_change: i32,
+    room_code: &str,
+    state: &AppState,
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
+    // Guessed words are revealed to everyone, including spectators
+    let word = if result.is_guessed() {
+        engine.get_previous_word().map(|w| w.word.clone())
+    } else {
+        None
+    };
 
     // Broadcast word result
     state
@@ -286,6 +597,7 @@ pub async fn handle_word_action(
             WebSocketMessage::WordResultRecorded {
                 result,
                 score_change,
+                word,
             },
         )
         .await;
@@ -296,50 +608,77 @@ pub async fn handle_word_action(
         Ok(Some(WebSocketMessage::WordReceived { word: word.clone() }))
     } else {
         // No more words, end round
-        let round = engine.end_round().map_err(|e| e.to_string())?;
+        end_round(engine, room_code, state).await?;
+        Ok(None)
+    }
+}
+
+/// End the current round and announce it, along with the game result if any
+pub(crate) async fn end_round(
+    engine: &mut GameEngine,
+    room_code: &str,
+    state: &AppState,
+) -> Result<Round, ProtocolError> {
+    let phase_before = engine.hat_phase();
+    let round = engine.end_round()?;
+
+    let next_team_id = engine
+        .game_state
+        .teams
+        .get(engine.game_state.current_team_index)
+        .map(|t| t.id.clone());
+
+    // Broadcast round ended
+    let round_ended = WebSocketMessage::RoundEnded {
+        round: round.clone(),
+        next_team_id,
+    };
+    webhooks::notify_room(
+        state,
+        room_code,
+        WebhookEvent::RoundEnded,
+        serde_json::to_value(&round_ended).unwrap_or_default(),
+    )
+    .await;
+    state
+        .websocket_manager
+        .broadcast_to_room(room_code, round_ended)
+        .await;
 
-        let next_team_id = engine
+    if engine.hat_phase() != phase_before && engine.game_state.winner_team_id.is_none() {
+        broadcast_hat_phase(engine, room_code, state).await;
+    }
+
+    // Check for winner
+    if let Some(winner_id) = &engine.game_state.winner_team_id {
+        let winner = engine
             .game_state
             .teams
-            .get(engine.game_state.current_team_index)
-            .map(|t| t.id.clone());
+            .iter()
+            .find(|t| &t.id == winner_id)
+            .cloned()
+            .unwrap();
 
-        // Broadcast round ended
+        let game_ended = WebSocketMessage::GameEnded {
+            winner_team: winner,
+            final_scores: engine.game_state.teams.clone(),
+        };
+        webhooks::notify_room(
+            state,
+            room_code,
+            WebhookEvent::GameEnded,
+            serde_json::to_value(&game_ended).unwrap_or_default(),
+        )
+        .await;
         state
             .websocket_manager
-            .broadcast_to_room(
-                room_code,
-                WebSocketMessage::RoundEnded {
-                    round,
-                    next_team_id,
-                },
-            )
+            .broadcast_to_room(room_code, game_ended)
             .await;
+    }
 
-        // Check for winner
-        if let Some(winner_id) = &engine.game_state.winner_team_id {
-            let winner = engine
-                .game_state
-                .teams
-                .iter()
-                .find(|t| &t.id == winner_id)
-                .cloned()
-                .unwrap();
-
-            state
-                .websocket_manager
-                .broadcast_to_room(
-                    room_code,
-                    WebSocketMessage::GameEnded {
-                        winner_team: winner,
-                        final_scores: engine.game_state.teams.clone(),
-                    },
-                )
-                .await;
-        }
+    bots::schedule_turn(state, room_code);
 
-        Ok(None)
-    }
+    Ok(round)
 }
 
 /// Handle request new word (skip)
@@ -347,7 +686,7 @@ pub async fn handle_request_new_word(
     user: &User,
     room_code: &str,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
     handle_word_action(user, WordResult::Skipped, room_code, state).await
 }
 
@@ -356,11 +695,13 @@ pub async fn handle_end_round(
     user: &User,
     room_code: &str,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
     let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
 
     let engines = GAME_ENGINES.read().await;
-    let engine = engines.get(room_code).ok_or("Game not found")?;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
 
     let mut engine = engine.write().await;
 
@@ -369,84 +710,180 @@ pub async fn handle_end_round(
         .game_state
         .current_round
         .as_ref()
-        .ok_or("No active round")?;
+        .ok_or_else(ProtocolError::no_active_round)?;
 
     if round.explainer_id != user_id {
         // Check if admin
         let rooms = state.rooms.read().await;
-        let room = rooms.get(room_code).ok_or("Room not found")?;
+        let room = rooms
+            .get(room_code)
+            .ok_or_else(ProtocolError::room_not_found)?;
         if room.admin_id != user_id {
-            return Err("Only explainer or admin can end round".to_string());
+            return Err(ProtocolError::new(
+                ErrorCode::NotExplainer,
+                "Only explainer or admin can end round",
+            ));
         }
     }
 
-    let round = engine.end_round().map_err(|e| e.to_string())?;
+    end_round(&mut engine, room_code, state).await?;
 
-    let next_team_id = engine
-        .game_state
-        .teams
-        .get(engine.game_state.current_team_index)
-        .map(|t| t.id.clone());
+    Ok(None)
+}
 
-    // Broadcast round ended
+/// Handle Hat word submission (words stay secret)
+pub async fn handle_submit_words(
+    user: &User,
+    words: Vec<String>,
+    room_code: &str,
+    state: &AppState,
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
+    let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
+
+    let engines = GAME_ENGINES.read().await;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
+
+    let mut engine = engine.write().await;
+    submit_words(&mut engine, &user_id, words, room_code, state).await?;
+
+    Ok(None)
+}
+
+/// Take a player's Hat words and tell the room, over REST or WebSocket.
+/// Returns whether that filled the hat and began the game.
+pub(crate) async fn submit_words(
+    engine: &mut GameEngine,
+    user_id: &str,
+    words: Vec<String>,
+    room_code: &str,
+    state: &AppState,
+) -> Result<bool, ProtocolError> {
+    let count = words.len();
+    let began = engine.submit_hat_words(user_id, words)?;
+
+    // Only announce who submitted, never the words
     state
         .websocket_manager
         .broadcast_to_room(
             room_code,
-            WebSocketMessage::RoundEnded {
-                round,
-                next_team_id,
+            WebSocketMessage::WordsSubmitted {
+                user_id: user_id.to_string(),
+                count,
             },
         )
         .await;
 
-    // Check for winner
-    if let Some(winner_id) = &engine.game_state.winner_team_id {
-        let winner = engine
-            .game_state
-            .teams
-            .iter()
-            .find(|t| &t.id == winner_id)
-            .cloned()
-            .unwrap();
+    if began {
+        broadcast_hat_phase(engine, room_code, state).await;
+        bots::schedule_turn(state, room_code);
+    }
 
+    Ok(began)
+}
+
+/// Broadcast the current Hat phase and game state
+async fn broadcast_hat_phase(engine: &GameEngine, room_code: &str, state: &AppState) {
+    if let Some(progress) = &engine.game_state.hat {
         state
             .websocket_manager
             .broadcast_to_room(
                 room_code,
-                WebSocketMessage::GameEnded {
-                    winner_team: winner,
-                    final_scores: engine.game_state.teams.clone(),
+                WebSocketMessage::HatPhaseChanged {
+                    phase: progress.phase,
+                    words_remaining: progress.words_remaining,
                 },
             )
             .await;
     }
 
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            room_code,
+            WebSocketMessage::GameStateUpdated {
+                game_state: Box::new(engine.game_state.clone()),
+            },
+        )
+        .await;
+}
+
+/// Handle score adjustment message (admin or moderators)
+pub async fn handle_adjust_score(
+    user: &User,
+    team_id: &str,
+    delta: i32,
+    room_code: &str,
+    state: &AppState,
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
+    let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
+
+    let rooms = state.rooms.read().await;
+    let room = rooms
+        .get(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
+    if !room.has_permission(&user_id, ModeratorPermission::AdjustScore) {
+        return Err(ProtocolError::new(
+            ErrorCode::PermissionDenied,
+            "Only admin or moderators can adjust scores",
+        ));
+    }
+    drop(rooms);
+
+    let engines = GAME_ENGINES.read().await;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
+    let mut engine = engine.write().await;
+
+    let score = engine.adjust_score(team_id, delta)?;
+    let teams = engine.game_state.teams.clone();
+    drop(engine);
+    drop(engines);
+
+    state
+        .websocket_manager
+        .broadcast_to_room(room_code, WebSocketMessage::TeamsUpdated { teams })
+        .await;
+
+    info!(
+        "User {} adjusted score of team {} by {} to {} in room {}",
+        user_id, team_id, delta, score, room_code
+    );
+
     Ok(None)
 }
 
-/// Handle pause game message (admin only)
+/// Handle pause game message (admin or moderators)
 pub async fn handle_pause_game(
     user: &User,
     room_code: &str,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
     let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
 
-    // Check if user is admin
+    // Check if user is admin or a moderator allowed to pause
     let rooms = state.rooms.read().await;
-    let room = rooms.get(room_code).ok_or("Room not found")?;
-
-    if room.admin_id != user_id {
-        return Err("Only admin can pause the game".to_string());
+    let room = rooms
+        .get(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
+
+    if !room.has_permission(&user_id, ModeratorPermission::Pause) {
+        return Err(ProtocolError::new(
+            ErrorCode::PermissionDenied,
+            "Only admin or moderators can pause the game",
+        ));
     }
     drop(rooms);
 
     let engines = GAME_ENGINES.read().await;
-    let engine = engines.get(room_code).ok_or("Game not found")?;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
 
     let mut engine = engine.write().await;
-    engine.pause_game().map_err(|e| e.to_string())?;
+    engine.pause_game()?;
 
     // Broadcast game paused
     state
@@ -457,28 +894,35 @@ pub async fn handle_pause_game(
     Ok(None)
 }
 
-/// Handle resume game message (admin only)
+/// Handle resume game message (admin or moderators)
 pub async fn handle_resume_game(
     user: &User,
     room_code: &str,
     state: &AppState,
-) -> Result<Option<WebSocketMessage>, String> {
+) -> Result<Option<WebSocketMessage>, ProtocolError> {
     let user_id = user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default();
 
-    // Check if user is admin
+    // Check if user is admin or a moderator allowed to pause
     let rooms = state.rooms.read().await;
-    let room = rooms.get(room_code).ok_or("Room not found")?;
-
-    if room.admin_id != user_id {
-        return Err("Only admin can resume the game".to_string());
+    let room = rooms
+        .get(room_code)
+        .ok_or_else(ProtocolError::room_not_found)?;
+
+    if !room.has_permission(&user_id, ModeratorPermission::Pause) {
+        return Err(ProtocolError::new(
+            ErrorCode::PermissionDenied,
+            "Only admin or moderators can resume the game",
+        ));
     }
     drop(rooms);
 
     let engines = GAME_ENGINES.read().await;
-    let engine = engines.get(room_code).ok_or("Game not found")?;
+    let engine = engines
+        .get(room_code)
+        .ok_or_else(ProtocolError::game_not_found)?;
 
     let mut engine = engine.write().await;
-    engine.resume_game().map_err(|e| e.to_string())?;
+    engine.resume_game()?;
 
     // Broadcast game resumed
     state
@@ -0,0 +1,202 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+async fn refresh(app: &Router, refresh_token: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/auth/refresh")
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    json!({ "refresh_token": refresh_token }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(json!({})))
+}
+
+async fn logout(app: &Router, access_token: &str) -> StatusCode {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/auth/logout")
+                .header("Authorization", format!("Bearer {}", access_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+/// Call a protected endpoint and return its status
+async fn protected_status(app: &Router, access_token: &str) -> StatusCode {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/api/v1/webhooks")
+                .header("Authorization", format!("Bearer {}", access_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_refresh_rotates_tokens() {
+    let (app, state) = create_test_app_with_state();
+    let tokens = create_test_session(&state, "refresh_user").await;
+    let first_refresh = tokens.refresh_token.unwrap();
+    assert!(tokens.expires_in <= 15 * 60);
+
+    let (status, refreshed) = refresh(&app, &first_refresh).await;
+    assert_eq!(status, StatusCode::OK);
+    let access_token = refreshed["access_token"].as_str().unwrap();
+    let second_refresh = refreshed["refresh_token"].as_str().unwrap();
+    assert_ne!(second_refresh, first_refresh);
+    assert_eq!(protected_status(&app, access_token).await, StatusCode::OK);
+
+    let (status, _) = refresh(&app, "unknown-token").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_reused_refresh_token_ends_session() {
+    let (app, state) = create_test_app_with_state();
+    let tokens = create_test_session(&state, "stolen_token_user").await;
+    let first_refresh = tokens.refresh_token.unwrap();
+
+    let (_, refreshed) = refresh(&app, &first_refresh).await;
+    let access_token = refreshed["access_token"].as_str().unwrap();
+    let second_refresh = refreshed["refresh_token"].as_str().unwrap();
+
+    // Replaying the rotated token revokes everything issued for the session
+    let (status, _) = refresh(&app, &first_refresh).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = refresh(&app, second_refresh).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        protected_status(&app, access_token).await,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn test_logout_revokes_session() {
+    let (app, state) = create_test_app_with_state();
+    let tokens = create_test_session(&state, "logout_user").await;
+    let other = create_test_session(&state, "other_user").await;
+
+    assert_eq!(
+        protected_status(&app, &tokens.access_token).await,
+        StatusCode::OK
+    );
+    assert_eq!(logout(&app, &tokens.access_token).await, StatusCode::OK);
+
+    assert_eq!(
+        protected_status(&app, &tokens.access_token).await,
+        StatusCode::UNAUTHORIZED
+    );
+    let (status, _) = refresh(&app, &tokens.refresh_token.unwrap()).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        logout(&app, &tokens.access_token).await,
+        StatusCode::UNAUTHORIZED
+    );
+
+    // Other sessions are unaffected
+    assert_eq!(
+        protected_status(&app, &other.access_token).await,
+        StatusCode::OK
+    );
+}
+
+async fn get_json(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(json!({})))
+}
+
+#[tokio::test]
+async fn test_identity_provider_login_urls() {
+    let (app, _state) = create_test_app_with_state();
+
+    let (status, body) = get_json(&app, "/api/v1/auth/providers").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["providers"], json!(["discord", "twitch"]));
+
+    let (status, body) = get_json(
+        &app,
+        "/api/v1/auth/discord/login?redirect_uri=http://localhost/cb",
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let auth_url = body["auth_url"].as_str().unwrap();
+    assert!(auth_url.starts_with("https://discord.com/oauth2/authorize?client_id=test_discord_id"));
+    assert!(auth_url.contains("redirect_uri=http%3A%2F%2Flocalhost%2Fcb"));
+
+    let (status, _) = get_json(&app, "/api/v1/auth/myspace/login").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_access_tokens_verify_against_jwks() {
+    let (app, state) = create_test_app_with_state();
+    let tokens = create_test_session(&state, "jwks_user").await;
+
+    let (status, jwks) = get_json(&app, "/.well-known/jwks.json").await;
+    assert_eq!(status, StatusCode::OK);
+    let jwks: jsonwebtoken::jwk::JwkSet = serde_json::from_value(jwks).unwrap();
+
+    // Verify the way another service would: pick the key by `kid`
+    let header = jsonwebtoken::decode_header(&tokens.access_token).unwrap();
+    let jwk = jwks
+        .find(&header.kid.unwrap())
+        .expect("signing key published");
+    let key = jsonwebtoken::DecodingKey::from_jwk(jwk).unwrap();
+    let claims = jsonwebtoken::decode::<shared::models::JwtClaims>(
+        &tokens.access_token,
+        &key,
+        &jsonwebtoken::Validation::new(header.alg),
+    )
+    .unwrap()
+    .claims;
+    assert_eq!(claims.username, "jwks_user");
+
+    // No secret is published
+    assert!(jwks.keys.iter().all(|jwk| !matches!(
+        jwk.algorithm,
+        jsonwebtoken::jwk::AlgorithmParameters::OctetKey(_)
+    )));
+}
@@ -0,0 +1,270 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use chrono::{Duration, Utc};
+use serde_json::{json, Value};
+use shared::models::{Ban, WebSocketMessage};
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+async fn send(
+    app: &Router,
+    method: Method,
+    auth_token: &str,
+    uri: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Authorization", format!("Bearer {}", auth_token))
+        .header("Content-Type", "application/json");
+    let body = body.map_or(Body::empty(), |body| Body::from(body.to_string()));
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(json!({})))
+}
+
+/// Join a room and return the joining user's ID
+async fn join_and_get_id(
+    app: &Router,
+    auth_token: &str,
+    room_code: &str,
+    username: &str,
+) -> String {
+    let (status, room) = join_test_room(app, auth_token, room_code).await;
+    assert_eq!(status, StatusCode::OK);
+    room["participants"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .find(|(_, p)| p["username"] == username)
+        .map(|(id, _)| id.clone())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_kicked_players_hear_the_reason_and_can_return() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let player_token = create_test_user(&app, "player_user").await;
+    let room_code = create_test_room(&app, &admin_token, "Kick Room", 6).await;
+    let player_id = join_and_get_id(&app, &player_token, &room_code, "player_user").await;
+
+    let mut room_events = state
+        .websocket_manager
+        .get_or_create_room_sender(&room_code)
+        .await
+        .subscribe();
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &admin_token,
+        &format!("/api/v1/rooms/{}/kick/{}", room_code, player_id),
+        Some(json!({ "reason": "Spoilers" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    match room_events.try_recv().unwrap().message {
+        WebSocketMessage::UserKicked {
+            user_id,
+            reason,
+            banned,
+            ..
+        } => {
+            assert_eq!(user_id, player_id);
+            assert_eq!(reason.as_deref(), Some("Spoilers"));
+            assert!(!banned);
+        }
+        other => panic!("unexpected room message: {:?}", other),
+    }
+
+    // A kick is not a ban
+    let (status, _) = join_test_room(&app, &player_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_room_bans_keep_players_out() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let player_token = create_test_user(&app, "player_user").await;
+    let room_code = create_test_room(&app, &admin_token, "Ban Room", 6).await;
+    let player_id = join_and_get_id(&app, &player_token, &room_code, "player_user").await;
+    let bans_uri = format!("/api/v1/rooms/{}/bans", room_code);
+
+    let mut room_events = state
+        .websocket_manager
+        .get_or_create_room_sender(&room_code)
+        .await
+        .subscribe();
+
+    // Only the admin bans
+    let ban_request = json!({ "user_id": player_id, "reason": "Cheating", "duration_minutes": 60 });
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &player_token,
+        &bans_uri,
+        Some(ban_request.clone()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, ban) = send(
+        &app,
+        Method::POST,
+        &admin_token,
+        &bans_uri,
+        Some(ban_request),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(ban["expires_at"].is_string());
+
+    match room_events.try_recv().unwrap().message {
+        WebSocketMessage::UserKicked {
+            user_id,
+            reason,
+            banned,
+            ..
+        } => {
+            assert_eq!(user_id, player_id);
+            assert_eq!(reason.as_deref(), Some("Cheating"));
+            assert!(banned);
+        }
+        other => panic!("unexpected room message: {:?}", other),
+    }
+
+    // Banned players can't come back, not even to watch or with an invite
+    let (_, room) = get_test_room(&app, &room_code).await;
+    assert!(room["participants"].get(&player_id).is_none());
+    assert!(room.get("bans").is_none());
+    let (status, _) = join_test_room(&app, &player_token, &room_code).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = spectate_test_room(&app, &player_token, &room_code).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (_, invite) = send(
+        &app,
+        Method::POST,
+        &admin_token,
+        &format!("/api/v1/rooms/{}/invites", room_code),
+        None,
+    )
+    .await;
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &player_token,
+        &format!("/api/v1/rooms/{}/join", room_code),
+        Some(json!({ "invite": invite["invite"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, bans) = send(&app, Method::GET, &admin_token, &bans_uri, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bans.as_array().unwrap().len(), 1);
+
+    // Lifting the ban lets them back in
+    let (status, _) = send(
+        &app,
+        Method::DELETE,
+        &admin_token,
+        &format!("{}/{}", bans_uri, player_id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = join_test_room(&app, &player_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_expired_bans_no_longer_apply() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let player_token = create_test_user(&app, "player_user").await;
+    let room_code = create_test_room(&app, &admin_token, "Ban Room", 6).await;
+    let player_id = join_and_get_id(&app, &player_token, &room_code, "player_user").await;
+    leave_test_room(&app, &player_token, &room_code).await;
+
+    state
+        .rooms
+        .write()
+        .await
+        .get_mut(&room_code)
+        .unwrap()
+        .bans
+        .insert(
+            player_id.clone(),
+            Ban {
+                user_id: player_id,
+                reason: None,
+                banned_by: "admin".to_string(),
+                banned_at: Utc::now() - Duration::hours(2),
+                expires_at: Some(Utc::now() - Duration::hours(1)),
+            },
+        );
+
+    let (status, _) = join_test_room(&app, &player_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_streamer_bans_apply_to_new_rooms() {
+    let app = create_test_app().await;
+    let streamer_token = create_test_user(&app, "streamer").await;
+    let troll_token = create_test_user(&app, "troll").await;
+    let first_room = create_test_room(&app, &streamer_token, "First Stream", 6).await;
+    let troll_id = join_and_get_id(&app, &troll_token, &first_room, "troll").await;
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &streamer_token,
+        "/api/v1/bans",
+        Some(json!({ "user_id": troll_id, "reason": "Harassment" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, bans) = send(&app, Method::GET, &streamer_token, "/api/v1/bans", None).await;
+    assert_eq!(bans.as_array().unwrap().len(), 1);
+    assert!(bans[0]["expires_at"].is_null());
+
+    let next_room = create_test_room(&app, &streamer_token, "Next Stream", 6).await;
+    let (status, body) = join_test_room(&app, &troll_token, &next_room).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(body["error"].as_str().unwrap().contains("Harassment"));
+
+    // Other streamers' rooms are unaffected
+    let other_token = create_test_user(&app, "other_streamer").await;
+    let other_room = create_test_room(&app, &other_token, "Other Stream", 6).await;
+    let (status, _) = join_test_room(&app, &troll_token, &other_room).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = send(
+        &app,
+        Method::DELETE,
+        &streamer_token,
+        &format!("/api/v1/bans/{}", troll_id),
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let later_room = create_test_room(&app, &streamer_token, "Later Stream", 6).await;
+    let (status, _) = join_test_room(&app, &troll_token, &later_room).await;
+    assert_eq!(status, StatusCode::OK);
+}
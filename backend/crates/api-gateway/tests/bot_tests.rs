@@ -0,0 +1,378 @@
+use api_gateway::bots;
+use api_gateway::game::GAME_ENGINES;
+use api_gateway::websocket::UserControl;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use game_engine::game::GameEngine;
+use serde_json::{json, Value};
+use shared::models::{
+    GameMode, GameSettings, HatPhase, HatPhaseRules, HatSettings, WebSocketMessage,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+async fn add_bot(
+    app: &Router,
+    auth_token: &str,
+    room_code: &str,
+    body: Value,
+) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/api/v1/rooms/{}/bots", room_code))
+                .header("Authorization", format!("Bearer {}", auth_token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(json!({})))
+}
+
+// Guesses every word within a few milliseconds
+fn sharp_bot(team_id: &str) -> Value {
+    json!({
+        "team_id": team_id,
+        "settings": { "accuracy": 1.0, "min_delay_ms": 10, "max_delay_ms": 30 },
+    })
+}
+
+#[tokio::test]
+async fn test_admin_adds_bots_to_teams() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "bot_admin").await;
+    let player_token = create_test_user(&app, "bot_player").await;
+    let room_code = create_test_room(&app, &admin_token, "Bot Room", 4).await;
+    join_test_room(&app, &player_token, &room_code).await;
+
+    let (status, bot) = add_bot(
+        &app,
+        &admin_token,
+        &room_code,
+        json!({ "team_id": "team_a" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bot["user_id"], "bot-1");
+    assert_eq!(bot["display_name"], "Бот 1");
+    assert_eq!(bot["team_id"], "team_a");
+    assert_eq!(bot["bot"]["accuracy"], 0.7);
+
+    let (status, bot) = add_bot(&app, &admin_token, &room_code, sharp_bot("team_b")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bot["user_id"], "bot-2");
+
+    // Only the admin adds bots, and only valid ones
+    let (status, _) = add_bot(&app, &player_token, &room_code, sharp_bot("team_a")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = add_bot(&app, &admin_token, &room_code, sharp_bot("team_z")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = add_bot(
+        &app,
+        &admin_token,
+        &room_code,
+        json!({ "team_id": "team_a", "settings": { "accuracy": 1.5, "min_delay_ms": 0, "max_delay_ms": 0 } }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Bots take seats like players
+    let (status, _) = add_bot(&app, &admin_token, &room_code, sharp_bot("team_a")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, room) = get_test_room(&app, &room_code).await;
+    assert_eq!(room["participants"].as_object().unwrap().len(), 4);
+    assert!(room["participants"]["bot-1"]["bot"].is_object());
+
+    // Kicking a bot frees its seat
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/api/v1/rooms/{}/kick/bot-1", room_code))
+                .header("Authorization", format!("Bearer {}", admin_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let (status, bot) = add_bot(&app, &admin_token, &room_code, sharp_bot("team_a")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(bot["user_id"], "bot-1");
+}
+
+#[tokio::test]
+async fn test_bots_dont_keep_a_room_open() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "lonely_admin").await;
+    let room_code = create_test_room(&app, &admin_token, "Lonely Room", 4).await;
+    add_bot(&app, &admin_token, &room_code, sharp_bot("team_a")).await;
+    add_bot(&app, &admin_token, &room_code, sharp_bot("team_b")).await;
+
+    assert_eq!(
+        leave_test_room(&app, &admin_token, &room_code).await,
+        StatusCode::OK
+    );
+    let (status, _) = get_test_room(&app, &room_code).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_bots_play_a_game_to_the_end() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "practice_admin").await;
+    let room_code = create_test_room(&app, &admin_token, "Practice Room", 8).await;
+    for team_id in ["team_a", "team_a", "team_b"] {
+        let (status, _) = add_bot(&app, &admin_token, &room_code, sharp_bot(team_id)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // The admin fills team B's second seat and puts the words in the hat
+    let admin_id = {
+        let mut rooms = state.rooms.write().await;
+        let room = rooms.get_mut(&room_code).unwrap();
+        let admin_id = room.admin_id.clone();
+        room.participants.get_mut(&admin_id).unwrap().team_id = Some("team_b".to_string());
+        admin_id
+    };
+
+    let settings = GameSettings {
+        mode: GameMode::Hat,
+        words_per_round: 10,
+        hat: HatSettings {
+            words_per_player: 3,
+            phases: vec![HatPhaseRules {
+                phase: HatPhase::Describe,
+                round_duration_seconds: 60,
+                allow_skip: true,
+            }],
+        },
+        ..GameSettings::default()
+    };
+    let mut engine = GameEngine::new(&state.mongo_client, Some(settings)).await;
+    for participant in state.rooms.read().await[&room_code].participants.values() {
+        engine
+            .team_manager
+            .add_player_to_team(
+                participant.user_id.clone(),
+                participant.team_id.as_deref().unwrap(),
+            )
+            .unwrap();
+    }
+    engine.start_game().await.unwrap();
+
+    // Bots have no words of their own, so the admin's fill the hat
+    let words = ["кіт", "сонце", "потяг"].map(String::from).to_vec();
+    assert!(engine.submit_hat_words(&admin_id, words).unwrap());
+
+    let engine = Arc::new(RwLock::new(engine));
+    GAME_ENGINES
+        .write()
+        .await
+        .insert(room_code.clone(), engine.clone());
+
+    // Team A's bots explain and guess every word, which ends the only phase
+    bots::schedule_turn(&state, &room_code);
+    let mut winner = None;
+    for _ in 0..100 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        winner = engine.read().await.game_state.winner_team_id.clone();
+        if winner.is_some() {
+            break;
+        }
+    }
+    assert_eq!(winner.as_deref(), Some("team_a"));
+
+    let engine = engine.read().await;
+    let round = &engine.game_state.round_history[0];
+    assert!(round.explainer_id.starts_with("bot-"));
+    assert_eq!(round.score_gained, 3);
+    assert!(!round.clues.is_empty());
+    drop(engine);
+
+    GAME_ENGINES.write().await.remove(&room_code);
+}
+
+#[tokio::test]
+async fn test_human_explainer_gets_the_next_word_after_a_bot_guess() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "coached_admin").await;
+    let room_code = create_test_room(&app, &admin_token, "Coached Room", 8).await;
+    for team_id in ["team_a", "team_b", "team_b"] {
+        let (status, _) = add_bot(&app, &admin_token, &room_code, sharp_bot(team_id)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let admin_id = state.rooms.read().await[&room_code].admin_id.clone();
+
+    let settings = GameSettings {
+        mode: GameMode::Hat,
+        hat: HatSettings {
+            words_per_player: 2,
+            phases: vec![HatPhaseRules {
+                phase: HatPhase::Describe,
+                round_duration_seconds: 60,
+                allow_skip: true,
+            }],
+        },
+        ..GameSettings::default()
+    };
+    let mut engine = GameEngine::new(&state.mongo_client, Some(settings)).await;
+    // The admin sits first on team A, so they explain its first round
+    engine
+        .team_manager
+        .add_player_to_team(admin_id.clone(), "team_a")
+        .unwrap();
+    for participant in state.rooms.read().await[&room_code].participants.values() {
+        if let Some(team_id) = participant.team_id.as_deref() {
+            engine
+                .team_manager
+                .add_player_to_team(participant.user_id.clone(), team_id)
+                .unwrap();
+        }
+    }
+    engine.start_game().await.unwrap();
+    let words = ["кіт", "сонце"].map(String::from).to_vec();
+    assert!(engine.submit_hat_words(&admin_id, words).unwrap());
+    let round = engine.start_round().await.unwrap();
+    assert_eq!(round.explainer_id, admin_id);
+    let first_word = engine.get_current_word().unwrap().word.clone();
+
+    GAME_ENGINES
+        .write()
+        .await
+        .insert(room_code.clone(), Arc::new(RwLock::new(engine)));
+    let (_, mut explainer) = state.websocket_manager.register_user(&admin_id).await;
+
+    // The bot teammate guesses the first word and the admin gets the second
+    bots::round_started(&state, &room_code, &round).await;
+    let control = tokio::time::timeout(Duration::from_secs(2), explainer.recv())
+        .await
+        .expect("explainer got no next word")
+        .unwrap();
+    match control {
+        UserControl::Send { message, .. } => assert!(
+            matches!(*message, WebSocketMessage::WordReceived { ref word } if word.word != first_word)
+        ),
+        other => panic!("unexpected control message {:?}", other),
+    }
+
+    GAME_ENGINES.write().await.remove(&room_code);
+}
+
+async fn post_as(
+    app: &Router,
+    auth_token: &str,
+    method: Method,
+    uri: &str,
+    body: Value,
+) -> StatusCode {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", format!("Bearer {}", auth_token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_bots_cant_be_given_room_roles() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "role_bot_admin").await;
+    let room_code = create_test_room(&app, &admin_token, "Role Bot Room", 4).await;
+    add_bot(&app, &admin_token, &room_code, sharp_bot("team_a")).await;
+
+    let status = post_as(
+        &app,
+        &admin_token,
+        Method::POST,
+        &format!("/api/v1/rooms/{}/transfer-admin", room_code),
+        json!({ "user_id": "bot-1" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let status = post_as(
+        &app,
+        &admin_token,
+        Method::PUT,
+        &format!("/api/v1/rooms/{}/roles/bot-1", room_code),
+        json!({ "role": "moderator" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, room) = get_test_room(&app, &room_code).await;
+    assert_eq!(room["participants"]["bot-1"]["role"], "player");
+}
+
+#[tokio::test]
+async fn test_kicked_players_leave_the_game_teams() {
+    let state = create_test_state();
+    let app = create_test_router(state.clone());
+    let admin_token = create_test_user(&app, "team_kick_admin").await;
+    let room_code = create_test_room(&app, &admin_token, "Team Kick Room", 4).await;
+
+    let engine = GameEngine::new(&state.mongo_client, None).await;
+    GAME_ENGINES
+        .write()
+        .await
+        .insert(room_code.clone(), Arc::new(RwLock::new(engine)));
+    add_bot(&app, &admin_token, &room_code, sharp_bot("team_a")).await;
+    let mut receiver = state
+        .websocket_manager
+        .get_or_create_room_sender(&room_code)
+        .await
+        .subscribe();
+
+    let status = post_as(
+        &app,
+        &admin_token,
+        Method::POST,
+        &format!("/api/v1/rooms/{}/kick/bot-1", room_code),
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let teams = tokio::time::timeout(Duration::from_secs(1), async {
+        loop {
+            if let WebSocketMessage::TeamsUpdated { teams } = receiver.recv().await.unwrap().message
+            {
+                break teams;
+            }
+        }
+    })
+    .await
+    .expect("teams were not updated");
+    assert!(teams.iter().all(|team| team.players.is_empty()));
+
+    let engines = GAME_ENGINES.read().await;
+    let engine = engines[&room_code].read().await;
+    assert!(engine.team_manager.get_player_team("bot-1").is_none());
+    drop(engine);
+    drop(engines);
+    GAME_ENGINES.write().await.remove(&room_code);
+}
@@ -0,0 +1,157 @@
+use api_gateway::delta::DeltaEncoder;
+use game_engine::game::GameEngine;
+use serde_json::{json, Value};
+use shared::delta::{self, PatchOp};
+use shared::models::WebSocketMessage;
+
+mod test_helpers;
+use test_helpers::*;
+
+#[test]
+fn test_diff_and_apply_round_trip() {
+    let old = json!({
+        "name": "Room",
+        "teams": [{ "id": "a", "score": 1 }, { "id": "b", "score": 2 }],
+        "words": ["кіт", "пес"],
+        "settings": { "a/b": 1, "gone": true },
+    });
+    let new = json!({
+        "name": "Room",
+        "teams": [{ "id": "a", "score": 4 }, { "id": "b", "score": 2 }],
+        "words": ["кіт"],
+        "settings": { "a/b": 2, "added": null },
+    });
+
+    let ops = delta::diff(&old, &new);
+    assert!(ops.contains(&PatchOp::Replace {
+        path: "/teams/0/score".to_string(),
+        value: json!(4),
+    }));
+    assert!(ops.contains(&PatchOp::Replace {
+        path: "/settings/a~1b".to_string(),
+        value: json!(2),
+    }));
+
+    let mut doc = old.clone();
+    delta::apply(&mut doc, &ops).unwrap();
+    assert_eq!(doc, new);
+
+    // And back again, with arrays growing
+    let mut doc = new.clone();
+    delta::apply(&mut doc, &delta::diff(&new, &old)).unwrap();
+    assert_eq!(doc, old);
+
+    assert!(delta::diff(&old, &old).is_empty());
+    assert!(delta::apply(
+        &mut doc,
+        &[PatchOp::Remove {
+            path: "/missing/0".to_string()
+        }]
+    )
+    .is_err());
+}
+
+/// Apply an encoded update to the client's copy of a document
+fn apply_update(doc: &mut Value, version: &mut u64, message: WebSocketMessage) -> Vec<PatchOp> {
+    let (base_version, new_version, ops) = match message {
+        WebSocketMessage::RoomDelta {
+            base_version,
+            version,
+            ops,
+        }
+        | WebSocketMessage::GameStateDelta {
+            base_version,
+            version,
+            ops,
+        } => (base_version, version, ops),
+        other => panic!("expected a delta, got {}", other.type_name()),
+    };
+    assert!(base_version == 0 || base_version == *version);
+    assert!(new_version > *version);
+    delta::apply(doc, &ops).unwrap();
+    *version = new_version;
+    ops
+}
+
+#[tokio::test]
+async fn test_room_updates_become_deltas() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let room_code = create_test_room(&app, &admin_token, "Delta Room", 8).await;
+    let room = state.rooms.read().await[&room_code].clone();
+
+    let mut encoder = DeltaEncoder::default();
+    let (mut doc, mut version) = (Value::Null, 0);
+
+    // The first update replaces the whole document
+    let ops = apply_update(
+        &mut doc,
+        &mut version,
+        encoder.encode(WebSocketMessage::RoomUpdated { room: room.clone() }),
+    );
+    assert!(matches!(&ops[..], [PatchOp::Replace { path, .. }] if path.is_empty()));
+    assert_eq!(doc, serde_json::to_value(&room).unwrap());
+
+    // Later ones only carry what changed
+    let player_token = create_test_user(&app, "player_user").await;
+    join_test_room(&app, &player_token, &room_code).await;
+    let room = state.rooms.read().await[&room_code].clone();
+    let ops = apply_update(
+        &mut doc,
+        &mut version,
+        encoder.encode(WebSocketMessage::RoomUpdated { room: room.clone() }),
+    );
+    assert!(ops.iter().all(|op| !op.path().is_empty()));
+    assert_eq!(doc, serde_json::to_value(&room).unwrap());
+    assert_eq!(version, 2);
+
+    // Full room state sent directly starts the documents over
+    encoder.sent_directly(&WebSocketMessage::RoomJoined { room: room.clone() });
+    match encoder.encode(WebSocketMessage::RoomUpdated { room }) {
+        WebSocketMessage::RoomDelta {
+            base_version,
+            version,
+            ..
+        } => assert_eq!((base_version, version), (0, 3)),
+        other => panic!("unexpected {}", other.type_name()),
+    }
+
+    // Other messages pass through
+    assert_eq!(encoder.encode(WebSocketMessage::Pong).type_name(), "pong");
+}
+
+#[tokio::test]
+async fn test_game_state_deltas_skip_unchanged_history() {
+    let state = create_test_state();
+    let mut engine = GameEngine::new(&state.mongo_client, None).await;
+    for (user_id, team) in [
+        ("a1", "team_a"),
+        ("a2", "team_a"),
+        ("b1", "team_b"),
+        ("b2", "team_b"),
+    ] {
+        engine
+            .team_manager
+            .add_player_to_team(user_id.to_string(), team)
+            .unwrap();
+    }
+    engine.start_game().await.unwrap();
+
+    let mut encoder = DeltaEncoder::default();
+    let (mut doc, mut version) = (Value::Null, 0);
+    let update = |engine: &GameEngine| WebSocketMessage::GameStateUpdated {
+        game_state: Box::new(engine.game_state.clone()),
+    };
+    apply_update(&mut doc, &mut version, encoder.encode(update(&engine)));
+
+    engine.adjust_score("team_b", 3).unwrap();
+    let ops = apply_update(&mut doc, &mut version, encoder.encode(update(&engine)));
+    assert_eq!(
+        ops,
+        vec![PatchOp::Replace {
+            path: "/teams/1/score".to_string(),
+            value: json!(3),
+        }]
+    );
+    assert_eq!(doc, serde_json::to_value(&engine.game_state).unwrap());
+}
@@ -0,0 +1,56 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+#[tokio::test]
+async fn test_registered_only_rooms_reject_guests() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let guest_token = create_test_guest("Guest Player").await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/rooms")
+                .header("Authorization", format!("Bearer {}", admin_token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    json!({
+                        "name": "Members Room",
+                        "max_players": 6,
+                        "registered_only": true
+                    })
+                    .to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let created: Value = serde_json::from_slice(&body).unwrap();
+    let members_room = created["room_code"].as_str().unwrap();
+
+    let (status, _) = join_test_room(&app, &guest_token, members_room).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = spectate_test_room(&app, &guest_token, members_room).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let player_token = create_test_user(&app, "registered_player").await;
+    let (status, _) = join_test_room(&app, &player_token, members_room).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Guests can join open rooms, and can host their own
+    let open_room = create_test_room(&app, &admin_token, "Open Room", 6).await;
+    let (status, _) = join_test_room(&app, &guest_token, &open_room).await;
+    assert_eq!(status, StatusCode::OK);
+    create_test_room(&app, &guest_token, "Guest Room", 6).await;
+}
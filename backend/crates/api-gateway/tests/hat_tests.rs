@@ -0,0 +1,102 @@
+use api_gateway::game::GAME_ENGINES;
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+use game_engine::game::GameEngine;
+use serde_json::json;
+use shared::models::{GameMode, GameSettings, HatSettings, WebSocketMessage};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+#[tokio::test]
+async fn test_words_submitted_over_rest_are_announced() {
+    let state = create_test_state();
+    let app = create_test_router(state.clone());
+    let admin_token = create_test_user(&app, "rest_hat_a1").await;
+    let room_code = create_test_room(&app, &admin_token, "Hat Room", 4).await;
+    for name in ["rest_hat_a2", "rest_hat_b1", "rest_hat_b2"] {
+        let token = create_test_user(&app, name).await;
+        let (status, _) = join_test_room(&app, &token, &room_code).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let settings = GameSettings {
+        mode: GameMode::Hat,
+        hat: HatSettings {
+            words_per_player: 1,
+            ..HatSettings::default()
+        },
+        ..GameSettings::default()
+    };
+    let mut engine = GameEngine::new(&state.mongo_client, Some(settings)).await;
+    let admin_id = {
+        let rooms = state.rooms.read().await;
+        let room = &rooms[&room_code];
+        for participant in room.participants.values() {
+            let team_id = if participant.username.contains("_a") {
+                "team_a"
+            } else {
+                "team_b"
+            };
+            engine
+                .team_manager
+                .add_player_to_team(participant.user_id.clone(), team_id)
+                .unwrap();
+        }
+        room.admin_id.clone()
+    };
+    engine.start_game().await.unwrap();
+
+    // Everyone but the admin has already filled in their words
+    let others: Vec<String> = state.rooms.read().await[&room_code]
+        .participants
+        .keys()
+        .filter(|id| **id != admin_id)
+        .cloned()
+        .collect();
+    for (user_id, word) in others.iter().zip(["кіт", "сонце", "потяг"]) {
+        engine
+            .submit_hat_words(user_id, vec![word.to_string()])
+            .unwrap();
+    }
+    GAME_ENGINES
+        .write()
+        .await
+        .insert(room_code.clone(), Arc::new(RwLock::new(engine)));
+    let mut receiver = state
+        .websocket_manager
+        .get_or_create_room_sender(&room_code)
+        .await
+        .subscribe();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/api/v1/game/{}/hat/words", room_code))
+                .header("Authorization", format!("Bearer {}", admin_token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "words": ["море"] }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let broadcast: Vec<WebSocketMessage> = std::iter::from_fn(|| receiver.try_recv().ok())
+        .map(|sequenced| sequenced.message)
+        .collect();
+    assert!(broadcast.iter().any(|message| matches!(
+        message,
+        WebSocketMessage::WordsSubmitted { user_id, count: 1 } if *user_id == admin_id
+    )));
+    assert!(broadcast
+        .iter()
+        .any(|message| matches!(message, WebSocketMessage::HatPhaseChanged { .. })));
+}
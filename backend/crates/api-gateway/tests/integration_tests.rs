@@ -108,7 +108,7 @@ async fn test_room_join_flow() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/join", room_code))
+                .uri(format!("/api/v1/rooms/{}/join", room_code))
                 .header("Authorization", format!("Bearer {}", player_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -165,7 +165,7 @@ async fn test_room_join_already_member() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/join", room_code))
+                .uri(format!("/api/v1/rooms/{}/join", room_code))
                 .header("Authorization", format!("Bearer {}", token))
                 .body(Body::empty())
                 .unwrap(),
@@ -198,7 +198,7 @@ async fn test_room_join_full_room() {
             .oneshot(
                 Request::builder()
                     .method(Method::POST)
-                    .uri(&format!("/api/v1/rooms/{}/join", room_code))
+                    .uri(format!("/api/v1/rooms/{}/join", room_code))
                     .header("Authorization", format!("Bearer {}", token))
                     .body(Body::empty())
                     .unwrap(),
@@ -215,7 +215,7 @@ async fn test_room_join_full_room() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/join", room_code))
+                .uri(format!("/api/v1/rooms/{}/join", room_code))
                 .header("Authorization", format!("Bearer {}", extra_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -240,7 +240,7 @@ async fn test_get_room_info() {
         .oneshot(
             Request::builder()
                 .method(Method::GET)
-                .uri(&format!("/api/v1/rooms/{}", room_code))
+                .uri(format!("/api/v1/rooms/{}", room_code))
                 .body(Body::empty())
                 .unwrap(),
         )
@@ -332,7 +332,7 @@ async fn test_leave_room() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/join", room_code))
+                .uri(format!("/api/v1/rooms/{}/join", room_code))
                 .header("Authorization", format!("Bearer {}", player_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -347,7 +347,7 @@ async fn test_leave_room() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/leave", room_code))
+                .uri(format!("/api/v1/rooms/{}/leave", room_code))
                 .header("Authorization", format!("Bearer {}", player_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -363,7 +363,7 @@ async fn test_leave_room() {
         .oneshot(
             Request::builder()
                 .method(Method::GET)
-                .uri(&format!("/api/v1/rooms/{}", room_code))
+                .uri(format!("/api/v1/rooms/{}", room_code))
                 .body(Body::empty())
                 .unwrap(),
         )
@@ -389,7 +389,7 @@ async fn test_admin_leaves_room_deletion() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/leave", room_code))
+                .uri(format!("/api/v1/rooms/{}/leave", room_code))
                 .header("Authorization", format!("Bearer {}", admin_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -405,7 +405,7 @@ async fn test_admin_leaves_room_deletion() {
         .oneshot(
             Request::builder()
                 .method(Method::GET)
-                .uri(&format!("/api/v1/rooms/{}", room_code))
+                .uri(format!("/api/v1/rooms/{}", room_code))
                 .body(Body::empty())
                 .unwrap(),
         )
@@ -429,7 +429,7 @@ async fn test_admin_leaves_room_transfer() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/join", room_code))
+                .uri(format!("/api/v1/rooms/{}/join", room_code))
                 .header("Authorization", format!("Bearer {}", player_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -444,7 +444,7 @@ async fn test_admin_leaves_room_transfer() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/leave", room_code))
+                .uri(format!("/api/v1/rooms/{}/leave", room_code))
                 .header("Authorization", format!("Bearer {}", admin_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -460,7 +460,7 @@ async fn test_admin_leaves_room_transfer() {
         .oneshot(
             Request::builder()
                 .method(Method::GET)
-                .uri(&format!("/api/v1/rooms/{}", room_code))
+                .uri(format!("/api/v1/rooms/{}", room_code))
                 .body(Body::empty())
                 .unwrap(),
         )
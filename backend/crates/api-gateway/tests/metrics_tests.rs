@@ -0,0 +1,44 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+async fn get_metrics(app: &Router) -> Value {
+    let response = app
+        .clone()
+        .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_metrics_report_websocket_lag() {
+    let (app, state) = create_test_app_with_state();
+    assert_eq!(
+        get_metrics(&app).await["websocket"],
+        json!({ "open_connections": 0, "lag_events": 0, "lagged_messages": 0, "resyncs": 0 })
+    );
+
+    let metrics = &state.websocket_manager.metrics;
+    metrics.connection_opened();
+    metrics.connection_opened();
+    metrics.record_lag(120);
+    metrics.record_resync();
+    metrics.record_lag(30);
+    metrics.record_resync();
+    metrics.connection_closed();
+
+    assert_eq!(
+        get_metrics(&app).await["websocket"],
+        json!({ "open_connections": 1, "lag_events": 2, "lagged_messages": 150, "resyncs": 2 })
+    );
+}
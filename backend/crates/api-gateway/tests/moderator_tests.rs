@@ -0,0 +1,361 @@
+use api_gateway::game::GAME_ENGINES;
+use api_gateway::websocket;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use game_engine::game::GameEngine;
+use serde_json::{json, Value};
+use shared::models::WebSocketMessage;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+async fn send(
+    app: &Router,
+    method: Method,
+    auth_token: &str,
+    uri: &str,
+    body: Value,
+) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", format!("Bearer {}", auth_token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(json!({})))
+}
+
+struct Player {
+    token: String,
+    id: String,
+}
+
+/// Create a user and seat them in the room
+async fn join(app: &Router, room_code: &str, username: &str) -> Player {
+    let token = create_test_user(app, username).await;
+    let (status, room) = join_test_room(app, &token, room_code).await;
+    assert_eq!(status, StatusCode::OK);
+    let id = room["participants"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .find(|(_, p)| p["username"] == username)
+        .map(|(id, _)| id.clone())
+        .unwrap();
+    Player { token, id }
+}
+
+async fn make_moderator(app: &Router, admin_token: &str, room_code: &str, user_id: &str) {
+    let (status, _) = send(
+        app,
+        Method::PUT,
+        admin_token,
+        &format!("/api/v1/rooms/{}/roles/{}", room_code, user_id),
+        json!({ "role": "moderator" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+async fn admin_of(app: &Router, room_code: &str) -> String {
+    let (_, room) = get_test_room(app, room_code).await;
+    let admin_id = room["admin_id"].as_str().unwrap().to_string();
+    assert_eq!(room["participants"][&admin_id]["role"], "admin");
+    admin_id
+}
+
+#[tokio::test]
+async fn test_admin_succession_prefers_moderators_then_seniority() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let room_code = create_test_room(&app, &admin_token, "Succession Room", 8).await;
+
+    let first = join(&app, &room_code, "first_player").await;
+    let _second = join(&app, &room_code, "second_player").await;
+    let moderator = join(&app, &room_code, "late_moderator").await;
+    let viewer_token = create_test_user(&app, "viewer").await;
+    spectate_test_room(&app, &viewer_token, &room_code).await;
+    make_moderator(&app, &admin_token, &room_code, &moderator.id).await;
+
+    // Moderators take over first, even if they joined last
+    assert_eq!(
+        leave_test_room(&app, &admin_token, &room_code).await,
+        StatusCode::OK
+    );
+    assert_eq!(admin_of(&app, &room_code).await, moderator.id);
+
+    // Then whoever has been in the room longest
+    leave_test_room(&app, &moderator.token, &room_code).await;
+    assert_eq!(admin_of(&app, &room_code).await, first.id);
+}
+
+#[tokio::test]
+async fn test_moderators_kick_only_with_permission() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let room_code = create_test_room(&app, &admin_token, "Moderated Room", 8).await;
+    let admin_id = admin_of(&app, &room_code).await;
+
+    let moderator = join(&app, &room_code, "moderator").await;
+    let player = join(&app, &room_code, "player").await;
+    let troll = join(&app, &room_code, "troll").await;
+    let kick = |kicker: String, target: String| {
+        let app = app.clone();
+        let uri = format!("/api/v1/rooms/{}/kick/{}", room_code, target);
+        async move { send(&app, Method::POST, &kicker, &uri, json!({})).await.0 }
+    };
+
+    // Only the admin hands out roles
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &player.token,
+        &format!("/api/v1/rooms/{}/roles/{}", room_code, player.id),
+        json!({ "role": "moderator" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(
+        &app,
+        Method::PUT,
+        &admin_token,
+        &format!("/api/v1/rooms/{}/roles/{}", room_code, player.id),
+        json!({ "role": "admin" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    make_moderator(&app, &admin_token, &room_code, &moderator.id).await;
+
+    assert_eq!(
+        kick(player.token.clone(), troll.id.clone()).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        kick(moderator.token.clone(), admin_id).await,
+        StatusCode::FORBIDDEN
+    );
+    assert_eq!(
+        kick(moderator.token.clone(), troll.id.clone()).await,
+        StatusCode::OK
+    );
+
+    // Without the kick permission moderators can't kick
+    let (status, room) = send(
+        &app,
+        Method::PUT,
+        &admin_token,
+        &format!("/api/v1/rooms/{}/moderator-permissions", room_code),
+        json!({ "permissions": ["pause", "adjust_score", "pause"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        room["moderator_permissions"],
+        json!(["pause", "adjust_score"])
+    );
+    assert_eq!(
+        kick(moderator.token.clone(), player.id.clone()).await,
+        StatusCode::FORBIDDEN
+    );
+}
+
+#[tokio::test]
+async fn test_kicked_socket_stops_receiving_room_messages() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "socket_admin").await;
+    let room_code = create_test_room(&app, &admin_token, "Kick Room", 4).await;
+    let player = join(&app, &room_code, "socket_player").await;
+
+    let mut socket = TestSocket::connect(&state, &player.token).await;
+    socket.send(json!({ "type": "join_room", "room_code": room_code }));
+    socket.recv_type("room_joined").await;
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &admin_token,
+        &format!("/api/v1/rooms/{}/kick/{}", room_code, player.id),
+        json!({ "reason": "spoilers" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // They learn why, and hear nothing more from the room
+    let kicked = socket.recv_type("user_kicked").await;
+    assert_eq!(kicked["user_id"], player.id.as_str());
+    assert_eq!(kicked["reason"], "spoilers");
+    state
+        .websocket_manager
+        .broadcast_to_room(&room_code, WebSocketMessage::GameStarted)
+        .await;
+    while let Some(message) = socket.recv().await {
+        assert_ne!(message["type"], "game_started");
+    }
+}
+
+#[tokio::test]
+async fn test_admin_transfer() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let room_code = create_test_room(&app, &admin_token, "Transfer Room", 8).await;
+    let admin_id = admin_of(&app, &room_code).await;
+    let player = join(&app, &room_code, "player").await;
+    let transfer_uri = format!("/api/v1/rooms/{}/transfer-admin", room_code);
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &player.token,
+        &transfer_uri,
+        json!({ "user_id": player.id }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let viewer_token = create_test_user(&app, "viewer").await;
+    let (_, room) = spectate_test_room(&app, &viewer_token, &room_code).await;
+    let viewer_id = room["participants"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .find(|(_, p)| p["role"] == "spectator")
+        .map(|(id, _)| id.clone())
+        .unwrap();
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &admin_token,
+        &transfer_uri,
+        json!({ "user_id": viewer_id }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, room) = send(
+        &app,
+        Method::POST,
+        &admin_token,
+        &transfer_uri,
+        json!({ "user_id": player.id }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(room["admin_id"], player.id.as_str());
+    assert_eq!(room["participants"][&player.id]["role"], "admin");
+    // The old admin stays on as a moderator
+    assert_eq!(room["participants"][&admin_id]["role"], "moderator");
+
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &admin_token,
+        &transfer_uri,
+        json!({ "user_id": admin_id }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_disconnected_admin_is_replaced_by_a_connected_player() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let room_code = create_test_room(&app, &admin_token, "Disconnect Room", 8).await;
+    let admin_id = admin_of(&app, &room_code).await;
+    let away = join(&app, &room_code, "away_moderator").await;
+    let present = join(&app, &room_code, "present_player").await;
+    make_moderator(&app, &admin_token, &room_code, &away.id).await;
+
+    // A connected admin keeps the room
+    websocket::hand_off_absent_admin(&state, &room_code, &admin_id).await;
+    assert_eq!(admin_of(&app, &room_code).await, admin_id);
+
+    {
+        let mut rooms = state.rooms.write().await;
+        let room = rooms.get_mut(&room_code).unwrap();
+        for id in [&admin_id, &away.id] {
+            room.participants.get_mut(id).unwrap().is_connected = false;
+        }
+    }
+    websocket::hand_off_absent_admin(&state, &room_code, &admin_id).await;
+    assert_eq!(admin_of(&app, &room_code).await, present.id);
+}
+
+#[tokio::test]
+async fn test_moderators_adjust_scores_and_start_rounds() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let room_code = create_test_room(&app, &admin_token, "Score Room", 8).await;
+    let moderator = join(&app, &room_code, "moderator").await;
+    let player = join(&app, &room_code, "player").await;
+    make_moderator(&app, &admin_token, &room_code, &moderator.id).await;
+
+    let mut engine = GameEngine::new(&state.mongo_client, None).await;
+    for (user_id, team) in [
+        ("explainer_a", "team_a"),
+        (player.id.as_str(), "team_a"),
+        ("b1", "team_b"),
+        ("b2", "team_b"),
+    ] {
+        engine
+            .team_manager
+            .add_player_to_team(user_id.to_string(), team)
+            .unwrap();
+    }
+    engine.start_game().await.unwrap();
+    GAME_ENGINES
+        .write()
+        .await
+        .insert(room_code.clone(), Arc::new(RwLock::new(engine)));
+
+    let score_uri = format!("/api/v1/game/{}/score", room_code);
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &player.token,
+        &score_uri,
+        json!({ "team_id": "team_a", "delta": 5 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = send(
+        &app,
+        Method::POST,
+        &moderator.token,
+        &score_uri,
+        json!({ "team_id": "team_b", "delta": -2 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["teams"][1]["score"], -2);
+
+    // Only the next explainer starts a round among the players
+    let (status, _) = send(
+        &app,
+        Method::POST,
+        &player.token,
+        &format!("/api/v1/game/{}/round/start", room_code),
+        json!({}),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    GAME_ENGINES.write().await.remove(&room_code);
+}
@@ -0,0 +1,236 @@
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use shared::models::{GameWord, WebSocketMessage};
+use std::time::Duration;
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(json!({})))
+}
+
+async fn rotate_overlay_token(
+    app: &Router,
+    auth_token: &str,
+    room_code: &str,
+) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("/api/v1/rooms/{}/overlay-token", room_code))
+                .header("Authorization", format!("Bearer {}", auth_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(json!({})))
+}
+
+#[tokio::test]
+async fn test_overlay_token_is_returned_only_to_admin() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "streamer").await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/api/v1/rooms")
+                .header("Authorization", format!("Bearer {}", admin_token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(
+                    json!({ "name": "Stream Room", "max_players": 6 }).to_string(),
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let created: Value = serde_json::from_slice(&body).unwrap();
+    let room_code = created["room_code"].as_str().unwrap();
+    assert!(!created["overlay_token"].as_str().unwrap().is_empty());
+
+    // Room details never include the token
+    let (status, room) = get(&app, &format!("/api/v1/rooms/{}", room_code)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(room.get("overlay_token").is_none());
+
+    // Only the admin can rotate it
+    let player_token = create_test_user(&app, "player").await;
+    let (status, _) = rotate_overlay_token(&app, &player_token, room_code).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_overlay_snapshot_requires_token() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "streamer").await;
+    let room_code = create_test_room(&app, &admin_token, "Stream Room", 6).await;
+
+    let (status, _) = get(&app, &format!("/api/v1/overlay/{}", room_code)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = get(&app, &format!("/api/v1/overlay/{}?token=wrong", room_code)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = rotate_overlay_token(&app, &admin_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+    let overlay_token = body["overlay_token"].as_str().unwrap().to_string();
+
+    let (status, snapshot) = get(
+        &app,
+        &format!("/api/v1/overlay/{}?token={}", room_code, overlay_token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(snapshot["room_code"], room_code.as_str());
+    assert_eq!(snapshot["room_name"], "Stream Room");
+    assert_eq!(snapshot["teams"], json!([]));
+    assert!(snapshot["current_round"].is_null());
+
+    // Rotating invalidates the old token
+    let (status, _) = rotate_overlay_token(&app, &admin_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = get(
+        &app,
+        &format!("/api/v1/overlay/{}?token={}", room_code, overlay_token),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_overlay_events_are_redacted() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "streamer").await;
+    let room_code = create_test_room(&app, &admin_token, "Stream Room", 6).await;
+    let (_, body) = rotate_overlay_token(&app, &admin_token, &room_code).await;
+    let overlay_token = body["overlay_token"].as_str().unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(format!(
+                    "/api/v1/overlay/{}/events?token={}",
+                    room_code, overlay_token
+                ))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"].to_str().unwrap(),
+        "text/event-stream"
+    );
+
+    // The secret word is dropped, public events go through
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            &room_code,
+            WebSocketMessage::WordReceived {
+                word: GameWord {
+                    word: "кіт".to_string(),
+                    difficulty: "easy".to_string(),
+                    category: None,
+                    result: None,
+                    time_spent: None,
+                    associations: Vec::new(),
+                },
+            },
+        )
+        .await;
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            &room_code,
+            WebSocketMessage::TimerUpdate { time_remaining: 42 },
+        )
+        .await;
+
+    let mut events = response.into_body().into_data_stream();
+    let chunk = tokio::time::timeout(Duration::from_secs(2), events.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    let text = String::from_utf8(chunk.to_vec()).unwrap();
+
+    assert!(text.contains("event: timer_update"));
+    assert!(text.contains("\"time_remaining\":42"));
+    assert!(!text.contains("кіт"));
+}
+
+#[tokio::test]
+async fn test_rotating_the_token_ends_open_streams() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "streamer").await;
+    let room_code = create_test_room(&app, &admin_token, "Stream Room", 6).await;
+    let (_, body) = rotate_overlay_token(&app, &admin_token, &room_code).await;
+    let leaked_token = body["overlay_token"].as_str().unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(format!(
+                    "/api/v1/overlay/{}/events?token={}",
+                    room_code, leaked_token
+                ))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let mut events = response.into_body().into_data_stream();
+
+    let (status, _) = rotate_overlay_token(&app, &admin_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+    state
+        .websocket_manager
+        .broadcast_to_room(
+            &room_code,
+            WebSocketMessage::TimerUpdate { time_remaining: 42 },
+        )
+        .await;
+
+    // The stream ends without passing on anything sent after the rotation
+    let next = tokio::time::timeout(Duration::from_secs(2), events.next())
+        .await
+        .unwrap();
+    assert!(next.is_none());
+}
@@ -0,0 +1,304 @@
+use api_gateway::game::GAME_ENGINES;
+use api_gateway::presence::{self, Heartbeat};
+use api_gateway::AppState;
+use axum::http::StatusCode;
+use game_engine::game::GameEngine;
+use serde_json::json;
+use shared::models::{
+    GameMode, GameSettings, HatPhase, HatPhaseRules, HatSettings, SequencedMessage,
+    WebSocketMessage,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{broadcast, RwLock};
+
+mod test_helpers;
+use test_helpers::*;
+
+struct Round {
+    room_code: String,
+    engine: Arc<RwLock<GameEngine>>,
+    explainer_id: String,
+    teammate_id: String,
+}
+
+// Four players in two teams of a Hat game, with team A's first round on
+async fn start_round(state: &AppState, prefix: &str) -> Round {
+    let app = create_test_router(state.clone());
+    let admin_token = create_test_user(&app, &format!("{}_a1", prefix)).await;
+    let room_code = create_test_room(&app, &admin_token, "Presence Room", 4).await;
+    for name in ["a2", "b1", "b2"] {
+        let token = create_test_user(&app, &format!("{}_{}", prefix, name)).await;
+        let (status, _) = join_test_room(&app, &token, &room_code).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let settings = GameSettings {
+        mode: GameMode::Hat,
+        hat: HatSettings {
+            words_per_player: 1,
+            phases: vec![HatPhaseRules {
+                phase: HatPhase::Describe,
+                round_duration_seconds: 60,
+                allow_skip: true,
+            }],
+        },
+        ..GameSettings::default()
+    };
+    let mut engine = GameEngine::new(&state.mongo_client, Some(settings)).await;
+    {
+        let mut rooms = state.rooms.write().await;
+        let room = rooms.get_mut(&room_code).unwrap();
+        for participant in room.participants.values_mut() {
+            let team_id = if participant.username.contains("_a") {
+                "team_a"
+            } else {
+                "team_b"
+            };
+            participant.team_id = Some(team_id.to_string());
+            engine
+                .team_manager
+                .add_player_to_team(participant.user_id.clone(), team_id)
+                .unwrap();
+        }
+    }
+    engine.start_game().await.unwrap();
+
+    let user_ids: Vec<String> = state.rooms.read().await[&room_code]
+        .participants
+        .keys()
+        .cloned()
+        .collect();
+    for (user_id, word) in user_ids.iter().zip(["кіт", "сонце", "потяг", "море"]) {
+        engine
+            .submit_hat_words(user_id, vec![word.to_string()])
+            .unwrap();
+    }
+
+    let round = engine.start_round().await.unwrap();
+    let teammate_id = engine
+        .team_manager
+        .get_team("team_a")
+        .unwrap()
+        .players
+        .iter()
+        .find(|id| **id != round.explainer_id)
+        .unwrap()
+        .clone();
+
+    let engine = Arc::new(RwLock::new(engine));
+    GAME_ENGINES
+        .write()
+        .await
+        .insert(room_code.clone(), engine.clone());
+
+    Round {
+        room_code,
+        engine,
+        explainer_id: round.explainer_id,
+        teammate_id,
+    }
+}
+
+async fn set_connected(state: &AppState, room_code: &str, user_id: &str, connected: bool) {
+    let mut rooms = state.rooms.write().await;
+    let room = rooms.get_mut(room_code).unwrap();
+    room.participants.get_mut(user_id).unwrap().is_connected = connected;
+}
+
+// Room broadcasts received so far
+fn received(receiver: &mut broadcast::Receiver<SequencedMessage>) -> Vec<WebSocketMessage> {
+    std::iter::from_fn(|| receiver.try_recv().ok())
+        .map(|sequenced| sequenced.message)
+        .collect()
+}
+
+#[tokio::test]
+async fn test_heartbeat_times_out_and_detects_afk() {
+    let mut heartbeat = Heartbeat::new(Duration::from_millis(50), Duration::from_millis(80));
+    assert!(!heartbeat.timed_out());
+    assert!(!heartbeat.went_afk());
+
+    // Pongs keep the connection alive, but only requests count as activity
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(heartbeat.timed_out());
+    heartbeat.seen();
+    assert!(!heartbeat.timed_out());
+
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    assert!(heartbeat.went_afk());
+    assert!(!heartbeat.went_afk()); // Reported once
+
+    assert!(heartbeat.active());
+    assert!(!heartbeat.active());
+    assert!(!heartbeat.went_afk());
+}
+
+#[tokio::test]
+async fn test_afk_explainer_hands_off_to_teammate() {
+    let state = create_test_state();
+    let round = start_round(&state, "handoff").await;
+    let mut receiver = state
+        .websocket_manager
+        .get_or_create_room_sender(&round.room_code)
+        .await
+        .subscribe();
+
+    presence::set_afk(&state, &round.room_code, &round.explainer_id, true).await;
+
+    // The room sees the player as AFK
+    let explainer =
+        state.rooms.read().await[&round.room_code].participants[&round.explainer_id].clone();
+    assert!(explainer.is_afk);
+    assert!(!explainer.is_present());
+
+    // The teammate explains the rest of the round
+    let engine = round.engine.read().await;
+    let current = engine.game_state.current_round.as_ref().unwrap();
+    assert_eq!(current.explainer_id, round.teammate_id);
+    assert_eq!(current.round_number, 1);
+    assert!(!engine.is_paused());
+    drop(engine);
+
+    let messages = received(&mut receiver);
+    assert!(messages.iter().any(|m| matches!(
+        m,
+        WebSocketMessage::ExplainerChanged { previous_explainer_id, explainer_id, .. }
+            if *previous_explainer_id == round.explainer_id && *explainer_id == round.teammate_id
+    )));
+
+    // Coming back doesn't take the turn back
+    presence::set_afk(&state, &round.room_code, &round.explainer_id, false).await;
+    let engine = round.engine.read().await;
+    assert_eq!(
+        engine
+            .game_state
+            .current_round
+            .as_ref()
+            .unwrap()
+            .explainer_id,
+        round.teammate_id
+    );
+    drop(engine);
+
+    GAME_ENGINES.write().await.remove(&round.room_code);
+}
+
+#[tokio::test]
+async fn test_game_pauses_until_someone_can_explain() {
+    let state = create_test_state();
+    let round = start_round(&state, "pause").await;
+    let mut receiver = state
+        .websocket_manager
+        .get_or_create_room_sender(&round.room_code)
+        .await
+        .subscribe();
+
+    // With the teammate gone too, nobody can take over
+    set_connected(&state, &round.room_code, &round.teammate_id, false).await;
+    presence::set_afk(&state, &round.room_code, &round.explainer_id, true).await;
+    {
+        let engine = round.engine.read().await;
+        assert!(engine.is_paused());
+        assert_eq!(engine.paused_for(), Some(round.explainer_id.as_str()));
+    }
+    assert!(received(&mut receiver)
+        .iter()
+        .any(|m| matches!(m, WebSocketMessage::GamePaused)));
+
+    // The explainer coming back resumes the game
+    presence::set_afk(&state, &round.room_code, &round.explainer_id, false).await;
+    {
+        let engine = round.engine.read().await;
+        assert!(!engine.is_paused());
+        assert_eq!(
+            engine
+                .game_state
+                .current_round
+                .as_ref()
+                .unwrap()
+                .explainer_id,
+            round.explainer_id
+        );
+    }
+    assert!(received(&mut receiver)
+        .iter()
+        .any(|m| matches!(m, WebSocketMessage::GameResumed)));
+
+    // So does a teammate reconnecting, who takes over
+    presence::set_afk(&state, &round.room_code, &round.explainer_id, true).await;
+    assert!(round.engine.read().await.is_paused());
+    set_connected(&state, &round.room_code, &round.teammate_id, true).await;
+    presence::check_explainer(&state, &round.room_code).await;
+    {
+        let engine = round.engine.read().await;
+        assert!(!engine.is_paused());
+        assert_eq!(
+            engine
+                .game_state
+                .current_round
+                .as_ref()
+                .unwrap()
+                .explainer_id,
+            round.teammate_id
+        );
+    }
+
+    // A manual pause is left for a moderator to lift
+    round.engine.write().await.pause_game().unwrap();
+    presence::check_explainer(&state, &round.room_code).await;
+    assert!(round.engine.read().await.is_paused());
+
+    GAME_ENGINES.write().await.remove(&round.room_code);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_second_connection_keeps_player_present() {
+    let state = create_test_state();
+    let app = create_test_router(state.clone());
+    let token = create_test_user(&app, "two_tabs").await;
+    let room_code = create_test_room(&app, &token, "Two Tabs", 4).await;
+    let participant = |state: &AppState| {
+        let state = state.clone();
+        let room_code = room_code.clone();
+        async move {
+            let rooms = state.rooms.read().await;
+            rooms[&room_code]
+                .participants
+                .values()
+                .next()
+                .unwrap()
+                .clone()
+        }
+    };
+
+    let mut idle_tab = TestSocket::connect(&state, &token).await;
+    idle_tab.send(json!({ "type": "join_room", "room_code": room_code }));
+    idle_tab.recv_type("room_joined").await;
+    let mut busy_tab = TestSocket::connect(&state, &token).await;
+    busy_tab.send(json!({ "type": "resume", "room_code": room_code, "last_seq": 0 }));
+    busy_tab.recv().await.unwrap();
+
+    // The first tab only answers keepalives until it has been idle long
+    // enough to go AFK, then stops answering and times out
+    for tick in 0..12 {
+        busy_tab.send(json!({ "type": "activity" }));
+        if tick < 7 {
+            idle_tab.send(json!({ "type": "ping" }));
+        }
+        tokio::time::sleep(presence::PING_INTERVAL).await;
+
+        let player = participant(&state).await;
+        assert!(player.is_connected);
+        assert!(!player.is_afk);
+    }
+    assert_eq!(
+        state.websocket_manager.metrics.snapshot().open_connections,
+        1
+    );
+
+    // Once the last tab goes, so does the player
+    drop(busy_tab);
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert!(!participant(&state).await.is_connected);
+}
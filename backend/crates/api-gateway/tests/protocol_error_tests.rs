@@ -0,0 +1,176 @@
+use api_gateway::error::AppError;
+use axum::{http::StatusCode, response::IntoResponse};
+use game_engine::error::GameError;
+use serde_json::json;
+use shared::errors::{ErrorCode, ProtocolError};
+use shared::models::{RequestEnvelope, WebSocketMessage};
+
+mod test_helpers;
+use test_helpers::*;
+
+#[test]
+fn test_errors_carry_stable_codes() {
+    let error = ProtocolError::new(ErrorCode::NotExplainer, "Only explainer can draw");
+    let message: WebSocketMessage = error.into();
+    assert_eq!(
+        serde_json::to_value(&message).unwrap(),
+        json!({ "type": "error", "code": "NOT_EXPLAINER", "message": "Only explainer can draw" })
+    );
+
+    for (error, code) in [
+        (ProtocolError::room_not_found(), "ROOM_NOT_FOUND"),
+        (ProtocolError::game_not_found(), "GAME_NOT_FOUND"),
+        (ProtocolError::no_active_round(), "NO_ACTIVE_ROUND"),
+        (
+            ProtocolError::invalid_action("Team is full"),
+            "INVALID_ACTION",
+        ),
+        (
+            ProtocolError::invalid_request("Bad reason"),
+            "INVALID_REQUEST",
+        ),
+        (
+            ProtocolError::new(ErrorCode::RoomFull, "Room is full"),
+            "ROOM_FULL",
+        ),
+    ] {
+        assert_eq!(serde_json::to_value(error.code).unwrap(), json!(code));
+    }
+}
+
+#[test]
+fn test_game_errors_map_to_codes_and_statuses() {
+    for (error, code, status) in [
+        (
+            GameError::AlreadyStarted,
+            ErrorCode::AlreadyStarted,
+            StatusCode::CONFLICT,
+        ),
+        (
+            GameError::NoActiveRound,
+            ErrorCode::NoActiveRound,
+            StatusCode::CONFLICT,
+        ),
+        (
+            GameError::TeamFull {
+                team: "Команда А".to_string(),
+                max: 5,
+            },
+            ErrorCode::TeamFull,
+            StatusCode::CONFLICT,
+        ),
+        (
+            GameError::TeamNotFound("team_c".to_string()),
+            ErrorCode::TeamNotFound,
+            StatusCode::NOT_FOUND,
+        ),
+        (
+            GameError::Unbalanced {
+                largest: 5,
+                smallest: 2,
+                max_difference: 2,
+            },
+            ErrorCode::TeamsNotReady,
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            GameError::WrongMode("drawing"),
+            ErrorCode::WrongMode,
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            GameError::NothingToUndo,
+            ErrorCode::InvalidAction,
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            GameError::Database("connection reset".to_string()),
+            ErrorCode::Internal,
+            StatusCode::INTERNAL_SERVER_ERROR,
+        ),
+    ] {
+        let protocol_error = ProtocolError::from(error.clone());
+        assert_eq!(protocol_error.code, code);
+        assert_eq!(AppError::from(error).into_response().status(), status);
+    }
+
+    // Messages read as before, except for database details
+    assert_eq!(
+        ProtocolError::from(GameError::WordAlreadyProcessed).message,
+        "Word already processed"
+    );
+    assert_eq!(
+        ProtocolError::from(GameError::Database("secret".to_string())).message,
+        "Internal error"
+    );
+}
+
+#[test]
+fn test_request_ids_are_echoed_in_replies() {
+    let request: RequestEnvelope = serde_json::from_str(
+        r#"{ "type": "join_team", "team_id": "team_a", "request_id": "req-42" }"#,
+    )
+    .unwrap();
+    assert_eq!(request.request_id.as_deref(), Some("req-42"));
+    assert_eq!(request.message.type_name(), "join_team");
+
+    let reply = RequestEnvelope {
+        request_id: request.request_id,
+        message: ProtocolError::game_not_found().into(),
+    };
+    assert_eq!(
+        serde_json::to_value(&reply).unwrap(),
+        json!({
+            "request_id": "req-42",
+            "type": "error",
+            "code": "GAME_NOT_FOUND",
+            "message": "Game not found",
+        })
+    );
+
+    // Without an id, requests and replies look as they always did
+    let request: RequestEnvelope = serde_json::from_str(r#"{ "type": "ping" }"#).unwrap();
+    assert!(request.request_id.is_none());
+    let reply = RequestEnvelope {
+        request_id: None,
+        message: WebSocketMessage::Pong,
+    };
+    assert_eq!(
+        serde_json::to_value(&reply).unwrap(),
+        json!({ "type": "pong" })
+    );
+}
+
+#[tokio::test]
+async fn test_socket_replies_carry_the_request_id() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "request_id_admin").await;
+    let room_code = create_test_room(&app, &admin_token, "Request Room", 4).await;
+
+    let mut socket = TestSocket::connect(&state, &admin_token).await;
+    socket.send(json!({ "type": "hello", "protocol_version": 2, "encodings": ["json"] }));
+    socket.recv_type("welcome").await;
+    socket.send(json!({ "type": "join_room", "room_code": room_code, "request_id": "join-1" }));
+    let joined = socket.recv_type("room_joined").await;
+    assert_eq!(joined["request_id"], "join-1");
+
+    socket.send(json!({ "type": "ping", "request_id": "ping-1" }));
+    let pong = socket.recv_type("pong").await;
+    assert_eq!(pong["request_id"], "ping-1");
+
+    // Bots fill the seats left, then the room turns the next one away
+    for _ in 0..3 {
+        socket.send(json!({ "type": "add_bot", "team_id": "team_a" }));
+    }
+    socket.send(json!({ "type": "add_bot", "team_id": "team_b", "request_id": "bot-4" }));
+    let error = socket.recv_type("error").await;
+    assert_eq!(
+        error,
+        json!({
+            "request_id": "bot-4",
+            "type": "error",
+            "code": "ROOM_FULL",
+            "message": "Room is full",
+        })
+    );
+}
@@ -0,0 +1,165 @@
+use api_gateway::protocol::{self, Codec, PROTOCOL_VERSION};
+use axum::extract::ws::Message;
+use serde_json::{json, Value};
+use shared::errors::{ErrorCode, ProtocolError};
+use shared::models::{Encoding, RequestEnvelope, SequencedMessage, WebSocketMessage};
+
+fn text(frame: Option<Message>) -> Value {
+    match frame {
+        Some(Message::Text(text)) => serde_json::from_str(&text).unwrap(),
+        other => panic!("expected a text frame, got {:?}", other),
+    }
+}
+
+fn binary(frame: Option<Message>) -> Value {
+    match frame {
+        Some(Message::Binary(bytes)) => rmp_serde::from_slice(&bytes).unwrap(),
+        other => panic!("expected a binary frame, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_negotiation() {
+    let codec = Codec::negotiate(2, &[Encoding::Msgpack, Encoding::Json]).unwrap();
+    assert_eq!((codec.version, codec.encoding), (2, Encoding::Msgpack));
+
+    // Newer clients fall back to what the server speaks
+    let codec = Codec::negotiate(PROTOCOL_VERSION + 5, &[]).unwrap();
+    assert_eq!(
+        (codec.version, codec.encoding),
+        (PROTOCOL_VERSION, Encoding::Json)
+    );
+    assert_eq!(
+        serde_json::to_value(codec.welcome()).unwrap(),
+        json!({ "type": "welcome", "protocol_version": PROTOCOL_VERSION, "encoding": "json" })
+    );
+
+    let error = Codec::negotiate(0, &[Encoding::Json]).unwrap_err();
+    assert_eq!(error.code, ErrorCode::UnsupportedVersion);
+
+    // Without a handshake, connections speak version 1 JSON
+    assert_eq!(
+        Codec::default(),
+        Codec {
+            version: 1,
+            encoding: Encoding::Json
+        }
+    );
+}
+
+#[test]
+fn test_decoding_json_and_msgpack_requests() {
+    let hello = Message::Text(
+        r#"{ "type": "hello", "protocol_version": 2, "encodings": ["msgpack"] }"#.to_string(),
+    );
+    match protocol::decode(&hello) {
+        Some(Ok(RequestEnvelope {
+            message:
+                WebSocketMessage::Hello {
+                    protocol_version,
+                    encodings,
+                },
+            ..
+        })) => assert_eq!((protocol_version, encodings), (2, vec![Encoding::Msgpack])),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    // Encodings from newer clients are skipped, not rejected
+    let hello = Message::Text(
+        r#"{ "type": "hello", "protocol_version": 3, "encodings": ["cbor", "msgpack", 7] }"#
+            .to_string(),
+    );
+    match protocol::decode(&hello) {
+        Some(Ok(RequestEnvelope {
+            message: WebSocketMessage::Hello { encodings, .. },
+            ..
+        })) => assert_eq!(encodings, vec![Encoding::Msgpack]),
+        other => panic!("unexpected {:?}", other),
+    }
+    let hello = rmp_serde::to_vec_named(
+        &json!({ "type": "hello", "protocol_version": 2, "encodings": ["cbor"] }),
+    )
+    .unwrap();
+    match protocol::decode(&Message::Binary(hello)) {
+        Some(Ok(RequestEnvelope {
+            message: WebSocketMessage::Hello { encodings, .. },
+            ..
+        })) => assert!(encodings.is_empty()),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    let request = RequestEnvelope {
+        request_id: Some("req-1".to_string()),
+        message: WebSocketMessage::JoinTeam {
+            team_id: "team_a".to_string(),
+        },
+    };
+    let frame = Message::Binary(rmp_serde::to_vec_named(&request).unwrap());
+    let decoded = protocol::decode(&frame).unwrap().unwrap();
+    assert_eq!(decoded.request_id.as_deref(), Some("req-1"));
+    assert_eq!(decoded.message.type_name(), "join_team");
+
+    // Unknown messages still give back their request id
+    let unknown =
+        rmp_serde::to_vec_named(&json!({ "type": "fly", "request_id": "req-2" })).unwrap();
+    let invalid = protocol::decode(&Message::Binary(unknown))
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(invalid.request_id.as_deref(), Some("req-2"));
+    assert!(protocol::decode(&Message::Binary(vec![0xc1]))
+        .unwrap()
+        .is_err());
+
+    assert!(protocol::decode(&Message::Ping(Vec::new())).is_none());
+}
+
+#[test]
+fn test_encoding_by_version() {
+    let broadcast = SequencedMessage {
+        seq: 7,
+        message: WebSocketMessage::RoomDeleted {
+            room_code: "ABC123".to_string(),
+        },
+    };
+    let error: WebSocketMessage = ProtocolError::game_not_found().into();
+
+    let current = Codec::negotiate(2, &[Encoding::Msgpack]).unwrap();
+    assert_eq!(
+        binary(current.broadcast(&broadcast)),
+        json!({ "seq": 7, "type": "room_deleted", "room_code": "ABC123" })
+    );
+    let replies = current.replies(Some("req-3".to_string()), vec![error.clone()]);
+    assert_eq!(
+        binary(replies.into_iter().next()),
+        json!({
+            "request_id": "req-3",
+            "type": "error",
+            "code": "GAME_NOT_FOUND",
+            "message": "Game not found",
+        })
+    );
+
+    // Requests without another reply are acknowledged
+    let replies = current.replies(Some("req-4".to_string()), Vec::new());
+    assert_eq!(
+        binary(replies.into_iter().next()),
+        json!({ "request_id": "req-4", "type": "ack" })
+    );
+    assert!(current.replies(None, Vec::new()).is_empty());
+
+    // Version 1 clients see messages as they were before
+    let legacy = Codec::default();
+    assert_eq!(
+        text(legacy.broadcast(&broadcast)),
+        json!({ "type": "room_deleted", "room_code": "ABC123" })
+    );
+    let replies = legacy.replies(Some("req-3".to_string()), vec![error]);
+    assert_eq!(
+        text(replies.into_iter().next()),
+        json!({ "type": "error", "message": "Game not found" })
+    );
+    assert_eq!(
+        text(legacy.message(&WebSocketMessage::Pong)),
+        json!({ "type": "pong" })
+    );
+}
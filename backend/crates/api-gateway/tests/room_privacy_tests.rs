@@ -0,0 +1,319 @@
+use api_gateway::game::GAME_ENGINES;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+    Router,
+};
+use game_engine::game::GameEngine;
+use serde_json::{json, Value};
+use shared::models::{GameWord, Round, WebSocketMessage};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+async fn post_json(app: &Router, auth_token: &str, uri: &str, body: Value) -> (StatusCode, Value) {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri(uri)
+                .header("Authorization", format!("Bearer {}", auth_token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(json!({})))
+}
+
+async fn create_room_with(app: &Router, auth_token: &str, settings: Value) -> String {
+    let (status, created) = post_json(app, auth_token, "/api/v1/rooms", settings).await;
+    assert_eq!(status, StatusCode::OK);
+    created["room_code"].as_str().unwrap().to_string()
+}
+
+async fn join_with(app: &Router, auth_token: &str, room_code: &str, access: Value) -> StatusCode {
+    let uri = format!("/api/v1/rooms/{}/join", room_code);
+    post_json(app, auth_token, &uri, access).await.0
+}
+
+#[tokio::test]
+async fn test_only_public_rooms_are_listed() {
+    let (app, state) = create_test_app_with_state();
+    let mut lobby = state.websocket_manager.lobby_sender.subscribe();
+    let admin_token = create_test_user(&app, "admin_user").await;
+
+    let public_room = create_test_room(&app, &admin_token, "Public Room", 6).await;
+    let unlisted_room = create_room_with(
+        &app,
+        &admin_token,
+        json!({ "name": "Unlisted Room", "max_players": 6, "visibility": "unlisted" }),
+    )
+    .await;
+    create_room_with(
+        &app,
+        &admin_token,
+        json!({
+            "name": "Password Room",
+            "max_players": 6,
+            "visibility": "password",
+            "password": "hunter2"
+        }),
+    )
+    .await;
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/api/v1/rooms")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let rooms: Value = serde_json::from_slice(&body).unwrap();
+    let rooms = rooms.as_array().unwrap();
+    assert_eq!(rooms.len(), 1);
+    assert_eq!(rooms[0]["room_code"], public_room.as_str());
+    assert_eq!(rooms[0]["visibility"], "public");
+
+    // The lobby only hears about the public room
+    match lobby.try_recv().unwrap() {
+        WebSocketMessage::RoomCreated { room_info } => {
+            assert_eq!(room_info.room_code, public_room)
+        }
+        other => panic!("unexpected lobby message: {:?}", other),
+    }
+    assert!(lobby.try_recv().is_err());
+
+    // Unlisted rooms are open to anyone with the code, without telling the lobby
+    let player_token = create_test_user(&app, "player_user").await;
+    let (status, _) = join_test_room(&app, &player_token, &unlisted_room).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(lobby.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_password_rooms_need_the_password() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "admin_user").await;
+
+    let (status, _) = post_json(
+        &app,
+        &admin_token,
+        "/api/v1/rooms",
+        json!({ "name": "No Password", "max_players": 6, "visibility": "password" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let room_code = create_room_with(
+        &app,
+        &admin_token,
+        json!({
+            "name": "Password Room",
+            "max_players": 6,
+            "visibility": "password",
+            "password": "hunter2"
+        }),
+    )
+    .await;
+
+    // The password is never sent back
+    let (_, room) = get_test_room(&app, &room_code).await;
+    assert_eq!(room["visibility"], "password");
+    assert!(room.get("password_hash").is_none());
+    assert!(room.get("invite_secret").is_none());
+
+    let player_token = create_test_user(&app, "player_user").await;
+    let (status, _) = join_test_room(&app, &player_token, &room_code).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let status = join_with(
+        &app,
+        &player_token,
+        &room_code,
+        json!({ "password": "wrong" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = spectate_test_room(&app, &player_token, &room_code).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let status = join_with(
+        &app,
+        &player_token,
+        &room_code,
+        json!({ "password": "hunter2" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Coming back doesn't need the password again
+    let (status, _) = join_test_room(&app, &player_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_invites_skip_the_password() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let room_code = create_room_with(
+        &app,
+        &admin_token,
+        json!({
+            "name": "Password Room",
+            "max_players": 6,
+            "visibility": "password",
+            "password": "hunter2"
+        }),
+    )
+    .await;
+    let invites_uri = format!("/api/v1/rooms/{}/invites", room_code);
+
+    // Only the admin hands out invites
+    let player_token = create_test_user(&app, "player_user").await;
+    let (status, _) = post_json(&app, &player_token, &invites_uri, json!({})).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = post_json(
+        &app,
+        &admin_token,
+        &invites_uri,
+        json!({ "expires_in_minutes": 60 * 24 * 30 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, invite) = post_json(
+        &app,
+        &admin_token,
+        &invites_uri,
+        json!({ "expires_in_minutes": 30 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(invite["room_code"], room_code.as_str());
+    let invite = invite["invite"].as_str().unwrap().to_string();
+
+    // Tampered invites, or invites to another room, are rejected
+    let (expires, signature) = invite.split_once('.').unwrap();
+    let extended = format!("{}.{}", expires.parse::<i64>().unwrap() + 3600, signature);
+    let status = join_with(
+        &app,
+        &player_token,
+        &room_code,
+        json!({ "invite": extended }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let other_room = create_room_with(
+        &app,
+        &admin_token,
+        json!({
+            "name": "Other Room",
+            "max_players": 6,
+            "visibility": "password",
+            "password": "secret"
+        }),
+    )
+    .await;
+    let status = join_with(
+        &app,
+        &player_token,
+        &other_room,
+        json!({ "invite": invite }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let status = join_with(&app, &player_token, &room_code, json!({ "invite": invite })).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let viewer_token = create_test_user(&app, "viewer_user").await;
+    let (status, _) = post_json(
+        &app,
+        &viewer_token,
+        &format!("/api/v1/rooms/{}/spectate", room_code),
+        json!({ "invite": invite }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+async fn game_state_as(app: &Router, auth_token: &str, room_code: &str) -> Value {
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(format!("/api/v1/game/{}/state", room_code))
+                .header("Authorization", format!("Bearer {}", auth_token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice::<Value>(&body).unwrap()["game_state"].clone()
+}
+
+#[tokio::test]
+async fn test_only_players_see_the_secret_words() {
+    let (app, state) = create_test_app_with_state();
+    let admin_token = create_test_user(&app, "words_admin").await;
+    let room_code = create_test_room(&app, &admin_token, "Secret Words", 6).await;
+    let spectator_token = create_test_user(&app, "words_spectator").await;
+    let (status, _) = spectate_test_room(&app, &spectator_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+    let outsider_token = create_test_user(&app, "words_outsider").await;
+
+    let mut engine = GameEngine::new(&state.mongo_client, None).await;
+    engine.game_state.current_round = Some(Round {
+        round_number: 1,
+        team_id: "team_a".to_string(),
+        explainer_id: "explainer".to_string(),
+        words: vec![GameWord {
+            word: "маяк".to_string(),
+            difficulty: "easy".to_string(),
+            category: None,
+            result: None,
+            time_spent: None,
+            associations: Vec::new(),
+        }],
+        timer_seconds: 60,
+        time_remaining: 60,
+        score_gained: 0,
+        started_at: None,
+        ended_at: None,
+        clues: Vec::new(),
+        canvas: Vec::new(),
+    });
+    engine.game_state.used_words = vec!["маяк".to_string()];
+    GAME_ENGINES
+        .write()
+        .await
+        .insert(room_code.clone(), Arc::new(RwLock::new(engine)));
+
+    let game_state = game_state_as(&app, &admin_token, &room_code).await;
+    assert_eq!(game_state["current_round"]["words"][0]["word"], "маяк");
+
+    for token in [&spectator_token, &outsider_token] {
+        let game_state = game_state_as(&app, token, &room_code).await;
+        assert_eq!(game_state["current_round"]["words"], json!([]));
+        assert_eq!(game_state["used_words"], json!([]));
+    }
+
+    GAME_ENGINES.write().await.remove(&room_code);
+}
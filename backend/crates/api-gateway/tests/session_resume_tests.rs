@@ -0,0 +1,204 @@
+use api_gateway::websocket::{Resumption, WebSocketManager, REPLAY_BUFFER_SIZE};
+use serde_json::json;
+use shared::models::{SequencedMessage, WebSocketMessage};
+use std::time::{Duration, Instant};
+
+mod test_helpers;
+use test_helpers::*;
+
+fn user_left(n: usize) -> WebSocketMessage {
+    WebSocketMessage::UserLeft {
+        user_id: format!("user{}", n),
+    }
+}
+
+fn seqs(events: &[SequencedMessage]) -> Vec<u64> {
+    events.iter().map(|m| m.seq).collect()
+}
+
+#[tokio::test]
+async fn test_room_broadcasts_are_numbered_in_order() {
+    let manager = WebSocketManager::new();
+    let mut receiver = manager
+        .get_or_create_room_sender("ROOM01")
+        .await
+        .subscribe();
+
+    for n in 0..3 {
+        manager.broadcast_to_room("ROOM01", user_left(n)).await;
+    }
+    for expected in 1..=3 {
+        assert_eq!(receiver.recv().await.unwrap().seq, expected);
+    }
+
+    // Numbering is per room
+    let mut other = manager
+        .get_or_create_room_sender("ROOM02")
+        .await
+        .subscribe();
+    manager.broadcast_to_room("ROOM02", user_left(0)).await;
+    assert_eq!(other.recv().await.unwrap().seq, 1);
+}
+
+#[tokio::test]
+async fn test_resume_replays_missed_broadcasts() {
+    let manager = WebSocketManager::new();
+    manager.get_or_create_room_sender("ROOM01").await;
+    for n in 0..5 {
+        manager.broadcast_to_room("ROOM01", user_left(n)).await;
+    }
+
+    let (mut receiver, resumption) = manager.resume_room("ROOM01", 2, false).await;
+    match resumption {
+        Resumption::Replay(events) => {
+            assert_eq!(seqs(&events), vec![3, 4, 5]);
+            assert_eq!(events[0].message.type_name(), "user_left");
+        }
+        Resumption::Snapshot { .. } => panic!("expected a replay"),
+    }
+
+    // Nothing missed: an empty replay
+    match manager.resume_room("ROOM01", 5, false).await.1 {
+        Resumption::Replay(events) => assert!(events.is_empty()),
+        Resumption::Snapshot { .. } => panic!("expected a replay"),
+    }
+
+    // Live broadcasts continue after the replay
+    manager.broadcast_to_room("ROOM01", user_left(5)).await;
+    assert_eq!(receiver.recv().await.unwrap().seq, 6);
+}
+
+#[tokio::test]
+async fn test_resume_falls_back_to_snapshot() {
+    let manager = WebSocketManager::new();
+    manager.get_or_create_room_sender("ROOM01").await;
+    let total = REPLAY_BUFFER_SIZE + 10;
+    for n in 0..total {
+        manager.broadcast_to_room("ROOM01", user_left(n)).await;
+    }
+
+    // The oldest messages have left the buffer
+    assert!(matches!(
+        manager.resume_room("ROOM01", 5, false).await.1,
+        Resumption::Snapshot { seq } if seq == total as u64
+    ));
+    match manager.resume_room("ROOM01", 10, false).await.1 {
+        Resumption::Replay(events) => assert_eq!(events.len(), REPLAY_BUFFER_SIZE),
+        Resumption::Snapshot { .. } => panic!("expected a replay"),
+    }
+
+    // A number from the future (e.g. the room was recreated)
+    assert!(matches!(
+        manager
+            .resume_room("ROOM01", total as u64 + 1, false)
+            .await
+            .1,
+        Resumption::Snapshot { .. }
+    ));
+
+    // Spectators always resync from a snapshot
+    assert!(matches!(
+        manager.resume_room("ROOM01", total as u64, true).await.1,
+        Resumption::Snapshot { .. }
+    ));
+}
+
+#[test]
+fn test_sequenced_message_wire_format() {
+    let message = SequencedMessage {
+        seq: 7,
+        message: user_left(1),
+    };
+    let json = serde_json::to_value(&message).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({ "seq": 7, "type": "user_left", "user_id": "user1" })
+    );
+
+    let resume: WebSocketMessage =
+        serde_json::from_str(r#"{ "type": "resume", "room_code": "ROOM01", "last_seq": 7 }"#)
+            .unwrap();
+    assert_eq!(resume.type_name(), "resume");
+
+    let resumed = WebSocketMessage::Resumed {
+        room_code: "ROOM01".to_string(),
+        events: vec![message],
+    };
+    let round_trip: WebSocketMessage =
+        serde_json::from_str(&serde_json::to_string(&resumed).unwrap()).unwrap();
+    match round_trip {
+        WebSocketMessage::Resumed { events, .. } => assert_eq!(seqs(&events), vec![7]),
+        other => panic!("unexpected {}", other.type_name()),
+    }
+}
+
+#[tokio::test]
+async fn test_lagging_connection_resyncs_with_a_snapshot() {
+    let state = create_test_state();
+    let app = create_test_router(state.clone());
+    let token = create_test_user(&app, "lagging_player").await;
+    let room_code = create_test_room(&app, &token, "Lag Room", 4).await;
+
+    let mut socket = TestSocket::connect(&state, &token).await;
+    socket.send(json!({ "type": "hello", "protocol_version": 2, "encodings": ["json"] }));
+    socket.recv_type("welcome").await;
+    socket.send(json!({ "type": "join_room", "room_code": room_code }));
+    socket.recv_type("room_joined").await;
+
+    // More broadcasts than the connection buffers, before it gets to run
+    tokio::task::unconstrained(async {
+        for n in 0..150 {
+            state
+                .websocket_manager
+                .broadcast_to_room(&room_code, user_left(n))
+                .await;
+        }
+    })
+    .await;
+    let last_seq = state.websocket_manager.last_seq(&room_code).await;
+
+    let snapshot = socket.recv_type("room_snapshot").await;
+    assert_eq!(snapshot["seq"], last_seq);
+    assert_eq!(snapshot["room"]["room_code"], room_code.as_str());
+
+    // What the snapshot covers isn't sent again
+    while let Some(message) = socket.recv().await {
+        assert!(
+            message["seq"].as_u64().is_none_or(|seq| seq > last_seq),
+            "stale broadcast after the snapshot: {message}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_delayed_spectators_get_their_snapshot_late() {
+    let state = create_test_state();
+    let app = create_test_router(state.clone());
+    let admin_token = create_test_user(&app, "delayed_admin").await;
+    let spectator_token = create_test_user(&app, "delayed_spectator").await;
+    let room_code = create_test_room(&app, &admin_token, "Delayed Room", 4).await;
+    spectate_test_room(&app, &spectator_token, &room_code).await;
+    state
+        .websocket_manager
+        .create_audience_channel(&room_code, 1)
+        .await;
+
+    let mut spectator = TestSocket::connect(&state, &spectator_token).await;
+    let resumed_at = Instant::now();
+    spectator.send(json!({ "type": "resume", "room_code": room_code, "last_seq": 0 }));
+
+    let snapshot = loop {
+        if let Some(message) = spectator.recv().await {
+            if message["type"] == "room_snapshot" {
+                break message;
+            }
+        }
+        assert!(
+            resumed_at.elapsed() < Duration::from_secs(3),
+            "no snapshot arrived"
+        );
+    };
+    // Held back as long as the rest of the spectator stream
+    assert!(resumed_at.elapsed() >= Duration::from_secs(1));
+    assert_eq!(snapshot["room"]["room_code"], room_code.as_str());
+}
@@ -0,0 +1,212 @@
+use api_gateway::websocket::WebSocketManager;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+};
+use serde_json::{json, Value};
+use shared::models::{GameWord, WebSocketMessage};
+use std::time::{Duration, Instant};
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+#[tokio::test]
+async fn test_spectators_do_not_take_seats() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let room_code = create_test_room(&app, &admin_token, "Watched Room", 4).await;
+
+    for i in 1..4 {
+        let token = create_test_user(&app, &format!("player_{}", i)).await;
+        let (status, _) = join_test_room(&app, &token, &room_code).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // A full room still accepts spectators
+    let viewer_token = create_test_user(&app, "viewer").await;
+    let (status, room) = spectate_test_room(&app, &viewer_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(room["participants"].as_object().unwrap().len(), 5);
+
+    // ...but the spectator can't take a seat
+    let (status, _) = join_test_room(&app, &viewer_token, &room_code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Players already in the room can't become spectators
+    let (status, _) = spectate_test_room(&app, &admin_token, &room_code).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Lobby info counts players and spectators separately
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/api/v1/rooms")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let rooms: Value = serde_json::from_slice(&body).unwrap();
+    let info = rooms
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|r| r["room_code"] == room_code.as_str())
+        .unwrap();
+    assert_eq!(info["current_players"], 4);
+    assert_eq!(info["spectators"], 1);
+}
+
+#[tokio::test]
+async fn test_spectator_takes_free_seat() {
+    let app = create_test_app().await;
+    let admin_token = create_test_user(&app, "admin_user").await;
+    let viewer_token = create_test_user(&app, "viewer").await;
+    let room_code = create_test_room(&app, &admin_token, "Open Room", 4).await;
+
+    let (status, room) = spectate_test_room(&app, &viewer_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+    let viewer = room["participants"]
+        .as_object()
+        .unwrap()
+        .values()
+        .find(|p| p["username"] == "viewer")
+        .unwrap();
+    assert_eq!(viewer["role"], "spectator");
+
+    let (status, room) = join_test_room(&app, &viewer_token, &room_code).await;
+    assert_eq!(status, StatusCode::OK);
+    let viewer = room["participants"]
+        .as_object()
+        .unwrap()
+        .values()
+        .find(|p| p["username"] == "viewer")
+        .unwrap();
+    assert_eq!(viewer["role"], "player");
+}
+
+#[tokio::test]
+async fn test_spectator_stream_is_redacted_and_delayed() {
+    let manager = WebSocketManager::new();
+    let player_sender = manager.get_or_create_room_sender("ROOM01").await;
+    let mut player_receiver = player_sender.subscribe();
+    manager.create_audience_channel("ROOM01", 1).await;
+    let mut spectator_receiver = manager
+        .get_or_create_audience_sender("ROOM01")
+        .await
+        .subscribe();
+
+    // The explainer's word never reaches spectators
+    manager
+        .broadcast_to_room(
+            "ROOM01",
+            WebSocketMessage::WordReceived {
+                word: GameWord {
+                    word: "кіт".to_string(),
+                    difficulty: "easy".to_string(),
+                    category: None,
+                    result: None,
+                    time_spent: None,
+                    associations: Vec::new(),
+                },
+            },
+        )
+        .await;
+    manager
+        .broadcast_to_room(
+            "ROOM01",
+            WebSocketMessage::UserLeft {
+                user_id: "user1".to_string(),
+            },
+        )
+        .await;
+
+    // Players get both messages right away
+    assert_eq!(
+        player_receiver.recv().await.unwrap().message.type_name(),
+        "word_received"
+    );
+    assert_eq!(
+        player_receiver.recv().await.unwrap().message.type_name(),
+        "user_left"
+    );
+
+    // Spectators get only the public one, after the delay
+    assert!(
+        tokio::time::timeout(Duration::from_millis(500), spectator_receiver.recv())
+            .await
+            .is_err()
+    );
+    let delayed = tokio::time::timeout(Duration::from_secs(2), spectator_receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(delayed.message.type_name(), "user_left");
+}
+
+#[tokio::test]
+async fn test_delayed_spectators_wait_for_requested_snapshots() {
+    let state = create_test_state();
+    let app = create_test_router(state.clone());
+    let admin_token = create_test_user(&app, "snapshot_admin").await;
+    let spectator_token = create_test_user(&app, "snapshot_spectator").await;
+    let room_code = create_test_room(&app, &admin_token, "Delayed Room", 4).await;
+    spectate_test_room(&app, &spectator_token, &room_code).await;
+    state
+        .websocket_manager
+        .create_audience_channel(&room_code, 1)
+        .await;
+
+    let mut spectator = TestSocket::connect(&state, &spectator_token).await;
+    spectator.send(json!({ "type": "join_room", "room_code": room_code }));
+    spectator.recv_type("room_joined").await;
+
+    let requested_at = Instant::now();
+    spectator.send(json!({ "type": "request_snapshot", "request_id": "snap-1" }));
+    let snapshot = loop {
+        let message = spectator.recv().await;
+        if let Some(message) = message {
+            // Live room state never goes out ahead of the delay
+            assert_ne!(message["type"], "room_updated");
+            assert_ne!(message["type"], "game_state_updated");
+            if message["type"] == "room_snapshot" {
+                break message;
+            }
+        }
+        assert!(
+            requested_at.elapsed() < Duration::from_secs(3),
+            "no snapshot arrived"
+        );
+    };
+    assert!(requested_at.elapsed() >= Duration::from_secs(1));
+    assert_eq!(snapshot["room"]["room_code"], room_code.as_str());
+}
+
+#[tokio::test]
+async fn test_delayed_spectators_cannot_request_the_live_canvas() {
+    let state = create_test_state();
+    let app = create_test_router(state.clone());
+    let admin_token = create_test_user(&app, "canvas_admin").await;
+    let spectator_token = create_test_user(&app, "canvas_spectator").await;
+    let room_code = create_test_room(&app, &admin_token, "Canvas Room", 4).await;
+    spectate_test_room(&app, &spectator_token, &room_code).await;
+    state
+        .websocket_manager
+        .create_audience_channel(&room_code, 1)
+        .await;
+
+    let mut spectator = TestSocket::connect(&state, &spectator_token).await;
+    spectator.send(json!({ "type": "hello", "protocol_version": 2, "encodings": ["json"] }));
+    spectator.recv_type("welcome").await;
+    spectator.send(json!({ "type": "join_room", "room_code": room_code }));
+    spectator.recv_type("room_joined").await;
+
+    spectator.send(json!({ "type": "request_canvas", "request_id": "canvas-1" }));
+    let error = spectator.recv_type("error").await;
+    assert_eq!(error["request_id"], "canvas-1");
+    assert_eq!(error["code"], "SPECTATOR_NOT_ALLOWED");
+}
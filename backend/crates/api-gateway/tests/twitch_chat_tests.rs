@@ -0,0 +1,250 @@
+use api_gateway::game::GAME_ENGINES;
+use api_gateway::twitch_chat::{self, parse_line, run_chat_client, ChatMessage, IrcLine};
+use api_gateway::websocket::UserControl;
+use game_engine::game::GameEngine;
+use shared::models::{
+    GameMode, GameSettings, HatSettings, TwitchChatSettings, WebSocketMessage, WordResult,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, RwLock};
+
+mod test_helpers;
+use test_helpers::*;
+
+/// Fake Twitch IRC server accepting a single client
+struct FakeIrcServer {
+    lines: Lines<BufReader<OwnedReadHalf>>,
+    writer: OwnedWriteHalf,
+}
+
+impl FakeIrcServer {
+    async fn accept(listener: &TcpListener) -> Self {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, writer) = stream.into_split();
+        Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        }
+    }
+
+    async fn read_line(&mut self) -> String {
+        tokio::time::timeout(Duration::from_secs(2), self.lines.next_line())
+            .await
+            .expect("client did not send a line")
+            .unwrap()
+            .expect("client closed the connection")
+    }
+
+    async fn send(&mut self, line: &str) {
+        self.writer
+            .write_all(format!("{}\r\n", line).as_bytes())
+            .await
+            .unwrap();
+    }
+
+    async fn expect_handshake(&mut self, channel: &str) {
+        assert_eq!(self.read_line().await, "CAP REQ :twitch.tv/tags");
+        assert!(self.read_line().await.starts_with("NICK justinfan"));
+        assert_eq!(self.read_line().await, format!("JOIN #{}", channel));
+    }
+}
+
+#[test]
+fn test_parse_irc_lines() {
+    assert_eq!(
+        parse_line("PING :tmi.twitch.tv"),
+        IrcLine::Ping("tmi.twitch.tv".to_string())
+    );
+    assert_eq!(
+        parse_line(":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #Streamer :це кіт\r\n"),
+        IrcLine::Privmsg {
+            channel: "streamer".to_string(),
+            user: "viewer".to_string(),
+            text: "це кіт".to_string(),
+        }
+    );
+    assert_eq!(
+        parse_line(
+            "@badges=;color=#FF0000;display-name=Viewer_One :viewer_one!viewer_one@viewer_one.tmi.twitch.tv PRIVMSG #streamer :hello :)"
+        ),
+        IrcLine::Privmsg {
+            channel: "streamer".to_string(),
+            user: "Viewer_One".to_string(),
+            text: "hello :)".to_string(),
+        }
+    );
+    assert_eq!(
+        parse_line(":tmi.twitch.tv 001 justinfan12345 :Welcome, GLHF!"),
+        IrcLine::Other
+    );
+    assert_eq!(parse_line("@broken"), IrcLine::Other);
+}
+
+#[tokio::test]
+async fn test_chat_client_against_fake_server() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let (sender, mut receiver) = mpsc::channel(16);
+
+    let client = tokio::spawn(async move { run_chat_client(&addr, "#Streamer", sender).await });
+
+    let mut server = FakeIrcServer::accept(&listener).await;
+    server.expect_handshake("streamer").await;
+
+    // Keepalive
+    server.send("PING :tmi.twitch.tv").await;
+    assert_eq!(server.read_line().await, "PONG :tmi.twitch.tv");
+
+    // Messages from other channels are ignored
+    server
+        .send(":other!other@other.tmi.twitch.tv PRIVMSG #elsewhere :кіт")
+        .await;
+    server
+        .send(":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #streamer :це кіт")
+        .await;
+
+    let message = tokio::time::timeout(Duration::from_secs(2), receiver.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        message,
+        ChatMessage {
+            user: "viewer".to_string(),
+            text: "це кіт".to_string(),
+        }
+    );
+
+    // Closing the connection ends the client
+    drop(server);
+    let result = tokio::time::timeout(Duration::from_secs(2), client)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(result.is_ok());
+}
+
+#[tokio::test]
+async fn test_audience_steals_word_from_chat() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    std::env::set_var(
+        "TWITCH_IRC_ADDR",
+        listener.local_addr().unwrap().to_string(),
+    );
+
+    let state = create_test_state();
+    let room_code = "CHAT01";
+
+    // Hat mode draws words from the players, so no word database is needed
+    let settings = GameSettings {
+        mode: GameMode::Hat,
+        hat: HatSettings {
+            words_per_player: 1,
+            ..HatSettings::default()
+        },
+        twitch_chat: Some(TwitchChatSettings {
+            channel: Some("streamer".to_string()),
+            steal_words: true,
+        }),
+        ..GameSettings::default()
+    };
+    let mut engine = GameEngine::new(&state.mongo_client, Some(settings)).await;
+    for (player, team) in [
+        ("p1", "team_a"),
+        ("p2", "team_a"),
+        ("p3", "team_b"),
+        ("p4", "team_b"),
+    ] {
+        engine
+            .team_manager
+            .add_player_to_team(player.to_string(), team)
+            .unwrap();
+    }
+    engine.start_game().await.unwrap();
+    for (player, word) in [
+        ("p1", "кіт"),
+        ("p2", "пес"),
+        ("p3", "сонце"),
+        ("p4", "море"),
+    ] {
+        engine
+            .submit_hat_words(player, vec![word.to_string()])
+            .unwrap();
+    }
+    let round = engine.start_round().await.unwrap();
+    let target = engine.get_current_word().unwrap().word.clone();
+    let (_, mut explainer) = state
+        .websocket_manager
+        .register_user(&round.explainer_id)
+        .await;
+
+    let engine = Arc::new(RwLock::new(engine));
+    GAME_ENGINES
+        .write()
+        .await
+        .insert(room_code.to_string(), engine.clone());
+
+    twitch_chat::start_connector(room_code, "streamer", state.clone()).await;
+    let mut server = FakeIrcServer::accept(&listener).await;
+    server.expect_handshake("streamer").await;
+
+    server
+        .send(":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #streamer :не знаю")
+        .await;
+    server
+        .send(&format!(
+            ":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #streamer :{}!",
+            target
+        ))
+        .await;
+
+    // Wait for the relay to score the guess
+    let mut stolen = false;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let engine = engine.read().await;
+        if engine
+            .game_state
+            .audience
+            .as_ref()
+            .is_some_and(|a| a.score > 0)
+        {
+            let round = engine.game_state.current_round.as_ref().unwrap();
+            assert_eq!(round.words[0].word, target);
+            assert_eq!(round.words[0].result, Some(WordResult::Stolen));
+            assert_eq!(round.score_gained, 0);
+
+            let audience = engine.game_state.audience.as_ref().unwrap();
+            assert_eq!(audience.score, 1);
+            assert_eq!(audience.guesses.get("viewer"), Some(&1));
+            stolen = true;
+            break;
+        }
+    }
+    assert!(stolen, "audience guess was not recorded");
+
+    // The explainer's connection gets the next word
+    let control = tokio::time::timeout(Duration::from_secs(2), explainer.recv())
+        .await
+        .expect("explainer got no next word")
+        .unwrap();
+    match control {
+        UserControl::Send {
+            room_code: to,
+            message,
+        } => {
+            assert_eq!(to, room_code);
+            assert!(
+                matches!(*message, WebSocketMessage::WordReceived { ref word } if word.word != target)
+            );
+        }
+        other => panic!("unexpected control message {:?}", other),
+    }
+
+    twitch_chat::stop_connector(room_code).await;
+    GAME_ENGINES.write().await.remove(room_code);
+}
@@ -0,0 +1,474 @@
+use api_gateway::game::GAME_ENGINES;
+use api_gateway::webhooks::{self, TargetError, WebhookManager};
+use api_gateway::AppState;
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::State,
+    http::{HeaderMap, Method, Request, StatusCode},
+    routing::post,
+    Router,
+};
+use game_engine::game::GameEngine;
+use serde_json::{json, Value};
+use shared::models::{GameMode, GameSettings, HatSettings, WebSocketMessage};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tower::ServiceExt;
+
+mod test_helpers;
+use test_helpers::*;
+
+/// Request received by the fake webhook endpoint
+#[derive(Clone)]
+struct Received {
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+/// Local HTTP endpoint that records requests and answers with queued statuses
+#[derive(Clone, Default)]
+struct FakeEndpoint {
+    received: Arc<Mutex<Vec<Received>>>,
+    statuses: Arc<Mutex<VecDeque<u16>>>,
+}
+
+impl FakeEndpoint {
+    /// Serve on a random local port and return the hook URL
+    async fn start(statuses: &[u16]) -> (Self, String) {
+        let endpoint = FakeEndpoint::default();
+        endpoint.statuses.lock().unwrap().extend(statuses);
+
+        let app = Router::new()
+            .route("/hook", post(record))
+            .with_state(endpoint.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (endpoint, format!("http://{}/hook", addr))
+    }
+
+    fn received(&self) -> Vec<Received> {
+        self.received.lock().unwrap().clone()
+    }
+
+    /// Wait until the endpoint got `count` requests
+    async fn wait_for(&self, count: usize) -> Vec<Received> {
+        for _ in 0..100 {
+            let received = self.received();
+            if received.len() >= count {
+                return received;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("Expected {} webhook requests", count);
+    }
+}
+
+async fn record(
+    State(endpoint): State<FakeEndpoint>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> StatusCode {
+    endpoint
+        .received
+        .lock()
+        .unwrap()
+        .push(Received { headers, body });
+    let status = endpoint.statuses.lock().unwrap().pop_front().unwrap_or(200);
+    StatusCode::from_u16(status).unwrap()
+}
+
+/// Test state whose webhooks retry quickly and may reach the local endpoint
+fn create_webhook_test_state() -> AppState {
+    let mut state = create_test_state();
+    state.webhook_manager = Arc::new(
+        WebhookManager::with_retry_policy(3, Duration::from_millis(10))
+            .allowing_private_addresses(),
+    );
+    state
+}
+
+fn create_webhook_test_app() -> Router {
+    create_test_router(create_webhook_test_state())
+}
+
+async fn send(
+    app: &Router,
+    method: Method,
+    uri: &str,
+    auth_token: &str,
+    body: Option<Value>,
+) -> (StatusCode, Value) {
+    let mut request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Authorization", format!("Bearer {}", auth_token));
+    let body = match body {
+        Some(body) => {
+            request = request.header("Content-Type", "application/json");
+            Body::from(body.to_string())
+        }
+        None => Body::empty(),
+    };
+
+    let response = app
+        .clone()
+        .oneshot(request.body(body).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&body).unwrap_or(json!({})))
+}
+
+async fn create_webhook(app: &Router, auth_token: &str, body: Value) -> (StatusCode, Value) {
+    send(
+        app,
+        Method::POST,
+        "/api/v1/webhooks",
+        auth_token,
+        Some(body),
+    )
+    .await
+}
+
+/// Poll the delivery log until the latest delivery is no longer pending
+async fn wait_for_delivery(app: &Router, auth_token: &str, webhook_id: &str) -> Value {
+    let uri = format!("/api/v1/webhooks/{}/deliveries", webhook_id);
+    for _ in 0..100 {
+        let (status, deliveries) = send(app, Method::GET, &uri, auth_token, None).await;
+        assert_eq!(status, StatusCode::OK);
+        if let Some(latest) = deliveries.as_array().and_then(|d| d.first()) {
+            if latest["status"] != "pending" {
+                return latest.clone();
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("Delivery did not complete");
+}
+
+#[tokio::test]
+async fn test_room_created_delivery_is_signed() {
+    let app = create_webhook_test_app();
+    let (endpoint, url) = FakeEndpoint::start(&[]).await;
+    let token = create_test_user(&app, "webhook_streamer").await;
+
+    let (status, created) = create_webhook(
+        &app,
+        &token,
+        json!({ "url": url, "events": ["room_created", "game_ended"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let secret = created["secret"].as_str().unwrap();
+    assert!(secret.starts_with("whsec_"));
+
+    let room_code = create_test_room(&app, &token, "Webhook Room", 6).await;
+
+    let received = endpoint.wait_for(1).await;
+    let request = &received[0];
+    assert_eq!(request.headers[webhooks::EVENT_HEADER], "room_created");
+
+    // Signature covers the timestamp and the raw body
+    let timestamp: i64 = request.headers[webhooks::TIMESTAMP_HEADER]
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert_eq!(
+        request.headers[webhooks::SIGNATURE_HEADER]
+            .to_str()
+            .unwrap(),
+        webhooks::sign(secret, timestamp, &request.body)
+    );
+    assert_ne!(
+        request.headers[webhooks::SIGNATURE_HEADER]
+            .to_str()
+            .unwrap(),
+        webhooks::sign("whsec_wrong", timestamp, &request.body)
+    );
+
+    let payload: Value = serde_json::from_slice(&request.body).unwrap();
+    assert_eq!(payload["event"], "room_created");
+    assert_eq!(payload["room_code"], room_code);
+    assert_eq!(payload["data"]["name"], "Webhook Room");
+}
+
+#[tokio::test]
+async fn test_failed_deliveries_are_retried() {
+    let app = create_webhook_test_app();
+    let (endpoint, url) = FakeEndpoint::start(&[500, 503]).await;
+    let token = create_test_user(&app, "retry_streamer").await;
+
+    let (_, created) = create_webhook(
+        &app,
+        &token,
+        json!({ "url": url, "events": ["room_created"] }),
+    )
+    .await;
+    let webhook_id = created["webhook"]["id"].as_str().unwrap();
+
+    create_test_room(&app, &token, "Retry Room", 6).await;
+
+    let delivery = wait_for_delivery(&app, &token, webhook_id).await;
+    assert_eq!(delivery["status"], "delivered");
+    assert_eq!(delivery["attempts"], 3);
+    assert_eq!(delivery["response_status"], 200);
+
+    // Every attempt carries the same delivery
+    let received = endpoint.received();
+    assert_eq!(received.len(), 3);
+    let ids: Vec<Value> = received
+        .iter()
+        .map(|r| serde_json::from_slice::<Value>(&r.body).unwrap()["delivery_id"].clone())
+        .collect();
+    assert!(ids.iter().all(|id| id == &ids[0]));
+}
+
+#[tokio::test]
+async fn test_client_errors_are_not_retried() {
+    let app = create_webhook_test_app();
+    let (endpoint, url) = FakeEndpoint::start(&[400]).await;
+    let token = create_test_user(&app, "rejected_streamer").await;
+
+    let (_, created) = create_webhook(
+        &app,
+        &token,
+        json!({ "url": url, "events": ["room_created"] }),
+    )
+    .await;
+    let webhook_id = created["webhook"]["id"].as_str().unwrap();
+
+    create_test_room(&app, &token, "Rejected Room", 6).await;
+
+    let delivery = wait_for_delivery(&app, &token, webhook_id).await;
+    assert_eq!(delivery["status"], "failed");
+    assert_eq!(delivery["attempts"], 1);
+    assert_eq!(delivery["response_status"], 400);
+    assert_eq!(endpoint.received().len(), 1);
+}
+
+#[tokio::test]
+async fn test_test_delivery_sends_ping() {
+    let app = create_webhook_test_app();
+    let (endpoint, url) = FakeEndpoint::start(&[]).await;
+    let token = create_test_user(&app, "ping_streamer").await;
+    let other_token = create_test_user(&app, "ping_stranger").await;
+
+    let (_, created) = create_webhook(
+        &app,
+        &token,
+        json!({ "url": url, "events": ["game_started"] }),
+    )
+    .await;
+    let webhook_id = created["webhook"]["id"].as_str().unwrap();
+    let test_uri = format!("/api/v1/webhooks/{}/test", webhook_id);
+
+    let (status, delivery) = send(&app, Method::POST, &test_uri, &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(delivery["status"], "delivered");
+    assert_eq!(delivery["event"], "ping");
+
+    let received = endpoint.received();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].headers[webhooks::EVENT_HEADER], "ping");
+
+    // Other users can't see or trigger someone else's webhook
+    let (status, _) = send(&app, Method::POST, &test_uri, &other_token, None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(
+        &app,
+        Method::GET,
+        &format!("/api/v1/webhooks/{}/deliveries", webhook_id),
+        &other_token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = send(
+        &app,
+        Method::DELETE,
+        &format!("/api/v1/webhooks/{}", webhook_id),
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (_, webhooks) = send(&app, Method::GET, "/api/v1/webhooks", &token, None).await;
+    assert_eq!(webhooks.as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn test_room_webhooks_are_admin_only() {
+    let app = create_webhook_test_app();
+    let admin_token = create_test_user(&app, "room_admin").await;
+    let player_token = create_test_user(&app, "room_player").await;
+    let room_code = create_test_room(&app, &admin_token, "Admin Room", 6).await;
+
+    let request = json!({
+        "url": "http://127.0.0.1:9/hook",
+        "events": ["round_ended"],
+        "room_code": room_code,
+    });
+
+    let (status, _) = create_webhook(&app, &player_token, request.clone()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, created) = create_webhook(&app, &admin_token, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(created["webhook"]["room_code"], room_code);
+
+    // Invalid subscriptions are rejected
+    let (status, _) = create_webhook(
+        &app,
+        &admin_token,
+        json!({ "url": "ftp://example.com", "events": ["round_ended"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = create_webhook(
+        &app,
+        &admin_token,
+        json!({ "url": "http://127.0.0.1:9/hook", "events": [] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_webhooks_cannot_target_internal_addresses() {
+    let app = create_test_app().await;
+    let token = create_test_user(&app, "ssrf_user").await;
+
+    for url in [
+        "http://127.0.0.1:9/hook",
+        "http://localhost:3000/hook",
+        "http://10.0.0.5/hook",
+        "http://192.168.1.1/hook",
+        "http://172.16.0.1/hook",
+        "http://169.254.169.254/latest/meta-data/",
+        "http://100.100.100.200/latest/meta-data/",
+        "http://0.0.0.0/hook",
+        "http://[::1]/hook",
+        "http://[::ffff:127.0.0.1]/hook",
+        "http://[fd00:ec2::254]/hook",
+        "http://[fe80::1]/hook",
+        "http://2130706433/hook", // 127.0.0.1 in decimal
+    ] {
+        let (status, body) = create_webhook(
+            &app,
+            &token,
+            json!({ "url": url, "events": ["game_started"] }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{} was accepted", url);
+        assert!(body["error"].as_str().is_some_and(|e| !e.is_empty()));
+    }
+
+    // Public addresses are fine
+    let (status, _) = create_webhook(
+        &app,
+        &token,
+        json!({ "url": "https://93.184.216.34/hook", "events": ["game_started"] }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_public_targets_are_pinned_to_their_addresses() {
+    let (host, addrs) = webhooks::resolve_public_target("https://93.184.216.34/hook")
+        .await
+        .unwrap();
+    assert_eq!(host, "93.184.216.34");
+    assert_eq!(addrs, vec!["93.184.216.34:443".parse().unwrap()]);
+
+    assert!(matches!(
+        webhooks::resolve_public_target("http://localhost/hook").await,
+        Err(TargetError::Forbidden(_))
+    ));
+    assert!(matches!(
+        webhooks::resolve_public_target("ftp://example.com").await,
+        Err(TargetError::Invalid(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_rounds_ended_over_rest_are_announced_and_reported() {
+    let state = create_webhook_test_state();
+    let app = create_test_router(state.clone());
+    let (endpoint, url) = FakeEndpoint::start(&[]).await;
+    let token = create_test_user(&app, "rest_round_admin").await;
+    let room_code = create_test_room(&app, &token, "Round Room", 6).await;
+    let (status, _) = create_webhook(
+        &app,
+        &token,
+        json!({ "url": url, "events": ["round_ended"], "room_code": room_code }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let settings = GameSettings {
+        mode: GameMode::Hat,
+        hat: HatSettings {
+            words_per_player: 1,
+            ..HatSettings::default()
+        },
+        ..GameSettings::default()
+    };
+    let mut engine = GameEngine::new(&state.mongo_client, Some(settings)).await;
+    let players = [
+        ("p1", "team_a"),
+        ("p2", "team_a"),
+        ("p3", "team_b"),
+        ("p4", "team_b"),
+    ];
+    for (player, team) in players {
+        engine
+            .team_manager
+            .add_player_to_team(player.to_string(), team)
+            .unwrap();
+    }
+    engine.start_game().await.unwrap();
+    for ((player, _), word) in players.into_iter().zip(["кіт", "пес", "сонце", "море"])
+    {
+        engine
+            .submit_hat_words(player, vec![word.to_string()])
+            .unwrap();
+    }
+    engine.start_round().await.unwrap();
+    GAME_ENGINES
+        .write()
+        .await
+        .insert(room_code.clone(), Arc::new(RwLock::new(engine)));
+    let mut receiver = state
+        .websocket_manager
+        .get_or_create_room_sender(&room_code)
+        .await
+        .subscribe();
+
+    // The admin ends the round over REST
+    let uri = format!("/api/v1/game/{}/round/end", room_code);
+    let (status, _) = send(&app, Method::POST, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let received = endpoint.wait_for(1).await;
+    assert_eq!(received[0].headers[webhooks::EVENT_HEADER], "round_ended");
+    let mut announced = false;
+    while let Ok(sequenced) = receiver.try_recv() {
+        announced |= matches!(sequenced.message, WebSocketMessage::RoundEnded { .. });
+    }
+    assert!(announced, "round end was not broadcast");
+
+    // The round is already over
+    let (status, _) = send(&app, Method::POST, &uri, &token, None).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    GAME_ENGINES.write().await.remove(&room_code);
+}
@@ -2,7 +2,7 @@ use axum::{
     body::Body,
     http::{Method, Request, StatusCode},
 };
-use serde_json::{json, Value};
+use serde_json::Value;
 use tower::ServiceExt;
 
 mod test_helpers;
@@ -67,7 +67,7 @@ async fn test_room_deletion_when_empty() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/join", room_code))
+                .uri(format!("/api/v1/rooms/{}/join", room_code))
                 .header("Authorization", format!("Bearer {}", player_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -103,7 +103,7 @@ async fn test_room_deletion_when_empty() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/leave", room_code))
+                .uri(format!("/api/v1/rooms/{}/leave", room_code))
                 .header("Authorization", format!("Bearer {}", player_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -139,7 +139,7 @@ async fn test_room_deletion_when_empty() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/leave", room_code))
+                .uri(format!("/api/v1/rooms/{}/leave", room_code))
                 .header("Authorization", format!("Bearer {}", admin_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -204,7 +204,7 @@ async fn test_room_info_updates_on_player_join_leave() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/join", room_code))
+                .uri(format!("/api/v1/rooms/{}/join", room_code))
                 .header("Authorization", format!("Bearer {}", player1_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -236,7 +236,7 @@ async fn test_room_info_updates_on_player_join_leave() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/join", room_code))
+                .uri(format!("/api/v1/rooms/{}/join", room_code))
                 .header("Authorization", format!("Bearer {}", player2_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -268,7 +268,7 @@ async fn test_room_info_updates_on_player_join_leave() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/leave", room_code))
+                .uri(format!("/api/v1/rooms/{}/leave", room_code))
                 .header("Authorization", format!("Bearer {}", player1_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -312,7 +312,7 @@ async fn test_multiple_rooms_independent_management() {
         .oneshot(
             Request::builder()
                 .method(Method::POST)
-                .uri(&format!("/api/v1/rooms/{}/join", room1_code))
+                .uri(format!("/api/v1/rooms/{}/join", room1_code))
                 .header("Authorization", format!("Bearer {}", player_token))
                 .body(Body::empty())
                 .unwrap(),
@@ -0,0 +1,97 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use shared::errors::AuthError;
+
+use crate::provider::{get_json, request_token, IdentityProvider, ProviderIdentity};
+
+const DISCORD_AUTHORIZE_URL: &str = "https://discord.com/oauth2/authorize";
+const DISCORD_API_URL: &str = "https://discord.com/api";
+const DISCORD_CDN_URL: &str = "https://cdn.discordapp.com";
+
+#[derive(Debug, Deserialize)]
+struct DiscordUser {
+    id: String,
+    username: String,
+    global_name: Option<String>,
+    avatar: Option<String>,
+    email: Option<String>,
+}
+
+pub struct DiscordProvider {
+    client: Client,
+    client_id: String,
+    client_secret: String,
+    authorize_url: String,
+    api_url: String,
+}
+
+impl DiscordProvider {
+    pub fn new(client_id: String, client_secret: String) -> Self {
+        Self {
+            client: Client::new(),
+            client_id,
+            client_secret,
+            authorize_url: DISCORD_AUTHORIZE_URL.to_string(),
+            api_url: DISCORD_API_URL.to_string(),
+        }
+    }
+
+    /// Use other authorize and API URLs (e.g. a mock server in tests)
+    pub fn with_endpoints(mut self, authorize_url: &str, api_url: &str) -> Self {
+        self.authorize_url = authorize_url.to_string();
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for DiscordProvider {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    fn authorize_url(&self, redirect_uri: &str) -> String {
+        format!(
+            "{}?client_id={}&redirect_uri={}&response_type=code&scope=identify%20email",
+            self.authorize_url,
+            self.client_id,
+            urlencoding::encode(redirect_uri)
+        )
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+    ) -> Result<ProviderIdentity, AuthError> {
+        let token = request_token(
+            &self.client,
+            &format!("{}/oauth2/token", self.api_url),
+            &self.client_id,
+            &self.client_secret,
+            code,
+            redirect_uri,
+        )
+        .await?;
+
+        let user: DiscordUser = get_json(
+            &self.client,
+            &format!("{}/users/@me", self.api_url),
+            &token.access_token,
+        )
+        .await?;
+
+        Ok(ProviderIdentity {
+            provider: self.name().to_string(),
+            avatar_url: user
+                .avatar
+                .map(|hash| format!("{}/avatars/{}/{}.png", DISCORD_CDN_URL, user.id, hash)),
+            display_name: user.global_name.unwrap_or_else(|| user.username.clone()),
+            subject: user.id,
+            username: user.username,
+            email: user.email,
+            tokens: None,
+        })
+    }
+}
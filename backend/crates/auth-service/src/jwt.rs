@@ -1,47 +1,64 @@
 use chrono::{Duration, Utc};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{decode, decode_header, Validation};
 use shared::errors::AuthError;
-use shared::models::{JwtClaims, User};
+use shared::models::JwtClaims;
+use std::sync::Arc;
+
+use crate::keys::KeyRing;
+use crate::session::Session;
+
+/// Access tokens are short-lived; clients renew them with a refresh token
+pub const ACCESS_TOKEN_DURATION: Duration = Duration::minutes(15);
 
 pub struct JwtService {
-    encoding_key: EncodingKey,
-    decoding_key: DecodingKey,
+    keys: Arc<KeyRing>,
     token_duration: Duration,
 }
 
 impl JwtService {
-    pub fn new(secret: &str) -> Self {
+    pub fn new(keys: Arc<KeyRing>) -> Self {
         Self {
-            encoding_key: EncodingKey::from_secret(secret.as_bytes()),
-            decoding_key: DecodingKey::from_secret(secret.as_bytes()),
-            token_duration: Duration::hours(24),
+            keys,
+            token_duration: ACCESS_TOKEN_DURATION,
         }
     }
 
-    pub fn generate_token(&self, user: &User) -> Result<String, AuthError> {
+    /// Issue an access token for a login session
+    pub fn generate_token(&self, session: &Session) -> Result<String, AuthError> {
         let now = Utc::now();
-        let exp = now + self.token_duration;
+        // Never outlive the session (guest sessions have a fixed end)
+        let exp = (now + self.token_duration).timestamp();
+        let exp = session.expires_at.map_or(exp, |end| exp.min(end));
 
         let claims = JwtClaims {
-            sub: user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default(),
-            twitch_id: user.twitch_id.clone(),
-            username: user.username.clone(),
-            exp: exp.timestamp(),
+            sub: session.user_id.clone(),
+            username: session.username.clone(),
+            sid: session.id.clone(),
+            exp,
             iat: now.timestamp(),
         };
 
-        encode(&Header::new(Algorithm::HS256), &claims, &self.encoding_key)
-            .map_err(|e| AuthError::InternalError(e.to_string()))
+        self.keys.current().sign(&claims)
     }
 
+    /// Check a token's signature with the key named in its `kid` header
     pub fn verify_token(&self, token: &str) -> Result<JwtClaims, AuthError> {
-        let validation = Validation::new(Algorithm::HS256);
+        let header = decode_header(token)?;
+        let key = header
+            .kid
+            .and_then(|kid| self.keys.find(&kid))
+            .ok_or(AuthError::InvalidToken)?;
+        let validation = Validation::new(key.algorithm());
 
-        decode::<JwtClaims>(token, &self.decoding_key, &validation)
+        decode::<JwtClaims>(token, key.decoding_key(), &validation)
             .map(|data| data.claims)
             .map_err(|e| e.into())
     }
 
+    pub fn token_duration(&self) -> Duration {
+        self.token_duration
+    }
+
     pub fn extract_token_from_header(auth_header: &str) -> Option<&str> {
         auth_header.strip_prefix("Bearer ")
     }
@@ -50,27 +67,48 @@ impl JwtService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::keys::SigningKey;
     use mongodb::bson::oid::ObjectId;
 
+    fn test_session() -> Session {
+        Session {
+            id: "session-1".to_string(),
+            user_id: ObjectId::new().to_hex(),
+            username: "testuser".to_string(),
+            created_at: Utc::now().timestamp(),
+            expires_at: None,
+        }
+    }
+
     #[test]
     fn test_jwt_generation_and_verification() {
-        let service = JwtService::new("test-secret");
+        let service = JwtService::new(Arc::new(KeyRing::new(SigningKey::generate().unwrap())));
+        let session = test_session();
 
-        let user = User {
-            id: Some(ObjectId::new()),
-            twitch_id: "123456".to_string(),
-            username: "testuser".to_string(),
-            display_name: "Test User".to_string(),
-            profile_image_url: None,
-            email: None,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        };
-
-        let token = service.generate_token(&user).unwrap();
+        let token = service.generate_token(&session).unwrap();
         let claims = service.verify_token(&token).unwrap();
 
-        assert_eq!(claims.twitch_id, user.twitch_id);
-        assert_eq!(claims.username, user.username);
+        assert_eq!(claims.username, session.username);
+        assert_eq!(claims.sid, session.id);
+        assert!(claims.exp - claims.iat <= ACCESS_TOKEN_DURATION.num_seconds());
+    }
+
+    #[test]
+    fn test_tokens_survive_key_rotation() {
+        let directory = tempfile::tempdir().unwrap();
+        let keys = Arc::new(KeyRing::load(directory.path()).unwrap());
+        let service = JwtService::new(keys.clone());
+        let old_token = service.generate_token(&test_session()).unwrap();
+
+        keys.rotate().unwrap();
+        let new_token = service.generate_token(&test_session()).unwrap();
+
+        assert!(service.verify_token(&old_token).is_ok());
+        assert!(service.verify_token(&new_token).is_ok());
+
+        // Tokens from keys we never had are rejected
+        let other = JwtService::new(Arc::new(KeyRing::new(SigningKey::generate().unwrap())));
+        let foreign_token = other.generate_token(&test_session()).unwrap();
+        assert!(service.verify_token(&foreign_token).is_err());
     }
 }
@@ -1,69 +1,311 @@
+pub mod discord;
 pub mod jwt;
+pub mod keys;
+pub mod oidc;
+pub mod provider;
+pub mod session;
+pub mod token_store;
 pub mod twitch;
+pub mod twitch_tokens;
 pub mod user;
 
+use jsonwebtoken::jwk::JwkSet;
 use mongodb::Database;
+use sha2::{Digest, Sha256};
 use shared::errors::AuthError;
-use shared::models::{LoginRequest, LoginResponse};
+use shared::models::{AuthTokens, GuestLoginRequest, JwtClaims, LoginRequest, LoginResponse, User};
+use std::net::IpAddr;
+use std::sync::Arc;
+use tracing::warn;
+
+use provider::{IdentityProviders, ProviderIdentity};
 
 pub struct AuthService {
     jwt_service: jwt::JwtService,
-    twitch_client: twitch::TwitchClient,
+    signing_keys: Arc<keys::KeyRing>,
+    providers: IdentityProviders,
     user_service: user::UserService,
+    guest_limiter: user::GuestLimiter,
+    session_store: session::SessionStore,
+    twitch_tokens: Option<twitch_tokens::TwitchTokens>,
+    test_token_secret: Option<String>,
 }
 
 impl AuthService {
     pub fn new(
         db: &Database,
-        jwt_secret: &str,
-        twitch_client_id: String,
-        twitch_client_secret: String,
+        signing_keys: Arc<keys::KeyRing>,
+        providers: IdentityProviders,
+        session_store: session::SessionStore,
     ) -> Self {
         Self {
-            jwt_service: jwt::JwtService::new(jwt_secret),
-            twitch_client: twitch::TwitchClient::new(twitch_client_id, twitch_client_secret),
+            jwt_service: jwt::JwtService::new(signing_keys.clone()),
+            signing_keys,
+            providers,
             user_service: user::UserService::new(db),
+            guest_limiter: user::GuestLimiter::default(),
+            session_store,
+            twitch_tokens: None,
+            test_token_secret: None,
         }
     }
 
-    pub async fn login(&self, request: LoginRequest) -> Result<LoginResponse, AuthError> {
-        // Exchange code for tokens
-        let token_response = self
-            .twitch_client
-            .exchange_code(&request.code, &request.redirect_uri)
-            .await?;
+    /// Keep users' Twitch tokens after sign-in
+    pub fn with_twitch_tokens(mut self, twitch_tokens: twitch_tokens::TwitchTokens) -> Self {
+        self.twitch_tokens = Some(twitch_tokens);
+        self
+    }
+
+    /// Allow `per_hour` guest accounts from one client address an hour
+    pub fn with_guest_limit(mut self, per_hour: usize) -> Self {
+        self.guest_limiter = user::GuestLimiter::per_hour(per_hour);
+        self
+    }
+
+    /// Let clients holding `secret` sign in synthetic guests without the
+    /// per-address guest limit, for load testing. Off unless configured.
+    pub fn with_test_tokens(mut self, secret: String) -> Self {
+        self.test_token_secret = Some(secret);
+        self
+    }
+
+    /// Names of the configured identity providers
+    pub fn provider_names(&self) -> Vec<String> {
+        self.providers.names()
+    }
 
-        // Get user info from Twitch
-        let user_response = self
-            .twitch_client
-            .get_user(&token_response.access_token)
-            .await?;
+    /// Where to send the user to sign in with a provider
+    pub fn authorize_url(&self, provider: &str, redirect_uri: &str) -> Result<String, AuthError> {
+        Ok(self.providers.get(provider)?.authorize_url(redirect_uri))
+    }
 
-        let twitch_user = user_response
-            .data
-            .first()
-            .ok_or_else(|| AuthError::TwitchApiError("No user data returned".to_string()))?;
+    /// Sign in with an identity provider's authorization code
+    pub async fn login(
+        &self,
+        provider: &str,
+        request: LoginRequest,
+    ) -> Result<LoginResponse, AuthError> {
+        let identity = self.fetch_identity(provider, &request).await?;
 
         // Create or update user in database
-        let user = self.user_service.create_or_update(twitch_user).await?;
+        let user = self.user_service.create_or_update(&identity).await?;
+        self.keep_provider_tokens(&user, &identity).await;
+
+        self.login_response(user).await
+    }
+
+    /// Sign in with just a display name; the account expires after a day.
+    /// Each client address may only create so many guests an hour.
+    pub async fn guest_login(
+        &self,
+        request: GuestLoginRequest,
+        client_ip: Option<IpAddr>,
+    ) -> Result<LoginResponse, AuthError> {
+        let display_name =
+            user::normalize_guest_name(&request.display_name).map_err(AuthError::InvalidRequest)?;
+        if let Some(ip) = client_ip {
+            if !self.guest_limiter.try_create(ip) {
+                warn!("Too many guest accounts from {}", ip);
+                return Err(AuthError::RateLimited);
+            }
+        }
+        let user = self.user_service.create_guest(&display_name).await?;
+
+        self.login_response(user).await
+    }
+
+    /// Sign in a synthetic guest for a load test. Needs the test token
+    /// secret, and isn't counted against the client's guest limit.
+    pub async fn test_login(
+        &self,
+        secret: &str,
+        request: GuestLoginRequest,
+    ) -> Result<LoginResponse, AuthError> {
+        let Some(expected) = &self.test_token_secret else {
+            return Err(AuthError::Unauthorized);
+        };
+        // Compare digests, so the time taken says nothing about the secret
+        if Sha256::digest(expected.as_bytes()) != Sha256::digest(secret.as_bytes()) {
+            warn!("Test sign-in with a wrong secret");
+            return Err(AuthError::Unauthorized);
+        }
+        let display_name =
+            user::normalize_guest_name(&request.display_name).map_err(AuthError::InvalidRequest)?;
+        let user = self.user_service.create_guest(&display_name).await?;
+
+        self.login_response(user).await
+    }
+
+    /// Link a provider account to the signed-in user. For a guest this is
+    /// the upgrade to a registered account: the user ID stays the same, so
+    /// rooms and game history carry over. The session is replaced either way.
+    pub async fn link_identity(
+        &self,
+        user: &User,
+        claims: &JwtClaims,
+        provider: &str,
+        request: LoginRequest,
+    ) -> Result<LoginResponse, AuthError> {
+        let identity = self.fetch_identity(provider, &request).await?;
+        let user = self.user_service.link_identity(user, &identity).await?;
+        self.keep_provider_tokens(&user, &identity).await;
+        self.session_store.revoke(&claims.sid).await?;
+
+        self.login_response(user).await
+    }
+
+    /// Unlink a provider account from the signed-in user
+    pub async fn unlink_identity(&self, user: &User, provider: &str) -> Result<User, AuthError> {
+        let user = self.user_service.unlink_identity(user, provider).await?;
+        if provider == "twitch" {
+            self.revoke_twitch_tokens(&user_id(&user)).await;
+        }
+        Ok(user)
+    }
+
+    /// Delete the signed-in user's account, revoking their Twitch access and
+    /// signing them out on every device
+    pub async fn delete_account(&self, claims: &JwtClaims) -> Result<(), AuthError> {
+        self.revoke_twitch_tokens(&claims.sub).await;
+        self.user_service.delete(&claims.sub).await?;
+        self.session_store.revoke_all(&claims.sub).await
+    }
+
+    /// A Twitch access token to call the Twitch API for a user, if they
+    /// signed in with Twitch
+    pub async fn twitch_access_token(&self, user_id: &str) -> Result<Option<String>, AuthError> {
+        match &self.twitch_tokens {
+            Some(twitch_tokens) => twitch_tokens.access_token(user_id).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Validate stored Twitch tokens; run hourly
+    pub async fn validate_twitch_tokens(
+        &self,
+    ) -> Result<Option<twitch_tokens::ValidationSummary>, AuthError> {
+        match &self.twitch_tokens {
+            Some(twitch_tokens) => twitch_tokens.validate_all().await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Create database indexes (linked identities, guest expiry, stored tokens)
+    pub async fn ensure_indexes(&self) -> Result<(), AuthError> {
+        self.user_service.ensure_indexes().await?;
+        if let Some(twitch_tokens) = &self.twitch_tokens {
+            twitch_tokens.ensure_indexes().await?;
+        }
+        Ok(())
+    }
+
+    /// Exchange an OAuth code for the provider account it belongs to
+    async fn fetch_identity(
+        &self,
+        provider: &str,
+        request: &LoginRequest,
+    ) -> Result<ProviderIdentity, AuthError> {
+        self.providers
+            .get(provider)?
+            .exchange_code(&request.code, &request.redirect_uri)
+            .await
+    }
 
-        // Generate JWT token
-        let jwt_token = self.jwt_service.generate_token(&user)?;
+    /// Store the provider's OAuth tokens; signing in still works without them
+    async fn keep_provider_tokens(&self, user: &User, identity: &ProviderIdentity) {
+        let (Some(twitch_tokens), Some(tokens)) = (&self.twitch_tokens, &identity.tokens) else {
+            return;
+        };
+        if identity.provider != "twitch" {
+            return;
+        }
+        if let Err(e) = twitch_tokens.save(&user_id(user), tokens).await {
+            warn!("Failed to store Twitch tokens of {}: {}", user.username, e);
+        }
+    }
+
+    async fn revoke_twitch_tokens(&self, user_id: &str) {
+        if let Some(twitch_tokens) = &self.twitch_tokens {
+            if let Err(e) = twitch_tokens.revoke(user_id).await {
+                warn!("Failed to revoke Twitch tokens of {}: {}", user_id, e);
+            }
+        }
+    }
+
+    /// Open a session and generate its tokens
+    async fn login_response(&self, user: User) -> Result<LoginResponse, AuthError> {
+        let tokens = self.start_session(&user).await?;
 
         Ok(LoginResponse {
-            access_token: jwt_token,
+            access_token: tokens.access_token,
+            refresh_token: tokens.refresh_token.unwrap_or_default(),
+            expires_in: tokens.expires_in,
             user: user.into(),
         })
     }
 
-    pub fn verify_token(&self, token: &str) -> Result<shared::models::JwtClaims, AuthError> {
+    /// Open a login session for a user
+    pub async fn start_session(&self, user: &User) -> Result<AuthTokens, AuthError> {
+        let (session, refresh_token) = self.session_store.create(user).await?;
+        self.session_tokens(&session, refresh_token)
+    }
+
+    /// Exchange a refresh token for a new access token and refresh token
+    pub async fn refresh(&self, refresh_token: &str) -> Result<AuthTokens, AuthError> {
+        let (session, refresh_token) = self.session_store.rotate(refresh_token).await?;
+        self.session_tokens(&session, refresh_token)
+    }
+
+    /// Revoke the session an access token belongs to. The user's Twitch
+    /// tokens go with their last session, not while other devices use them.
+    pub async fn logout(&self, claims: &JwtClaims) -> Result<(), AuthError> {
+        self.session_store.revoke(&claims.sid).await?;
+        if !self.session_store.has_sessions(&claims.sub).await? {
+            self.revoke_twitch_tokens(&claims.sub).await;
+        }
+        Ok(())
+    }
+
+    /// Check the token signature only; use `authenticate` for requests
+    pub fn verify_token(&self, token: &str) -> Result<JwtClaims, AuthError> {
         self.jwt_service.verify_token(token)
     }
 
-    pub async fn get_user_by_id(
-        &self,
-        user_id: &str,
-    ) -> Result<Option<shared::models::User>, AuthError> {
+    /// Verify an access token and reject it if its session was revoked
+    pub async fn authenticate(&self, token: &str) -> Result<JwtClaims, AuthError> {
+        let claims = self.jwt_service.verify_token(token)?;
+        if self.session_store.is_revoked(&claims.sid).await? {
+            return Err(AuthError::TokenRevoked);
+        }
+        Ok(claims)
+    }
+
+    /// Public keys that verify access tokens
+    pub fn jwks(&self) -> JwkSet {
+        self.signing_keys.jwks()
+    }
+
+    pub async fn get_user_by_id(&self, user_id: &str) -> Result<Option<User>, AuthError> {
         self.user_service.get_by_id(user_id).await
     }
+
+    fn session_tokens(
+        &self,
+        session: &session::Session,
+        refresh_token: String,
+    ) -> Result<AuthTokens, AuthError> {
+        Ok(AuthTokens {
+            access_token: self.jwt_service.generate_token(session)?,
+            refresh_token: Some(refresh_token),
+            expires_in: self
+                .jwt_service
+                .token_duration()
+                .min(session.lifetime())
+                .num_seconds(),
+        })
+    }
+}
+
+fn user_id(user: &User) -> String {
+    user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default()
 }
@@ -0,0 +1,145 @@
+//! Generic OpenID Connect provider (Google, Keycloak, ...).
+//!
+//! The signed-in user is read from the userinfo endpoint with the access
+//! token, so ID tokens don't need to be verified here.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use shared::errors::AuthError;
+
+use crate::provider::{
+    get_json, provider_error, request_token, IdentityProvider, ProviderIdentity,
+};
+
+/// Endpoints from the provider's discovery document
+#[derive(Debug, Clone, Deserialize)]
+pub struct OidcEndpoints {
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserInfoClaims {
+    sub: String,
+    preferred_username: Option<String>,
+    name: Option<String>,
+    email: Option<String>,
+    picture: Option<String>,
+}
+
+pub struct OidcProvider {
+    name: String,
+    client: Client,
+    client_id: String,
+    client_secret: String,
+    endpoints: OidcEndpoints,
+}
+
+impl OidcProvider {
+    pub fn new(
+        name: &str,
+        client_id: String,
+        client_secret: String,
+        endpoints: OidcEndpoints,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            client: Client::new(),
+            client_id,
+            client_secret,
+            endpoints,
+        }
+    }
+
+    /// Configure a provider from its issuer's discovery document
+    pub async fn discover(
+        name: &str,
+        issuer: &str,
+        client_id: String,
+        client_secret: String,
+    ) -> Result<Self, AuthError> {
+        let url = format!(
+            "{}/.well-known/openid-configuration",
+            issuer.trim_end_matches('/')
+        );
+        let response = Client::new()
+            .get(&url)
+            .send()
+            .await
+            .map_err(provider_error)?;
+
+        if !response.status().is_success() {
+            return Err(AuthError::ProviderError(format!(
+                "OIDC discovery failed for {} (status: {})",
+                issuer,
+                response.status()
+            )));
+        }
+
+        let endpoints = response.json().await.map_err(provider_error)?;
+        Ok(Self::new(name, client_id, client_secret, endpoints))
+    }
+}
+
+#[async_trait]
+impl IdentityProvider for OidcProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn authorize_url(&self, redirect_uri: &str) -> String {
+        format!(
+            "{}?client_id={}&redirect_uri={}&response_type=code&scope=openid%20profile%20email",
+            self.endpoints.authorization_endpoint,
+            self.client_id,
+            urlencoding::encode(redirect_uri)
+        )
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+    ) -> Result<ProviderIdentity, AuthError> {
+        let token = request_token(
+            &self.client,
+            &self.endpoints.token_endpoint,
+            &self.client_id,
+            &self.client_secret,
+            code,
+            redirect_uri,
+        )
+        .await?;
+
+        let claims: UserInfoClaims = get_json(
+            &self.client,
+            &self.endpoints.userinfo_endpoint,
+            &token.access_token,
+        )
+        .await?;
+
+        // Fall back to the email's local part, then the subject
+        let username = claims
+            .preferred_username
+            .clone()
+            .or_else(|| {
+                claims
+                    .email
+                    .as_ref()
+                    .and_then(|email| email.split('@').next().map(str::to_string))
+            })
+            .unwrap_or_else(|| claims.sub.clone());
+
+        Ok(ProviderIdentity {
+            provider: self.name.clone(),
+            display_name: claims.name.unwrap_or_else(|| username.clone()),
+            subject: claims.sub,
+            username,
+            email: claims.email,
+            avatar_url: claims.picture,
+            tokens: None,
+        })
+    }
+}
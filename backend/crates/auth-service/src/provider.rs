@@ -0,0 +1,142 @@
+//! OAuth identity providers users can sign in with.
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use shared::errors::AuthError;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Who signed in, as reported by the provider
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderIdentity {
+    pub provider: String,
+    pub subject: String, // Account ID at the provider
+    pub username: String,
+    pub display_name: String,
+    pub email: Option<String>,
+    pub avatar_url: Option<String>,
+    pub tokens: Option<ProviderTokens>, // Kept to call the provider's API later
+}
+
+/// OAuth tokens that let us act on the user's behalf
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProviderTokens {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_in: Option<i64>, // Seconds
+    pub scopes: Vec<String>,
+}
+
+#[async_trait]
+pub trait IdentityProvider: Send + Sync {
+    /// Short name used in routes and linked identities, e.g. "twitch"
+    fn name(&self) -> &str;
+
+    /// Where to send the user to sign in
+    fn authorize_url(&self, redirect_uri: &str) -> String;
+
+    /// Exchange an authorization code for the signed-in user's identity
+    async fn exchange_code(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+    ) -> Result<ProviderIdentity, AuthError>;
+}
+
+/// Configured providers by name
+#[derive(Clone, Default)]
+pub struct IdentityProviders {
+    providers: HashMap<String, Arc<dyn IdentityProvider>>,
+}
+
+impl IdentityProviders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, provider: impl IdentityProvider + 'static) -> Self {
+        self.providers
+            .insert(provider.name().to_string(), Arc::new(provider));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Arc<dyn IdentityProvider>, AuthError> {
+        self.providers
+            .get(name)
+            .ok_or_else(|| AuthError::InvalidRequest(format!("Unknown identity provider: {name}")))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.providers.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+/// Token endpoint response of a standard OAuth 2.0 provider
+#[derive(Debug, Deserialize)]
+pub(crate) struct OAuthTokenResponse {
+    pub access_token: String,
+}
+
+/// Authorization code grant shared by Discord and OIDC providers
+pub(crate) async fn request_token(
+    client: &reqwest::Client,
+    token_url: &str,
+    client_id: &str,
+    client_secret: &str,
+    code: &str,
+    redirect_uri: &str,
+) -> Result<OAuthTokenResponse, AuthError> {
+    let params = [
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", redirect_uri),
+        ("client_id", client_id),
+        ("client_secret", client_secret),
+    ];
+
+    let response = client
+        .post(token_url)
+        .form(&params)
+        .send()
+        .await
+        .map_err(provider_error)?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(AuthError::ProviderError(format!(
+            "Failed to exchange code (status: {status}): {error_text}"
+        )));
+    }
+
+    response.json().await.map_err(provider_error)
+}
+
+/// GET a JSON resource with a bearer token
+pub(crate) async fn get_json<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+    access_token: &str,
+) -> Result<T, AuthError> {
+    let response = client
+        .get(url)
+        .bearer_auth(access_token)
+        .send()
+        .await
+        .map_err(provider_error)?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(AuthError::ProviderError(format!(
+            "Failed to get user info: {error_text}"
+        )));
+    }
+
+    response.json().await.map_err(provider_error)
+}
+
+pub(crate) fn provider_error(err: reqwest::Error) -> AuthError {
+    AuthError::ProviderError(err.to_string())
+}
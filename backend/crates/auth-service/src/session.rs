@@ -0,0 +1,506 @@
+//! Login sessions, rotating refresh tokens and the access token revocation list.
+//!
+//! Refresh tokens are single use: each refresh swaps the token for a new one.
+//! Presenting a token that was already swapped means it leaked, so the whole
+//! session is revoked.
+
+use chrono::{Duration, Utc};
+use redis::aio::MultiplexedConnection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use shared::errors::AuthError;
+use shared::models::User;
+use std::collections::HashMap;
+use std::time::Instant;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::jwt::ACCESS_TOKEN_DURATION;
+
+/// Session lifetime, extended by every refresh
+pub const REFRESH_TOKEN_DURATION: Duration = Duration::days(30);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub user_id: String,
+    pub username: String,
+    pub created_at: i64,
+    #[serde(default)]
+    pub expires_at: Option<i64>, // Fixed end for guest sessions; others slide on refresh
+}
+
+impl Session {
+    /// Time the session and its refresh tokens stay valid from now
+    pub fn lifetime(&self) -> Duration {
+        match self.expires_at {
+            Some(expires_at) => Duration::seconds(expires_at - Utc::now().timestamp()),
+            None => REFRESH_TOKEN_DURATION,
+        }
+    }
+}
+
+// Where session keys live
+enum Backend {
+    Redis {
+        client: redis::Client,
+        connection: tokio::sync::Mutex<Option<MultiplexedConnection>>,
+    },
+    // Value and expiry per key, for tests and local runs without Redis
+    Memory(std::sync::Mutex<HashMap<String, (String, Instant)>>),
+}
+
+pub struct SessionStore {
+    backend: Backend,
+}
+
+impl SessionStore {
+    pub fn redis(client: redis::Client) -> Self {
+        Self {
+            backend: Backend::Redis {
+                client,
+                connection: tokio::sync::Mutex::new(None),
+            },
+        }
+    }
+
+    pub fn memory() -> Self {
+        Self {
+            backend: Backend::Memory(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Open a session for a user and return it with its first refresh token
+    pub async fn create(&self, user: &User) -> Result<(Session, String), AuthError> {
+        let session = Session {
+            id: Uuid::new_v4().simple().to_string(),
+            user_id: user.id.as_ref().map(|id| id.to_hex()).unwrap_or_default(),
+            username: user.username.clone(),
+            created_at: Utc::now().timestamp(),
+            // Guest sessions end with the guest account
+            expires_at: user
+                .guest_expires_at
+                .map(|expires_at| expires_at.timestamp_millis() / 1000),
+        };
+
+        let session_json =
+            serde_json::to_string(&session).map_err(|e| AuthError::InternalError(e.to_string()))?;
+        self.set(&session_key(&session.id), &session_json, session.lifetime())
+            .await?;
+        self.add_member(
+            &user_sessions_key(&session.user_id),
+            &session.id,
+            REFRESH_TOKEN_DURATION,
+        )
+        .await?;
+        let refresh_token = self.issue_refresh_token(&session).await?;
+
+        Ok((session, refresh_token))
+    }
+
+    /// Swap a refresh token for a new one
+    pub async fn rotate(&self, refresh_token: &str) -> Result<(Session, String), AuthError> {
+        let hash = hash_token(refresh_token);
+
+        let Some(session_id) = self.take(&refresh_key(&hash)).await? else {
+            // Reuse of a rotated token: assume it was stolen
+            if let Some(session_id) = self.get(&used_key(&hash)).await? {
+                warn!("Refresh token reused, revoking session {}", session_id);
+                self.revoke(&session_id).await?;
+            }
+            return Err(AuthError::InvalidToken);
+        };
+
+        // Logged out or expired
+        let Some(session) = self.load(&session_id).await? else {
+            return Err(AuthError::InvalidToken);
+        };
+        if session.lifetime() <= Duration::zero() {
+            return Err(AuthError::TokenExpired);
+        }
+
+        self.set(&used_key(&hash), &session_id, REFRESH_TOKEN_DURATION)
+            .await?;
+        self.expire(&session_key(&session_id), session.lifetime())
+            .await?;
+        self.expire(&user_sessions_key(&session.user_id), REFRESH_TOKEN_DURATION)
+            .await?;
+        let refresh_token = self.issue_refresh_token(&session).await?;
+
+        Ok((session, refresh_token))
+    }
+
+    /// End a session. Its refresh tokens stop working right away; its access
+    /// tokens are rejected until they would have expired anyway.
+    pub async fn revoke(&self, session_id: &str) -> Result<(), AuthError> {
+        if let Some(session) = self.load(session_id).await? {
+            self.remove_member(&user_sessions_key(&session.user_id), session_id)
+                .await?;
+        }
+        self.delete(&session_key(session_id)).await?;
+        self.set(&revoked_key(session_id), "1", ACCESS_TOKEN_DURATION)
+            .await
+    }
+
+    /// End every session of a user, on every device
+    pub async fn revoke_all(&self, user_id: &str) -> Result<(), AuthError> {
+        let key = user_sessions_key(user_id);
+        for session_id in self.members(&key).await? {
+            self.revoke(&session_id).await?;
+        }
+        self.delete(&key).await
+    }
+
+    /// Whether a user is still signed in anywhere
+    pub async fn has_sessions(&self, user_id: &str) -> Result<bool, AuthError> {
+        let key = user_sessions_key(user_id);
+        for session_id in self.members(&key).await? {
+            match self.load(&session_id).await? {
+                Some(session) if session.lifetime() > Duration::zero() => return Ok(true),
+                // Expired without a logout
+                _ => self.remove_member(&key, &session_id).await?,
+            }
+        }
+        Ok(false)
+    }
+
+    pub async fn is_revoked(&self, session_id: &str) -> Result<bool, AuthError> {
+        Ok(self.get(&revoked_key(session_id)).await?.is_some())
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<Session>, AuthError> {
+        self.get(&session_key(session_id))
+            .await?
+            .map(|json| {
+                serde_json::from_str(&json).map_err(|e| AuthError::InternalError(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn issue_refresh_token(&self, session: &Session) -> Result<String, AuthError> {
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        self.set(
+            &refresh_key(&hash_token(&token)),
+            &session.id,
+            session.lifetime(),
+        )
+        .await?;
+        Ok(token)
+    }
+
+    /// Run a command on the shared Redis connection, connecting on first use
+    async fn query<T: redis::FromRedisValue>(&self, cmd: &redis::Cmd) -> Result<T, AuthError> {
+        let Backend::Redis { client, connection } = &self.backend else {
+            unreachable!("only called for the Redis backend");
+        };
+
+        let mut shared = connection.lock().await;
+        let mut conn = match shared.as_ref() {
+            Some(conn) => conn.clone(),
+            None => {
+                let conn = client
+                    .get_multiplexed_async_connection()
+                    .await
+                    .map_err(redis_error)?;
+                *shared = Some(conn.clone());
+                conn
+            }
+        };
+        drop(shared);
+
+        cmd.query_async(&mut conn).await.map_err(|e| {
+            // Drop a broken connection so the next call reconnects
+            if e.is_io_error() || e.is_connection_dropped() {
+                if let Ok(mut shared) = connection.try_lock() {
+                    *shared = None;
+                }
+            }
+            redis_error(e)
+        })
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<(), AuthError> {
+        match &self.backend {
+            Backend::Redis { .. } => {
+                self.query(
+                    redis::cmd("SET")
+                        .arg(key)
+                        .arg(value)
+                        .arg("EX")
+                        .arg(ttl.num_seconds().max(1)),
+                )
+                .await
+            }
+            Backend::Memory(entries) => {
+                let now = Instant::now();
+                let mut entries = entries.lock().unwrap();
+                // Nothing else deletes expired keys, so drop them as new ones come in
+                entries.retain(|_, (_, expires_at)| *expires_at > now);
+                entries.insert(
+                    key.to_string(),
+                    (value.to_string(), now + ttl.to_std().unwrap_or_default()),
+                );
+                Ok(())
+            }
+        }
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<String>, AuthError> {
+        match &self.backend {
+            Backend::Redis { .. } => self.query(redis::cmd("GET").arg(key)).await,
+            Backend::Memory(entries) => Ok(entries
+                .lock()
+                .unwrap()
+                .get(key)
+                .filter(|(_, expires_at)| *expires_at > Instant::now())
+                .map(|(value, _)| value.clone())),
+        }
+    }
+
+    /// Get and delete in one step, so a token can only be used once
+    async fn take(&self, key: &str) -> Result<Option<String>, AuthError> {
+        match &self.backend {
+            Backend::Redis { .. } => self.query(redis::cmd("GETDEL").arg(key)).await,
+            Backend::Memory(entries) => Ok(entries
+                .lock()
+                .unwrap()
+                .remove(key)
+                .filter(|(_, expires_at)| *expires_at > Instant::now())
+                .map(|(value, _)| value)),
+        }
+    }
+
+    async fn expire(&self, key: &str, ttl: Duration) -> Result<(), AuthError> {
+        match &self.backend {
+            Backend::Redis { .. } => {
+                self.query(redis::cmd("EXPIRE").arg(key).arg(ttl.num_seconds().max(1)))
+                    .await
+            }
+            Backend::Memory(entries) => {
+                if let Some((_, expires_at)) = entries.lock().unwrap().get_mut(key) {
+                    *expires_at = Instant::now() + ttl.to_std().unwrap_or_default();
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Add to a set of IDs, keeping the set for at least `ttl`
+    async fn add_member(&self, key: &str, member: &str, ttl: Duration) -> Result<(), AuthError> {
+        match &self.backend {
+            Backend::Redis { .. } => {
+                self.query::<()>(redis::cmd("SADD").arg(key).arg(member))
+                    .await?;
+                self.expire(key, ttl).await
+            }
+            // Space separated in memory
+            Backend::Memory(_) => {
+                let mut members = self.members(key).await?;
+                if !members.iter().any(|m| m == member) {
+                    members.push(member.to_string());
+                }
+                self.set(key, &members.join(" "), ttl).await
+            }
+        }
+    }
+
+    async fn remove_member(&self, key: &str, member: &str) -> Result<(), AuthError> {
+        match &self.backend {
+            Backend::Redis { .. } => self.query(redis::cmd("SREM").arg(key).arg(member)).await,
+            Backend::Memory(entries) => {
+                if let Some((members, _)) = entries.lock().unwrap().get_mut(key) {
+                    *members = members
+                        .split(' ')
+                        .filter(|m| *m != member)
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    async fn members(&self, key: &str) -> Result<Vec<String>, AuthError> {
+        match &self.backend {
+            Backend::Redis { .. } => self.query(redis::cmd("SMEMBERS").arg(key)).await,
+            Backend::Memory(_) => Ok(self
+                .get(key)
+                .await?
+                .map(|members| members.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default()),
+        }
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), AuthError> {
+        match &self.backend {
+            Backend::Redis { .. } => self.query(redis::cmd("DEL").arg(key)).await,
+            Backend::Memory(entries) => {
+                entries.lock().unwrap().remove(key);
+                Ok(())
+            }
+        }
+    }
+}
+
+// Only hashes of refresh tokens are stored
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+fn session_key(session_id: &str) -> String {
+    format!("auth:session:{}", session_id)
+}
+
+fn refresh_key(hash: &str) -> String {
+    format!("auth:refresh:{}", hash)
+}
+
+fn used_key(hash: &str) -> String {
+    format!("auth:refresh_used:{}", hash)
+}
+
+fn user_sessions_key(user_id: &str) -> String {
+    format!("auth:user_sessions:{}", user_id)
+}
+
+fn revoked_key(session_id: &str) -> String {
+    format!("auth:revoked:{}", session_id)
+}
+
+fn redis_error(err: redis::RedisError) -> AuthError {
+    AuthError::DatabaseError(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mongodb::bson::oid::ObjectId;
+
+    fn bson_time(time: chrono::DateTime<Utc>) -> mongodb::bson::DateTime {
+        mongodb::bson::DateTime::from_millis(time.timestamp_millis())
+    }
+
+    fn test_user() -> User {
+        User {
+            id: Some(ObjectId::new()),
+            identities: Vec::new(),
+            username: "testuser".to_string(),
+            display_name: "Test User".to_string(),
+            profile_image_url: None,
+            email: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            is_guest: false,
+            guest_expires_at: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refresh_tokens_rotate() {
+        let store = SessionStore::memory();
+        let (session, first) = store.create(&test_user()).await.unwrap();
+
+        let (refreshed, second) = store.rotate(&first).await.unwrap();
+        assert_eq!(refreshed.id, session.id);
+        assert_ne!(first, second);
+
+        let (_, third) = store.rotate(&second).await.unwrap();
+        assert_ne!(second, third);
+        assert!(store.rotate("not-a-token").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reused_refresh_token_revokes_session() {
+        let store = SessionStore::memory();
+        let (_, first) = store.create(&test_user()).await.unwrap();
+        let (_, second) = store.rotate(&first).await.unwrap();
+
+        // The old token shows up again: both tokens stop working
+        assert!(store.rotate(&first).await.is_err());
+        assert!(store.rotate(&second).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_revoked_session_cannot_refresh() {
+        let store = SessionStore::memory();
+        let (session, refresh_token) = store.create(&test_user()).await.unwrap();
+        assert!(!store.is_revoked(&session.id).await.unwrap());
+
+        store.revoke(&session.id).await.unwrap();
+
+        assert!(store.is_revoked(&session.id).await.unwrap());
+        assert!(store.rotate(&refresh_token).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_users_stay_signed_in_until_their_last_session_ends() {
+        let store = SessionStore::memory();
+        let user = test_user();
+        let user_id = user.id.unwrap().to_hex();
+        assert!(!store.has_sessions(&user_id).await.unwrap());
+
+        let (phone, _) = store.create(&user).await.unwrap();
+        let (laptop, _) = store.create(&user).await.unwrap();
+        store.revoke(&phone.id).await.unwrap();
+        assert!(store.has_sessions(&user_id).await.unwrap());
+
+        store.revoke(&laptop.id).await.unwrap();
+        assert!(!store.has_sessions(&user_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_revoking_all_sessions_signs_out_every_device() {
+        let store = SessionStore::memory();
+        let user = test_user();
+        let user_id = user.id.unwrap().to_hex();
+        let (phone, _) = store.create(&user).await.unwrap();
+        let (laptop, laptop_token) = store.create(&user).await.unwrap();
+        let (_, other_user_token) = store.create(&test_user()).await.unwrap();
+
+        // The account is deleted from the phone
+        store.revoke_all(&user_id).await.unwrap();
+
+        assert!(store.is_revoked(&phone.id).await.unwrap());
+        assert!(store.is_revoked(&laptop.id).await.unwrap());
+        assert!(store.rotate(&laptop_token).await.is_err());
+        assert!(!store.has_sessions(&user_id).await.unwrap());
+        assert!(store.rotate(&other_user_token).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_forgets_expired_keys() {
+        let store = SessionStore::memory();
+        for n in 0..10 {
+            store
+                .set(&format!("short:{}", n), "1", Duration::zero())
+                .await
+                .unwrap();
+        }
+        store.set("long", "1", Duration::hours(1)).await.unwrap();
+
+        let Backend::Memory(entries) = &store.backend else {
+            unreachable!();
+        };
+        assert_eq!(entries.lock().unwrap().len(), 1);
+        assert!(store.get("long").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_guest_sessions_end_with_the_account() {
+        let store = SessionStore::memory();
+        let mut guest = test_user();
+        guest.is_guest = true;
+        guest.guest_expires_at = Some(bson_time(Utc::now() + Duration::hours(1)));
+
+        let (session, refresh_token) = store.create(&guest).await.unwrap();
+        assert!(session.lifetime() <= Duration::hours(1));
+
+        // Refreshing doesn't push the end back
+        let (refreshed, _) = store.rotate(&refresh_token).await.unwrap();
+        assert_eq!(refreshed.expires_at, session.expires_at);
+
+        guest.guest_expires_at = Some(bson_time(Utc::now() - Duration::minutes(1)));
+        let (_, expired_token) = store.create(&guest).await.unwrap();
+        assert!(store.rotate(&expired_token).await.is_err());
+    }
+}
@@ -0,0 +1,381 @@
+//! Provider OAuth tokens kept per user, encrypted at rest with AES-256-GCM.
+//!
+//! Each ciphertext is bound to its user and provider, so a stored token can't
+//! be copied onto another account.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::{doc, to_bson},
+    options::{IndexOptions, ReplaceOptions},
+    Collection, Database, IndexModel,
+};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use shared::errors::AuthError;
+use std::collections::HashMap;
+use tracing::warn;
+
+use crate::provider::ProviderTokens;
+
+pub struct TokenCipher {
+    key: LessSafeKey,
+    rng: SystemRandom,
+}
+
+impl TokenCipher {
+    /// Cipher from a 32-byte key
+    pub fn new(key: &[u8]) -> Result<Self, AuthError> {
+        let key = UnboundKey::new(&AES_256_GCM, key).map_err(|_| {
+            AuthError::InternalError("Token encryption key must be 32 bytes".to_string())
+        })?;
+
+        Ok(Self {
+            key: LessSafeKey::new(key),
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// Cipher from a base64-encoded 32-byte key
+    pub fn from_base64(key: &str) -> Result<Self, AuthError> {
+        let key = STANDARD.decode(key.trim()).map_err(|_| {
+            AuthError::InternalError("Token encryption key is not valid base64".to_string())
+        })?;
+        Self::new(&key)
+    }
+
+    /// Encrypt to base64 of nonce, ciphertext and tag
+    pub fn encrypt(&self, plaintext: &str, context: &str) -> Result<String, AuthError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| AuthError::InternalError("Failed to generate nonce".to_string()))?;
+
+        let mut data = plaintext.as_bytes().to_vec();
+        self.key
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(context.as_bytes()),
+                &mut data,
+            )
+            .map_err(|_| AuthError::InternalError("Failed to encrypt token".to_string()))?;
+
+        Ok(STANDARD.encode([nonce.as_slice(), &data].concat()))
+    }
+
+    pub fn decrypt(&self, encrypted: &str, context: &str) -> Result<String, AuthError> {
+        let failed = || AuthError::InternalError("Failed to decrypt stored token".to_string());
+
+        let data = STANDARD.decode(encrypted).map_err(|_| failed())?;
+        if data.len() < NONCE_LEN {
+            return Err(failed());
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| failed())?;
+
+        let mut ciphertext = ciphertext.to_vec();
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from(context.as_bytes()), &mut ciphertext)
+            .map_err(|_| failed())?;
+
+        String::from_utf8(plaintext.to_vec()).map_err(|_| failed())
+    }
+}
+
+/// Stored document; the tokens are encrypted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredTokens {
+    user_id: String,
+    provider: String,
+    access_token: String,
+    refresh_token: Option<String>,
+    scopes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+    validated_at: DateTime<Utc>,
+}
+
+/// Decrypted tokens of one user
+#[derive(Debug, Clone)]
+pub struct SavedTokens {
+    pub user_id: String,
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub validated_at: DateTime<Utc>,
+}
+
+impl SavedTokens {
+    /// Whether the access token expires within `margin`
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at - Utc::now() <= margin)
+    }
+}
+
+enum Backend {
+    Mongo(Collection<StoredTokens>),
+    // Keyed by user and provider, for tests and local runs without MongoDB
+    Memory(std::sync::Mutex<HashMap<(String, String), StoredTokens>>),
+}
+
+pub struct TokenStore {
+    backend: Backend,
+    cipher: TokenCipher,
+}
+
+impl TokenStore {
+    pub fn mongo(db: &Database, cipher: TokenCipher) -> Self {
+        Self {
+            backend: Backend::Mongo(db.collection("provider_tokens")),
+            cipher,
+        }
+    }
+
+    pub fn memory(cipher: TokenCipher) -> Self {
+        Self {
+            backend: Backend::Memory(std::sync::Mutex::new(HashMap::new())),
+            cipher,
+        }
+    }
+
+    /// One document per user and provider
+    pub async fn ensure_indexes(&self) -> Result<(), AuthError> {
+        if let Backend::Mongo(collection) = &self.backend {
+            let index = IndexModel::builder()
+                .keys(doc! { "user_id": 1, "provider": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build();
+            collection.create_index(index, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Store a user's tokens for a provider, replacing earlier ones
+    pub async fn save(
+        &self,
+        user_id: &str,
+        provider: &str,
+        tokens: &ProviderTokens,
+    ) -> Result<(), AuthError> {
+        let context = context(user_id, provider);
+        let now = Utc::now();
+        let stored = StoredTokens {
+            user_id: user_id.to_string(),
+            provider: provider.to_string(),
+            access_token: self.cipher.encrypt(&tokens.access_token, &context)?,
+            refresh_token: tokens
+                .refresh_token
+                .as_deref()
+                .map(|token| self.cipher.encrypt(token, &context))
+                .transpose()?,
+            scopes: tokens.scopes.clone(),
+            expires_at: tokens
+                .expires_in
+                .map(|seconds| now + Duration::seconds(seconds)),
+            validated_at: now,
+        };
+
+        match &self.backend {
+            Backend::Mongo(collection) => {
+                collection
+                    .replace_one(
+                        doc! { "user_id": user_id, "provider": provider },
+                        &stored,
+                        ReplaceOptions::builder().upsert(true).build(),
+                    )
+                    .await?;
+            }
+            Backend::Memory(entries) => {
+                entries
+                    .lock()
+                    .unwrap()
+                    .insert((user_id.to_string(), provider.to_string()), stored);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn get(
+        &self,
+        user_id: &str,
+        provider: &str,
+    ) -> Result<Option<SavedTokens>, AuthError> {
+        let stored = match &self.backend {
+            Backend::Mongo(collection) => {
+                collection
+                    .find_one(doc! { "user_id": user_id, "provider": provider }, None)
+                    .await?
+            }
+            Backend::Memory(entries) => entries
+                .lock()
+                .unwrap()
+                .get(&(user_id.to_string(), provider.to_string()))
+                .cloned(),
+        };
+
+        stored.map(|stored| self.decrypt(stored)).transpose()
+    }
+
+    /// Every user's tokens for a provider. Tokens that no longer decrypt
+    /// (e.g. after an encryption key change) are skipped.
+    pub async fn list(&self, provider: &str) -> Result<Vec<SavedTokens>, AuthError> {
+        let stored: Vec<StoredTokens> = match &self.backend {
+            Backend::Mongo(collection) => {
+                collection
+                    .find(doc! { "provider": provider }, None)
+                    .await?
+                    .try_collect()
+                    .await?
+            }
+            Backend::Memory(entries) => entries
+                .lock()
+                .unwrap()
+                .values()
+                .filter(|stored| stored.provider == provider)
+                .cloned()
+                .collect(),
+        };
+
+        Ok(stored
+            .into_iter()
+            .filter_map(|stored| {
+                let user_id = stored.user_id.clone();
+                self.decrypt(stored)
+                    .map_err(|e| warn!("Skipping {} tokens of user {}: {}", provider, user_id, e))
+                    .ok()
+            })
+            .collect())
+    }
+
+    /// Record a successful validation with the provider
+    pub async fn mark_validated(&self, user_id: &str, provider: &str) -> Result<(), AuthError> {
+        let now = Utc::now();
+        match &self.backend {
+            Backend::Mongo(collection) => {
+                let validated_at =
+                    to_bson(&now).map_err(|e| AuthError::InternalError(e.to_string()))?;
+                collection
+                    .update_one(
+                        doc! { "user_id": user_id, "provider": provider },
+                        doc! { "$set": { "validated_at": validated_at } },
+                        None,
+                    )
+                    .await?;
+            }
+            Backend::Memory(entries) => {
+                if let Some(stored) = entries
+                    .lock()
+                    .unwrap()
+                    .get_mut(&(user_id.to_string(), provider.to_string()))
+                {
+                    stored.validated_at = now;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn delete(&self, user_id: &str, provider: &str) -> Result<(), AuthError> {
+        match &self.backend {
+            Backend::Mongo(collection) => {
+                collection
+                    .delete_one(doc! { "user_id": user_id, "provider": provider }, None)
+                    .await?;
+            }
+            Backend::Memory(entries) => {
+                entries
+                    .lock()
+                    .unwrap()
+                    .remove(&(user_id.to_string(), provider.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn decrypt(&self, stored: StoredTokens) -> Result<SavedTokens, AuthError> {
+        let context = context(&stored.user_id, &stored.provider);
+        Ok(SavedTokens {
+            access_token: self.cipher.decrypt(&stored.access_token, &context)?,
+            refresh_token: stored
+                .refresh_token
+                .as_deref()
+                .map(|token| self.cipher.decrypt(token, &context))
+                .transpose()?,
+            user_id: stored.user_id,
+            scopes: stored.scopes,
+            expires_at: stored.expires_at,
+            validated_at: stored.validated_at,
+        })
+    }
+}
+
+// Additional authenticated data tying a ciphertext to its owner
+fn context(user_id: &str, provider: &str) -> String {
+    format!("{}:{}", provider, user_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher() -> TokenCipher {
+        TokenCipher::new(&[7u8; 32]).unwrap()
+    }
+
+    fn tokens() -> ProviderTokens {
+        ProviderTokens {
+            access_token: "access".to_string(),
+            refresh_token: Some("refresh".to_string()),
+            expires_in: Some(3600),
+            scopes: vec!["user:read:email".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_cipher_round_trip() {
+        let cipher = cipher();
+        let encrypted = cipher.encrypt("secret-token", "twitch:user1").unwrap();
+        assert!(!encrypted.contains("secret-token"));
+        assert_ne!(
+            encrypted,
+            cipher.encrypt("secret-token", "twitch:user1").unwrap()
+        );
+
+        assert_eq!(
+            cipher.decrypt(&encrypted, "twitch:user1").unwrap(),
+            "secret-token"
+        );
+        // Bound to its owner and key
+        assert!(cipher.decrypt(&encrypted, "twitch:user2").is_err());
+        let other = TokenCipher::new(&[8u8; 32]).unwrap();
+        assert!(other.decrypt(&encrypted, "twitch:user1").is_err());
+        assert!(TokenCipher::new(&[0u8; 16]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tokens_are_stored_encrypted() {
+        let store = TokenStore::memory(cipher());
+        store.save("user1", "twitch", &tokens()).await.unwrap();
+
+        if let Backend::Memory(entries) = &store.backend {
+            let entries = entries.lock().unwrap();
+            let stored = entries.values().next().unwrap();
+            assert_ne!(stored.access_token, "access");
+            assert_ne!(stored.refresh_token.as_deref(), Some("refresh"));
+        }
+
+        let saved = store.get("user1", "twitch").await.unwrap().unwrap();
+        assert_eq!(saved.access_token, "access");
+        assert_eq!(saved.refresh_token.as_deref(), Some("refresh"));
+        assert!(!saved.expires_within(Duration::minutes(5)));
+        assert!(saved.expires_within(Duration::hours(2)));
+
+        store.delete("user1", "twitch").await.unwrap();
+        assert!(store.get("user1", "twitch").await.unwrap().is_none());
+    }
+}
@@ -1,15 +1,20 @@
-use reqwest::Client;
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
 use shared::errors::AuthError;
 use shared::models::{TwitchTokenResponse, TwitchUserResponse};
 use tracing;
 
-const TWITCH_OAUTH_TOKEN_URL: &str = "https://id.twitch.tv/oauth2/token";
-const TWITCH_USER_API_URL: &str = "https://api.twitch.tv/helix/users";
+use crate::provider::{IdentityProvider, ProviderIdentity, ProviderTokens};
+
+const TWITCH_OAUTH_URL: &str = "https://id.twitch.tv/oauth2";
+const TWITCH_API_URL: &str = "https://api.twitch.tv/helix";
 
 pub struct TwitchClient {
     client: Client,
     client_id: String,
     client_secret: String,
+    oauth_url: String,
+    api_url: String,
 }
 
 impl TwitchClient {
@@ -18,9 +23,18 @@ impl TwitchClient {
             client: Client::new(),
             client_id,
             client_secret,
+            oauth_url: TWITCH_OAUTH_URL.to_string(),
+            api_url: TWITCH_API_URL.to_string(),
         }
     }
 
+    /// Use other OAuth and Helix base URLs (e.g. a mock server in tests)
+    pub fn with_endpoints(mut self, oauth_url: &str, api_url: &str) -> Self {
+        self.oauth_url = oauth_url.trim_end_matches('/').to_string();
+        self.api_url = api_url.trim_end_matches('/').to_string();
+        self
+    }
+
     pub async fn exchange_code(
         &self,
         code: &str,
@@ -42,7 +56,7 @@ impl TwitchClient {
 
         let response = self
             .client
-            .post(TWITCH_OAUTH_TOKEN_URL)
+            .post(format!("{}/token", self.oauth_url))
             .form(&params)
             .send()
             .await?;
@@ -71,7 +85,7 @@ impl TwitchClient {
     pub async fn get_user(&self, access_token: &str) -> Result<TwitchUserResponse, AuthError> {
         let response = self
             .client
-            .get(TWITCH_USER_API_URL)
+            .get(format!("{}/users", self.api_url))
             .header("Authorization", format!("Bearer {access_token}"))
             .header("Client-Id", &self.client_id)
             .send()
@@ -90,15 +104,61 @@ impl TwitchClient {
             .map_err(|e| AuthError::TwitchApiError(e.to_string()))
     }
 
+    /// Get a new access token with a refresh token. `None` when Twitch
+    /// rejects the refresh token (e.g. the user disconnected the app).
+    pub async fn refresh_token(
+        &self,
+        refresh_token: &str,
+    ) -> Result<Option<TwitchTokenResponse>, AuthError> {
+        let params = [
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ];
+
+        let response = self
+            .client
+            .post(format!("{}/token", self.oauth_url))
+            .form(&params)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status == StatusCode::BAD_REQUEST || status == StatusCode::UNAUTHORIZED {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(AuthError::TwitchApiError(format!(
+                "Failed to refresh token (status: {status}): {error_text}"
+            )));
+        }
+
+        response
+            .json::<TwitchTokenResponse>()
+            .await
+            .map(Some)
+            .map_err(|e| AuthError::TwitchApiError(e.to_string()))
+    }
+
+    /// Whether Twitch still accepts an access token. Errors only when the
+    /// answer is unknown (network or server trouble).
     pub async fn validate_token(&self, access_token: &str) -> Result<bool, AuthError> {
         let response = self
             .client
-            .get("https://id.twitch.tv/oauth2/validate")
-            .header("Authorization", format!("Bearer {access_token}"))
+            .get(format!("{}/validate", self.oauth_url))
+            .header("Authorization", format!("OAuth {access_token}"))
             .send()
             .await?;
 
-        Ok(response.status().is_success())
+        match response.status() {
+            status if status.is_success() => Ok(true),
+            StatusCode::UNAUTHORIZED => Ok(false),
+            status => Err(AuthError::TwitchApiError(format!(
+                "Failed to validate token (status: {status})"
+            ))),
+        }
     }
 
     pub async fn revoke_token(&self, access_token: &str) -> Result<(), AuthError> {
@@ -109,7 +169,7 @@ impl TwitchClient {
 
         let response = self
             .client
-            .post("https://id.twitch.tv/oauth2/revoke")
+            .post(format!("{}/revoke", self.oauth_url))
             .form(&params)
             .send()
             .await?;
@@ -124,3 +184,56 @@ impl TwitchClient {
         Ok(())
     }
 }
+
+#[async_trait]
+impl IdentityProvider for TwitchClient {
+    fn name(&self) -> &str {
+        "twitch"
+    }
+
+    fn authorize_url(&self, redirect_uri: &str) -> String {
+        format!(
+            "{}/authorize?client_id={}&redirect_uri={}&response_type=code&scope=user:read:email&force_verify=true",
+            self.oauth_url,
+            self.client_id,
+            urlencoding::encode(redirect_uri)
+        )
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+    ) -> Result<ProviderIdentity, AuthError> {
+        let token_response = TwitchClient::exchange_code(self, code, redirect_uri).await?;
+        let user_response = self.get_user(&token_response.access_token).await?;
+
+        let twitch_user = user_response
+            .data
+            .into_iter()
+            .next()
+            .ok_or_else(|| AuthError::TwitchApiError("No user data returned".to_string()))?;
+
+        Ok(ProviderIdentity {
+            provider: self.name().to_string(),
+            subject: twitch_user.id,
+            username: twitch_user.login,
+            display_name: twitch_user.display_name,
+            email: twitch_user.email,
+            avatar_url: Some(twitch_user.profile_image_url),
+            tokens: Some(token_response.into()),
+        })
+    }
+}
+
+impl From<TwitchTokenResponse> for ProviderTokens {
+    fn from(response: TwitchTokenResponse) -> Self {
+        ProviderTokens {
+            access_token: response.access_token,
+            refresh_token: response.refresh_token,
+            // Refresh responses may leave it out
+            expires_in: (response.expires_in > 0).then_some(response.expires_in),
+            scopes: response.scope,
+        }
+    }
+}
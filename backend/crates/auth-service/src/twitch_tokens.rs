@@ -0,0 +1,142 @@
+//! Twitch tokens kept after sign-in, so the backend can call the Twitch API
+//! on a user's behalf. Twitch requires apps to validate stored tokens hourly.
+
+use chrono::Duration;
+use shared::errors::AuthError;
+use tracing::{info, warn};
+
+use crate::provider::ProviderTokens;
+use crate::token_store::{SavedTokens, TokenStore};
+use crate::twitch::TwitchClient;
+
+/// How often stored tokens must be validated with Twitch
+pub const VALIDATION_INTERVAL: Duration = Duration::hours(1);
+
+// Refresh access tokens this long before they expire
+const REFRESH_MARGIN: Duration = Duration::minutes(5);
+
+const PROVIDER: &str = "twitch";
+
+/// Outcome of validating every stored token
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ValidationSummary {
+    pub valid: usize,
+    pub refreshed: usize,
+    pub dropped: usize,
+    pub failed: usize, // Twitch unreachable; retried next time
+}
+
+pub struct TwitchTokens {
+    client: TwitchClient,
+    store: TokenStore,
+}
+
+impl TwitchTokens {
+    pub fn new(client: TwitchClient, store: TokenStore) -> Self {
+        Self { client, store }
+    }
+
+    pub async fn ensure_indexes(&self) -> Result<(), AuthError> {
+        self.store.ensure_indexes().await
+    }
+
+    pub async fn save(&self, user_id: &str, tokens: &ProviderTokens) -> Result<(), AuthError> {
+        self.store.save(user_id, PROVIDER, tokens).await
+    }
+
+    /// A usable access token for the user, refreshed first if it is about to
+    /// expire. `None` if the user never signed in with Twitch or revoked access.
+    pub async fn access_token(&self, user_id: &str) -> Result<Option<String>, AuthError> {
+        let Some(saved) = self.store.get(user_id, PROVIDER).await? else {
+            return Ok(None);
+        };
+        if !saved.expires_within(REFRESH_MARGIN) {
+            return Ok(Some(saved.access_token));
+        }
+
+        Ok(self
+            .refresh(&saved)
+            .await?
+            .map(|tokens| tokens.access_token))
+    }
+
+    /// Validate every stored token. Rejected tokens are refreshed, or dropped
+    /// when that fails too.
+    pub async fn validate_all(&self) -> Result<ValidationSummary, AuthError> {
+        let mut summary = ValidationSummary::default();
+
+        for saved in self.store.list(PROVIDER).await? {
+            let valid = match self.client.validate_token(&saved.access_token).await {
+                Ok(valid) => valid,
+                Err(e) => {
+                    warn!(
+                        "Could not validate Twitch token of {}: {}",
+                        saved.user_id, e
+                    );
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+
+            if valid && !saved.expires_within(REFRESH_MARGIN) {
+                self.store.mark_validated(&saved.user_id, PROVIDER).await?;
+                summary.valid += 1;
+                continue;
+            }
+
+            match self.refresh(&saved).await {
+                Ok(Some(_)) => summary.refreshed += 1,
+                Ok(None) => summary.dropped += 1,
+                Err(e) => {
+                    warn!("Could not refresh Twitch token of {}: {}", saved.user_id, e);
+                    summary.failed += 1;
+                }
+            }
+        }
+
+        info!(
+            "Validated Twitch tokens: {} valid, {} refreshed, {} dropped, {} failed",
+            summary.valid, summary.refreshed, summary.dropped, summary.failed
+        );
+        Ok(summary)
+    }
+
+    /// Revoke the user's token with Twitch and forget it
+    pub async fn revoke(&self, user_id: &str) -> Result<(), AuthError> {
+        let Some(saved) = self.store.get(user_id, PROVIDER).await? else {
+            return Ok(());
+        };
+        if let Err(e) = self.client.revoke_token(&saved.access_token).await {
+            // Forget it anyway; Twitch expires it on its own
+            warn!("Failed to revoke Twitch token of {}: {}", user_id, e);
+        }
+
+        self.store.delete(user_id, PROVIDER).await
+    }
+
+    /// Swap the refresh token for new tokens. Tokens Twitch won't refresh are
+    /// dropped: the user has to sign in with Twitch again.
+    async fn refresh(&self, saved: &SavedTokens) -> Result<Option<ProviderTokens>, AuthError> {
+        let response = match &saved.refresh_token {
+            Some(refresh_token) => self.client.refresh_token(refresh_token).await?,
+            None => None,
+        };
+
+        let Some(response) = response else {
+            info!(
+                "Dropping Twitch token of {}: refresh rejected",
+                saved.user_id
+            );
+            self.store.delete(&saved.user_id, PROVIDER).await?;
+            return Ok(None);
+        };
+
+        let mut tokens = ProviderTokens::from(response);
+        if tokens.refresh_token.is_none() {
+            tokens.refresh_token = saved.refresh_token.clone();
+        }
+        self.store.save(&saved.user_id, PROVIDER, &tokens).await?;
+
+        Ok(Some(tokens))
+    }
+}
@@ -1,10 +1,104 @@
-use chrono::Utc;
+use chrono::{Duration, Utc};
 use mongodb::{
-    bson::{doc, oid::ObjectId},
-    Collection, Database,
+    bson::{doc, oid::ObjectId, DateTime as BsonDateTime},
+    options::IndexOptions,
+    Collection, Database, IndexModel,
 };
 use shared::errors::AuthError;
-use shared::models::{TwitchUser, User};
+use shared::models::{LinkedIdentity, User};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr};
+use std::sync::Mutex;
+use std::time::Instant;
+use uuid::Uuid;
+
+use crate::provider::ProviderIdentity;
+
+/// How long a guest account lasts before it is deleted
+pub const GUEST_ACCOUNT_DURATION: Duration = Duration::hours(24);
+
+/// Guest accounts one address may create per hour, unless configured
+pub const DEFAULT_GUESTS_PER_ADDRESS: usize = 10;
+
+const GUEST_LIMIT_WINDOW: std::time::Duration = std::time::Duration::from_secs(3600);
+
+const MIN_GUEST_NAME_LEN: usize = 2;
+const MAX_GUEST_NAME_LEN: usize = 24;
+
+/// Trim and check a guest's chosen display name
+pub fn normalize_guest_name(name: &str) -> Result<String, String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    let len = name.chars().count();
+    if !(MIN_GUEST_NAME_LEN..=MAX_GUEST_NAME_LEN).contains(&len) {
+        return Err(format!(
+            "Display name must be {} to {} characters",
+            MIN_GUEST_NAME_LEN, MAX_GUEST_NAME_LEN
+        ));
+    }
+    if name.chars().any(char::is_control) {
+        return Err("Display name contains invalid characters".to_string());
+    }
+    Ok(name)
+}
+
+/// Counts the guest accounts each client address created recently, so one
+/// client can't fill the database with guests
+pub struct GuestLimiter {
+    limit: usize,
+    window: std::time::Duration,
+    created: Mutex<HashMap<IpAddr, Vec<Instant>>>,
+}
+
+impl Default for GuestLimiter {
+    fn default() -> Self {
+        Self::per_hour(DEFAULT_GUESTS_PER_ADDRESS)
+    }
+}
+
+impl GuestLimiter {
+    /// Allow `limit` guests per address an hour
+    pub fn per_hour(limit: usize) -> Self {
+        Self::new(limit, GUEST_LIMIT_WINDOW)
+    }
+
+    pub fn new(limit: usize, window: std::time::Duration) -> Self {
+        Self {
+            limit,
+            window,
+            created: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count a new guest from `ip`, or refuse if the address is at the limit
+    pub fn try_create(&self, ip: IpAddr) -> bool {
+        let now = Instant::now();
+        let mut created = self.created.lock().unwrap();
+
+        // Forget guests older than the window, and addresses with none left
+        created.retain(|_, times| {
+            times.retain(|time| now.duration_since(*time) < self.window);
+            !times.is_empty()
+        });
+
+        let times = created.entry(address_block(ip)).or_default();
+        if times.len() >= self.limit {
+            return false;
+        }
+        times.push(now);
+        true
+    }
+}
+
+// An IPv6 client usually has a whole /64 to pick addresses from
+fn address_block(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(ip) if ip.to_ipv4_mapped().is_none() => {
+            let prefix = u128::from(ip) & (u128::MAX << 64);
+            IpAddr::V6(Ipv6Addr::from(prefix))
+        }
+        ip => ip,
+    }
+}
 
 pub struct UserService {
     collection: Collection<User>,
@@ -17,42 +111,66 @@ impl UserService {
         }
     }
 
-    pub async fn find_by_twitch_id(&self, twitch_id: &str) -> Result<Option<User>, AuthError> {
+    pub async fn find_by_identity(
+        &self,
+        provider: &str,
+        subject: &str,
+    ) -> Result<Option<User>, AuthError> {
         self.collection
-            .find_one(doc! { "twitch_id": twitch_id }, None)
+            .find_one(
+                doc! { "identities": { "$elemMatch": { "provider": provider, "subject": subject } } },
+                None,
+            )
             .await
             .map_err(|e| e.into())
     }
 
-    pub async fn create_or_update(&self, twitch_user: &TwitchUser) -> Result<User, AuthError> {
+    /// Find the user an identity is linked to, creating a new user on first sign-in
+    pub async fn create_or_update(&self, identity: &ProviderIdentity) -> Result<User, AuthError> {
         let now = Utc::now();
 
         // Try to find existing user
-        if let Some(mut user) = self.find_by_twitch_id(&twitch_user.id).await? {
-            // Update existing user
-            user.username = twitch_user.login.clone();
-            user.display_name = twitch_user.display_name.clone();
-            user.profile_image_url = Some(twitch_user.profile_image_url.clone());
-            user.email = twitch_user.email.clone();
+        if let Some(mut user) = self
+            .find_by_identity(&identity.provider, &identity.subject)
+            .await?
+        {
+            // The profile follows the identity the account was created with
+            let primary = user
+                .identities
+                .first()
+                .is_some_and(|i| i.provider == identity.provider);
+            if primary {
+                apply_profile(&mut user, identity);
+            }
+            if let Some(linked) = user
+                .identities
+                .iter_mut()
+                .find(|i| i.provider == identity.provider)
+            {
+                linked.username = identity.username.clone();
+            }
             user.updated_at = now;
 
             self.collection
-                .replace_one(doc! { "twitch_id": &twitch_user.id }, &user, None)
+                .replace_one(doc! { "_id": user.id }, &user, None)
                 .await?;
 
             Ok(user)
         } else {
             // Create new user
-            let new_user = User {
+            let mut new_user = User {
                 id: None,
-                twitch_id: twitch_user.id.clone(),
-                username: twitch_user.login.clone(),
-                display_name: twitch_user.display_name.clone(),
-                profile_image_url: Some(twitch_user.profile_image_url.clone()),
-                email: twitch_user.email.clone(),
+                identities: vec![linked_identity(identity)],
+                username: String::new(),
+                display_name: String::new(),
+                profile_image_url: None,
+                email: None,
                 created_at: now,
                 updated_at: now,
+                is_guest: false,
+                guest_expires_at: None,
             };
+            apply_profile(&mut new_user, identity);
 
             let result = self.collection.insert_one(&new_user, None).await?;
 
@@ -63,6 +181,131 @@ impl UserService {
         }
     }
 
+    /// Create a temporary account that needs no provider sign-in
+    pub async fn create_guest(&self, display_name: &str) -> Result<User, AuthError> {
+        let now = Utc::now();
+        let guest = User {
+            id: None,
+            identities: Vec::new(),
+            username: format!("guest_{}", &Uuid::new_v4().simple().to_string()[..8]),
+            display_name: display_name.to_string(),
+            profile_image_url: None,
+            email: None,
+            created_at: now,
+            updated_at: now,
+            is_guest: true,
+            guest_expires_at: Some(BsonDateTime::from_millis(
+                (now + GUEST_ACCOUNT_DURATION).timestamp_millis(),
+            )),
+        };
+
+        let result = self.collection.insert_one(&guest, None).await?;
+
+        let mut user = guest;
+        user.id = Some(result.inserted_id.as_object_id().unwrap());
+
+        Ok(user)
+    }
+
+    /// Link another provider account to a user. Linking the first one turns
+    /// a guest into a registered user with the same ID.
+    pub async fn link_identity(
+        &self,
+        user: &User,
+        identity: &ProviderIdentity,
+    ) -> Result<User, AuthError> {
+        if let Some(owner) = self
+            .find_by_identity(&identity.provider, &identity.subject)
+            .await?
+        {
+            if owner.id != user.id {
+                return Err(AuthError::AlreadyRegistered);
+            }
+            return Ok(owner);
+        }
+        if user.identity(&identity.provider).is_some() {
+            return Err(AuthError::InvalidRequest(format!(
+                "A {} account is already linked",
+                identity.provider
+            )));
+        }
+
+        let mut linked = user.clone();
+        linked.identities.push(linked_identity(identity));
+        if linked.is_guest {
+            apply_profile(&mut linked, identity);
+            linked.is_guest = false;
+            linked.guest_expires_at = None;
+        }
+        linked.updated_at = Utc::now();
+
+        self.collection
+            .replace_one(doc! { "_id": user.id }, &linked, None)
+            .await?;
+
+        Ok(linked)
+    }
+
+    /// Unlink a provider account, keeping at least one way to sign in
+    pub async fn unlink_identity(&self, user: &User, provider: &str) -> Result<User, AuthError> {
+        if user.identity(provider).is_none() {
+            return Err(AuthError::InvalidRequest(format!(
+                "No {} account is linked",
+                provider
+            )));
+        }
+        if user.identities.len() == 1 {
+            return Err(AuthError::InvalidRequest(
+                "Can't unlink the only sign-in method".to_string(),
+            ));
+        }
+
+        let mut unlinked = user.clone();
+        unlinked.identities.retain(|i| i.provider != provider);
+        unlinked.updated_at = Utc::now();
+
+        self.collection
+            .replace_one(doc! { "_id": user.id }, &unlinked, None)
+            .await?;
+
+        Ok(unlinked)
+    }
+
+    /// One user per provider account, and let MongoDB delete guest
+    /// accounts once they expire
+    pub async fn ensure_indexes(&self) -> Result<(), AuthError> {
+        let identities = IndexModel::builder()
+            .keys(doc! { "identities.provider": 1, "identities.subject": 1 })
+            .options(
+                IndexOptions::builder()
+                    .unique(true)
+                    // Guests have no identities
+                    .partial_filter_expression(doc! { "identities.subject": { "$exists": true } })
+                    .build(),
+            )
+            .build();
+        let guest_expiry = IndexModel::builder()
+            .keys(doc! { "guest_expires_at": 1 })
+            .options(
+                IndexOptions::builder()
+                    .expire_after(std::time::Duration::ZERO)
+                    .build(),
+            )
+            .build();
+        self.collection
+            .create_indexes([identities, guest_expiry], None)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, user_id: &str) -> Result<(), AuthError> {
+        let object_id = ObjectId::parse_str(user_id).map_err(|_| AuthError::InvalidCredentials)?;
+        self.collection
+            .delete_one(doc! { "_id": object_id }, None)
+            .await?;
+        Ok(())
+    }
+
     pub async fn get_by_id(&self, user_id: &str) -> Result<Option<User>, AuthError> {
         let object_id = ObjectId::parse_str(user_id).map_err(|_| AuthError::InvalidCredentials)?;
 
@@ -72,3 +315,62 @@ impl UserService {
             .map_err(|e| e.into())
     }
 }
+
+fn linked_identity(identity: &ProviderIdentity) -> LinkedIdentity {
+    LinkedIdentity {
+        provider: identity.provider.clone(),
+        subject: identity.subject.clone(),
+        username: identity.username.clone(),
+        linked_at: Utc::now(),
+    }
+}
+
+fn apply_profile(user: &mut User, identity: &ProviderIdentity) {
+    user.username = identity.username.clone();
+    user.display_name = identity.display_name.clone();
+    user.profile_image_url = identity.avatar_url.clone();
+    user.email = identity.email.clone();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guest_names_are_normalized() {
+        assert_eq!(normalize_guest_name("  Олена  ").unwrap(), "Олена");
+        assert_eq!(normalize_guest_name("Big   Bob").unwrap(), "Big Bob");
+
+        assert!(normalize_guest_name(" a ").is_err());
+        assert!(normalize_guest_name(&"x".repeat(25)).is_err());
+        assert!(normalize_guest_name("bad\u{7}name").is_err());
+    }
+
+    #[test]
+    fn test_guests_are_limited_per_address() {
+        let limiter = GuestLimiter::new(2, std::time::Duration::from_secs(3600));
+        let home: IpAddr = "203.0.113.7".parse().unwrap();
+        assert!(limiter.try_create(home));
+        assert!(limiter.try_create(home));
+        assert!(!limiter.try_create(home));
+
+        // Other addresses have their own count, but not a neighbouring IPv6 one
+        assert!(limiter.try_create("203.0.113.8".parse().unwrap()));
+        assert!(limiter.try_create("2001:db8::1".parse().unwrap()));
+        assert!(limiter.try_create("2001:db8::2".parse().unwrap()));
+        assert!(!limiter.try_create("2001:db8::3".parse().unwrap()));
+        assert!(limiter.try_create("2001:db8:0:1::1".parse().unwrap()));
+
+        // Players behind one NAT may need more
+        let limiter = GuestLimiter::per_hour(DEFAULT_GUESTS_PER_ADDRESS + 5);
+        for _ in 0..DEFAULT_GUESTS_PER_ADDRESS + 5 {
+            assert!(limiter.try_create(home));
+        }
+        assert!(!limiter.try_create(home));
+
+        // The count resets once the window passes
+        let limiter = GuestLimiter::new(1, std::time::Duration::ZERO);
+        assert!(limiter.try_create(home));
+        assert!(limiter.try_create(home));
+    }
+}
@@ -0,0 +1,242 @@
+//! Identity providers against a local mock OAuth server.
+
+use auth_service::discord::DiscordProvider;
+use auth_service::oidc::OidcProvider;
+use auth_service::provider::{IdentityProvider, IdentityProviders};
+use auth_service::twitch::TwitchClient;
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Form, Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const CLIENT_ID: &str = "mock_client_id";
+const CLIENT_SECRET: &str = "mock_client_secret";
+const GOOD_CODE: &str = "good_code";
+const ACCESS_TOKEN: &str = "mock_access_token";
+const REDIRECT_URI: &str = "http://localhost:4200/auth/callback";
+
+/// Authorization code grant: only the good code with our credentials works
+async fn token(Form(form): Form<HashMap<String, String>>) -> Result<Json<Value>, StatusCode> {
+    let field = |name: &str| form.get(name).map(String::as_str);
+    if field("grant_type") != Some("authorization_code")
+        || field("client_id") != Some(CLIENT_ID)
+        || field("client_secret") != Some(CLIENT_SECRET)
+        || field("redirect_uri") != Some(REDIRECT_URI)
+        || field("code") != Some(GOOD_CODE)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    Ok(Json(json!({
+        "access_token": ACCESS_TOKEN,
+        "refresh_token": "mock_refresh_token",
+        "expires_in": 3600,
+        "scope": ["user:read:email"],
+        "token_type": "bearer",
+    })))
+}
+
+fn check_bearer(headers: &HeaderMap) -> Result<(), StatusCode> {
+    let expected = format!("Bearer {ACCESS_TOKEN}");
+    match headers.get("Authorization") {
+        Some(value) if value == expected.as_str() => Ok(()),
+        _ => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+async fn twitch_users(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    check_bearer(&headers)?;
+    if headers.get("Client-Id").map(|v| v.as_bytes()) != Some(CLIENT_ID.as_bytes()) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(Json(json!({
+        "data": [{
+            "id": "1001",
+            "login": "streamer",
+            "display_name": "Streamer",
+            "profile_image_url": "https://example.com/streamer.png",
+            "email": "streamer@example.com",
+        }]
+    })))
+}
+
+async fn discord_me(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    check_bearer(&headers)?;
+    Ok(Json(json!({
+        "id": "2002",
+        "username": "gamer",
+        "global_name": "Gamer",
+        "avatar": "abc123",
+        "email": null,
+    })))
+}
+
+async fn oidc_discovery(State(base_url): State<String>) -> Json<Value> {
+    Json(json!({
+        "issuer": format!("{base_url}/oidc"),
+        "authorization_endpoint": format!("{base_url}/oidc/authorize"),
+        "token_endpoint": format!("{base_url}/oidc/token"),
+        "userinfo_endpoint": format!("{base_url}/oidc/userinfo"),
+    }))
+}
+
+async fn oidc_userinfo(headers: HeaderMap) -> Result<Json<Value>, StatusCode> {
+    check_bearer(&headers)?;
+    Ok(Json(json!({
+        "sub": "oidc-subject-3003",
+        "name": "Jane Doe",
+        "email": "jane@example.com",
+    })))
+}
+
+/// Start the mock server and return its base URL
+async fn start_mock_server() -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let app = Router::new()
+        .route("/twitch/oauth2/token", post(token))
+        .route("/twitch/helix/users", get(twitch_users))
+        .route("/discord/api/oauth2/token", post(token))
+        .route("/discord/api/users/@me", get(discord_me))
+        .route(
+            "/oidc/.well-known/openid-configuration",
+            get(oidc_discovery),
+        )
+        .route("/oidc/token", post(token))
+        .route("/oidc/userinfo", get(oidc_userinfo))
+        .with_state(base_url.clone());
+
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    base_url
+}
+
+fn twitch(base_url: &str) -> TwitchClient {
+    TwitchClient::new(CLIENT_ID.to_string(), CLIENT_SECRET.to_string()).with_endpoints(
+        &format!("{base_url}/twitch/oauth2"),
+        &format!("{base_url}/twitch/helix"),
+    )
+}
+
+fn discord(base_url: &str) -> DiscordProvider {
+    DiscordProvider::new(CLIENT_ID.to_string(), CLIENT_SECRET.to_string()).with_endpoints(
+        &format!("{base_url}/discord/oauth2/authorize"),
+        &format!("{base_url}/discord/api"),
+    )
+}
+
+#[tokio::test]
+async fn test_twitch_login() {
+    let base_url = start_mock_server().await;
+    // Through the trait: TwitchClient has its own token-only exchange_code
+    let provider: Box<dyn IdentityProvider> = Box::new(twitch(&base_url));
+
+    let identity = provider
+        .exchange_code(GOOD_CODE, REDIRECT_URI)
+        .await
+        .unwrap();
+    assert_eq!(identity.provider, "twitch");
+    assert_eq!(identity.subject, "1001");
+    assert_eq!(identity.username, "streamer");
+    assert_eq!(identity.display_name, "Streamer");
+    assert_eq!(identity.email.as_deref(), Some("streamer@example.com"));
+
+    assert!(provider
+        .exchange_code("bad_code", REDIRECT_URI)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_discord_login() {
+    let base_url = start_mock_server().await;
+    let provider = discord(&base_url);
+
+    let identity = provider
+        .exchange_code(GOOD_CODE, REDIRECT_URI)
+        .await
+        .unwrap();
+    assert_eq!(identity.provider, "discord");
+    assert_eq!(identity.subject, "2002");
+    assert_eq!(identity.username, "gamer");
+    assert_eq!(identity.display_name, "Gamer");
+    assert_eq!(
+        identity.avatar_url.as_deref(),
+        Some("https://cdn.discordapp.com/avatars/2002/abc123.png")
+    );
+
+    assert!(provider
+        .exchange_code(GOOD_CODE, "http://evil.example/callback")
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_oidc_discovery_and_login() {
+    let base_url = start_mock_server().await;
+    let provider = OidcProvider::discover(
+        "keycloak",
+        &format!("{base_url}/oidc/"),
+        CLIENT_ID.to_string(),
+        CLIENT_SECRET.to_string(),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(provider.name(), "keycloak");
+    assert!(provider
+        .authorize_url(REDIRECT_URI)
+        .starts_with(&format!("{base_url}/oidc/authorize?client_id={CLIENT_ID}")));
+
+    let identity = provider
+        .exchange_code(GOOD_CODE, REDIRECT_URI)
+        .await
+        .unwrap();
+    assert_eq!(identity.provider, "keycloak");
+    assert_eq!(identity.subject, "oidc-subject-3003");
+    // No preferred_username: falls back to the email's local part
+    assert_eq!(identity.username, "jane");
+    assert_eq!(identity.display_name, "Jane Doe");
+
+    assert!(provider
+        .exchange_code("bad_code", REDIRECT_URI)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_oidc_discovery_failure() {
+    let base_url = start_mock_server().await;
+    let result = OidcProvider::discover(
+        "oidc",
+        &format!("{base_url}/missing"),
+        CLIENT_ID.to_string(),
+        CLIENT_SECRET.to_string(),
+    )
+    .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_provider_registry() {
+    let base_url = start_mock_server().await;
+    let providers = IdentityProviders::new()
+        .with(twitch(&base_url))
+        .with(discord(&base_url));
+
+    assert_eq!(providers.names(), vec!["discord", "twitch"]);
+    assert!(providers.get("discord").is_ok());
+    assert!(providers.get("myspace").is_err());
+
+    let url = providers.get("twitch").unwrap().authorize_url(REDIRECT_URI);
+    assert!(url.contains("redirect_uri=http%3A%2F%2Flocalhost%3A4200%2Fauth%2Fcallback"));
+}
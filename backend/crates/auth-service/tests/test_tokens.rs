@@ -0,0 +1,85 @@
+//! Load test sign-in against the per-address guest limit.
+
+use auth_service::keys::{KeyRing, SigningKey};
+use auth_service::provider::IdentityProviders;
+use auth_service::session::SessionStore;
+use auth_service::AuthService;
+use shared::errors::AuthError;
+use shared::models::GuestLoginRequest;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+const SECRET: &str = "load-test-secret-0123456789";
+
+/// An auth service whose database can't be reached, so sign-ins that get as
+/// far as creating the account fail with a database error
+fn auth_service() -> AuthService {
+    let mongo_client = mongodb::Client::with_options(
+        mongodb::options::ClientOptions::builder()
+            .hosts(vec![mongodb::options::ServerAddress::Tcp {
+                host: "mock-host".to_string(),
+                port: Some(27017),
+            }])
+            .server_selection_timeout(Duration::from_millis(10))
+            .build(),
+    )
+    .unwrap();
+
+    AuthService::new(
+        &mongo_client.database("test_db"),
+        Arc::new(KeyRing::new(SigningKey::generate().unwrap())),
+        IdentityProviders::new(),
+        SessionStore::memory(),
+    )
+}
+
+fn request(display_name: &str) -> GuestLoginRequest {
+    GuestLoginRequest {
+        display_name: display_name.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_sign_in_is_off_by_default() {
+    let auth = auth_service();
+
+    let result = auth.test_login(SECRET, request("Load 0-0")).await;
+    assert!(matches!(result, Err(AuthError::Unauthorized)));
+}
+
+#[tokio::test]
+async fn test_sign_in_needs_the_secret() {
+    let auth = auth_service().with_test_tokens(SECRET.to_string());
+
+    let result = auth.test_login("wrong-secret", request("Load 0-0")).await;
+    assert!(matches!(result, Err(AuthError::Unauthorized)));
+    let result = auth.test_login("", request("Load 0-0")).await;
+    assert!(matches!(result, Err(AuthError::Unauthorized)));
+}
+
+#[tokio::test]
+async fn test_load_test_players_skip_the_guest_limit() {
+    let auth = auth_service().with_test_tokens(SECRET.to_string());
+    let load_tester: IpAddr = "203.0.113.7".parse().unwrap();
+
+    // Guests from one address run into the limit after ten
+    for seat in 0..10 {
+        let result = auth
+            .guest_login(request(&format!("Guest {}", seat)), Some(load_tester))
+            .await;
+        assert!(matches!(result, Err(AuthError::DatabaseError(_))));
+    }
+    let result = auth
+        .guest_login(request("Guest 10"), Some(load_tester))
+        .await;
+    assert!(matches!(result, Err(AuthError::RateLimited)));
+
+    // The default load test's 10 rooms of 4 players all get their accounts
+    for seat in 0..40 {
+        let result = auth
+            .test_login(SECRET, request(&format!("Load {}-{}", seat / 4, seat % 4)))
+            .await;
+        assert!(matches!(result, Err(AuthError::DatabaseError(_))));
+    }
+}
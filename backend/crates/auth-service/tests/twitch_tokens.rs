@@ -0,0 +1,215 @@
+//! Stored Twitch tokens against a mock Twitch OAuth server.
+
+use auth_service::provider::ProviderTokens;
+use auth_service::token_store::{TokenCipher, TokenStore};
+use auth_service::twitch::TwitchClient;
+use auth_service::twitch_tokens::{TwitchTokens, ValidationSummary};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Form, Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+#[derive(Default)]
+struct MockTwitch {
+    valid_access_tokens: HashSet<String>,
+    valid_refresh_tokens: HashSet<String>,
+    revoked: Vec<String>,
+    issued: usize,
+}
+
+type Mock = Arc<Mutex<MockTwitch>>;
+
+async fn token(
+    State(mock): State<Mock>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Result<Json<Value>, StatusCode> {
+    let mut mock = mock.lock().unwrap();
+    let refresh_token = form.get("refresh_token").cloned().unwrap_or_default();
+    if form.get("grant_type").map(String::as_str) != Some("refresh_token")
+        || !mock.valid_refresh_tokens.remove(&refresh_token)
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    mock.issued += 1;
+    let access_token = format!("access_{}", mock.issued);
+    let refresh_token = format!("refresh_{}", mock.issued);
+    mock.valid_access_tokens.insert(access_token.clone());
+    mock.valid_refresh_tokens.insert(refresh_token.clone());
+
+    // Like Twitch's refresh responses, without expires_in
+    Ok(Json(json!({
+        "access_token": access_token,
+        "refresh_token": refresh_token,
+        "scope": ["user:read:email"],
+        "token_type": "bearer",
+    })))
+}
+
+async fn validate(State(mock): State<Mock>, headers: HeaderMap) -> StatusCode {
+    let token = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("OAuth "))
+        .unwrap_or_default();
+
+    if mock.lock().unwrap().valid_access_tokens.contains(token) {
+        StatusCode::OK
+    } else {
+        StatusCode::UNAUTHORIZED
+    }
+}
+
+async fn revoke(State(mock): State<Mock>, Form(form): Form<HashMap<String, String>>) {
+    let mut mock = mock.lock().unwrap();
+    let token = form.get("token").cloned().unwrap_or_default();
+    mock.valid_access_tokens.remove(&token);
+    mock.revoked.push(token);
+}
+
+async fn start_mock_twitch() -> (TwitchTokens, Mock) {
+    let mock = Mock::default();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let app = Router::new()
+        .route("/oauth2/token", post(token))
+        .route("/oauth2/validate", get(validate))
+        .route("/oauth2/revoke", post(revoke))
+        .with_state(mock.clone());
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let client = TwitchClient::new("client_id".to_string(), "client_secret".to_string())
+        .with_endpoints(&format!("{base_url}/oauth2"), &format!("{base_url}/helix"));
+    let store = TokenStore::memory(TokenCipher::new(&[42u8; 32]).unwrap());
+
+    (TwitchTokens::new(client, store), mock)
+}
+
+/// Tokens as handed out at sign-in, registered with the mock
+fn issue(mock: &Mock, name: &str, expires_in: i64) -> ProviderTokens {
+    let mut mock = mock.lock().unwrap();
+    mock.valid_access_tokens.insert(format!("access_{name}"));
+    mock.valid_refresh_tokens.insert(format!("refresh_{name}"));
+
+    ProviderTokens {
+        access_token: format!("access_{name}"),
+        refresh_token: Some(format!("refresh_{name}")),
+        expires_in: Some(expires_in),
+        scopes: vec!["user:read:email".to_string()],
+    }
+}
+
+#[tokio::test]
+async fn test_access_token_is_refreshed_before_expiry() {
+    let (twitch_tokens, mock) = start_mock_twitch().await;
+    twitch_tokens
+        .save("fresh", &issue(&mock, "fresh", 3600))
+        .await
+        .unwrap();
+    twitch_tokens
+        .save("expiring", &issue(&mock, "expiring", 60))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        twitch_tokens
+            .access_token("fresh")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("access_fresh")
+    );
+    assert_eq!(
+        twitch_tokens
+            .access_token("expiring")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("access_1")
+    );
+    assert!(twitch_tokens
+        .access_token("unknown")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_validation_refreshes_or_drops_rejected_tokens() {
+    let (twitch_tokens, mock) = start_mock_twitch().await;
+    twitch_tokens
+        .save("valid", &issue(&mock, "valid", 3600))
+        .await
+        .unwrap();
+    twitch_tokens
+        .save("refreshable", &issue(&mock, "refreshable", 3600))
+        .await
+        .unwrap();
+    twitch_tokens
+        .save("disconnected", &issue(&mock, "disconnected", 3600))
+        .await
+        .unwrap();
+
+    {
+        let mut mock = mock.lock().unwrap();
+        // Access token rejected, but the refresh token still works
+        mock.valid_access_tokens.remove("access_refreshable");
+        // The user disconnected the app on Twitch
+        mock.valid_access_tokens.remove("access_disconnected");
+        mock.valid_refresh_tokens.remove("refresh_disconnected");
+    }
+
+    let summary = twitch_tokens.validate_all().await.unwrap();
+    assert_eq!(
+        summary,
+        ValidationSummary {
+            valid: 1,
+            refreshed: 1,
+            dropped: 1,
+            failed: 0,
+        }
+    );
+
+    assert_eq!(
+        twitch_tokens
+            .access_token("refreshable")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("access_1")
+    );
+    assert!(twitch_tokens
+        .access_token("disconnected")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_revoke_tells_twitch_and_forgets_tokens() {
+    let (twitch_tokens, mock) = start_mock_twitch().await;
+    twitch_tokens
+        .save("leaving", &issue(&mock, "leaving", 3600))
+        .await
+        .unwrap();
+
+    twitch_tokens.revoke("leaving").await.unwrap();
+
+    assert_eq!(mock.lock().unwrap().revoked, vec!["access_leaving"]);
+    assert!(twitch_tokens
+        .access_token("leaving")
+        .await
+        .unwrap()
+        .is_none());
+    // Nothing stored: nothing to revoke
+    twitch_tokens.revoke("leaving").await.unwrap();
+    assert_eq!(mock.lock().unwrap().revoked.len(), 1);
+}
@@ -0,0 +1,469 @@
+//! Connection to the game server: handshake, request/reply matching,
+//! keepalive pings and reconnects, driven by a background task.
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use shared::errors::ProtocolError;
+use shared::models::{
+    Encoding, GameRoom, GameSettings, RequestEnvelope, RoomInfo, UserInfo, WebSocketMessage,
+    WordResult,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::select;
+use tokio::sync::{mpsc, oneshot, RwLock};
+use tokio::time::{self, Instant};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+use tracing::{info, warn};
+
+use crate::error::ClientError;
+use crate::mirror::RoomMirror;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type Reply = Result<WebSocketMessage, ClientError>;
+
+// Protocol version this client speaks
+const PROTOCOL_VERSION: u32 = 2;
+
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    pub url: String, // e.g. ws://localhost:8080/ws
+    pub token: String,
+    pub ping_interval: Duration,
+    pub request_timeout: Duration,
+    /// Wait before the first reconnect attempt; doubles after each failure
+    pub reconnect_delay: Duration,
+    pub max_reconnect_attempts: u32,
+}
+
+impl ClientConfig {
+    pub fn new(url: impl Into<String>, token: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            token: token.into(),
+            ping_interval: Duration::from_secs(15),
+            request_timeout: Duration::from_secs(10),
+            reconnect_delay: Duration::from_millis(500),
+            max_reconnect_attempts: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ClientEvent {
+    /// A server message that wasn't the reply to one of our requests
+    Message(Box<WebSocketMessage>),
+    Reconnecting {
+        attempt: u32,
+    },
+    /// Back online; the room is resumed where it was left
+    Reconnected,
+    /// Gave up reconnecting, the client is closed
+    Disconnected,
+}
+
+enum Command {
+    Request {
+        message: Box<WebSocketMessage>,
+        reply: oneshot::Sender<Reply>,
+    },
+    Close,
+}
+
+pub struct Client {
+    commands: mpsc::UnboundedSender<Command>,
+    events: mpsc::UnboundedReceiver<ClientEvent>,
+    mirror: Arc<RwLock<RoomMirror>>,
+    user: UserInfo,
+    request_timeout: Duration,
+}
+
+impl Client {
+    /// Connect, negotiate the protocol version and authenticate
+    pub async fn connect(config: ClientConfig) -> Result<Self, ClientError> {
+        let (socket, user) = open(&config).await?;
+        info!("Connected to {} as {}", config.url, user.username);
+
+        let (commands, command_receiver) = mpsc::unbounded_channel();
+        let (event_sender, events) = mpsc::unbounded_channel();
+        let mirror = Arc::new(RwLock::new(RoomMirror::default()));
+        let request_timeout = config.request_timeout;
+
+        let connection = Connection {
+            config,
+            commands: command_receiver,
+            events: event_sender,
+            mirror: mirror.clone(),
+            pending: HashMap::new(),
+            next_request_id: 0,
+        };
+        tokio::spawn(connection.run(socket));
+
+        Ok(Self {
+            commands,
+            events,
+            mirror,
+            user,
+            request_timeout,
+        })
+    }
+
+    pub fn user(&self) -> &UserInfo {
+        &self.user
+    }
+
+    /// Next event; None once the connection is gone for good
+    pub async fn next_event(&mut self) -> Option<ClientEvent> {
+        self.events.recv().await
+    }
+
+    /// Copy of the local room and game state
+    pub async fn mirror(&self) -> RoomMirror {
+        self.mirror.read().await.clone()
+    }
+
+    /// Send a request and wait for its direct reply
+    pub async fn request(&self, message: WebSocketMessage) -> Reply {
+        let (reply, response) = oneshot::channel();
+        self.commands
+            .send(Command::Request {
+                message: Box::new(message),
+                reply,
+            })
+            .map_err(|_| ClientError::Closed)?;
+
+        match time::timeout(self.request_timeout, response).await {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(_)) => Err(ClientError::Closed),
+            Err(_) => Err(ClientError::Timeout),
+        }
+    }
+
+    pub async fn request_room_list(&self) -> Result<Vec<RoomInfo>, ClientError> {
+        match self.request(WebSocketMessage::RequestRoomList).await? {
+            WebSocketMessage::RoomList { rooms } => Ok(rooms),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn join_room(&self, room_code: &str) -> Result<GameRoom, ClientError> {
+        let message = WebSocketMessage::JoinRoom {
+            room_code: room_code.to_string(),
+        };
+        match self.request(message).await? {
+            WebSocketMessage::RoomJoined { room } => Ok(room),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    pub async fn leave_room(&self) -> Result<(), ClientError> {
+        self.request(WebSocketMessage::LeaveRoom).await?;
+        self.mirror.write().await.clear();
+        Ok(())
+    }
+
+    pub async fn join_team(&self, team_id: &str) -> Result<(), ClientError> {
+        let message = WebSocketMessage::JoinTeam {
+            team_id: team_id.to_string(),
+        };
+        self.request(message).await.map(|_| ())
+    }
+
+    pub async fn start_game(&self, settings: Option<GameSettings>) -> Result<(), ClientError> {
+        let message = WebSocketMessage::StartGame { settings };
+        self.request(message).await.map(|_| ())
+    }
+
+    pub async fn start_round(&self) -> Result<(), ClientError> {
+        self.request(WebSocketMessage::StartRound).await.map(|_| ())
+    }
+
+    pub async fn word_action(&self, result: WordResult) -> Result<(), ClientError> {
+        let message = WebSocketMessage::WordAction { result };
+        self.request(message).await.map(|_| ())
+    }
+
+    pub async fn end_round(&self) -> Result<(), ClientError> {
+        self.request(WebSocketMessage::EndRound).await.map(|_| ())
+    }
+
+    /// Tell the server the player is still there without taking an action,
+    /// so they don't show as AFK
+    pub async fn report_activity(&self) -> Result<(), ClientError> {
+        self.request(WebSocketMessage::Activity).await.map(|_| ())
+    }
+
+    /// Receive room and game state as patches; the mirror applies them
+    pub async fn set_delta_updates(&self, enabled: bool) -> Result<(), ClientError> {
+        let message = WebSocketMessage::SetDeltaUpdates { enabled };
+        self.request(message).await.map(|_| ())
+    }
+
+    /// Close the connection; pending requests fail with `Closed`
+    pub fn close(&self) {
+        let _ = self.commands.send(Command::Close);
+    }
+}
+
+fn unexpected(message: WebSocketMessage) -> ClientError {
+    ClientError::UnexpectedReply(message.type_name())
+}
+
+// A decoded server frame
+struct Frame {
+    request_id: Option<String>,
+    seq: Option<u64>, // Set on room broadcasts
+    message: WebSocketMessage,
+}
+
+fn parse(text: &str) -> Result<Frame, ClientError> {
+    let value: Value =
+        serde_json::from_str(text).map_err(|e| ClientError::InvalidMessage(e.to_string()))?;
+    let request_id = value
+        .get("request_id")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let seq = value.get("seq").and_then(Value::as_u64);
+    let message: WebSocketMessage =
+        serde_json::from_value(value).map_err(|e| ClientError::InvalidMessage(e.to_string()))?;
+
+    // A snapshot's own seq is part of the message
+    let seq = match message {
+        WebSocketMessage::RoomSnapshot { .. } => None,
+        _ => seq,
+    };
+    Ok(Frame {
+        request_id,
+        seq,
+        message,
+    })
+}
+
+async fn send(
+    socket: &mut Socket,
+    request_id: Option<String>,
+    message: WebSocketMessage,
+) -> Result<(), ClientError> {
+    let text = serde_json::to_string(&RequestEnvelope {
+        request_id,
+        message,
+    })
+    .map_err(|e| ClientError::InvalidMessage(e.to_string()))?;
+    socket.send(Message::Text(text)).await?;
+    Ok(())
+}
+
+/// Open a socket, say hello and authenticate
+async fn open(config: &ClientConfig) -> Result<(Socket, UserInfo), ClientError> {
+    let (mut socket, _) = connect_async(config.url.as_str()).await?;
+
+    let hello = WebSocketMessage::Hello {
+        protocol_version: PROTOCOL_VERSION,
+        encodings: vec![Encoding::Json],
+    };
+    send(&mut socket, Some("hello".to_string()), hello).await?;
+    let authenticate = WebSocketMessage::Authenticate {
+        token: config.token.clone(),
+    };
+    send(&mut socket, Some("authenticate".to_string()), authenticate).await?;
+
+    let user = time::timeout(config.request_timeout, async {
+        while let Some(message) = socket.next().await {
+            let Message::Text(text) = message? else {
+                continue;
+            };
+            let frame = parse(&text)?;
+            match frame.message {
+                WebSocketMessage::Authenticated { user } => return Ok(user),
+                // Servers without versioning don't know hello
+                WebSocketMessage::Error { .. } if frame.request_id.as_deref() == Some("hello") => {}
+                WebSocketMessage::Error { code, message } => {
+                    return Err(ClientError::Server(ProtocolError::new(code, message)))
+                }
+                _ => {}
+            }
+        }
+        Err(ClientError::Closed)
+    })
+    .await
+    .map_err(|_| ClientError::Timeout)??;
+
+    Ok((socket, user))
+}
+
+// Why serving a socket stopped
+enum Exit {
+    Closed, // By us
+    Lost,
+}
+
+// Background task owning the socket
+struct Connection {
+    config: ClientConfig,
+    commands: mpsc::UnboundedReceiver<Command>,
+    events: mpsc::UnboundedSender<ClientEvent>,
+    mirror: Arc<RwLock<RoomMirror>>,
+    pending: HashMap<String, oneshot::Sender<Reply>>, // Request id -> waiting caller
+    next_request_id: u64,
+}
+
+impl Connection {
+    async fn run(mut self, mut socket: Socket) {
+        loop {
+            match self.serve(&mut socket).await {
+                Exit::Closed => {
+                    let _ = socket.close(None).await;
+                    break;
+                }
+                Exit::Lost => {
+                    warn!("Connection to {} lost", self.config.url);
+                    self.fail_pending();
+                    match self.reconnect().await {
+                        Some(reconnected) => socket = reconnected,
+                        None => {
+                            let _ = self.events.send(ClientEvent::Disconnected);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        self.fail_pending();
+    }
+
+    async fn serve(&mut self, socket: &mut Socket) -> Exit {
+        let mut ping = time::interval(self.config.ping_interval);
+        ping.tick().await; // The first tick is immediate
+        let mut last_heard = Instant::now();
+
+        loop {
+            select! {
+                message = socket.next() => {
+                    let text = match message {
+                        Some(Ok(Message::Text(text))) => text,
+                        Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return Exit::Lost,
+                        Some(Ok(_)) => {
+                            // Protocol pings are answered by the socket itself
+                            last_heard = Instant::now();
+                            continue;
+                        }
+                    };
+                    last_heard = Instant::now();
+
+                    match parse(&text) {
+                        Ok(frame) => {
+                            if !self.handle(frame).await
+                                && send(socket, None, WebSocketMessage::RequestSnapshot).await.is_err()
+                            {
+                                return Exit::Lost;
+                            }
+                        }
+                        Err(e) => warn!("Ignoring server message: {}", e),
+                    }
+                }
+
+                command = self.commands.recv() => match command {
+                    Some(Command::Request { message, reply }) => {
+                        self.next_request_id += 1;
+                        let request_id = format!("req-{}", self.next_request_id);
+                        if let Err(e) = send(socket, Some(request_id.clone()), *message).await {
+                            let _ = reply.send(Err(e));
+                            return Exit::Lost;
+                        }
+                        self.pending.insert(request_id, reply);
+                    }
+                    // Closed by the user, or the client was dropped
+                    Some(Command::Close) | None => return Exit::Closed,
+                },
+
+                _ = ping.tick() => {
+                    // Nothing heard for two intervals: the connection is dead
+                    if last_heard.elapsed() > self.config.ping_interval * 2 {
+                        return Exit::Lost;
+                    }
+                    // Callers that timed out no longer wait
+                    self.pending.retain(|_, reply| !reply.is_closed());
+                    if send(socket, None, WebSocketMessage::Ping).await.is_err() {
+                        return Exit::Lost;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Update the mirror and hand the message to its requester or the
+    /// event stream. Returns false when the mirror needs a snapshot.
+    async fn handle(&mut self, frame: Frame) -> bool {
+        let in_sync = self.mirror.write().await.apply(frame.seq, &frame.message);
+
+        let reply = frame
+            .request_id
+            .and_then(|request_id| self.pending.remove(&request_id));
+        if let Some(reply) = reply {
+            let _ = reply.send(match frame.message {
+                WebSocketMessage::Error { code, message } => {
+                    Err(ClientError::Server(ProtocolError::new(code, message)))
+                }
+                message => Ok(message),
+            });
+            return in_sync;
+        }
+
+        match frame.message {
+            WebSocketMessage::Pong | WebSocketMessage::Ack | WebSocketMessage::Welcome { .. } => {}
+            message => {
+                let _ = self.events.send(ClientEvent::Message(Box::new(message)));
+            }
+        }
+        in_sync
+    }
+
+    async fn reconnect(&mut self) -> Option<Socket> {
+        let mut delay = self.config.reconnect_delay;
+
+        for attempt in 1..=self.config.max_reconnect_attempts {
+            let _ = self.events.send(ClientEvent::Reconnecting { attempt });
+            time::sleep(delay).await;
+            delay *= 2;
+
+            let mut socket = match open(&self.config).await {
+                Ok((socket, _)) => socket,
+                Err(e) => {
+                    warn!("Reconnect attempt {} failed: {}", attempt, e);
+                    continue;
+                }
+            };
+
+            // Pick the room up where we left off
+            let resume = {
+                let mirror = self.mirror.read().await;
+                mirror
+                    .room_code()
+                    .map(|room_code| WebSocketMessage::Resume {
+                        room_code: room_code.to_string(),
+                        last_seq: mirror.last_seq,
+                    })
+            };
+            if let Some(resume) = resume {
+                if send(&mut socket, None, resume).await.is_err() {
+                    continue;
+                }
+            }
+
+            info!("Reconnected to {}", self.config.url);
+            let _ = self.events.send(ClientEvent::Reconnected);
+            return Some(socket);
+        }
+        None
+    }
+
+    /// Requests sent on a lost socket will never be answered
+    fn fail_pending(&mut self) {
+        for (_, reply) in self.pending.drain() {
+            let _ = reply.send(Err(ClientError::Closed));
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use shared::errors::ProtocolError;
+use thiserror::Error;
+use tokio_tungstenite::tungstenite;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ClientError {
+    #[error("Connection failed: {0}")]
+    Connection(String),
+
+    #[error("Connection closed")]
+    Closed,
+
+    #[error("Request timed out")]
+    Timeout,
+
+    #[error("Invalid message: {0}")]
+    InvalidMessage(String),
+
+    /// The server answered with an error
+    #[error("Server error: {0}")]
+    Server(ProtocolError),
+
+    #[error("Unexpected reply: {0}")]
+    UnexpectedReply(&'static str),
+}
+
+impl From<tungstenite::Error> for ClientError {
+    fn from(err: tungstenite::Error) -> Self {
+        ClientError::Connection(err.to_string())
+    }
+}
@@ -0,0 +1,10 @@
+//! Async client for the game's WebSocket protocol, for bots, load tests and
+//! integration tests.
+
+pub mod connection;
+pub mod error;
+pub mod mirror;
+
+pub use connection::{Client, ClientConfig, ClientEvent};
+pub use error::ClientError;
+pub use mirror::RoomMirror;
@@ -0,0 +1,131 @@
+//! Local copy of the room and game state, kept up to date from server messages.
+
+use serde_json::Value;
+use shared::delta::{self, PatchOp};
+use shared::models::{GameRoom, GameState, WebSocketMessage};
+
+#[derive(Debug, Clone, Default)]
+pub struct RoomMirror {
+    pub room: Option<GameRoom>,
+    pub game_state: Option<GameState>,
+    /// Sequence number of the last room broadcast seen, for resuming
+    pub last_seq: u64,
+    room_version: u64,
+    game_state_version: u64,
+}
+
+impl RoomMirror {
+    pub fn room_code(&self) -> Option<&str> {
+        self.room.as_ref().map(|room| room.room_code.as_str())
+    }
+
+    /// Apply a server message. Returns false when a delta doesn't fit the
+    /// local copy and a snapshot is needed.
+    pub fn apply(&mut self, seq: Option<u64>, message: &WebSocketMessage) -> bool {
+        if let Some(seq) = seq {
+            self.last_seq = seq;
+        }
+
+        match message {
+            WebSocketMessage::RoomJoined { room } => {
+                *self = Self {
+                    room: Some(room.clone()),
+                    ..Self::default()
+                };
+            }
+            WebSocketMessage::RoomUpdated { room } => self.room = Some(room.clone()),
+            WebSocketMessage::GameStateUpdated { game_state } => {
+                self.game_state = Some(game_state.as_ref().clone());
+            }
+            WebSocketMessage::RoomSnapshot {
+                room,
+                game_state,
+                seq,
+            } => {
+                self.room = Some(room.clone());
+                self.game_state = game_state.as_deref().cloned();
+                self.last_seq = *seq;
+            }
+            WebSocketMessage::Resumed { events, .. } => {
+                // Every event is applied, even after one didn't fit
+                let mut in_sync = true;
+                for event in events {
+                    in_sync &= self.apply(Some(event.seq), &event.message);
+                }
+                return in_sync;
+            }
+            WebSocketMessage::RoomDeleted { room_code } if self.room_code() == Some(room_code) => {
+                self.clear();
+            }
+            WebSocketMessage::RoomDelta {
+                base_version,
+                version,
+                ops,
+            } => {
+                return patch(
+                    &mut self.room,
+                    &mut self.room_version,
+                    *base_version,
+                    *version,
+                    ops,
+                );
+            }
+            WebSocketMessage::GameStateDelta {
+                base_version,
+                version,
+                ops,
+            } => {
+                return patch(
+                    &mut self.game_state,
+                    &mut self.game_state_version,
+                    *base_version,
+                    *version,
+                    ops,
+                );
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Forget the room, e.g. after leaving it
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+// Base version 0 replaces the whole document
+fn patch<T>(
+    document: &mut Option<T>,
+    current_version: &mut u64,
+    base_version: u64,
+    version: u64,
+    ops: &[PatchOp],
+) -> bool
+where
+    T: serde::Serialize + serde::de::DeserializeOwned,
+{
+    let base = match document.as_ref() {
+        _ if base_version == 0 => Value::Null,
+        Some(document) if base_version == *current_version => {
+            match serde_json::to_value(document) {
+                Ok(value) => value,
+                Err(_) => return false,
+            }
+        }
+        _ => return false,
+    };
+
+    let mut value = base;
+    if delta::apply(&mut value, ops).is_err() {
+        return false;
+    }
+    match serde_json::from_value(value) {
+        Ok(patched) => {
+            *document = Some(patched);
+            *current_version = version;
+            true
+        }
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,232 @@
+use client::{Client, ClientConfig, ClientError, ClientEvent};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use shared::errors::ErrorCode;
+use shared::models::WordResult;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+type ServerSocket = WebSocketStream<TcpStream>;
+
+fn room(room_code: &str, name: &str) -> Value {
+    json!({
+        "room_code": room_code,
+        "name": name,
+        "admin_id": "admin",
+        "participants": {},
+        "state": "waiting",
+        "max_players": 8,
+        "created_at": "2026-01-01T00:00:00Z",
+        "updated_at": "2026-01-01T00:00:00Z",
+        "game_data": null,
+    })
+}
+
+async fn listen() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+    (listener, url)
+}
+
+async fn accept(listener: &TcpListener) -> ServerSocket {
+    let (stream, _) = listener.accept().await.unwrap();
+    tokio_tungstenite::accept_async(stream).await.unwrap()
+}
+
+async fn receive(socket: &mut ServerSocket) -> Value {
+    loop {
+        match socket.next().await.unwrap().unwrap() {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            _ => continue,
+        }
+    }
+}
+
+async fn reply(socket: &mut ServerSocket, value: Value) {
+    socket.send(Message::Text(value.to_string())).await.unwrap();
+}
+
+/// Play the server side of hello and authenticate
+async fn handshake(socket: &mut ServerSocket) {
+    let hello = receive(socket).await;
+    assert_eq!(hello["type"], "hello");
+    assert_eq!(hello["protocol_version"], 2);
+    reply(
+        socket,
+        json!({ "request_id": hello["request_id"], "type": "welcome", "protocol_version": 2, "encoding": "json" }),
+    )
+    .await;
+
+    let authenticate = receive(socket).await;
+    assert_eq!(authenticate["token"], "test-token");
+    reply(
+        socket,
+        json!({
+            "request_id": authenticate["request_id"],
+            "type": "authenticated",
+            "user": { "id": "u1", "username": "bot", "display_name": "Bot", "profile_image_url": null },
+        }),
+    )
+    .await;
+}
+
+fn config(url: String) -> ClientConfig {
+    let mut config = ClientConfig::new(url, "test-token");
+    config.request_timeout = Duration::from_secs(2);
+    config.reconnect_delay = Duration::from_millis(10);
+    config
+}
+
+#[tokio::test]
+async fn test_requests_replies_and_mirror() {
+    let (listener, url) = listen().await;
+    let server = tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        handshake(&mut socket).await;
+
+        let join = receive(&mut socket).await;
+        assert_eq!(join["type"], "join_room");
+        // A broadcast arrives before the reply
+        reply(
+            &mut socket,
+            json!({ "seq": 4, "type": "user_left", "user_id": "someone" }),
+        )
+        .await;
+        reply(
+            &mut socket,
+            json!({ "request_id": join["request_id"], "type": "room_joined", "room": room("ABC123", "Lobby") }),
+        )
+        .await;
+
+        let word = receive(&mut socket).await;
+        assert_eq!(word["result"], "correct");
+        reply(
+            &mut socket,
+            json!({ "request_id": word["request_id"], "type": "error", "code": "NOT_EXPLAINER", "message": "Only the explainer can do that" }),
+        )
+        .await;
+
+        let start = receive(&mut socket).await;
+        reply(
+            &mut socket,
+            json!({ "request_id": start["request_id"], "type": "ack" }),
+        )
+        .await;
+        reply(
+            &mut socket,
+            json!({ "seq": 5, "type": "room_updated", "room": room("ABC123", "Renamed") }),
+        )
+        .await;
+        socket
+    });
+
+    let mut client = Client::connect(config(url)).await.unwrap();
+    assert_eq!(client.user().username, "bot");
+
+    let room = client.join_room("ABC123").await.unwrap();
+    assert_eq!(room.name, "Lobby");
+    match client.next_event().await.unwrap() {
+        ClientEvent::Message(message) => assert_eq!(message.type_name(), "user_left"),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    match client.word_action(WordResult::Correct).await {
+        Err(ClientError::Server(error)) => assert_eq!(error.code, ErrorCode::NotExplainer),
+        other => panic!("unexpected {:?}", other),
+    }
+
+    client.start_round().await.unwrap();
+    match client.next_event().await.unwrap() {
+        ClientEvent::Message(message) => assert_eq!(message.type_name(), "room_updated"),
+        other => panic!("unexpected {:?}", other),
+    }
+    let mirror = client.mirror().await;
+    assert_eq!(mirror.room.unwrap().name, "Renamed");
+    assert_eq!(mirror.last_seq, 5);
+
+    let _socket = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_reconnects_and_resumes_the_room() {
+    let (listener, url) = listen().await;
+    let server = tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        handshake(&mut socket).await;
+        let join = receive(&mut socket).await;
+        reply(
+            &mut socket,
+            json!({ "request_id": join["request_id"], "type": "room_joined", "room": room("ABC123", "Lobby") }),
+        )
+        .await;
+        reply(
+            &mut socket,
+            json!({ "seq": 9, "type": "user_left", "user_id": "someone" }),
+        )
+        .await;
+        drop(socket);
+
+        // The client comes back and asks for what it missed
+        let mut socket = accept(&listener).await;
+        handshake(&mut socket).await;
+        let resume = receive(&mut socket).await;
+        assert_eq!(
+            resume,
+            json!({ "type": "resume", "room_code": "ABC123", "last_seq": 9 })
+        );
+        reply(
+            &mut socket,
+            json!({
+                "type": "resumed",
+                "room_code": "ABC123",
+                "events": [{ "seq": 10, "type": "room_updated", "room": room("ABC123", "Missed") }],
+            }),
+        )
+        .await;
+        socket
+    });
+
+    let mut client = Client::connect(config(url)).await.unwrap();
+    client.join_room("ABC123").await.unwrap();
+
+    let mut reconnected = false;
+    while let Some(event) = client.next_event().await {
+        match event {
+            ClientEvent::Reconnected => reconnected = true,
+            ClientEvent::Message(message) if message.type_name() == "resumed" => break,
+            ClientEvent::Disconnected => panic!("gave up reconnecting"),
+            _ => {}
+        }
+    }
+    assert!(reconnected);
+
+    let mirror = client.mirror().await;
+    assert_eq!(mirror.room.unwrap().name, "Missed");
+    assert_eq!(mirror.last_seq, 10);
+
+    let _socket = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pings_keep_the_connection_alive() {
+    let (listener, url) = listen().await;
+    let server = tokio::spawn(async move {
+        let mut socket = accept(&listener).await;
+        handshake(&mut socket).await;
+        for _ in 0..2 {
+            let ping = receive(&mut socket).await;
+            assert_eq!(ping, json!({ "type": "ping" }));
+            reply(&mut socket, json!({ "type": "pong" })).await;
+        }
+        socket
+    });
+
+    let mut config = config(url);
+    config.ping_interval = Duration::from_millis(50);
+    let client = Client::connect(config).await.unwrap();
+
+    let _socket = server.await.unwrap();
+    client.close();
+}
@@ -0,0 +1,51 @@
+//! Matching Twitch chat messages against the current word.
+//!
+//! A chat message counts as a guess when it contains the word as whole
+//! words ("кіт", "це кіт!"). Long messages are ignored so viewers can't
+//! guess by pasting word lists.
+
+use crate::clue::tokenize;
+
+// Words a message may have beside the guess itself
+const MAX_EXTRA_WORDS: usize = 2;
+
+/// Check whether a chat message guesses the target word
+pub fn is_guess(message: &str, target: &str) -> bool {
+    let target_tokens = tokenize(target);
+    if target_tokens.is_empty() {
+        return false;
+    }
+
+    let message_tokens = tokenize(message);
+    if message_tokens.len() > target_tokens.len() + MAX_EXTRA_WORDS {
+        return false;
+    }
+
+    message_tokens
+        .windows(target_tokens.len())
+        .any(|window| window == target_tokens.as_slice())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guesses_match_whole_words() {
+        assert!(is_guess("кіт", "кіт"));
+        assert!(is_guess("Це КІТ!!!", "кіт"));
+        assert!(is_guess("залізна   дорога?", "Залізна дорога"));
+        assert!(is_guess("п'ять", "пʼять"));
+
+        assert!(!is_guess("котик", "кіт"));
+        assert!(!is_guess("суперкіт", "кіт"));
+        assert!(!is_guess("залізна", "залізна дорога"));
+        assert!(!is_guess("", "кіт"));
+    }
+
+    #[test]
+    fn test_word_lists_are_ignored() {
+        assert!(is_guess("може це кіт", "кіт"));
+        assert!(!is_guess("пес кіт сонце море ліс", "кіт"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Server-side bot players.
+//!
+//! Bot explainers give canned clues: the word's associations from the word
+//! database, then hints from its category and spelling. Bot guessers get
+//! each word with their configured accuracy, after a random delay.
+
+use crate::clue;
+use rand::Rng;
+use shared::models::{BotSettings, GameWord};
+use std::time::Duration;
+
+// User IDs of bots start with this, so they never clash with database IDs
+pub const BOT_ID_PREFIX: &str = "bot-";
+
+pub fn is_bot(user_id: &str) -> bool {
+    user_id.starts_with(BOT_ID_PREFIX)
+}
+
+/// Clues for a word, most helpful first. None of them name the word.
+pub fn clues(word: &GameWord) -> Vec<String> {
+    let mut clues: Vec<String> = word
+        .associations
+        .iter()
+        .map(|association| association.trim())
+        .filter(|association| !association.is_empty() && !clue::violates(association, &word.word))
+        .map(str::to_string)
+        .collect();
+
+    if let Some(category) = &word.category {
+        clues.push(format!("Категорія: {}", category));
+    }
+    let letters = word.word.chars().filter(|c| c.is_alphabetic()).count();
+    clues.push(format!("Літер: {}", letters));
+    if let Some(first) = word.word.chars().next() {
+        clues.push(format!("Починається на «{}»", first.to_uppercase()));
+    }
+    clues
+}
+
+/// How long the fastest guesser takes to get the word, or None if none of
+/// them gets it
+pub fn guess_time(guessers: &[BotSettings], rng: &mut impl Rng) -> Option<Duration> {
+    let mut fastest: Option<Duration> = None;
+    for settings in guessers {
+        if !rng.gen_bool(settings.accuracy) {
+            continue;
+        }
+        let time =
+            Duration::from_millis(rng.gen_range(settings.min_delay_ms..=settings.max_delay_ms));
+        fastest = Some(fastest.map_or(time, |fastest| fastest.min(time)));
+    }
+    fastest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word(word: &str, associations: &[&str]) -> GameWord {
+        GameWord {
+            word: word.to_string(),
+            difficulty: "easy".to_string(),
+            category: Some("тварини".to_string()),
+            result: None,
+            time_spent: None,
+            associations: associations.iter().map(|a| a.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_clues_start_with_associations() {
+        let clues = clues(&word("кіт", &["нявкає", "мишоловка"]));
+        assert_eq!(
+            clues,
+            vec![
+                "нявкає",
+                "мишоловка",
+                "Категорія: тварини",
+                "Літер: 3",
+                "Починається на «К»"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clues_never_name_the_word() {
+        let clues = clues(&word("кіт", &["котик", "  ", "вуса"]));
+        assert_eq!(clues[0], "вуса");
+        assert!(clues.iter().all(|c| !clue::violates(c, "кіт")));
+    }
+
+    #[test]
+    fn test_guess_time_follows_accuracy() {
+        let mut rng = rand::thread_rng();
+        let never = BotSettings {
+            accuracy: 0.0,
+            ..BotSettings::default()
+        };
+        let always = BotSettings {
+            accuracy: 1.0,
+            min_delay_ms: 100,
+            max_delay_ms: 200,
+        };
+
+        assert_eq!(guess_time(&[never], &mut rng), None);
+        assert_eq!(guess_time(&[], &mut rng), None);
+
+        let time = guess_time(&[never, always], &mut rng).unwrap();
+        assert!((100..=200).contains(&time.as_millis()));
+    }
+
+    #[test]
+    fn test_bot_ids() {
+        assert!(is_bot("bot-1"));
+        assert!(!is_bot("65f0c0ffee0000000000beef"));
+    }
+}
@@ -0,0 +1,158 @@
+use crate::error::GameError;
+use shared::models::Stroke;
+use std::time::Instant;
+
+// Coordinates are normalized to a square canvas of this size
+pub const CANVAS_SIZE: u16 = 1000;
+pub const MAX_POINTS_PER_STROKE: usize = 500;
+pub const MAX_STROKES_PER_CANVAS: usize = 1000;
+pub const MAX_STROKE_WIDTH: u8 = 50;
+
+// Point budget for the explainer: burst capacity and refill per second
+const POINT_BUDGET: f64 = 2000.0;
+const POINTS_PER_SECOND: f64 = 1000.0;
+// Undoing or clearing costs as much budget as a stroke this long
+pub const CANVAS_ACTION_COST: usize = 100;
+
+/// Validate stroke size and contents
+pub fn validate_stroke(stroke: &Stroke) -> Result<(), GameError> {
+    if stroke.points.is_empty() {
+        return Err(GameError::InvalidStroke("Stroke has no points".to_string()));
+    }
+    if stroke.points.len() > MAX_POINTS_PER_STROKE {
+        return Err(GameError::InvalidStroke(format!(
+            "Stroke is too long (max {} points)",
+            MAX_POINTS_PER_STROKE
+        )));
+    }
+    if stroke
+        .points
+        .iter()
+        .any(|[x, y]| *x > CANVAS_SIZE || *y > CANVAS_SIZE)
+    {
+        return Err(GameError::InvalidStroke(format!(
+            "Stroke points must be within 0..={}",
+            CANVAS_SIZE
+        )));
+    }
+    if stroke.width == 0 || stroke.width > MAX_STROKE_WIDTH {
+        return Err(GameError::InvalidStroke(format!(
+            "Stroke width must be between 1 and {}",
+            MAX_STROKE_WIDTH
+        )));
+    }
+    if !is_hex_color(&stroke.color) {
+        return Err(GameError::InvalidStroke(
+            "Stroke color must be #RRGGBB".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn is_hex_color(color: &str) -> bool {
+    color.len() == 7 && color.starts_with('#') && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Token bucket limiting how many stroke points and other canvas actions
+/// the explainer can send
+pub struct StrokeRateLimiter {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl StrokeRateLimiter {
+    pub fn new() -> Self {
+        Self {
+            tokens: POINT_BUDGET,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// Try to spend budget for a stroke with the given number of points
+    pub fn try_acquire(&mut self, points: usize) -> bool {
+        self.try_acquire_at(points, Instant::now())
+    }
+
+    fn try_acquire_at(&mut self, points: usize, now: Instant) -> bool {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * POINTS_PER_SECOND).min(POINT_BUDGET);
+        self.last_refill = now;
+
+        let cost = points.max(1) as f64;
+        if self.tokens >= cost {
+            self.tokens -= cost;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl Default for StrokeRateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn stroke(points: usize) -> Stroke {
+        Stroke {
+            points: vec![[10, 20]; points],
+            color: "#FF6B6B".to_string(),
+            width: 4,
+        }
+    }
+
+    #[test]
+    fn test_stroke_validation() {
+        assert!(validate_stroke(&stroke(3)).is_ok());
+        assert!(validate_stroke(&stroke(0)).is_err());
+        assert!(validate_stroke(&stroke(MAX_POINTS_PER_STROKE + 1)).is_err());
+
+        let mut out_of_bounds = stroke(1);
+        out_of_bounds.points[0] = [CANVAS_SIZE + 1, 0];
+        assert!(validate_stroke(&out_of_bounds).is_err());
+
+        let mut bad_color = stroke(1);
+        bad_color.color = "red".to_string();
+        assert!(validate_stroke(&bad_color).is_err());
+
+        let mut too_wide = stroke(1);
+        too_wide.width = MAX_STROKE_WIDTH + 1;
+        assert!(validate_stroke(&too_wide).is_err());
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let mut limiter = StrokeRateLimiter::new();
+        let start = limiter.last_refill;
+
+        // Burst budget
+        assert!(limiter.try_acquire_at(1500, start));
+        assert!(!limiter.try_acquire_at(600, start));
+        assert!(limiter.try_acquire_at(500, start));
+
+        // Budget refills over time
+        assert!(!limiter.try_acquire_at(400, start + Duration::from_millis(100)));
+        assert!(limiter.try_acquire_at(400, start + Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_canvas_actions_share_the_budget() {
+        let mut limiter = StrokeRateLimiter::new();
+        let start = limiter.last_refill;
+
+        let burst = (POINT_BUDGET as usize) / CANVAS_ACTION_COST;
+        for _ in 0..burst {
+            assert!(limiter.try_acquire_at(CANVAS_ACTION_COST, start));
+        }
+        assert!(!limiter.try_acquire_at(CANVAS_ACTION_COST, start));
+        assert!(!limiter.try_acquire_at(1, start));
+    }
+}
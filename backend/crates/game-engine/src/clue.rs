@@ -0,0 +1,214 @@
+//! Same-root detection for the text-clue mode.
+//!
+//! Clues are checked against the target word with a light Ukrainian stemmer:
+//! inflection suffixes are stripped and common consonant/vowel alternations
+//! (г/ж/з, к/ч/ц, х/ш, і/о/е) are folded, so "кіт", "кота" and "котик" share a root.
+
+// Inflectional and derivational endings, longest first
+const SUFFIXES: &[&str] = &[
+    "ського",
+    "ському",
+    "ськими",
+    "ський",
+    "цький",
+    "ення",
+    "ання",
+    "ість",
+    "ться",
+    "ями",
+    "ами",
+    "ові",
+    "еві",
+    "ого",
+    "ому",
+    "ими",
+    "іми",
+    "ний",
+    "ній",
+    "ька",
+    "ик",
+    "ок",
+    "ка",
+    "ко",
+    "ця",
+    "ці",
+    "ий",
+    "ій",
+    "ої",
+    "ою",
+    "ею",
+    "єю",
+    "ів",
+    "их",
+    "іх",
+    "ам",
+    "ям",
+    "ах",
+    "ях",
+    "ом",
+    "ем",
+    "ти",
+    "ть",
+    "ла",
+    "ло",
+    "ли",
+    "а",
+    "я",
+    "о",
+    "е",
+    "є",
+    "у",
+    "ю",
+    "і",
+    "ї",
+    "и",
+    "ь",
+    "й",
+];
+
+// Shortest stem left after stripping a suffix
+const MIN_STEM_LEN: usize = 3;
+// Shortest shared beginning that can count as a common root
+const ROOT_PREFIX_LEN: usize = 5;
+// Shorter stem length required for prefix containment
+const MIN_CONTAINED_STEM_LEN: usize = 4;
+
+/// Check whether a clue contains the target word or a cognate of it
+pub fn violates(clue: &str, target: &str) -> bool {
+    let target_tokens = tokenize(target);
+    if target_tokens.is_empty() {
+        return false;
+    }
+
+    let clue_tokens = tokenize(clue);
+
+    target_tokens.iter().any(|target_token| {
+        // The whole target hidden inside a clue word ("суперкіт")
+        if target_token.chars().count() >= MIN_STEM_LEN
+            && clue_tokens.iter().any(|t| hides(t, target_token))
+        {
+            return true;
+        }
+
+        let target_stem = fold(&stem(target_token));
+        clue_tokens
+            .iter()
+            .any(|token| shares_root(&fold(&stem(token)), &target_stem))
+    })
+}
+
+/// Split text into lowercase letter-only words
+pub(crate) fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .replace(['\'', '’', 'ʼ', '`'], "")
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|t| !t.is_empty())
+        .map(|t| t.replace('ґ', "г"))
+        .collect()
+}
+
+/// Strip the longest known ending while keeping a meaningful stem
+fn stem(word: &str) -> String {
+    let len = word.chars().count();
+    for suffix in SUFFIXES {
+        let suffix_len = suffix.chars().count();
+        if len >= suffix_len + MIN_STEM_LEN && word.ends_with(suffix) {
+            return word.chars().take(len - suffix_len).collect();
+        }
+    }
+    word.to_string()
+}
+
+/// Fold alternating letters so related forms compare equal
+fn fold(stem: &str) -> String {
+    stem.chars()
+        .map(|c| match c {
+            'ж' | 'з' => 'г',
+            'ч' | 'ц' => 'к',
+            'ш' => 'х',
+            'і' | 'е' => 'о',
+            'ї' => 'і',
+            'є' => 'е',
+            other => other,
+        })
+        .collect()
+}
+
+/// Whether a clue word is the target with something in front ("суперкіт"),
+/// or starts with it and is mostly made of it. Letters that merely happen
+/// to spell the target ("дракон" for "рак") don't count.
+fn hides(token: &str, target: &str) -> bool {
+    token.ends_with(target)
+        || (token.starts_with(target) && covers_most(target.chars().count(), token.chars().count()))
+}
+
+/// Whether `part` letters make up at least three quarters of `whole`
+fn covers_most(part: usize, whole: usize) -> bool {
+    part * 4 >= whole * 3
+}
+
+fn shares_root(a: &str, b: &str) -> bool {
+    if a == b {
+        return true;
+    }
+
+    let (shorter, longer) = if a.chars().count() <= b.chars().count() {
+        (a, b)
+    } else {
+        (b, a)
+    };
+    let (shorter_len, longer_len) = (shorter.chars().count(), longer.chars().count());
+    if shorter_len >= MIN_CONTAINED_STEM_LEN
+        && longer.starts_with(shorter)
+        && covers_most(shorter_len, longer_len)
+    {
+        return true;
+    }
+
+    // A long shared beginning, relative to the words it begins
+    let common = a.chars().zip(b.chars()).take_while(|(x, y)| x == y).count();
+    common >= ROOT_PREFIX_LEN && covers_most(common, longer_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_word_is_violation() {
+        assert!(violates("це кіт", "кіт"));
+        assert!(violates("КІТ!", "кіт"));
+        assert!(violates("суперкіт", "кіт"));
+    }
+
+    #[test]
+    fn test_inflected_forms_are_violations() {
+        assert!(violates("немає кота", "кіт"));
+        assert!(violates("маленький котик", "кіт"));
+        assert!(violates("книжка", "книга"));
+        assert!(violates("вчителька", "вчитель"));
+        assert!(violates("сонцем", "сонце"));
+    }
+
+    #[test]
+    fn test_multi_word_targets() {
+        assert!(violates("залізна дорога", "залізниця"));
+        assert!(violates("дорогами", "залізна дорога"));
+    }
+
+    #[test]
+    fn test_unrelated_clues_pass() {
+        assert!(!violates("тварина, що нявкає", "кіт"));
+        assert!(!violates("світить вдень на небі", "сонце"));
+        assert!(!violates("читаємо її", "книга"));
+        assert!(!violates("", "кіт"));
+    }
+
+    #[test]
+    fn test_words_that_merely_contain_the_target_pass() {
+        assert!(!violates("дракон", "рак"));
+        assert!(!violates("ракета", "рак"));
+        assert!(!violates("перемога", "перемир'я"));
+        assert!(!violates("універсам", "університет"));
+    }
+}
@@ -0,0 +1,136 @@
+use shared::errors::{ErrorCode, ProtocolError};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum GameError {
+    #[error("Game already started")]
+    AlreadyStarted,
+
+    #[error("Game has already ended")]
+    GameEnded,
+
+    #[error("No active round")]
+    NoActiveRound,
+
+    #[error("No word to explain")]
+    NoCurrentWord,
+
+    #[error("Word already processed")]
+    WordAlreadyProcessed,
+
+    #[error("Skipping is not allowed in this phase")]
+    SkipNotAllowed,
+
+    #[error("Invalid team index")]
+    InvalidTeamIndex,
+
+    #[error("No explainer available")]
+    NoExplainer,
+
+    #[error("{0} can't take over explaining this round")]
+    InvalidExplainer(String),
+
+    #[error("Team {0} not found")]
+    TeamNotFound(String),
+
+    #[error("Team {team} is full (max {max} players)")]
+    TeamFull { team: String, max: usize },
+
+    #[error("At least 2 teams required to start the game")]
+    NotEnoughTeams,
+
+    #[error("Team {team} needs at least {min} players (currently has {count})")]
+    NotEnoughPlayers {
+        team: String,
+        min: usize,
+        count: usize,
+    },
+
+    #[error("Teams are too unbalanced. Difference should not exceed {max_difference} players")]
+    Unbalanced {
+        largest: usize,
+        smallest: usize,
+        max_difference: usize,
+    },
+
+    #[error("Not enough words available. Found {found} but need {needed}")]
+    NotEnoughWords { found: usize, needed: usize },
+
+    /// The action belongs to another game mode ("Hat", "drawing", "text-clue")
+    #[error("Game is not in {0} mode")]
+    WrongMode(&'static str),
+
+    #[error("Only team players can submit words")]
+    NotInTeam,
+
+    #[error("Word submission is closed")]
+    SubmissionClosed,
+
+    #[error("Exactly {expected} words required (got {got})")]
+    WrongWordCount { expected: usize, got: usize },
+
+    #[error("Duplicate word: {0}")]
+    DuplicateWord(String),
+
+    #[error("No words submitted")]
+    NoWordsSubmitted,
+
+    #[error("No Hat phases configured")]
+    NoHatPhases,
+
+    #[error("Waiting for all players to submit their words")]
+    AwaitingSubmissions,
+
+    #[error("The hat is empty")]
+    HatEmpty,
+
+    #[error("{0}")]
+    InvalidClue(String),
+
+    #[error("{0}")]
+    InvalidStroke(String),
+
+    #[error("Canvas is full, clear it first")]
+    CanvasFull,
+
+    #[error("Drawing too fast, slow down")]
+    DrawingTooFast,
+
+    #[error("Nothing to undo")]
+    NothingToUndo,
+
+    #[error("Database error: {0}")]
+    Database(String),
+}
+
+impl GameError {
+    /// WebSocket error code; REST status codes are derived from it too
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            GameError::AlreadyStarted => ErrorCode::AlreadyStarted,
+            GameError::GameEnded => ErrorCode::GameEnded,
+            GameError::NoActiveRound => ErrorCode::NoActiveRound,
+            GameError::WordAlreadyProcessed => ErrorCode::WordAlreadyProcessed,
+            GameError::TeamNotFound(_) => ErrorCode::TeamNotFound,
+            GameError::TeamFull { .. } => ErrorCode::TeamFull,
+            GameError::NotEnoughTeams
+            | GameError::NotEnoughPlayers { .. }
+            | GameError::Unbalanced { .. } => ErrorCode::TeamsNotReady,
+            GameError::NotEnoughWords { .. } => ErrorCode::NotEnoughWords,
+            GameError::WrongMode(_) => ErrorCode::WrongMode,
+            GameError::NotInTeam => ErrorCode::NotInTeam,
+            GameError::Database(_) => ErrorCode::Internal,
+            _ => ErrorCode::InvalidAction,
+        }
+    }
+}
+
+impl From<GameError> for ProtocolError {
+    fn from(err: GameError) -> Self {
+        match err {
+            // Don't leak database details to clients
+            GameError::Database(_) => ProtocolError::new(ErrorCode::Internal, "Internal error"),
+            err => ProtocolError::new(err.code(), err.to_string()),
+        }
+    }
+}
@@ -1,22 +1,45 @@
+use crate::audience;
+use crate::bot;
+use crate::canvas::{self, StrokeRateLimiter, CANVAS_ACTION_COST, MAX_STROKES_PER_CANVAS};
+use crate::clue;
+use crate::error::GameError;
+use crate::hat::{self, HatPool};
 use crate::team::TeamManager;
 use chrono::Utc;
 use futures_util::TryStreamExt;
 use mongodb::{bson::doc, Client, Collection};
 use rand::seq::SliceRandom;
-use shared::models::{GameSettings, GameState, GameWord, Round, Team, WordResult};
+use shared::models::{
+    AudienceTeam, Clue, GameMode, GameSettings, GameState, GameWord, HatPhase, Round, Stroke, Team,
+    WordResult,
+};
 use tracing::info;
 
+// Longest clue accepted in text-clue mode
+const MAX_CLUE_LENGTH: usize = 200;
+
 pub struct GameEngine {
     pub game_state: GameState,
     pub team_manager: TeamManager,
     word_collection: Collection<mongodb::bson::Document>,
     timer_handle: Option<tokio::task::JoinHandle<()>>,
+    hat_pool: Option<HatPool>, // Only set in Hat mode
+    stroke_limiter: StrokeRateLimiter,
+    audience_scored_word: Option<usize>, // Word the audience already scored this round
+    paused: bool,
+    paused_for: Option<String>, // Absent explainer the game paused itself for
 }
 
 impl GameEngine {
     pub async fn new(mongo_client: &Client, settings: Option<GameSettings>) -> Self {
         let db = mongo_client.database("alias_game");
         let word_collection = db.collection("words");
+        let settings = settings.unwrap_or_default();
+        let hat_pool = (settings.mode == GameMode::Hat).then(|| HatPool::new(&settings.hat));
+        let audience = settings
+            .twitch_chat
+            .as_ref()
+            .map(|_| AudienceTeam::default());
 
         Self {
             game_state: GameState {
@@ -26,19 +49,26 @@ impl GameEngine {
                 current_team_index: 0,
                 current_word_index: 0,
                 used_words: Vec::new(),
-                settings: settings.unwrap_or_default(),
+                settings,
                 winner_team_id: None,
                 started_at: None,
                 ended_at: None,
+                hat: hat_pool.as_ref().map(|hat| hat.progress()),
+                audience,
             },
             team_manager: TeamManager::new(),
             word_collection,
             timer_handle: None,
+            hat_pool,
+            stroke_limiter: StrokeRateLimiter::new(),
+            audience_scored_word: None,
+            paused: false,
+            paused_for: None,
         }
     }
 
     /// Initialize game with teams
-    pub fn initialize_teams(&mut self) -> Result<(), String> {
+    pub fn initialize_teams(&mut self) -> Result<(), GameError> {
         // Validate teams are ready
         self.team_manager.validate_for_game_start()?;
 
@@ -49,9 +79,9 @@ impl GameEngine {
     }
 
     /// Start the game
-    pub async fn start_game(&mut self) -> Result<(), String> {
+    pub async fn start_game(&mut self) -> Result<(), GameError> {
         if self.game_state.started_at.is_some() {
-            return Err("Game already started".to_string());
+            return Err(GameError::AlreadyStarted);
         }
 
         self.initialize_teams()?;
@@ -65,49 +95,46 @@ impl GameEngine {
     }
 
     /// Start a new round
-    pub async fn start_round(&mut self) -> Result<Round, String> {
+    pub async fn start_round(&mut self) -> Result<Round, GameError> {
         if self.game_state.winner_team_id.is_some() {
-            return Err("Game has already ended".to_string());
+            return Err(GameError::GameEnded);
         }
 
         let current_team = self
             .game_state
             .teams
             .get(self.game_state.current_team_index)
-            .ok_or("Invalid team index")?
+            .ok_or(GameError::InvalidTeamIndex)?
             .clone();
-
-        // Get the explainer for this round
-        let previous_explainer = self
-            .game_state
-            .round_history
-            .iter()
-            .rev()
-            .find(|r| r.team_id == current_team.id)
-            .map(|r| r.explainer_id.as_str());
-
-        let explainer_id = self
-            .team_manager
-            .get_next_explainer(&current_team.id, previous_explainer)
-            .ok_or("No explainer available")?;
+        let explainer_id = self.next_explainer()?;
 
         // Fetch words for this round
-        let words = self.fetch_words_for_round().await?;
+        let words = match self.game_state.settings.mode {
+            GameMode::Classic | GameMode::TextClue | GameMode::Drawing => {
+                self.fetch_words_for_round().await?
+            }
+            GameMode::Hat => self.draw_hat_words()?,
+        };
+        let round_duration = self.round_duration();
 
         let round = Round {
             round_number: (self.game_state.round_history.len() + 1) as u32,
             team_id: current_team.id.clone(),
             explainer_id,
             words,
-            timer_seconds: self.game_state.settings.round_duration_seconds,
-            time_remaining: self.game_state.settings.round_duration_seconds,
+            timer_seconds: round_duration,
+            time_remaining: round_duration,
             score_gained: 0,
             started_at: Some(Utc::now()),
             ended_at: None,
+            clues: Vec::new(),
+            canvas: Vec::new(),
         };
 
         self.game_state.current_round = Some(round.clone());
         self.game_state.current_word_index = 0;
+        self.stroke_limiter = StrokeRateLimiter::new();
+        self.audience_scored_word = None;
 
         info!(
             "Round {} started for team {}",
@@ -117,8 +144,29 @@ impl GameEngine {
         Ok(round)
     }
 
+    /// Who explains in the next round: the current team's players take turns
+    pub fn next_explainer(&self) -> Result<String, GameError> {
+        let current_team = self
+            .game_state
+            .teams
+            .get(self.game_state.current_team_index)
+            .ok_or(GameError::InvalidTeamIndex)?;
+
+        let previous_explainer = self
+            .game_state
+            .round_history
+            .iter()
+            .rev()
+            .find(|r| r.team_id == current_team.id)
+            .map(|r| r.explainer_id.as_str());
+
+        self.team_manager
+            .get_next_explainer(&current_team.id, previous_explainer)
+            .ok_or(GameError::NoExplainer)
+    }
+
     /// Fetch words for a round from the database
-    async fn fetch_words_for_round(&mut self) -> Result<Vec<GameWord>, String> {
+    async fn fetch_words_for_round(&mut self) -> Result<Vec<GameWord>, GameError> {
         let difficulty = &self.game_state.settings.difficulty;
         let word_count = self.game_state.settings.words_per_round as usize;
 
@@ -141,19 +189,18 @@ impl GameEngine {
             .word_collection
             .find(query, None)
             .await
-            .map_err(|e| format!("Failed to fetch words: {}", e))?;
+            .map_err(|e| GameError::Database(format!("Failed to fetch words: {}", e)))?;
 
         let words: Vec<mongodb::bson::Document> = cursor
             .try_collect()
             .await
-            .map_err(|e| format!("Failed to collect words: {}", e))?;
+            .map_err(|e| GameError::Database(format!("Failed to collect words: {}", e)))?;
 
         if words.len() < word_count {
-            return Err(format!(
-                "Not enough words available. Found {} but need {}",
-                words.len(),
-                word_count
-            ));
+            return Err(GameError::NotEnoughWords {
+                found: words.len(),
+                needed: word_count,
+            });
         }
 
         // Randomly select words
@@ -163,6 +210,15 @@ impl GameEngine {
                 let word = doc.get_str("word").unwrap_or("").to_string();
                 let difficulty = doc.get_str("difficulty").unwrap_or("medium").to_string();
                 let category = doc.get_str("category").ok().map(|s| s.to_string());
+                let associations = doc
+                    .get_array("associations")
+                    .map(|words| {
+                        words
+                            .iter()
+                            .filter_map(|w| w.as_str().map(str::to_string))
+                            .collect()
+                    })
+                    .unwrap_or_default();
 
                 // Add to used words
                 self.game_state.used_words.push(word.clone());
@@ -173,6 +229,7 @@ impl GameEngine {
                     category,
                     result: None,
                     time_spent: None,
+                    associations,
                 }
             })
             .collect();
@@ -180,13 +237,106 @@ impl GameEngine {
         Ok(selected_words)
     }
 
+    /// Draw round words from the Hat pool
+    fn draw_hat_words(&mut self) -> Result<Vec<GameWord>, GameError> {
+        let word_count = self.game_state.settings.words_per_round as usize;
+        let hat = self.hat_pool.as_mut().ok_or(GameError::WrongMode("Hat"))?;
+
+        if hat.phase() == HatPhase::Submission {
+            return Err(GameError::AwaitingSubmissions);
+        }
+
+        let words = hat
+            .draw(word_count)
+            .into_iter()
+            .map(|word| GameWord {
+                word,
+                difficulty: "custom".to_string(),
+                category: None,
+                result: None,
+                time_spent: None,
+                associations: Vec::new(),
+            })
+            .collect::<Vec<_>>();
+
+        if words.is_empty() {
+            return Err(GameError::HatEmpty);
+        }
+
+        self.sync_hat_progress();
+        Ok(words)
+    }
+
+    /// Round duration for the current mode and phase
+    fn round_duration(&self) -> u32 {
+        self.hat_pool
+            .as_ref()
+            .and_then(|hat| hat.phase_rules())
+            .map(|rules| rules.round_duration_seconds)
+            .unwrap_or(self.game_state.settings.round_duration_seconds)
+    }
+
+    /// Secretly submit a player's words for the Hat mode.
+    /// Returns true when this was the last missing submission and the first phase began.
+    pub fn submit_hat_words(
+        &mut self,
+        user_id: &str,
+        words: Vec<String>,
+    ) -> Result<bool, GameError> {
+        let players: Vec<String> = self
+            .team_manager
+            .get_teams()
+            .iter()
+            .flat_map(|t| t.players.iter().cloned())
+            .collect();
+
+        if !players.iter().any(|p| p == user_id) {
+            return Err(GameError::NotInTeam);
+        }
+
+        let hat = self.hat_pool.as_mut().ok_or(GameError::WrongMode("Hat"))?;
+        hat.submit_words(user_id, words)?;
+
+        // Bots have no words of their own to put in the hat
+        let humans: Vec<String> = players.into_iter().filter(|p| !bot::is_bot(p)).collect();
+        let began = if hat.all_submitted(&humans) {
+            hat.begin()?;
+            info!("All words submitted, Hat phase {:?} began", hat.phase());
+            true
+        } else {
+            false
+        };
+
+        self.sync_hat_progress();
+        Ok(began)
+    }
+
+    /// Current Hat phase (None outside of Hat mode)
+    pub fn hat_phase(&self) -> Option<HatPhase> {
+        self.hat_pool.as_ref().map(|hat| hat.phase())
+    }
+
+    fn sync_hat_progress(&mut self) {
+        self.game_state.hat = self.hat_pool.as_ref().map(|hat| hat.progress());
+    }
+
     /// Process word result (correct, skip, penalty)
-    pub fn process_word_result(&mut self, result: WordResult) -> Result<i32, String> {
+    pub fn process_word_result(&mut self, result: WordResult) -> Result<i32, GameError> {
+        if result == WordResult::Skipped
+            && self
+                .hat_pool
+                .as_ref()
+                .and_then(|hat| hat.phase_rules())
+                .is_some_and(|rules| !rules.allow_skip)
+        {
+            return Err(GameError::SkipNotAllowed);
+        }
+
         let round = self
             .game_state
             .current_round
             .as_mut()
-            .ok_or("No active round")?;
+            .ok_or(GameError::NoActiveRound)?;
 
         // Check if word already processed
         if round
@@ -195,7 +345,7 @@ impl GameEngine {
             .and_then(|w| w.result.as_ref())
             .is_some()
         {
-            return Err("Word already processed".to_string());
+            return Err(GameError::WordAlreadyProcessed);
         }
 
         // Calculate score change
@@ -216,6 +366,7 @@ impl GameEngine {
                 }
             }
             WordResult::Penalty => -1,
+            WordResult::Stolen => 0, // The audience scores instead
         };
 
         // Update word result
@@ -228,6 +379,9 @@ impl GameEngine {
         // Update round score
         round.score_gained += score_change;
 
+        // Each word gets a fresh canvas
+        round.canvas.clear();
+
         // Update team score
         if let Some(team) = self
             .game_state
@@ -249,6 +403,171 @@ impl GameEngine {
         Ok(score_change)
     }
 
+    /// Submit a typed clue for the current word (text-clue mode).
+    /// Clues containing the word or a cognate are recorded as a penalty.
+    pub fn submit_clue(&mut self, text: &str) -> Result<ClueOutcome, GameError> {
+        if self.game_state.settings.mode != GameMode::TextClue {
+            return Err(GameError::WrongMode("text-clue"));
+        }
+
+        let text = text.trim();
+        if text.is_empty() {
+            return Err(GameError::InvalidClue("Clue is empty".to_string()));
+        }
+        if text.chars().count() > MAX_CLUE_LENGTH {
+            return Err(GameError::InvalidClue(format!(
+                "Clue is too long (max {} characters)",
+                MAX_CLUE_LENGTH
+            )));
+        }
+
+        let word = self
+            .get_current_word()
+            .ok_or(GameError::NoCurrentWord)?
+            .word
+            .clone();
+
+        if clue::violates(text, &word) {
+            let score_change = self.process_word_result(WordResult::Penalty)?;
+            info!("Clue rejected: contains the word or a cognate");
+            return Ok(ClueOutcome::Rejected { score_change });
+        }
+
+        self.push_clue(text).map(ClueOutcome::Accepted)
+    }
+
+    /// Record a bot explainer's clue. Bots explain this way in every mode,
+    /// and their canned clues never name the word.
+    pub fn add_bot_clue(&mut self, text: &str) -> Result<Clue, GameError> {
+        if self.get_current_word().is_none() {
+            return Err(GameError::NoCurrentWord);
+        }
+        self.push_clue(text)
+    }
+
+    fn push_clue(&mut self, text: &str) -> Result<Clue, GameError> {
+        let clue = Clue {
+            text: text.to_string(),
+            word_index: self.game_state.current_word_index,
+            sent_at: Utc::now(),
+        };
+
+        let round = self
+            .game_state
+            .current_round
+            .as_mut()
+            .ok_or(GameError::NoActiveRound)?;
+        round.clues.push(clue.clone());
+
+        Ok(clue)
+    }
+
+    /// Add a stroke to the canvas (drawing mode)
+    pub fn draw_stroke(&mut self, stroke: Stroke) -> Result<Stroke, GameError> {
+        let round = self.active_drawing_round()?;
+        if round.canvas.len() >= MAX_STROKES_PER_CANVAS {
+            return Err(GameError::CanvasFull);
+        }
+
+        canvas::validate_stroke(&stroke)?;
+        self.spend_drawing_budget(stroke.points.len())?;
+
+        self.active_drawing_round()?.canvas.push(stroke.clone());
+        Ok(stroke)
+    }
+
+    /// Remove the last stroke from the canvas (drawing mode)
+    pub fn undo_stroke(&mut self) -> Result<(), GameError> {
+        self.active_drawing_round()?;
+        self.spend_drawing_budget(CANVAS_ACTION_COST)?;
+
+        self.active_drawing_round()?
+            .canvas
+            .pop()
+            .map(|_| ())
+            .ok_or(GameError::NothingToUndo)
+    }
+
+    /// Clear the canvas (drawing mode)
+    pub fn clear_canvas(&mut self) -> Result<(), GameError> {
+        self.active_drawing_round()?;
+        self.spend_drawing_budget(CANVAS_ACTION_COST)?;
+
+        self.active_drawing_round()?.canvas.clear();
+        Ok(())
+    }
+
+    // Every canvas action is broadcast to the room, so all of them count
+    fn spend_drawing_budget(&mut self, cost: usize) -> Result<(), GameError> {
+        if self.stroke_limiter.try_acquire(cost) {
+            Ok(())
+        } else {
+            Err(GameError::DrawingTooFast)
+        }
+    }
+
+    /// Strokes of the current canvas, for late joiners to redraw
+    pub fn canvas(&self) -> &[Stroke] {
+        self.game_state
+            .current_round
+            .as_ref()
+            .map(|round| round.canvas.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn active_drawing_round(&mut self) -> Result<&mut Round, GameError> {
+        if self.game_state.settings.mode != GameMode::Drawing {
+            return Err(GameError::WrongMode("drawing"));
+        }
+
+        self.game_state
+            .current_round
+            .as_mut()
+            .ok_or(GameError::NoActiveRound)
+    }
+
+    /// Check a Twitch chat message against the current word.
+    /// The audience scores a point per word; with stealing enabled the word
+    /// is also taken from the playing team.
+    pub fn record_audience_guess(&mut self, viewer: &str, message: &str) -> Option<AudienceGuess> {
+        let steal = self.game_state.settings.twitch_chat.as_ref()?.steal_words;
+        let index = self.game_state.current_word_index;
+
+        let word = self.get_current_word()?;
+        if word.result.is_some() || !audience::is_guess(message, &word.word) {
+            return None;
+        }
+        let word = word.word.clone();
+
+        if self.audience_scored_word == Some(index) {
+            return None;
+        }
+
+        if steal {
+            self.process_word_result(WordResult::Stolen).ok()?;
+        } else {
+            self.audience_scored_word = Some(index);
+        }
+
+        let audience = self
+            .game_state
+            .audience
+            .get_or_insert_with(AudienceTeam::default);
+        audience.score += 1;
+        *audience.guesses.entry(viewer.to_string()).or_insert(0) += 1;
+
+        info!(
+            "Audience guessed the word (viewer {}, stolen: {})",
+            viewer, steal
+        );
+
+        Some(AudienceGuess {
+            word,
+            stolen: steal,
+            audience_score: audience.score,
+        })
+    }
+
     /// Get current word for explainer
     pub fn get_current_word(&self) -> Option<&GameWord> {
         self.game_state
@@ -257,15 +576,26 @@ impl GameEngine {
             .and_then(|round| round.words.get(self.game_state.current_word_index))
     }
 
+    /// Get the word that was just played
+    pub fn get_previous_word(&self) -> Option<&GameWord> {
+        let index = self.game_state.current_word_index.checked_sub(1)?;
+        self.game_state
+            .current_round
+            .as_ref()
+            .and_then(|round| round.words.get(index))
+    }
+
     /// End the current round
-    pub fn end_round(&mut self) -> Result<Round, String> {
+    pub fn end_round(&mut self) -> Result<Round, GameError> {
         let mut round = self
             .game_state
             .current_round
             .take()
-            .ok_or("No active round")?;
+            .ok_or(GameError::NoActiveRound)?;
 
         round.ended_at = Some(Utc::now());
+        self.paused = false;
+        self.paused_for = None;
 
         // Calculate final score for the round
         let correct_count = round
@@ -287,11 +617,54 @@ impl GameEngine {
             (self.game_state.current_team_index + 1) % self.game_state.teams.len();
 
         // Check for winner
-        self.check_for_winner();
+        if self.hat_pool.is_some() {
+            self.settle_hat_round(&round);
+        } else {
+            self.check_for_winner();
+        }
 
         Ok(round)
     }
 
+    /// Return unguessed words to the hat and move through the phases
+    fn settle_hat_round(&mut self, round: &Round) {
+        let Some(hat) = self.hat_pool.as_mut() else {
+            return;
+        };
+
+        let unguessed: Vec<String> = round
+            .words
+            .iter()
+            .filter(|w| !w.result.is_some_and(WordResult::is_guessed))
+            .map(|w| w.word.clone())
+            .collect();
+        hat.return_words(unguessed);
+
+        match hat.advance_phase() {
+            Some(phase) => info!(
+                "Hat phase: {:?}, {} words left",
+                phase,
+                hat.words_remaining()
+            ),
+            None => {
+                // Every phase played through - highest score wins
+                if let Some(team) =
+                    hat::hat_winner(&self.game_state.teams, &self.game_state.round_history)
+                {
+                    self.game_state.winner_team_id = Some(team.id.clone());
+                    self.game_state.ended_at = Some(Utc::now());
+
+                    info!(
+                        "Hat game ended! Winner: {} with {} points",
+                        team.name, team.score
+                    );
+                }
+            }
+        }
+
+        self.sync_hat_progress();
+    }
+
     /// Check if any team has reached the winning score
     fn check_for_winner(&mut self) {
         let winning_team = self
@@ -312,12 +685,12 @@ impl GameEngine {
     }
 
     /// Update timer for current round
-    pub fn update_timer(&mut self, time_remaining: u32) -> Result<(), String> {
+    pub fn update_timer(&mut self, time_remaining: u32) -> Result<(), GameError> {
         let round = self
             .game_state
             .current_round
             .as_mut()
-            .ok_or("No active round")?;
+            .ok_or(GameError::NoActiveRound)?;
 
         round.time_remaining = time_remaining;
 
@@ -329,10 +702,28 @@ impl GameEngine {
         Ok(())
     }
 
+    /// Correct a team's score by hand, e.g. after a misclick
+    pub fn adjust_score(&mut self, team_id: &str, delta: i32) -> Result<i32, GameError> {
+        if self.game_state.ended_at.is_some() {
+            return Err(GameError::GameEnded);
+        }
+
+        let team = self
+            .game_state
+            .teams
+            .iter_mut()
+            .find(|t| t.id == team_id)
+            .ok_or_else(|| GameError::TeamNotFound(team_id.to_string()))?;
+        team.score += delta;
+
+        info!("Score of team {} adjusted by {}", team_id, delta);
+        Ok(team.score)
+    }
+
     /// Pause the game
-    pub fn pause_game(&mut self) -> Result<(), String> {
+    pub fn pause_game(&mut self) -> Result<(), GameError> {
         if self.game_state.current_round.is_none() {
-            return Err("No active round to pause".to_string());
+            return Err(GameError::NoActiveRound);
         }
 
         // Cancel timer if running
@@ -340,20 +731,64 @@ impl GameEngine {
             handle.abort();
         }
 
+        self.paused = true;
         info!("Game paused");
         Ok(())
     }
 
+    /// Pause until the explainer, who went AFK or disconnected, is back
+    pub fn pause_for_absent_explainer(&mut self, explainer_id: &str) -> Result<(), GameError> {
+        self.pause_game()?;
+        self.paused_for = Some(explainer_id.to_string());
+        Ok(())
+    }
+
     /// Resume the game
-    pub fn resume_game(&mut self) -> Result<(), String> {
+    pub fn resume_game(&mut self) -> Result<(), GameError> {
         if self.game_state.current_round.is_none() {
-            return Err("No active round to resume".to_string());
+            return Err(GameError::NoActiveRound);
         }
 
+        self.paused = false;
+        self.paused_for = None;
         info!("Game resumed");
         Ok(())
     }
 
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// The absent explainer the game is waiting for, if it paused itself
+    pub fn paused_for(&self) -> Option<&str> {
+        self.paused_for.as_deref()
+    }
+
+    /// Let a teammate take over explaining the current round, e.g. when the
+    /// explainer has gone. The round carries on with the same words and time.
+    pub fn hand_off_round(&mut self, explainer_id: &str) -> Result<Round, GameError> {
+        let round = self
+            .game_state
+            .current_round
+            .as_mut()
+            .ok_or(GameError::NoActiveRound)?;
+
+        let in_team = self
+            .team_manager
+            .get_team(&round.team_id)
+            .is_some_and(|t| t.players.iter().any(|p| p == explainer_id));
+        if !in_team || round.explainer_id == explainer_id {
+            return Err(GameError::InvalidExplainer(explainer_id.to_string()));
+        }
+
+        info!(
+            "Round {}: {} takes over explaining from {}",
+            round.round_number, explainer_id, round.explainer_id
+        );
+        round.explainer_id = explainer_id.to_string();
+        Ok(round.clone())
+    }
+
     /// Get game statistics
     pub fn get_statistics(&self) -> GameStatistics {
         let total_rounds = self.game_state.round_history.len();
@@ -412,7 +847,20 @@ impl GameEngine {
             winner_team_id: None,
             started_at: None,
             ended_at: None,
+            hat: None,
+            audience: self
+                .game_state
+                .settings
+                .twitch_chat
+                .as_ref()
+                .map(|_| AudienceTeam::default()),
         };
+        self.audience_scored_word = None;
+
+        if self.hat_pool.is_some() {
+            self.hat_pool = Some(HatPool::new(&self.game_state.settings.hat));
+            self.sync_hat_progress();
+        }
 
         self.team_manager.reset_scores();
 
@@ -424,6 +872,19 @@ impl GameEngine {
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum ClueOutcome {
+    Accepted(Clue),
+    Rejected { score_change: i32 }, // Recorded as WordResult::Penalty
+}
+
+#[derive(Debug, Clone)]
+pub struct AudienceGuess {
+    pub word: String,
+    pub stolen: bool, // Word was taken from the playing team
+    pub audience_score: i32,
+}
+
 #[derive(Debug, Clone)]
 pub struct GameStatistics {
     pub total_rounds: u32,
@@ -0,0 +1,318 @@
+use crate::error::GameError;
+use rand::seq::SliceRandom;
+use shared::models::{HatPhase, HatPhaseRules, HatProgress, HatSettings, Round, Team};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+/// Word pool for the Hat ("Шляпа") mode.
+///
+/// Every player secretly submits a fixed number of words. Once submissions
+/// are closed the pool is played through once per phase, and refilled with
+/// all submitted words whenever a phase is exhausted.
+pub struct HatPool {
+    words_per_player: usize,
+    phases: Vec<HatPhaseRules>,
+    submissions: HashMap<String, Vec<String>>,
+    phase_index: Option<usize>, // None while submissions are open
+    pool: Vec<String>,
+    finished: bool,
+}
+
+impl HatPool {
+    pub fn new(settings: &HatSettings) -> Self {
+        Self {
+            words_per_player: settings.words_per_player as usize,
+            phases: settings.phases.clone(),
+            submissions: HashMap::new(),
+            phase_index: None,
+            pool: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Store a player's secret words, replacing any earlier submission
+    pub fn submit_words(&mut self, user_id: &str, words: Vec<String>) -> Result<(), GameError> {
+        if self.phase_index.is_some() {
+            return Err(GameError::SubmissionClosed);
+        }
+
+        let words: Vec<String> = words
+            .into_iter()
+            .map(|w| w.trim().to_string())
+            .filter(|w| !w.is_empty())
+            .collect();
+
+        if words.len() != self.words_per_player {
+            return Err(GameError::WrongWordCount {
+                expected: self.words_per_player,
+                got: words.len(),
+            });
+        }
+
+        // Reject duplicates within a single submission
+        let mut seen: Vec<String> = Vec::with_capacity(words.len());
+        for word in &words {
+            let normalized = word.to_lowercase();
+            if seen.contains(&normalized) {
+                return Err(GameError::DuplicateWord(word.clone()));
+            }
+            seen.push(normalized);
+        }
+
+        self.submissions.insert(user_id.to_string(), words);
+        Ok(())
+    }
+
+    /// Check whether a player has already submitted words
+    pub fn has_submitted(&self, user_id: &str) -> bool {
+        self.submissions.contains_key(user_id)
+    }
+
+    /// Check whether every listed player has submitted words
+    pub fn all_submitted(&self, players: &[String]) -> bool {
+        !players.is_empty() && players.iter().all(|p| self.has_submitted(p))
+    }
+
+    /// Close submissions and fill the pool for the first phase
+    pub fn begin(&mut self) -> Result<(), GameError> {
+        if self.phase_index.is_some() {
+            return Err(GameError::AlreadyStarted);
+        }
+        if self.submissions.is_empty() {
+            return Err(GameError::NoWordsSubmitted);
+        }
+        if self.phases.is_empty() {
+            return Err(GameError::NoHatPhases);
+        }
+
+        self.phase_index = Some(0);
+        self.refill();
+        Ok(())
+    }
+
+    /// Current phase
+    pub fn phase(&self) -> HatPhase {
+        match self.phase_index {
+            Some(index) => self.phases[index].phase,
+            None => HatPhase::Submission,
+        }
+    }
+
+    /// Rules of the current phase (None during submission)
+    pub fn phase_rules(&self) -> Option<&HatPhaseRules> {
+        self.phase_index.map(|index| &self.phases[index])
+    }
+
+    /// Take up to `count` random words out of the pool
+    pub fn draw(&mut self, count: usize) -> Vec<String> {
+        let take = count.min(self.pool.len());
+        self.pool.split_off(self.pool.len() - take)
+    }
+
+    /// Put words that were not guessed back into the pool
+    pub fn return_words(&mut self, words: Vec<String>) {
+        self.pool.extend(words);
+        self.pool.shuffle(&mut rand::thread_rng());
+    }
+
+    /// Move to the next phase once the pool is empty.
+    /// Returns the new phase, or None when the last phase is done.
+    pub fn advance_phase(&mut self) -> Option<HatPhase> {
+        let index = self.phase_index?;
+        if !self.pool.is_empty() {
+            return Some(self.phase());
+        }
+
+        if index + 1 >= self.phases.len() {
+            self.finished = true;
+            return None;
+        }
+
+        self.phase_index = Some(index + 1);
+        self.refill();
+        Some(self.phase())
+    }
+
+    /// Check whether all phases have been played through
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Words left in the pool for the current phase
+    pub fn words_remaining(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// Public progress snapshot (never includes the words themselves)
+    pub fn progress(&self) -> HatProgress {
+        let mut submitted_players: Vec<String> = self.submissions.keys().cloned().collect();
+        submitted_players.sort();
+
+        HatProgress {
+            phase: self.phase(),
+            words_remaining: self.pool.len(),
+            total_words: self.submissions.values().map(|w| w.len()).sum(),
+            submitted_players,
+        }
+    }
+
+    fn refill(&mut self) {
+        self.pool = self.submissions.values().flatten().cloned().collect();
+        self.pool.shuffle(&mut rand::thread_rng());
+    }
+}
+
+/// Winner once every phase is played through: the highest score. On a tie
+/// the team that had fewer turns wins, then the one earlier in turn order.
+pub fn hat_winner<'a>(teams: &'a [Team], rounds: &[Round]) -> Option<&'a Team> {
+    let turns = |team: &Team| rounds.iter().filter(|r| r.team_id == team.id).count();
+    teams
+        .iter()
+        .enumerate()
+        .max_by_key(|(index, team)| (team.score, Reverse(turns(team)), Reverse(*index)))
+        .map(|(_, team)| team)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|w| w.to_string()).collect()
+    }
+
+    fn pool_with_two_players() -> HatPool {
+        let settings = HatSettings {
+            words_per_player: 2,
+            ..HatSettings::default()
+        };
+        let mut hat = HatPool::new(&settings);
+        hat.submit_words("user1", words(&["кіт", "сонце"])).unwrap();
+        hat.submit_words("user2", words(&["море", "ліс"])).unwrap();
+        hat
+    }
+
+    #[test]
+    fn test_submission_validation() {
+        let settings = HatSettings {
+            words_per_player: 2,
+            ..HatSettings::default()
+        };
+        let mut hat = HatPool::new(&settings);
+
+        // Wrong count
+        assert!(hat.submit_words("user1", words(&["кіт"])).is_err());
+        // Blank words don't count
+        assert!(hat.submit_words("user1", words(&["кіт", "  "])).is_err());
+        // Duplicates
+        assert!(hat.submit_words("user1", words(&["кіт", "Кіт"])).is_err());
+
+        assert!(hat.submit_words("user1", words(&["кіт", "пес"])).is_ok());
+        assert!(hat.has_submitted("user1"));
+        assert!(!hat.all_submitted(&words(&["user1", "user2"])));
+    }
+
+    #[test]
+    fn test_progress_hides_words() {
+        let hat = pool_with_two_players();
+        let progress = hat.progress();
+
+        assert_eq!(progress.phase, HatPhase::Submission);
+        assert_eq!(progress.total_words, 4);
+        assert_eq!(progress.submitted_players, words(&["user1", "user2"]));
+    }
+
+    #[test]
+    fn test_draw_and_return() {
+        let mut hat = pool_with_two_players();
+        hat.begin().unwrap();
+        assert_eq!(hat.phase(), HatPhase::Describe);
+        assert_eq!(hat.words_remaining(), 4);
+
+        // Submissions are closed once the game begins
+        assert!(hat.submit_words("user3", words(&["дім", "сад"])).is_err());
+
+        let drawn = hat.draw(3);
+        assert_eq!(drawn.len(), 3);
+        assert_eq!(hat.words_remaining(), 1);
+
+        hat.return_words(drawn[..1].to_vec());
+        assert_eq!(hat.words_remaining(), 2);
+
+        // Drawing more than available returns the rest
+        assert_eq!(hat.draw(10).len(), 2);
+    }
+
+    #[test]
+    fn test_phases_cycle_until_finished() {
+        let mut hat = pool_with_two_players();
+        hat.begin().unwrap();
+
+        // Pool not empty yet - phase stays
+        assert_eq!(hat.advance_phase(), Some(HatPhase::Describe));
+
+        hat.draw(4);
+        assert_eq!(hat.advance_phase(), Some(HatPhase::OneWord));
+        assert_eq!(hat.words_remaining(), 4);
+
+        hat.draw(4);
+        assert_eq!(hat.advance_phase(), Some(HatPhase::Gestures));
+        assert!(!hat.phase_rules().unwrap().allow_skip);
+
+        hat.draw(4);
+        assert_eq!(hat.advance_phase(), None);
+        assert!(hat.is_finished());
+    }
+
+    fn team(id: &str, score: i32) -> Team {
+        Team {
+            id: id.to_string(),
+            name: id.to_string(),
+            color: "#000000".to_string(),
+            players: Vec::new(),
+            score,
+            is_ready: true,
+        }
+    }
+
+    fn turns(team_ids: &[&str]) -> Vec<Round> {
+        team_ids
+            .iter()
+            .enumerate()
+            .map(|(index, team_id)| Round {
+                round_number: index as u32 + 1,
+                team_id: team_id.to_string(),
+                explainer_id: "user1".to_string(),
+                words: Vec::new(),
+                timer_seconds: 60,
+                time_remaining: 0,
+                score_gained: 0,
+                started_at: None,
+                ended_at: None,
+                clues: Vec::new(),
+                canvas: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hat_winner_breaks_ties() {
+        let rounds = turns(&["team_a", "team_b", "team_a"]);
+
+        // Highest score wins outright
+        let teams = vec![team("team_a", 5), team("team_b", 7)];
+        assert_eq!(hat_winner(&teams, &rounds).unwrap().id, "team_b");
+
+        // On a tie, fewer turns wins
+        let teams = vec![team("team_a", 6), team("team_b", 6)];
+        assert_eq!(hat_winner(&teams, &rounds).unwrap().id, "team_b");
+
+        // Same turns too: the team earlier in turn order
+        let rounds = turns(&["team_a", "team_b"]);
+        assert_eq!(hat_winner(&teams, &rounds).unwrap().id, "team_a");
+        let teams = vec![team("team_b", 6), team("team_a", 6)];
+        assert_eq!(hat_winner(&teams, &rounds).unwrap().id, "team_b");
+
+        assert!(hat_winner(&[], &rounds).is_none());
+    }
+}
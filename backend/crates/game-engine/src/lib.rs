@@ -1,3 +1,9 @@
+pub mod audience;
+pub mod bot;
+pub mod canvas;
+pub mod clue;
+pub mod error;
 pub mod game;
+pub mod hat;
 pub mod scoring;
 pub mod team;
@@ -42,6 +42,7 @@ impl ScoringSystem {
                 }
             }
             WordResult::Penalty => self.penalty_per_violation,
+            WordResult::Stolen => 0, // The audience took the word
         }
     }
 
@@ -71,6 +72,7 @@ impl ScoringSystem {
                         penalty_count += 1;
                         base_score += self.penalty_per_violation;
                     }
+                    WordResult::Stolen => {}
                 }
             }
         }
@@ -113,7 +115,7 @@ impl ScoringSystem {
             .collect();
 
         // Sort by score (descending)
-        rankings.sort_by(|a, b| b.score.cmp(&a.score));
+        rankings.sort_by_key(|r| std::cmp::Reverse(r.score));
 
         // Assign ranks
         let mut current_rank = 1;
@@ -157,6 +159,7 @@ impl ScoringSystem {
                         WordResult::Correct => stats.total_correct += 1,
                         WordResult::Skipped => stats.total_skipped += 1,
                         WordResult::Penalty => stats.total_penalties += 1,
+                        WordResult::Stolen => {}
                     }
                 }
             }
@@ -309,6 +312,7 @@ mod tests {
                     category: None,
                     result: Some(WordResult::Correct),
                     time_spent: Some(5),
+                    associations: Vec::new(),
                 },
                 GameWord {
                     word: "test2".to_string(),
@@ -316,6 +320,7 @@ mod tests {
                     category: None,
                     result: Some(WordResult::Correct),
                     time_spent: Some(5),
+                    associations: Vec::new(),
                 },
                 GameWord {
                     word: "test3".to_string(),
@@ -323,6 +328,7 @@ mod tests {
                     category: None,
                     result: Some(WordResult::Skipped),
                     time_spent: Some(3),
+                    associations: Vec::new(),
                 },
             ],
             timer_seconds: 60,
@@ -330,6 +336,8 @@ mod tests {
             score_gained: 2,
             started_at: Some(Utc::now()),
             ended_at: Some(Utc::now()),
+            clues: vec![],
+            canvas: vec![],
         };
 
         let score = scoring.calculate_round_score(&round);
@@ -1,3 +1,4 @@
+use crate::error::GameError;
 use shared::models::{RoomParticipant, Team};
 use std::collections::HashMap;
 
@@ -37,7 +38,7 @@ impl TeamManager {
     }
 
     /// Add a player to a specific team
-    pub fn add_player_to_team(&mut self, user_id: String, team_id: &str) -> Result<(), String> {
+    pub fn add_player_to_team(&mut self, user_id: String, team_id: &str) -> Result<(), GameError> {
         // Remove player from any existing team first
         self.remove_player(&user_id);
 
@@ -46,14 +47,14 @@ impl TeamManager {
             .teams
             .iter_mut()
             .find(|t| t.id == team_id)
-            .ok_or_else(|| format!("Team {} not found", team_id))?;
+            .ok_or_else(|| GameError::TeamNotFound(team_id.to_string()))?;
 
         // Check team capacity
         if team.players.len() >= self.max_players_per_team {
-            return Err(format!(
-                "Team {} is full (max {} players)",
-                team.name, self.max_players_per_team
-            ));
+            return Err(GameError::TeamFull {
+                team: team.name.clone(),
+                max: self.max_players_per_team,
+            });
         }
 
         // Add player to team
@@ -113,10 +114,10 @@ impl TeamManager {
     }
 
     /// Update team score
-    pub fn update_score(&mut self, team_id: &str, score_change: i32) -> Result<i32, String> {
+    pub fn update_score(&mut self, team_id: &str, score_change: i32) -> Result<i32, GameError> {
         let team = self
             .get_team_mut(team_id)
-            .ok_or_else(|| format!("Team {} not found", team_id))?;
+            .ok_or_else(|| GameError::TeamNotFound(team_id.to_string()))?;
 
         team.score += score_change;
         Ok(team.score)
@@ -193,22 +194,21 @@ impl TeamManager {
     }
 
     /// Validate team setup for game start
-    pub fn validate_for_game_start(&self) -> Result<(), String> {
+    pub fn validate_for_game_start(&self) -> Result<(), GameError> {
         // Check minimum teams
         let active_teams = self.teams.iter().filter(|t| !t.players.is_empty()).count();
         if active_teams < 2 {
-            return Err("At least 2 teams required to start the game".to_string());
+            return Err(GameError::NotEnoughTeams);
         }
 
         // Check each team has minimum players
         for team in &self.teams {
             if !team.players.is_empty() && team.players.len() < self.min_players_per_team {
-                return Err(format!(
-                    "Team {} needs at least {} players (currently has {})",
-                    team.name,
-                    self.min_players_per_team,
-                    team.players.len()
-                ));
+                return Err(GameError::NotEnoughPlayers {
+                    team: team.name.clone(),
+                    min: self.min_players_per_team,
+                    count: team.players.len(),
+                });
             }
         }
 
@@ -218,9 +218,11 @@ impl TeamManager {
         let min_size = *team_sizes.iter().filter(|&&s| s > 0).min().unwrap_or(&0);
 
         if max_size > 0 && min_size > 0 && max_size - min_size > 2 {
-            return Err(
-                "Teams are too unbalanced. Difference should not exceed 2 players".to_string(),
-            );
+            return Err(GameError::Unbalanced {
+                largest: max_size,
+                smallest: min_size,
+                max_difference: 2,
+            });
         }
 
         Ok(())
@@ -289,9 +291,10 @@ mod tests {
         }
 
         // Try to add one more - should fail
-        assert!(manager
-            .add_player_to_team("user6".to_string(), "team_a")
-            .is_err());
+        assert!(matches!(
+            manager.add_player_to_team("user6".to_string(), "team_a"),
+            Err(GameError::TeamFull { max: 5, .. })
+        ));
     }
 
     #[test]
@@ -317,7 +320,10 @@ mod tests {
         let mut manager = TeamManager::new();
 
         // Not enough teams
-        assert!(manager.validate_for_game_start().is_err());
+        assert_eq!(
+            manager.validate_for_game_start(),
+            Err(GameError::NotEnoughTeams)
+        );
 
         // Add players to both teams
         manager
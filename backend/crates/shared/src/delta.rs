@@ -0,0 +1,147 @@
+// JSON Patch (RFC 6902) subset used for delta state updates
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// One patch operation; paths are JSON Pointers ("" is the whole document)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PatchOp {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+}
+
+impl PatchOp {
+    /// Replace the whole document
+    pub fn replace_root(value: Value) -> Self {
+        PatchOp::Replace {
+            path: String::new(),
+            value,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            PatchOp::Add { path, .. }
+            | PatchOp::Remove { path }
+            | PatchOp::Replace { path, .. } => path,
+        }
+    }
+}
+
+/// Operations turning `old` into `new`
+pub fn diff(old: &Value, new: &Value) -> Vec<PatchOp> {
+    let mut ops = Vec::new();
+    diff_at(String::new(), old, new, &mut ops);
+    ops
+}
+
+fn child(path: &str, key: &str) -> String {
+    format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn diff_at(path: String, old: &Value, new: &Value, ops: &mut Vec<PatchOp>) {
+    if old == new {
+        return;
+    }
+
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, old_value) in old {
+                let path = child(&path, key);
+                match new.get(key) {
+                    Some(new_value) => diff_at(path, old_value, new_value, ops),
+                    None => ops.push(PatchOp::Remove { path }),
+                }
+            }
+            for (key, value) in new {
+                if !old.contains_key(key) {
+                    ops.push(PatchOp::Add {
+                        path: child(&path, key),
+                        value: value.clone(),
+                    });
+                }
+            }
+        }
+        // Arrays mostly grow at the end (round history, used words)
+        (Value::Array(old), Value::Array(new)) => {
+            let common = old.len().min(new.len());
+            for i in 0..common {
+                diff_at(child(&path, &i.to_string()), &old[i], &new[i], ops);
+            }
+            for (i, value) in new.iter().enumerate().skip(common) {
+                ops.push(PatchOp::Add {
+                    path: child(&path, &i.to_string()),
+                    value: value.clone(),
+                });
+            }
+            // From the end, so the remaining indices stay valid
+            for i in (common..old.len()).rev() {
+                ops.push(PatchOp::Remove {
+                    path: child(&path, &i.to_string()),
+                });
+            }
+        }
+        _ => ops.push(PatchOp::Replace {
+            path,
+            value: new.clone(),
+        }),
+    }
+}
+
+/// Apply operations in order; stops at the first one that doesn't fit
+pub fn apply(doc: &mut Value, ops: &[PatchOp]) -> Result<(), String> {
+    ops.iter().try_for_each(|op| apply_op(doc, op))
+}
+
+fn apply_op(doc: &mut Value, op: &PatchOp) -> Result<(), String> {
+    let path = op.path();
+    if path.is_empty() {
+        return match op {
+            PatchOp::Add { value, .. } | PatchOp::Replace { value, .. } => {
+                *doc = value.clone();
+                Ok(())
+            }
+            PatchOp::Remove { .. } => Err("Cannot remove the whole document".to_string()),
+        };
+    }
+
+    let not_found = || format!("Path {} not found", path);
+    let (parent_path, key) = path.rsplit_once('/').ok_or_else(not_found)?;
+    let key = key.replace("~1", "/").replace("~0", "~");
+
+    match doc.pointer_mut(parent_path).ok_or_else(not_found)? {
+        Value::Object(map) => match op {
+            PatchOp::Add { value, .. } => {
+                map.insert(key, value.clone());
+            }
+            PatchOp::Replace { value, .. } => {
+                *map.get_mut(&key).ok_or_else(not_found)? = value.clone();
+            }
+            PatchOp::Remove { .. } => {
+                map.remove(&key).ok_or_else(not_found)?;
+            }
+        },
+        Value::Array(items) => {
+            let index = match key.as_str() {
+                "-" => items.len(),
+                index => index.parse::<usize>().map_err(|_| not_found())?,
+            };
+            match op {
+                PatchOp::Add { value, .. } if index <= items.len() => {
+                    items.insert(index, value.clone())
+                }
+                PatchOp::Replace { value, .. } if index < items.len() => {
+                    items[index] = value.clone()
+                }
+                PatchOp::Remove { .. } if index < items.len() => {
+                    items.remove(index);
+                }
+                _ => return Err(not_found()),
+            }
+        }
+        _ => return Err(not_found()),
+    }
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,12 +12,27 @@ pub enum AuthError {
     #[error("Token expired")]
     TokenExpired,
 
+    #[error("Token revoked")]
+    TokenRevoked,
+
     #[error("Unauthorized")]
     Unauthorized,
 
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Too many requests")]
+    RateLimited,
+
+    #[error("Account is already linked to another user")]
+    AlreadyRegistered,
+
     #[error("Twitch API error: {0}")]
     TwitchApiError(String),
 
+    #[error("Identity provider error: {0}")]
+    ProviderError(String),
+
     #[error("Database error: {0}")]
     DatabaseError(String),
 
@@ -24,6 +40,77 @@ pub enum AuthError {
     InternalError(String),
 }
 
+// Stable, machine-readable codes for WebSocket errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    InvalidMessage,
+    UnsupportedMessage,
+    UnsupportedVersion,
+    NotAuthenticated,
+    AuthenticationFailed,
+    NotInRoom,
+    RoomNotFound,
+    RoomFull,
+    NotParticipant,
+    NotAdmin,
+    PermissionDenied,
+    SpectatorNotAllowed,
+    NotExplainer,
+    NotInTeam,
+    GameNotFound,
+    NoActiveRound,
+    AlreadyStarted,
+    GameEnded,
+    WordAlreadyProcessed,
+    TeamNotFound,
+    TeamFull,
+    TeamsNotReady, // Too few teams or players, or unbalanced teams
+    NotEnoughWords,
+    WrongMode,
+    InvalidRequest,
+    InvalidAction, // Breaks a game rule
+    Internal,
+}
+
+// Error returned by WebSocket message handlers
+#[derive(Error, Debug, Clone, PartialEq)]
+#[error("{message}")]
+pub struct ProtocolError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl ProtocolError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn room_not_found() -> Self {
+        Self::new(ErrorCode::RoomNotFound, "Room not found")
+    }
+
+    pub fn game_not_found() -> Self {
+        Self::new(ErrorCode::GameNotFound, "Game not found")
+    }
+
+    pub fn no_active_round() -> Self {
+        Self::new(ErrorCode::NoActiveRound, "No active round")
+    }
+
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidRequest, message)
+    }
+
+    /// A game engine refused the action
+    pub fn invalid_action(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidAction, message)
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum ApiError {
     #[error("Bad request: {0}")]
@@ -35,6 +122,9 @@ pub enum ApiError {
     #[error("Forbidden: {0}")]
     Forbidden(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     #[error("Internal server error")]
     InternalServerError,
 
@@ -1,3 +1,4 @@
+pub mod delta;
 pub mod errors;
 pub mod models;
 pub mod types;
@@ -1,19 +1,43 @@
 use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use std::collections::HashMap;
 
+use crate::delta::PatchOp;
+use crate::errors::{ErrorCode, ProtocolError};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct User {
     #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
     pub id: Option<ObjectId>,
-    pub twitch_id: String,
+    #[serde(default)]
+    pub identities: Vec<LinkedIdentity>, // Accounts the user can sign in with
     pub username: String,
     pub display_name: String,
     pub profile_image_url: Option<String>,
     pub email: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub is_guest: bool, // Signed in without any linked identity
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub guest_expires_at: Option<mongodb::bson::DateTime>, // Guest accounts are deleted after this
+}
+
+impl User {
+    /// The user's account at an identity provider, if linked
+    pub fn identity(&self, provider: &str) -> Option<&LinkedIdentity> {
+        self.identities.iter().find(|i| i.provider == provider)
+    }
+}
+
+/// Account at an OAuth identity provider (Twitch, Discord, ...) linked to a user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedIdentity {
+    pub provider: String,
+    pub subject: String, // Account ID at the provider
+    pub username: String,
+    pub linked_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,8 +50,8 @@ pub struct AuthTokens {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JwtClaims {
     pub sub: String, // User ID
-    pub twitch_id: String,
     pub username: String,
+    pub sid: String, // Session ID, revoked on logout
     pub exp: i64,
     pub iat: i64,
 }
@@ -41,22 +65,40 @@ pub struct LoginRequest {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LoginResponse {
     pub access_token: String,
+    pub refresh_token: String,
+    pub expires_in: i64, // Access token lifetime in seconds
     pub user: UserInfo,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuestLoginRequest {
+    pub display_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserInfo {
     pub id: String,
     pub username: String,
     pub display_name: String,
     pub profile_image_url: Option<String>,
+    #[serde(default)]
+    pub is_guest: bool,
+    #[serde(default)]
+    pub providers: Vec<String>, // Linked identity providers
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TwitchTokenResponse {
     pub access_token: String,
     pub refresh_token: Option<String>,
+    #[serde(default)]
     pub expires_in: i64,
+    #[serde(default)]
     pub scope: Vec<String>,
     pub token_type: String,
 }
@@ -82,6 +124,8 @@ impl From<User> for UserInfo {
             username: user.username,
             display_name: user.display_name,
             profile_image_url: user.profile_image_url,
+            is_guest: user.is_guest,
+            providers: user.identities.into_iter().map(|i| i.provider).collect(),
         }
     }
 }
@@ -90,8 +134,33 @@ impl From<User> for UserInfo {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum UserRole {
-    Admin,  // Room creator, observer
-    Player, // Regular player
+    Admin,     // Room creator, observer
+    Moderator, // Player allowed some admin actions
+    Player,    // Regular player
+    Spectator, // Watches a redacted stream, not counted against max_players
+}
+
+// Admin actions a room can allow its moderators
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModeratorPermission {
+    Kick,
+    Pause, // Pause and resume
+    AdjustScore,
+    StartRound,
+}
+
+impl ModeratorPermission {
+    pub const ALL: [ModeratorPermission; 4] = [
+        ModeratorPermission::Kick,
+        ModeratorPermission::Pause,
+        ModeratorPermission::AdjustScore,
+        ModeratorPermission::StartRound,
+    ];
+}
+
+fn default_moderator_permissions() -> Vec<ModeratorPermission> {
+    ModeratorPermission::ALL.to_vec()
 }
 
 // Game room participant
@@ -104,7 +173,58 @@ pub struct RoomParticipant {
     pub role: UserRole,
     pub team_id: Option<String>,
     pub is_connected: bool,
+    #[serde(default)]
+    pub is_afk: bool, // Connected, but idle for a while
     pub joined_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot: Option<BotSettings>, // Set for server-side bot players
+}
+
+impl RoomParticipant {
+    pub fn is_bot(&self) -> bool {
+        self.bot.is_some()
+    }
+
+    /// Connected and not AFK; bots are always there
+    pub fn is_present(&self) -> bool {
+        self.is_bot() || (self.is_connected && !self.is_afk)
+    }
+}
+
+// How a bot player guesses. Bots explain with canned clues.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BotSettings {
+    pub accuracy: f64, // Chance of guessing a word, 0.0 to 1.0
+    pub min_delay_ms: u64,
+    pub max_delay_ms: u64, // Time taken per guess is picked between the two
+}
+
+impl Default for BotSettings {
+    fn default() -> Self {
+        Self {
+            accuracy: 0.7,
+            min_delay_ms: 3000,
+            max_delay_ms: 8000,
+        }
+    }
+}
+
+impl BotSettings {
+    pub fn validate(&self) -> Result<(), ProtocolError> {
+        if !(0.0..=1.0).contains(&self.accuracy) {
+            return Err(ProtocolError::new(
+                ErrorCode::InvalidRequest,
+                "Bot accuracy must be between 0 and 1",
+            ));
+        }
+        if self.min_delay_ms > self.max_delay_ms {
+            return Err(ProtocolError::new(
+                ErrorCode::InvalidRequest,
+                "Bot minimum delay must not exceed the maximum",
+            ));
+        }
+        Ok(())
+    }
 }
 
 // Game room states
@@ -132,6 +252,161 @@ pub struct GameRoom {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub game_data: Option<serde_json::Value>, // Game-specific data
+    #[serde(default)]
+    pub spectator_delay_seconds: u32, // Delay of the spectator stream
+    #[serde(default, skip_serializing)]
+    pub overlay_token: String, // Grants read-only overlay access, never sent to clients
+    #[serde(default)]
+    pub registered_only: bool, // Guests can't join or spectate
+    #[serde(default)]
+    pub visibility: RoomVisibility,
+    #[serde(default, skip_serializing)]
+    pub password_hash: Option<String>, // Salted hash, only for password rooms
+    #[serde(default, skip_serializing)]
+    pub invite_secret: String, // Signs invite links, never sent to clients
+    #[serde(default, skip_serializing)]
+    pub bans: HashMap<String, Ban>, // Banned user ID -> ban, admin-only
+    #[serde(default = "default_moderator_permissions")]
+    pub moderator_permissions: Vec<ModeratorPermission>,
+}
+
+impl GameRoom {
+    /// Participants counted against max_players
+    pub fn player_count(&self) -> usize {
+        self.participants
+            .values()
+            .filter(|p| p.role != UserRole::Spectator)
+            .count()
+    }
+
+    /// Participants watching as spectators
+    pub fn spectator_count(&self) -> usize {
+        self.participants.len() - self.player_count()
+    }
+
+    /// Check whether a user is in the room as a spectator
+    pub fn is_spectator(&self, user_id: &str) -> bool {
+        self.participants
+            .get(user_id)
+            .is_some_and(|p| p.role == UserRole::Spectator)
+    }
+
+    /// Whether the user may take an admin action: always for the admin,
+    /// for moderators if the room allows it
+    pub fn has_permission(&self, user_id: &str, permission: ModeratorPermission) -> bool {
+        self.admin_id == user_id
+            || (self
+                .participants
+                .get(user_id)
+                .is_some_and(|p| p.role == UserRole::Moderator)
+                && self.moderator_permissions.contains(&permission))
+    }
+
+    /// Whether anyone but bots is left in the room
+    pub fn has_humans(&self) -> bool {
+        self.participants.values().any(|p| !p.is_bot())
+    }
+
+    /// Who takes over as admin: connected participants first, then
+    /// moderators before players, longest in the room first. Spectators
+    /// and bots never take over.
+    pub fn admin_successor(&self) -> Option<String> {
+        self.participants
+            .values()
+            .filter(|p| p.user_id != self.admin_id && p.role != UserRole::Spectator && !p.is_bot())
+            .min_by_key(|p| {
+                (
+                    !p.is_connected,
+                    p.role != UserRole::Moderator,
+                    p.joined_at,
+                    &p.user_id,
+                )
+            })
+            .map(|p| p.user_id.clone())
+    }
+
+    /// Make another player admin. The old admin, if still in the room,
+    /// stays on as a moderator.
+    pub fn transfer_admin(&mut self, new_admin_id: &str) -> Result<(), String> {
+        if new_admin_id == self.admin_id {
+            return Err("User is already the admin".to_string());
+        }
+        let new_admin = self
+            .participants
+            .get_mut(new_admin_id)
+            .ok_or("Player not found in room")?;
+        if new_admin.role == UserRole::Spectator {
+            return Err("Spectators cannot become admin".to_string());
+        }
+        if new_admin.is_bot() {
+            return Err("Bots cannot become admin".to_string());
+        }
+        new_admin.role = UserRole::Admin;
+
+        let old_admin_id = std::mem::replace(&mut self.admin_id, new_admin_id.to_string());
+        if let Some(old_admin) = self.participants.get_mut(&old_admin_id) {
+            old_admin.role = UserRole::Moderator;
+        }
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// The user's ban from this room, if it hasn't expired
+    pub fn active_ban(&self, user_id: &str) -> Option<&Ban> {
+        self.bans.get(user_id).filter(|ban| ban.is_active())
+    }
+}
+
+// Ban from a room, or from every room a streamer creates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ban {
+    pub user_id: String,
+    pub reason: Option<String>,
+    pub banned_by: String,
+    pub banned_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>, // None for permanent bans
+}
+
+impl Ban {
+    pub fn is_active(&self) -> bool {
+        self.expires_at
+            .is_none_or(|expires_at| expires_at > Utc::now())
+    }
+}
+
+// Ban request; without a duration the ban is permanent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BanRequest {
+    pub user_id: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub duration_minutes: Option<u32>,
+}
+
+// Kick request (body optional)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KickRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+// Add a bot player to a team
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AddBotRequest {
+    pub team_id: String,
+    #[serde(default)]
+    pub settings: BotSettings,
+}
+
+// Who can find and enter a room
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoomVisibility {
+    #[default]
+    Public, // Listed in the lobby
+    Unlisted, // Joinable with the room code, but not listed
+    Password, // Not listed; joining needs the password or an invite
 }
 
 // Room creation request
@@ -139,6 +414,14 @@ pub struct GameRoom {
 pub struct CreateRoomRequest {
     pub name: String,
     pub max_players: u8,
+    #[serde(default)]
+    pub spectator_delay_seconds: u32,
+    #[serde(default)]
+    pub registered_only: bool,
+    #[serde(default)]
+    pub visibility: RoomVisibility,
+    #[serde(default)]
+    pub password: Option<String>, // Required for password rooms
 }
 
 // Room creation response
@@ -148,6 +431,35 @@ pub struct CreateRoomResponse {
     pub room_code: String,
     pub name: String,
     pub admin_id: String,
+    pub overlay_token: String,
+}
+
+// Overlay token for the room admin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayTokenResponse {
+    pub overlay_token: String,
+}
+
+// Read-only room view for stream overlays
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlaySnapshot {
+    pub room_code: String,
+    pub room_name: String,
+    pub state: RoomState,
+    pub teams: Vec<Team>,
+    pub current_round: Option<OverlayRound>,
+    pub audience_score: Option<i32>, // Only set when Twitch chat is connected
+}
+
+// Current round as shown on the overlay (no words)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverlayRound {
+    pub round_number: u32,
+    pub team_id: String,
+    pub explainer_id: String,
+    pub explainer_name: String,
+    pub time_remaining: u32,
+    pub score_gained: i32,
 }
 
 // Join room request
@@ -156,6 +468,30 @@ pub struct JoinRoomRequest {
     pub room_code: String,
 }
 
+// Credentials for entering a password room (body of join and spectate)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomAccessRequest {
+    #[serde(default)]
+    pub password: Option<String>,
+    #[serde(default)]
+    pub invite: Option<String>, // Invite token, skips the password
+}
+
+// Invite creation request (admin only)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CreateInviteRequest {
+    #[serde(default)]
+    pub expires_in_minutes: Option<u32>,
+}
+
+// Signed invite to a room
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomInvite {
+    pub room_code: String,
+    pub invite: String,
+    pub expires_at: DateTime<Utc>,
+}
+
 // Room info for listing
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoomInfo {
@@ -166,6 +502,33 @@ pub struct RoomInfo {
     pub max_players: u8,
     pub state: RoomState,
     pub admin_username: String,
+    #[serde(default)]
+    pub spectators: usize,
+    #[serde(default)]
+    pub registered_only: bool,
+    #[serde(default)]
+    pub visibility: RoomVisibility,
+}
+
+impl From<&GameRoom> for RoomInfo {
+    fn from(room: &GameRoom) -> Self {
+        RoomInfo {
+            id: room.id.map(|id| id.to_hex()).unwrap_or_default(),
+            room_code: room.room_code.clone(),
+            name: room.name.clone(),
+            current_players: room.player_count(),
+            max_players: room.max_players,
+            state: room.state,
+            admin_username: room
+                .participants
+                .get(&room.admin_id)
+                .map(|p| p.username.clone())
+                .unwrap_or_default(),
+            spectators: room.spectator_count(),
+            registered_only: room.registered_only,
+            visibility: room.visibility,
+        }
+    }
 }
 
 // Team models for Alias game
@@ -194,6 +557,14 @@ pub enum WordResult {
     Correct,
     Skipped,
     Penalty, // For violations or wrong actions
+    Stolen,  // Guessed by the Twitch chat audience first
+}
+
+impl WordResult {
+    /// Whether the word was guessed (and can be revealed)
+    pub fn is_guessed(self) -> bool {
+        matches!(self, WordResult::Correct | WordResult::Stolen)
+    }
 }
 
 // Single word in a round
@@ -204,6 +575,24 @@ pub struct GameWord {
     pub category: Option<String>,
     pub result: Option<WordResult>,
     pub time_spent: Option<u32>, // Seconds spent on this word
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub associations: Vec<String>, // Related words bot explainers use as clues
+}
+
+// Clue typed by the explainer in text-clue mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Clue {
+    pub text: String,
+    pub word_index: usize, // Index of the word the clue was given for
+    pub sent_at: DateTime<Utc>,
+}
+
+// Vector stroke in drawing mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stroke {
+    pub points: Vec<[u16; 2]>, // Normalized canvas coordinates
+    pub color: String,         // #RRGGBB
+    pub width: u8,
 }
 
 // Game round
@@ -218,6 +607,107 @@ pub struct Round {
     pub score_gained: i32,
     pub started_at: Option<DateTime<Utc>>,
     pub ended_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub clues: Vec<Clue>, // Accepted clues (text-clue mode)
+    #[serde(default)]
+    pub canvas: Vec<Stroke>, // Strokes for the current word (drawing mode)
+}
+
+impl Round {
+    /// Copy of the round with unrevealed words removed
+    pub fn redacted(&self) -> Round {
+        let mut round = self.clone();
+        round
+            .words
+            .retain(|w| w.result.is_some_and(WordResult::is_guessed));
+        round
+    }
+}
+
+// Game modes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    #[default]
+    Classic, // Words come from the shared dictionary
+    Hat,      // "Шляпа": players contribute the word pool
+    TextClue, // Explainer types clues instead of speaking
+    Drawing,  // Explainer draws instead of speaking
+}
+
+// Phases of the Hat mode. The whole pool is played once per phase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HatPhase {
+    Submission, // Players secretly submit their words
+    Describe,   // Explain with any words
+    OneWord,    // Explain with a single word
+    Gestures,   // Explain with gestures only
+}
+
+// Rules for a single Hat phase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HatPhaseRules {
+    pub phase: HatPhase,
+    pub round_duration_seconds: u32,
+    pub allow_skip: bool,
+}
+
+// Hat mode settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HatSettings {
+    pub words_per_player: u32,
+    pub phases: Vec<HatPhaseRules>, // Played in order after submission
+}
+
+impl Default for HatSettings {
+    fn default() -> Self {
+        Self {
+            words_per_player: 5,
+            phases: vec![
+                HatPhaseRules {
+                    phase: HatPhase::Describe,
+                    round_duration_seconds: 60,
+                    allow_skip: true,
+                },
+                HatPhaseRules {
+                    phase: HatPhase::OneWord,
+                    round_duration_seconds: 45,
+                    allow_skip: true,
+                },
+                HatPhaseRules {
+                    phase: HatPhase::Gestures,
+                    round_duration_seconds: 60,
+                    allow_skip: false,
+                },
+            ],
+        }
+    }
+}
+
+// Twitch chat audience settings
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TwitchChatSettings {
+    #[serde(default)]
+    pub channel: Option<String>, // Defaults to the room admin's Twitch login
+    #[serde(default)]
+    pub steal_words: bool, // Audience guesses take the word from the playing team
+}
+
+// Audience team made of Twitch chat viewers
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AudienceTeam {
+    pub score: i32,
+    pub guesses: HashMap<String, u32>, // Viewer name -> guessed words
+}
+
+// Public Hat progress. Submitted words stay on the server.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HatProgress {
+    pub phase: HatPhase,
+    pub words_remaining: usize,
+    pub total_words: usize,
+    pub submitted_players: Vec<String>, // User IDs
 }
 
 // Game settings
@@ -228,6 +718,12 @@ pub struct GameSettings {
     pub skip_penalty_after: u32, // Penalty after N skips
     pub win_score: i32,
     pub difficulty: String, // easy, medium, hard, mixed
+    #[serde(default)]
+    pub mode: GameMode,
+    #[serde(default)]
+    pub hat: HatSettings, // Only used in Hat mode
+    #[serde(default)]
+    pub twitch_chat: Option<TwitchChatSettings>, // Audience guesses from Twitch chat
 }
 
 impl Default for GameSettings {
@@ -238,6 +734,9 @@ impl Default for GameSettings {
             skip_penalty_after: 3,
             win_score: 50,
             difficulty: "mixed".to_string(),
+            mode: GameMode::Classic,
+            hat: HatSettings::default(),
+            twitch_chat: None,
         }
     }
 }
@@ -255,6 +754,21 @@ pub struct GameState {
     pub winner_team_id: Option<String>,
     pub started_at: Option<DateTime<Utc>>,
     pub ended_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub hat: Option<HatProgress>, // Only set in Hat mode
+    #[serde(default)]
+    pub audience: Option<AudienceTeam>, // Only set when Twitch chat is connected
+}
+
+impl GameState {
+    /// Copy of the state with unrevealed words removed
+    pub fn redacted(&self) -> GameState {
+        let mut state = self.clone();
+        state.current_round = state.current_round.map(|r| r.redacted());
+        state.round_history = state.round_history.iter().map(|r| r.redacted()).collect();
+        state.used_words.clear();
+        state
+    }
 }
 
 // Team assignment request
@@ -263,6 +777,31 @@ pub struct JoinTeamRequest {
     pub team_id: String,
 }
 
+// Role change request (admin only): player or moderator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateRoleRequest {
+    pub role: UserRole,
+}
+
+// Admin transfer request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferAdminRequest {
+    pub user_id: String,
+}
+
+// Moderator permission update (admin only)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeratorPermissionsRequest {
+    pub permissions: Vec<ModeratorPermission>,
+}
+
+// Manual score correction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdjustScoreRequest {
+    pub team_id: String,
+    pub delta: i32,
+}
+
 // Game start request
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StartGameRequest {
@@ -275,6 +814,12 @@ pub struct WordActionRequest {
     pub word_result: WordResult,
 }
 
+// Hat word submission request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitWordsRequest {
+    pub words: Vec<String>,
+}
+
 // WebSocket messages
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -290,15 +835,43 @@ pub enum WebSocketMessage {
     LeaveRoom,
     KickPlayer {
         user_id: String,
+        #[serde(default)]
+        reason: Option<String>,
+    },
+    BanPlayer {
+        user_id: String,
+        #[serde(default)]
+        reason: Option<String>,
+        #[serde(default)]
+        duration_minutes: Option<u32>,
+    },
+    AddBot {
+        team_id: String,
+        #[serde(default)]
+        settings: BotSettings,
     },
     UpdateRole {
         user_id: String,
         role: UserRole,
     },
-    StartGame,
+    TransferAdmin {
+        user_id: String,
+    },
+    UpdateModeratorPermissions {
+        permissions: Vec<ModeratorPermission>,
+    },
+    AdjustScore {
+        team_id: String,
+        delta: i32,
+    },
+    StartGame {
+        #[serde(default)]
+        settings: Option<GameSettings>,
+    },
     PauseGame,
     ResumeGame,
     Ping,
+    Activity, // The player is at the screen, e.g. moved the mouse; keeps them from going AFK
 
     // Game-specific messages
     JoinTeam {
@@ -312,12 +885,38 @@ pub enum WebSocketMessage {
     },
     RequestNewWord,
     EndRound,
+    SubmitWords {
+        words: Vec<String>,
+    },
+    SubmitClue {
+        text: String,
+    },
+    DrawStroke {
+        stroke: Stroke,
+    },
+    UndoStroke,
+    ClearCanvas,
+    RequestCanvas,
+    Resume {
+        room_code: String,
+        last_seq: u64,
+    },
+    SetDeltaUpdates {
+        enabled: bool,
+    },
+    RequestSnapshot,
+    Hello {
+        protocol_version: u32,
+        #[serde(default, deserialize_with = "known_encodings")]
+        encodings: Vec<Encoding>,
+    },
 
     // Server to client
     Authenticated {
         user: UserInfo,
     },
     Pong,
+    Ack, // Reply to a request that has no other direct response
     RoomList {
         rooms: Vec<RoomInfo>,
     },
@@ -345,6 +944,10 @@ pub enum WebSocketMessage {
     UserKicked {
         user_id: String,
         kicked_by: String,
+        #[serde(default)]
+        reason: Option<String>,
+        #[serde(default)]
+        banned: bool,
     },
     RoleUpdated {
         user_id: String,
@@ -353,7 +956,14 @@ pub enum WebSocketMessage {
     GameStarted,
     GamePaused,
     GameResumed,
+    // A teammate took over the turn of an explainer who went AFK or disconnected
+    ExplainerChanged {
+        round_number: u32,
+        previous_explainer_id: String,
+        explainer_id: String,
+    },
     Error {
+        code: ErrorCode,
         message: String,
     },
 
@@ -381,6 +991,8 @@ pub enum WebSocketMessage {
     WordResultRecorded {
         result: WordResult,
         score_change: i32,
+        #[serde(default)]
+        word: Option<String>, // Revealed once guessed
     },
     TimerUpdate {
         time_remaining: u32,
@@ -394,8 +1006,118 @@ pub enum WebSocketMessage {
         final_scores: Vec<Team>,
     },
     GameStateUpdated {
-        game_state: GameState,
+        game_state: Box<GameState>, // Boxed to keep messages small
+    },
+    WordsSubmitted {
+        user_id: String,
+        count: usize,
+    },
+    HatPhaseChanged {
+        phase: HatPhase,
+        words_remaining: usize,
+    },
+    ClueAdded {
+        clue: Clue,
+    },
+    ClueRejected {
+        user_id: String,
+        score_change: i32,
     },
+    AudienceGuessed {
+        viewer: String,
+        word: Option<String>, // Revealed only when stolen
+        stolen: bool,
+        audience_score: i32,
+    },
+    StrokeDrawn {
+        stroke: Stroke,
+    },
+    StrokeUndone,
+    CanvasCleared,
+    CanvasSnapshot {
+        strokes: Vec<Stroke>,
+    },
+    Resumed {
+        room_code: String,
+        events: Vec<SequencedMessage>, // Missed broadcasts, oldest first
+    },
+    RoomSnapshot {
+        room: GameRoom,
+        game_state: Option<Box<GameState>>,
+        seq: u64, // Last broadcast the snapshot covers
+    },
+    // Sent instead of room_updated / game_state_updated once deltas are on.
+    // base_version 0 means the ops replace the whole document.
+    RoomDelta {
+        base_version: u64,
+        version: u64,
+        ops: Vec<PatchOp>,
+    },
+    GameStateDelta {
+        base_version: u64,
+        version: u64,
+        ops: Vec<PatchOp>,
+    },
+    Welcome {
+        protocol_version: u32,
+        encoding: Encoding,
+    },
+}
+
+impl From<ProtocolError> for WebSocketMessage {
+    fn from(error: ProtocolError) -> Self {
+        WebSocketMessage::Error {
+            code: error.code,
+            message: error.message,
+        }
+    }
+}
+
+// Frame encoding of a WebSocket connection, agreed on in the handshake
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Json, // Text frames
+    Msgpack, // Binary frames
+}
+
+/// The encodings a client offers, minus ones this server doesn't know, so
+/// newer clients can still connect
+fn known_encodings<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Encoding>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Offered {
+        Known(Encoding),
+        Unknown(serde::de::IgnoredAny),
+    }
+
+    let offered = Vec::<Offered>::deserialize(deserializer)?;
+    Ok(offered
+        .into_iter()
+        .filter_map(|encoding| match encoding {
+            Offered::Known(encoding) => Some(encoding),
+            Offered::Unknown(_) => None,
+        })
+        .collect())
+}
+
+/// A client request, or the direct reply to one, with the optional id the
+/// client uses to match them up
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestEnvelope {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(flatten)]
+    pub message: WebSocketMessage,
+}
+
+/// A room broadcast tagged with its place in the room's stream
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequencedMessage {
+    pub seq: u64,
+    #[serde(flatten)]
+    pub message: WebSocketMessage,
 }
 
 impl WebSocketMessage {
@@ -407,11 +1129,17 @@ impl WebSocketMessage {
             WebSocketMessage::JoinRoom { .. } => "join_room",
             WebSocketMessage::LeaveRoom => "leave_room",
             WebSocketMessage::KickPlayer { .. } => "kick_player",
+            WebSocketMessage::BanPlayer { .. } => "ban_player",
+            WebSocketMessage::AddBot { .. } => "add_bot",
             WebSocketMessage::UpdateRole { .. } => "update_role",
-            WebSocketMessage::StartGame => "start_game",
+            WebSocketMessage::TransferAdmin { .. } => "transfer_admin",
+            WebSocketMessage::UpdateModeratorPermissions { .. } => "update_moderator_permissions",
+            WebSocketMessage::AdjustScore { .. } => "adjust_score",
+            WebSocketMessage::StartGame { .. } => "start_game",
             WebSocketMessage::PauseGame => "pause_game",
             WebSocketMessage::ResumeGame => "resume_game",
             WebSocketMessage::Ping => "ping",
+            WebSocketMessage::Activity => "activity",
             WebSocketMessage::JoinTeam { .. } => "join_team",
             WebSocketMessage::LeaveTeam => "leave_team",
             WebSocketMessage::MarkReady => "mark_ready",
@@ -419,10 +1147,21 @@ impl WebSocketMessage {
             WebSocketMessage::WordAction { .. } => "word_action",
             WebSocketMessage::RequestNewWord => "request_new_word",
             WebSocketMessage::EndRound => "end_round",
+            WebSocketMessage::SubmitWords { .. } => "submit_words",
+            WebSocketMessage::SubmitClue { .. } => "submit_clue",
+            WebSocketMessage::DrawStroke { .. } => "draw_stroke",
+            WebSocketMessage::UndoStroke => "undo_stroke",
+            WebSocketMessage::ClearCanvas => "clear_canvas",
+            WebSocketMessage::RequestCanvas => "request_canvas",
+            WebSocketMessage::Resume { .. } => "resume",
+            WebSocketMessage::SetDeltaUpdates { .. } => "set_delta_updates",
+            WebSocketMessage::RequestSnapshot => "request_snapshot",
+            WebSocketMessage::Hello { .. } => "hello",
 
             // Server messages
             WebSocketMessage::Authenticated { .. } => "authenticated",
             WebSocketMessage::Pong => "pong",
+            WebSocketMessage::Ack => "ack",
             WebSocketMessage::RoomList { .. } => "room_list",
             WebSocketMessage::RoomJoined { .. } => "room_joined",
             WebSocketMessage::RoomUpdated { .. } => "room_updated",
@@ -436,6 +1175,7 @@ impl WebSocketMessage {
             WebSocketMessage::GameStarted => "game_started",
             WebSocketMessage::GamePaused => "game_paused",
             WebSocketMessage::GameResumed => "game_resumed",
+            WebSocketMessage::ExplainerChanged { .. } => "explainer_changed",
             WebSocketMessage::Error { .. } => "error",
             WebSocketMessage::TeamJoined { .. } => "team_joined",
             WebSocketMessage::TeamLeft { .. } => "team_left",
@@ -448,6 +1188,208 @@ impl WebSocketMessage {
             WebSocketMessage::RoundEnded { .. } => "round_ended",
             WebSocketMessage::GameEnded { .. } => "game_ended",
             WebSocketMessage::GameStateUpdated { .. } => "game_state_updated",
+            WebSocketMessage::WordsSubmitted { .. } => "words_submitted",
+            WebSocketMessage::HatPhaseChanged { .. } => "hat_phase_changed",
+            WebSocketMessage::ClueAdded { .. } => "clue_added",
+            WebSocketMessage::ClueRejected { .. } => "clue_rejected",
+            WebSocketMessage::AudienceGuessed { .. } => "audience_guessed",
+            WebSocketMessage::StrokeDrawn { .. } => "stroke_drawn",
+            WebSocketMessage::StrokeUndone => "stroke_undone",
+            WebSocketMessage::CanvasCleared => "canvas_cleared",
+            WebSocketMessage::CanvasSnapshot { .. } => "canvas_snapshot",
+            WebSocketMessage::Resumed { .. } => "resumed",
+            WebSocketMessage::RoomSnapshot { .. } => "room_snapshot",
+            WebSocketMessage::RoomDelta { .. } => "room_delta",
+            WebSocketMessage::GameStateDelta { .. } => "game_state_delta",
+            WebSocketMessage::Welcome { .. } => "welcome",
+        }
+    }
+
+    /// Client messages that change team or game state
+    pub fn is_game_action(&self) -> bool {
+        matches!(
+            self,
+            WebSocketMessage::KickPlayer { .. }
+                | WebSocketMessage::BanPlayer { .. }
+                | WebSocketMessage::AddBot { .. }
+                | WebSocketMessage::UpdateRole { .. }
+                | WebSocketMessage::TransferAdmin { .. }
+                | WebSocketMessage::UpdateModeratorPermissions { .. }
+                | WebSocketMessage::AdjustScore { .. }
+                | WebSocketMessage::StartGame { .. }
+                | WebSocketMessage::PauseGame
+                | WebSocketMessage::ResumeGame
+                | WebSocketMessage::JoinTeam { .. }
+                | WebSocketMessage::LeaveTeam
+                | WebSocketMessage::MarkReady
+                | WebSocketMessage::StartRound
+                | WebSocketMessage::WordAction { .. }
+                | WebSocketMessage::RequestNewWord
+                | WebSocketMessage::EndRound
+                | WebSocketMessage::SubmitWords { .. }
+                | WebSocketMessage::SubmitClue { .. }
+                | WebSocketMessage::DrawStroke { .. }
+                | WebSocketMessage::UndoStroke
+                | WebSocketMessage::ClearCanvas
+        )
+    }
+
+    /// Version of a room broadcast that is safe to show an audience.
+    /// Secret words are removed; None means the message is not shown at all,
+    /// which is the case for anything not known to be safe.
+    pub fn redacted_for_audience(&self) -> Option<WebSocketMessage> {
+        match self {
+            WebSocketMessage::RoundStarted { round } => Some(WebSocketMessage::RoundStarted {
+                round: round.redacted(),
+            }),
+            WebSocketMessage::RoundEnded {
+                round,
+                next_team_id,
+            } => Some(WebSocketMessage::RoundEnded {
+                round: round.redacted(),
+                next_team_id: next_team_id.clone(),
+            }),
+            WebSocketMessage::GameStateUpdated { game_state } => {
+                Some(WebSocketMessage::GameStateUpdated {
+                    game_state: Box::new(game_state.redacted()),
+                })
+            }
+            WebSocketMessage::RoomSnapshot {
+                room,
+                game_state,
+                seq,
+            } => Some(WebSocketMessage::RoomSnapshot {
+                room: room.clone(),
+                game_state: game_state.as_ref().map(|state| Box::new(state.redacted())),
+                seq: *seq,
+            }),
+            WebSocketMessage::Resumed { room_code, events } => Some(WebSocketMessage::Resumed {
+                room_code: room_code.clone(),
+                events: events
+                    .iter()
+                    .filter_map(|event| {
+                        Some(SequencedMessage {
+                            seq: event.seq,
+                            message: event.message.redacted_for_audience()?,
+                        })
+                    })
+                    .collect(),
+            }),
+            WebSocketMessage::Pong
+            | WebSocketMessage::Ack
+            | WebSocketMessage::Error { .. }
+            | WebSocketMessage::RoomJoined { .. }
+            | WebSocketMessage::RoomUpdated { .. }
+            | WebSocketMessage::RoomDeleted { .. }
+            | WebSocketMessage::RoomInfoUpdated { .. }
+            | WebSocketMessage::UserJoined { .. }
+            | WebSocketMessage::UserLeft { .. }
+            | WebSocketMessage::UserKicked { .. }
+            | WebSocketMessage::RoleUpdated { .. }
+            | WebSocketMessage::GameStarted
+            | WebSocketMessage::GamePaused
+            | WebSocketMessage::GameResumed
+            | WebSocketMessage::ExplainerChanged { .. }
+            | WebSocketMessage::TeamJoined { .. }
+            | WebSocketMessage::TeamLeft { .. }
+            | WebSocketMessage::TeamReady { .. }
+            | WebSocketMessage::TeamsUpdated { .. }
+            | WebSocketMessage::WordResultRecorded { .. } // Words only once guessed
+            | WebSocketMessage::TimerUpdate { .. }
+            | WebSocketMessage::GameEnded { .. }
+            | WebSocketMessage::WordsSubmitted { .. }
+            | WebSocketMessage::HatPhaseChanged { .. }
+            | WebSocketMessage::ClueAdded { .. }
+            | WebSocketMessage::ClueRejected { .. }
+            | WebSocketMessage::AudienceGuessed { .. } // Words only once stolen
+            | WebSocketMessage::StrokeDrawn { .. }
+            | WebSocketMessage::StrokeUndone
+            | WebSocketMessage::CanvasCleared
+            | WebSocketMessage::CanvasSnapshot { .. } => Some(self.clone()),
+            // Secret words, deltas of unredacted state, and requests
+            _ => None,
         }
     }
 }
+
+// Events a webhook can subscribe to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Ping, // Test deliveries only
+    RoomCreated,
+    GameStarted,
+    RoundEnded,
+    GameEnded,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::Ping => "ping",
+            WebhookEvent::RoomCreated => "room_created",
+            WebhookEvent::GameStarted => "game_started",
+            WebhookEvent::RoundEnded => "round_ended",
+            WebhookEvent::GameEnded => "game_ended",
+        }
+    }
+}
+
+// Webhook subscription (the signing secret is only returned on creation)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub owner_id: String,
+    pub room_code: Option<String>, // None = every room the owner administers
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    pub created_at: DateTime<Utc>,
+}
+
+// Webhook registration request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    #[serde(default)]
+    pub room_code: Option<String>,
+}
+
+// Webhook registration response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateWebhookResponse {
+    pub webhook: Webhook,
+    pub secret: String, // HMAC-SHA256 key for X-Alias-Signature
+}
+
+// Body POSTed to webhook URLs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub delivery_id: String,
+    pub event: WebhookEvent,
+    pub room_code: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+// Entry in a webhook's delivery log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event: WebhookEvent,
+    pub status: DeliveryStatus,
+    pub attempts: u32,
+    pub response_status: Option<u16>, // Status of the last attempt
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
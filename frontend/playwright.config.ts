@@ -31,7 +31,7 @@ export default defineConfig({
       cwd: '../frontend',
     },
     {
-      command: 'TWITCH_CLIENT_ID=test_client_id TWITCH_CLIENT_SECRET=test_secret JWT_SECRET=test_jwt_secret MONGODB_URL=mongodb://localhost:27017/alias_test REDIS_URL=redis://localhost:6379 cargo run --bin api-gateway',
+      command: 'TWITCH_CLIENT_ID=test_client_id TWITCH_CLIENT_SECRET=test_secret MONGODB_URL=mongodb://localhost:27017/alias_test REDIS_URL=redis://localhost:6379 cargo run --bin api-gateway',
       port: 3000,
       reuseExistingServer: !process.env['CI'],
       cwd: '../backend',